@@ -0,0 +1,35 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "ffi")]
+    write_c_header();
+}
+
+/// Generates `include/time_track.h` from the C interface in `src/ffi.rs`.
+#[cfg(feature = "ffi")]
+fn write_c_header() {
+    println!("cargo:rerun-if-changed=src/ffi.rs");
+
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let mut config = cbindgen::Config {
+        language: cbindgen::Language::C,
+        include_guard: Some("TIME_TRACK_H".to_string()),
+        header: Some(
+            "/* Generated by cbindgen from src/ffi.rs, don't edit by hand. */".to_string(),
+        ),
+        ..Default::default()
+    };
+    config.enumeration.rename_variants = cbindgen::RenameRule::ScreamingSnakeCase;
+    config.enumeration.prefix_with_name = true;
+    config.export.exclude.push("TrackedDuration".to_string());
+    config
+        .export
+        .rename
+        .insert("CheckpointDb".to_string(), "TtDb".to_string());
+
+    cbindgen::Builder::new()
+        .with_crate(&crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("failed to generate the C header")
+        .write_to_file(format!("{}/include/time_track.h", crate_dir));
+}
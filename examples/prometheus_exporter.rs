@@ -0,0 +1,57 @@
+//! Serves the metrics of a time tracking database for Prometheus to scrape.
+//!
+//! Usage: `prometheus_exporter <database file> [address]`, where the address defaults to
+//! `127.0.0.1:9864`. The database is read again on every scrape.
+
+use std::env;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use time_track::CheckpointDb;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let Some(path) = args.next() else {
+        eprintln!("usage: prometheus_exporter <database file> [address]");
+        std::process::exit(2);
+    };
+    let address = args.next().unwrap_or_else(|| "127.0.0.1:9864".to_string());
+
+    let listener = match TcpListener::bind(&address) {
+        Ok(listener) => listener,
+        Err(e) => {
+            eprintln!("could not listen on {}: {}", address, e);
+            std::process::exit(1);
+        }
+    };
+    for stream in listener.incoming().flatten() {
+        if let Err(e) = respond(stream, Path::new(&path)) {
+            eprintln!("could not respond: {}", e);
+        }
+    }
+}
+
+fn respond(mut stream: TcpStream, path: &Path) -> std::io::Result<()> {
+    // Read the request headers, which don't matter since there's only one thing to serve.
+    let mut reader = BufReader::new(&stream);
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let (status, body) = match CheckpointDb::read(path) {
+        Ok(checkpoint_db) => (
+            "200 OK",
+            checkpoint_db.prometheus_metrics(chrono::Local::now().timestamp()),
+        ),
+        Err(e) => ("500 Internal Server Error", format!("{}\n", e)),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
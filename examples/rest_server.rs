@@ -0,0 +1,39 @@
+//! Serves the HTTP API from `rest_router` over a time tracking database.
+//!
+//! Usage: `rest_server <database file> [address]`, where the address defaults to
+//! `127.0.0.1:8642`. Changes are saved to the database file as they are made.
+
+use std::env;
+use std::path::PathBuf;
+use time_track::{rest_router, CheckpointDb, SharedCheckpointDb};
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let Some(path) = args.next().map(PathBuf::from) else {
+        eprintln!("usage: rest_server <database file> [address]");
+        std::process::exit(2);
+    };
+    let address = args.next().unwrap_or_else(|| "127.0.0.1:8642".to_string());
+
+    let checkpoint_db = match CheckpointDb::read(&path) {
+        Ok(checkpoint_db) => checkpoint_db,
+        Err(e) => {
+            eprintln!("could not read {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    };
+    let router = rest_router(SharedCheckpointDb::new(checkpoint_db), Some(path));
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()
+        .expect("could not start the runtime");
+    let result = runtime.block_on(async {
+        let listener = tokio::net::TcpListener::bind(&address).await?;
+        axum::serve(listener, router).await
+    });
+    if let Err(e) = result {
+        eprintln!("could not serve on {}: {}", address, e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,144 @@
+/* Generated by cbindgen from src/ffi.rs, don't edit by hand. */
+
+#ifndef TIME_TRACK_H
+#define TIME_TRACK_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The result of a call through the C interface. The values won't change between versions.
+ */
+typedef enum TtStatus {
+  TT_STATUS_OK = 0,
+  /**
+   * A pointer that must not be null was null.
+   */
+  TT_STATUS_NULL_ARGUMENT = 1,
+  /**
+   * A string wasn't valid UTF-8.
+   */
+  TT_STATUS_INVALID_UTF8 = 2,
+  TT_STATUS_PROJECT_NOT_FOUND = 3,
+  /**
+   * The file couldn't be read or written.
+   */
+  TT_STATUS_IO = 4,
+  /**
+   * The file isn't a valid database.
+   */
+  TT_STATUS_INVALID_FILE = 5,
+  TT_STATUS_OTHER = 6,
+  /**
+   * There already is a checkpoint at the time.
+   */
+  TT_STATUS_CHECKPOINT_EXISTS = 7,
+} TtStatus;
+
+/**
+ * All projects, clients and checkpoints. The default is an empty database.
+ */
+typedef struct TtDb TtDb;
+
+/**
+ * A checkpoint returned by `tt_db_query_range`.
+ */
+typedef struct TtCheckpoint {
+  int64_t timestamp;
+  /**
+   * The seconds since the checkpoint before it, which is 0 for the oldest checkpoint.
+   */
+  int64_t duration;
+  /**
+   * The id of the project, or -1 if it has none.
+   */
+  int32_t project_id;
+  bool is_break;
+  /**
+   * A null-terminated UTF-8 string owned by the list the checkpoint is in.
+   */
+  char *message;
+} TtCheckpoint;
+
+/**
+ * Checkpoints returned by `tt_db_query_range`, from the latest to the oldest. Must be freed
+ * with `tt_checkpoint_list_free`.
+ */
+typedef struct TtCheckpointList {
+  struct TtCheckpoint *checkpoints;
+  uintptr_t len;
+} TtCheckpointList;
+
+
+
+/**
+ * Returns a new empty database, which must be freed with `tt_db_free`.
+ */
+struct TtDb *tt_db_new(void);
+
+/**
+ * Reads the database at the path, creating an empty one if there is no file, and stores it in
+ * `out`. It must be freed with `tt_db_free`. `out` is left untouched on failure.
+ *
+ * # Safety
+ *
+ * `path` must be a null-terminated string and `out` must be valid for writes.
+ */
+enum TtStatus tt_db_open(const char *path, struct TtDb **out);
+
+/**
+ * Writes the database to the path.
+ *
+ * # Safety
+ *
+ * `db` must come from `tt_db_new` or `tt_db_open` and `path` must be a null-terminated
+ * string.
+ */
+enum TtStatus tt_db_write(const struct TtDb *db, const char *path);
+
+/**
+ * Adds a checkpoint at the timestamp, failing with `TtStatus::CheckpointExists` if there
+ * already is one there. A negative `project_id` means no project.
+ *
+ * # Safety
+ *
+ * `db` must come from `tt_db_new` or `tt_db_open` and `message` must be a null-terminated
+ * string.
+ */
+enum TtStatus tt_db_add_checkpoint(struct TtDb *db,
+                                   int64_t timestamp,
+                                   const char *message,
+                                   int32_t project_id);
+
+/**
+ * Returns the checkpoints between two timestamps, like `CheckpointDb::get_log_between_times`.
+ * A null `db` gives an empty list.
+ *
+ * # Safety
+ *
+ * `db` must be null or come from `tt_db_new` or `tt_db_open`.
+ */
+struct TtCheckpointList tt_db_query_range(const struct TtDb *db, int64_t start, int64_t end);
+
+/**
+ * Frees a list returned by `tt_db_query_range`, including its messages.
+ *
+ * # Safety
+ *
+ * `list` must come from `tt_db_query_range` and not have been freed already.
+ */
+void tt_checkpoint_list_free(struct TtCheckpointList list);
+
+/**
+ * Frees a database. Null is ignored.
+ *
+ * # Safety
+ *
+ * `db` must be null or come from `tt_db_new` or `tt_db_open`, and not have been freed
+ * already.
+ */
+void tt_db_free(struct TtDb *db);
+
+#endif  /* TIME_TRACK_H */
@@ -0,0 +1,170 @@
+use crate::{ImportedCheckpoint, Result};
+use std::collections::BTreeMap;
+
+/// What the user was doing at a moment, like the focused window.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct ActivitySample {
+    pub timestamp: i64,
+    /// The name of the application, like `firefox`.
+    pub app: String,
+    /// The title of the window.
+    pub title: String,
+}
+
+/// Tells what the user is doing, usually from the window system. The probes live in
+/// frontends, which know the system they run on.
+pub trait ActivitySource {
+    /// Returns what the user is doing now, or `None` if there's nothing to tell, like when the
+    /// screen is locked.
+    fn sample(&self) -> Result<Option<ActivitySample>>;
+}
+
+/// Turns activity samples into suggested checkpoints, which can be shown to the user and added
+/// with `CheckpointDb::import` once confirmed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ActivitySummarizer {
+    /// Maps application names to the short names of projects in the database. Activity in
+    /// other applications gets no project.
+    pub projects: BTreeMap<String, String>,
+    /// Activity in one application shorter than this many seconds is counted towards the
+    /// activity before it. 5 minutes by default.
+    pub min_duration: i64,
+    /// Samples further apart than this many seconds have a gap between them, where the user
+    /// was away. 5 minutes by default.
+    pub max_gap: i64,
+}
+
+impl Default for ActivitySummarizer {
+    fn default() -> ActivitySummarizer {
+        ActivitySummarizer {
+            projects: BTreeMap::new(),
+            min_duration: 5 * 60,
+            max_gap: 5 * 60,
+        }
+    }
+}
+
+/// A stretch of time spent in one application.
+struct Segment<'a> {
+    start: i64,
+    end: i64,
+    app: &'a str,
+    /// The seconds spent in each window title.
+    titles: BTreeMap<&'a str, i64>,
+}
+
+impl ActivitySummarizer {
+    /// Suggests a checkpoint for each stretch of time spent in one application, at its end and
+    /// with the title that was focused the longest as message. Each suggestion starts where
+    /// its stretch did, so that gaps become breaks when imported.
+    pub fn summarize(&self, samples: &[ActivitySample]) -> Vec<ImportedCheckpoint> {
+        let mut samples: Vec<&ActivitySample> = samples.iter().collect();
+        samples.sort_by_key(|sample| sample.timestamp);
+
+        let mut segments: Vec<Segment> = Vec::new();
+        for (i, sample) in samples.iter().enumerate() {
+            // A sample lasts until the next one, unless the user was away in between.
+            let end = samples
+                .get(i + 1)
+                .map(|next| next.timestamp)
+                .filter(|next| next - sample.timestamp <= self.max_gap)
+                .unwrap_or(sample.timestamp);
+            let duration = end - sample.timestamp;
+
+            match segments.last_mut() {
+                Some(segment) if segment.end == sample.timestamp && segment.app == sample.app => {
+                    segment.end = end;
+                    *segment.titles.entry(&sample.title).or_default() += duration;
+                }
+                _ => segments.push(Segment {
+                    start: sample.timestamp,
+                    end,
+                    app: &sample.app,
+                    titles: BTreeMap::from([(sample.title.as_str(), duration)]),
+                }),
+            }
+        }
+
+        let mut merged: Vec<Segment> = Vec::new();
+        for segment in segments {
+            match merged.last_mut() {
+                Some(last)
+                    if last.end == segment.start
+                        && (last.app == segment.app
+                            || segment.end - segment.start < self.min_duration) =>
+                {
+                    last.end = segment.end;
+                    if last.app == segment.app {
+                        for (title, duration) in segment.titles {
+                            *last.titles.entry(title).or_default() += duration;
+                        }
+                    }
+                }
+                _ => merged.push(segment),
+            }
+        }
+
+        merged
+            .into_iter()
+            .filter(|segment| segment.end - segment.start >= self.min_duration)
+            .map(|segment| {
+                let title = segment
+                    .titles
+                    .iter()
+                    .max_by_key(|(_, duration)| **duration)
+                    .map_or("", |(title, _)| title);
+                ImportedCheckpoint {
+                    timestamp: segment.end,
+                    message: title.to_string(),
+                    project: self.projects.get(segment.app).cloned(),
+                    tags: Vec::new(),
+                    start: Some(segment.start),
+                    external_id: None,
+                    kind: None,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Samples are grouped by application, short switches are absorbed and gaps split them.
+    fn summarize_samples() {
+        let sample = |timestamp: i64, app: &str, title: &str| ActivitySample {
+            timestamp,
+            app: app.to_string(),
+            title: title.to_string(),
+        };
+        let mut samples = Vec::new();
+        for minute in 0..20 {
+            samples.push(sample(minute * 60, "code", "main.rs"));
+        }
+        samples[12] = sample(12 * 60, "slack", "general");
+        samples[15].title = "lib.rs".to_string();
+        for minute in 20..30 {
+            samples.push(sample(minute * 60, "firefox", "Docs"));
+        }
+        samples.push(sample(3600, "firefox", "News"));
+
+        let summarizer = ActivitySummarizer {
+            projects: BTreeMap::from([("code".to_string(), "web".to_string())]),
+            ..ActivitySummarizer::default()
+        };
+        let suggested = summarizer.summarize(&samples);
+        assert_eq!(suggested.len(), 2);
+        assert_eq!(suggested[0].message, "main.rs");
+        assert_eq!(suggested[0].project.as_deref(), Some("web"));
+        assert_eq!(
+            (suggested[0].start, suggested[0].timestamp),
+            (Some(0), 20 * 60)
+        );
+        assert_eq!(suggested[1].message, "Docs");
+        assert_eq!(suggested[1].project, None);
+        assert_eq!(suggested[1].timestamp, 29 * 60);
+    }
+}
@@ -0,0 +1,542 @@
+use crate::{
+    CheckpointDb, CheckpointId, CheckpointKind, LogCheckpoint, Operation, ProjectId, Result,
+    TimeRange, TrackedDuration,
+};
+use std::{
+    cmp::{max, Reverse},
+    collections::BTreeMap,
+};
+
+/// How a duration changed from one period to another.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct DurationChange {
+    pub before: i64,
+    pub after: i64,
+    /// `after - before`, in seconds.
+    pub delta: i64,
+    /// The change relative to `before` in percent, or `None` if `before` is zero.
+    pub percentage: Option<f64>,
+}
+
+impl DurationChange {
+    pub fn new(before: i64, after: i64) -> DurationChange {
+        let delta = after - before;
+        DurationChange {
+            before,
+            after,
+            delta,
+            percentage: if before == 0 {
+                None
+            } else {
+                Some(delta as f64 / before as f64 * 100.0)
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct ProjectChange {
+    pub project_id: ProjectId,
+    pub change: DurationChange,
+}
+
+/// The difference in tracked time between two periods, per project and in total.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Comparison {
+    pub range_a: TimeRange,
+    pub range_b: TimeRange,
+    pub projects: Vec<ProjectChange>,
+    pub total: DurationChange,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct ProjectForecast {
+    pub project_id: ProjectId,
+    /// The time tracked so far this month.
+    pub tracked: i64,
+    /// The time expected to be tracked by the end of the month.
+    pub projected: i64,
+    pub budget: Option<i64>,
+    /// Whether the project is on track to exceed its budget.
+    pub over_budget: bool,
+}
+
+/// A projection of the month's totals, assuming work continues at the same pace as in the
+/// elapsed part of the month.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Forecast {
+    pub month: TimeRange,
+    /// The seconds of the month that have passed.
+    pub elapsed: i64,
+    pub projects: Vec<ProjectForecast>,
+    pub tracked: i64,
+    pub projected: i64,
+}
+
+/// A project's share of the total tracked time.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct ProjectShare {
+    pub project_id: ProjectId,
+    pub duration: i64,
+    /// The exact share of the total, between 0 and 1.
+    pub fraction: f64,
+    /// The share in whole percent. The percentages of a breakdown always add up to 100.
+    pub percent: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct ProjectActivity {
+    pub project_id: ProjectId,
+    /// The timestamp of the latest checkpoint on the project.
+    pub last_active: Option<i64>,
+}
+
+/// What is going on right now.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Status {
+    /// The most recent checkpoint.
+    pub latest: LogCheckpoint,
+    /// The seconds since the most recent checkpoint.
+    pub elapsed: i64,
+    /// The project that is probably being worked on, which is assumed to be the project of the
+    /// most recent checkpoint.
+    pub project_id: ProjectId,
+}
+
+/// How often a message was used, and the time tracked with it.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct MessageFrequency {
+    /// The normalized message.
+    pub message: String,
+    pub count: usize,
+    pub duration: i64,
+}
+
+impl CheckpointDb {
+    /// Compares the time tracked in `range_a` with the time tracked in `range_b`, like last
+    /// month with this month. Projects with time in either range are included.
+    pub fn compare(&self, range_a: &TimeRange, range_b: &TimeRange) -> Comparison {
+        let report_a = self.report(range_a, None);
+        let report_b = self.report(range_b, None);
+
+        let mut projects: BTreeMap<ProjectId, (i64, i64)> = BTreeMap::new();
+        for project in &report_a.projects {
+            projects.entry(project.project_id).or_default().0 += project.duration.seconds();
+        }
+        for project in &report_b.projects {
+            projects.entry(project.project_id).or_default().1 += project.duration.seconds();
+        }
+
+        Comparison {
+            range_a: *range_a,
+            range_b: *range_b,
+            projects: projects
+                .into_iter()
+                .map(|(project_id, (before, after))| ProjectChange {
+                    project_id,
+                    change: DurationChange::new(before, after),
+                })
+                .collect(),
+            total: DurationChange::new(report_a.duration.seconds(), report_b.duration.seconds()),
+        }
+    }
+
+    /// Returns every project's share of the time tracked in the given `TimeRange`, largest
+    /// first.
+    pub fn breakdown(&self, range: &TimeRange) -> Vec<ProjectShare> {
+        let report = self.report(range, None);
+        let total = report.duration.seconds();
+        let mut shares: Vec<ProjectShare> = report
+            .projects
+            .iter()
+            .filter(|project| project.duration > TrackedDuration::ZERO)
+            .map(|project| ProjectShare {
+                project_id: project.project_id,
+                duration: project.duration.seconds(),
+                fraction: project.duration.seconds() as f64 / total as f64,
+                percent: (i128::from(project.duration.seconds()) * 100 / i128::from(total)) as u32,
+            })
+            .collect();
+
+        // Hand out the percentage points lost to truncation to the largest remainders, so the
+        // percentages add up to 100.
+        let remainder = |share: &ProjectShare| i128::from(share.duration) * 100 % i128::from(total);
+        let missing = 100 - shares.iter().map(|share| share.percent).sum::<u32>();
+        let mut by_remainder: Vec<usize> = (0..shares.len()).collect();
+        by_remainder.sort_by_key(|&i| Reverse(remainder(&shares[i])));
+        for &i in by_remainder.iter().take(missing as usize) {
+            shares[i].percent += 1;
+        }
+
+        shares.sort_by_key(|share| Reverse(share.duration));
+        shares
+    }
+
+    /// Returns the `n` projects with the most time tracked in the given `TimeRange`, largest
+    /// first.
+    pub fn top_projects(&self, range: &TimeRange, n: usize) -> Vec<ProjectShare> {
+        let mut shares = self.breakdown(range);
+        shares.truncate(n);
+        shares
+    }
+
+    /// Returns the `Status` at the time `now`, or `None` if there are no checkpoints.
+    pub fn current_status(&self, now: i64) -> Option<Status> {
+        let latest = self.get_log(&CheckpointId::Position(0))?;
+        Some(Status {
+            elapsed: now - latest.timestamp.seconds(),
+            project_id: latest.checkpoint.project_id,
+            latest,
+        })
+    }
+
+    /// Returns the messages used in the given `TimeRange`, the most common first. Messages are
+    /// compared case-insensitively and with whitespace collapsed, so "Email" and " email "
+    /// count as the same message.
+    pub fn message_frequencies(&self, range: &TimeRange) -> Vec<MessageFrequency> {
+        let mut messages: BTreeMap<String, (usize, i64)> = BTreeMap::new();
+        for entry in self.entries_in(range) {
+            let (count, duration) = messages
+                .entry(normalize_message(&entry.checkpoint.message))
+                .or_default();
+            *count += 1;
+            *duration += entry.duration;
+        }
+
+        let mut messages: Vec<MessageFrequency> = messages
+            .into_iter()
+            .map(|(message, (count, duration))| MessageFrequency {
+                message,
+                count,
+                duration,
+            })
+            .collect();
+        messages.sort_by_key(|message| Reverse((message.count, message.duration)));
+        messages
+    }
+
+    /// Returns the seconds between the end of the latest break and `now`, or `None` if no break
+    /// has been taken.
+    pub fn time_since_last_break(&self, now: i64) -> Option<i64> {
+        self.checkpoints
+            .iter()
+            .rev()
+            .find(|(_, checkpoint)| checkpoint.kind == CheckpointKind::Break)
+            .map(|(time, _)| now - time)
+    }
+
+    /// Returns the seconds worked without a break up until `now`. The time since the latest
+    /// checkpoint counts as work.
+    pub fn continuous_work_duration(&self, now: i64) -> i64 {
+        let work_start = self
+            .checkpoints
+            .iter()
+            .rev()
+            .find(|(_, checkpoint)| checkpoint.kind == CheckpointKind::Break)
+            .or_else(|| self.checkpoints.iter().next())
+            .map(|(time, _)| *time);
+
+        work_start.map_or(0, |start| max(now - start, 0))
+    }
+
+    /// Returns the timestamp of the latest checkpoint on the given project.
+    pub fn project_last_active(&self, project_id: ProjectId) -> Option<i64> {
+        self.checkpoints
+            .iter()
+            .rev()
+            .find(|(_, checkpoint)| checkpoint.project_id == project_id)
+            .map(|(time, _)| *time)
+    }
+
+    /// Returns all projects, the most recently used first. Projects that have never been used
+    /// come last.
+    pub fn projects_by_activity(&self) -> Vec<ProjectActivity> {
+        let mut last_active: BTreeMap<u16, i64> = BTreeMap::new();
+        for (time, checkpoint) in self.checkpoints.iter().rev() {
+            if let ProjectId::Id(id) = checkpoint.project_id {
+                last_active.entry(id).or_insert(*time);
+            }
+        }
+
+        let mut projects: Vec<ProjectActivity> = self
+            .projects
+            .keys()
+            .map(|id| ProjectActivity {
+                project_id: ProjectId::Id(*id),
+                last_active: last_active.get(id).copied(),
+            })
+            .collect();
+        projects.sort_by_key(|project| Reverse(project.last_active));
+        projects
+    }
+
+    /// Sets the time in seconds that may be spent on a project each month.
+    pub fn set_project_budget(&mut self, project_id: ProjectId, budget: Option<i64>) -> Result<()> {
+        self.apply(Operation::SetProjectBudget { project_id, budget })
+    }
+
+    /// Returns a `Forecast` of the month that `now` is in, in the time zone of the settings.
+    pub fn forecast_month(&self, now: i64) -> Forecast {
+        let month = self.month_range(self.date_of(now));
+        let elapsed = (now - month.start).clamp(0, month.duration());
+        let project = |tracked: i64| {
+            if elapsed == 0 {
+                tracked
+            } else {
+                (i128::from(tracked) * i128::from(month.duration()) / i128::from(elapsed)) as i64
+            }
+        };
+
+        let report = self.report(&TimeRange::new(month.start, now), None);
+        let projects: Vec<ProjectForecast> = report
+            .projects
+            .iter()
+            .map(|total| {
+                let projected = project(total.duration.seconds());
+                let budget = self
+                    .project_from_project_id(total.project_id)
+                    .and_then(|project| project.budget);
+
+                ProjectForecast {
+                    project_id: total.project_id,
+                    tracked: total.duration.seconds(),
+                    projected,
+                    budget,
+                    over_budget: budget.is_some_and(|budget| projected > budget),
+                }
+            })
+            .collect();
+
+        Forecast {
+            month,
+            elapsed,
+            tracked: report.duration.seconds(),
+            projected: project(report.duration.seconds()),
+            projects,
+        }
+    }
+}
+
+/// Lowercases the message and collapses all whitespace into single spaces.
+pub(crate) fn normalize_message(message: &str) -> String {
+    message
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Timestamp;
+    use chrono::prelude::*;
+
+    #[test]
+    /// Compares two periods where time moved from one project to another.
+    fn compare() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let first_id = checkpoint_db.add_project("First", "frs").unwrap();
+        let second_id = checkpoint_db.add_project("Second", "scn").unwrap();
+
+        let start = Utc
+            .with_ymd_and_hms(2021, 6, 15, 12, 0, 0)
+            .unwrap()
+            .timestamp();
+        for (minutes, project_id) in [(0, first_id), (40, first_id), (60, first_id)] {
+            checkpoint_db
+                .add_checkpoint(start + minutes * 60, "Work", project_id)
+                .unwrap();
+        }
+        for (minutes, project_id) in [(90, first_id), (120, second_id)] {
+            checkpoint_db
+                .add_checkpoint(start + minutes * 60, "Work", project_id)
+                .unwrap();
+        }
+
+        let comparison = checkpoint_db.compare(
+            &TimeRange::new(start, start + 3000),
+            &TimeRange::new(start + 3000, start + 8000),
+        );
+        assert_eq!(comparison.total, DurationChange::new(2400, 4800));
+        assert_eq!(comparison.total.percentage, Some(100.0));
+        assert_eq!(
+            comparison.projects,
+            vec![
+                ProjectChange {
+                    project_id: first_id,
+                    change: DurationChange::new(2400, 3000),
+                },
+                ProjectChange {
+                    project_id: second_id,
+                    change: DurationChange::new(0, 1800),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    /// Thirds are rounded so the percentages still add up to 100.
+    fn breakdown() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let mut time = Utc
+            .with_ymd_and_hms(2021, 6, 15, 12, 0, 0)
+            .unwrap()
+            .timestamp();
+        checkpoint_db
+            .add_checkpoint(time, "Start", ProjectId::NoId)
+            .unwrap();
+        for short_name in ["frs", "scn", "thr"] {
+            let project_id = checkpoint_db.add_project(short_name, short_name).unwrap();
+            time += 600;
+            checkpoint_db
+                .add_checkpoint(time, "Work", project_id)
+                .unwrap();
+        }
+
+        let range = TimeRange::new(time - 3600, time + 1);
+        let breakdown = checkpoint_db.breakdown(&range);
+        let percents: Vec<u32> = breakdown.iter().map(|share| share.percent).collect();
+        assert_eq!(percents, vec![34, 33, 33]);
+        assert_eq!(checkpoint_db.top_projects(&range, 2).len(), 2);
+    }
+
+    #[test]
+    fn message_frequencies() {
+        let mut checkpoint_db = CheckpointDb::new();
+        checkpoint_db
+            .add_checkpoint(0, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(600, "Email", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(4200, "Coding", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(4500, " email ", ProjectId::NoId)
+            .unwrap();
+
+        let frequencies = checkpoint_db.message_frequencies(&TimeRange::new(1, 5000));
+        assert_eq!(
+            frequencies[0],
+            MessageFrequency {
+                message: "email".to_string(),
+                count: 2,
+                duration: 900,
+            }
+        );
+        assert_eq!(frequencies[1].message, "coding");
+    }
+
+    #[test]
+    /// Only the time after the latest break counts as continuous work.
+    fn breaks() {
+        let mut checkpoint_db = CheckpointDb::new();
+        assert_eq!(checkpoint_db.continuous_work_duration(1000), 0);
+
+        checkpoint_db
+            .add_checkpoint(100, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(400, "Work", ProjectId::NoId)
+            .unwrap();
+        assert_eq!(checkpoint_db.time_since_last_break(1000), None);
+        assert_eq!(checkpoint_db.continuous_work_duration(1000), 900);
+
+        checkpoint_db
+            .add_checkpoint(500, "Lunch", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .set_checkpoint_kind(CheckpointId::Timestamp(500), CheckpointKind::Break)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(700, "Work", ProjectId::NoId)
+            .unwrap();
+        assert_eq!(checkpoint_db.time_since_last_break(1000), Some(500));
+        assert_eq!(checkpoint_db.continuous_work_duration(1000), 500);
+    }
+
+    #[test]
+    fn current_status() {
+        let mut checkpoint_db = CheckpointDb::new();
+        assert!(checkpoint_db.current_status(1000).is_none());
+
+        let project_id = checkpoint_db.add_project("Project", "prj").unwrap();
+        checkpoint_db
+            .add_checkpoint(100, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(400, "Work", project_id)
+            .unwrap();
+
+        let status = checkpoint_db.current_status(1000).unwrap();
+        assert_eq!(status.latest.timestamp, Timestamp(400));
+        assert_eq!(status.latest.duration, Some(TrackedDuration(300)));
+        assert_eq!(status.elapsed, 600);
+        assert_eq!(status.project_id, project_id);
+    }
+
+    #[test]
+    /// The most recently used project comes first and unused projects come last.
+    fn projects_by_activity() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let first_id = checkpoint_db.add_project("First", "frs").unwrap();
+        let second_id = checkpoint_db.add_project("Second", "scn").unwrap();
+        let unused_id = checkpoint_db.add_project("Unused", "unu").unwrap();
+        checkpoint_db
+            .add_checkpoint(100, "Work", second_id)
+            .unwrap();
+        checkpoint_db.add_checkpoint(200, "Work", first_id).unwrap();
+        checkpoint_db
+            .add_checkpoint(300, "Work", ProjectId::NoId)
+            .unwrap();
+
+        assert_eq!(checkpoint_db.project_last_active(first_id), Some(200));
+        assert_eq!(checkpoint_db.project_last_active(unused_id), None);
+        let order: Vec<ProjectId> = checkpoint_db
+            .projects_by_activity()
+            .iter()
+            .map(|project| project.project_id)
+            .collect();
+        assert_eq!(order, vec![first_id, second_id, unused_id]);
+    }
+
+    #[test]
+    /// Ten days into a 30 day month, the tracked time is projected to triple.
+    fn forecast_month() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Project", "prj").unwrap();
+        checkpoint_db
+            .set_project_budget(project_id, Some(40 * 3600))
+            .unwrap();
+
+        let month = TimeRange::month(NaiveDate::from_ymd_opt(2021, 6, 1).unwrap());
+        checkpoint_db
+            .add_checkpoint(month.start + 3600, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(month.start + 3600 * 15, "Work", project_id)
+            .unwrap();
+
+        let forecast = checkpoint_db.forecast_month(month.start + month.duration() / 3);
+        assert_eq!(forecast.tracked, 14 * 3600);
+        assert_eq!(forecast.projected, 42 * 3600);
+        let project = forecast
+            .projects
+            .iter()
+            .find(|project| project.project_id == project_id)
+            .unwrap();
+        assert_eq!(project.projected, 42 * 3600);
+        assert!(project.over_budget);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{CheckpointDb, Result};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// Hands out pseudonyms, giving the same text the same pseudonym every time.
+struct Pseudonyms {
+    prefix: &'static str,
+    assigned: BTreeMap<String, String>,
+}
+
+impl Pseudonyms {
+    fn new(prefix: &'static str) -> Pseudonyms {
+        Pseudonyms {
+            prefix,
+            assigned: BTreeMap::new(),
+        }
+    }
+
+    /// Replaces the string in a value with its pseudonym. Empty strings stay empty.
+    fn replace(&mut self, value: &mut Value) {
+        let Some(text) = value.as_str().filter(|text| !text.is_empty()) else {
+            return;
+        };
+        let next = self.assigned.len() + 1;
+        let pseudonym = self
+            .assigned
+            .entry(text.to_string())
+            .or_insert_with(|| format!("{} {}", self.prefix, next));
+        *value = Value::String(pseudonym.clone());
+    }
+}
+
+impl CheckpointDb {
+    /// Writes the database like `write` does, but with messages, tags, expense notes and the
+    /// names of projects and clients replaced by pseudonyms and receipts left out, so that it
+    /// can be shared without leaking what the time was spent on. Everything else, including
+    /// durations and which checkpoints share messages, is kept as it is.
+    ///
+    /// Projects and clients are named after their ids, like "Project 2", and messages and tags
+    /// are numbered in the order they first appear in, like "Message 1".
+    pub fn export_anonymized<W: Write>(&self, writer: W) -> Result<()> {
+        let mut value = serde_json::to_value(self)?;
+        // The indexes have the words of every message.
+        if let Some(fields) = value.as_object_mut() {
+            fields.remove("indexes");
+        }
+
+        if let Some(projects) = value.get_mut("projects").and_then(Value::as_object_mut) {
+            for (id, project) in projects {
+                project["long_name"] = Value::String(format!("Project {}", id));
+                project["short_name"] = Value::String(format!("p{}", id));
+            }
+        }
+        if let Some(clients) = value.get_mut("clients").and_then(Value::as_object_mut) {
+            for (id, client) in clients {
+                client["name"] = Value::String(format!("Client {}", id));
+            }
+        }
+
+        let mut messages = Pseudonyms::new("Message");
+        let mut tags = Pseudonyms::new("tag");
+        // The map is sorted by the keys as strings rather than as timestamps, so go through
+        // the checkpoints in the database's order to number messages chronologically.
+        if let Some(checkpoints) = value.get_mut("checkpoints").and_then(Value::as_object_mut) {
+            for timestamp in self.checkpoints.keys() {
+                let Some(checkpoint) = checkpoints.get_mut(&timestamp.to_string()) else {
+                    continue;
+                };
+                if let Some(message) = checkpoint.get_mut("message") {
+                    messages.replace(message);
+                }
+                if let Some(checkpoint_tags) =
+                    checkpoint.get_mut("tags").and_then(Value::as_array_mut)
+                {
+                    checkpoint_tags.iter_mut().for_each(|tag| tags.replace(tag));
+                }
+            }
+        }
+
+        // The paths of receipts may name people or places.
+        let mut notes = Pseudonyms::new("Expense");
+        if let Some(expenses) = value.get_mut("expenses").and_then(Value::as_object_mut) {
+            for expense in expenses.values_mut() {
+                if let Some(note) = expense.get_mut("note") {
+                    notes.replace(note);
+                }
+                if let Some(expense) = expense.as_object_mut() {
+                    expense.remove("receipt");
+                }
+            }
+        }
+
+        serde_json::to_writer_pretty(writer, &value)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckpointId, ProjectId};
+
+    #[test]
+    /// Repeated messages get the same pseudonym, and durations stay the same.
+    fn export_anonymized() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Acme website", "acme").unwrap();
+        checkpoint_db
+            .add_checkpoint(900, "Call with Jane", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db.add_checkpoint(1000, "", project_id).unwrap();
+        checkpoint_db
+            .add_checkpoint(2000, "Call with Jane", project_id)
+            .unwrap();
+        checkpoint_db
+            .set_checkpoint_tags(CheckpointId::Timestamp(2000), vec!["jane".to_string()])
+            .unwrap();
+
+        let mut output = Vec::new();
+        checkpoint_db.export_anonymized(&mut output).unwrap();
+        let anonymized: CheckpointDb = serde_json::from_slice(&output).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(!output.contains("Jane") && !output.contains("acme") && !output.contains("jane"));
+        assert_eq!(anonymized.projects[&0].short_name, "p0");
+        assert_eq!(&*anonymized.checkpoints[&900].message, "Message 1");
+        assert_eq!(&*anonymized.checkpoints[&1000].message, "");
+        assert_eq!(&*anonymized.checkpoints[&2000].message, "Message 1");
+        assert_eq!(anonymized.checkpoints[&2000].tags, vec!["tag 1"]);
+        assert_eq!(
+            anonymized.get_checkpoint_duration(&CheckpointId::Timestamp(2000)),
+            checkpoint_db.get_checkpoint_duration(&CheckpointId::Timestamp(2000))
+        );
+    }
+}
@@ -0,0 +1,111 @@
+use crate::{options, CheckpointDb, CheckpointDbError, Result};
+use std::{
+    future::Future,
+    io,
+    path::{Path, PathBuf},
+};
+
+impl CheckpointDb {
+    /// Reads the database at the path like `read`, along with its delta file, without blocking
+    /// the executor on the files. Like when opening, operations in the delta file that can't be
+    /// applied are skipped.
+    pub async fn read_async<P: AsRef<Path>>(path: P) -> Result<CheckpointDb> {
+        let path = path.as_ref();
+        let mut checkpoint_db = match tokio::fs::read(path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let checkpoint_db = CheckpointDb::new();
+                checkpoint_db.write_async(path).await?;
+                return Ok(checkpoint_db);
+            }
+            Err(e) => return Err(e.into()),
+        };
+        match tokio::fs::read(options::delta_path(path)).await {
+            Ok(delta) => {
+                options::apply_delta(&mut checkpoint_db, &delta);
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+            Err(e) => return Err(e.into()),
+        }
+        Ok(checkpoint_db)
+    }
+
+    /// Writes the database to the path like `write`, without blocking the executor on the file.
+    ///
+    /// The database is serialized when this is called rather than when the future is awaited,
+    /// and the future doesn't borrow it. A database behind a lock only has to be locked for the
+    /// call, and the lock can be released before awaiting the write:
+    ///
+    /// ```ignore
+    /// let write = checkpoint_db.lock().await.write_async(path);
+    /// write.await?;
+    /// ```
+    ///
+    /// The file is written to a temporary file next to it first and then renamed, so readers
+    /// never see a partially written database, and the delta file is removed.
+    pub fn write_async<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> impl Future<Output = Result<()>> + Send + 'static {
+        let path = path.as_ref().to_path_buf();
+        let json = serde_json::to_vec_pretty(self);
+        async move { write_file(path, json?).await }
+    }
+}
+
+async fn write_file(path: PathBuf, json: Vec<u8>) -> Result<()> {
+    let Some(file_name) = path.file_name() else {
+        return Err(CheckpointDbError::InvalidInput(format!(
+            "{} is not a path to a file",
+            path.display()
+        )));
+    };
+    if let Some(write_dir) = path.parent() {
+        tokio::fs::create_dir_all(write_dir).await?;
+    }
+
+    let mut temporary_name = file_name.to_owned();
+    temporary_name.push(".tmp");
+    let temporary_path = path.with_file_name(temporary_name);
+    tokio::fs::write(&temporary_path, json).await?;
+    tokio::fs::rename(&temporary_path, &path).await?;
+    match tokio::fs::remove_file(options::delta_path(&path)).await {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProjectId;
+
+    #[test]
+    /// Missing files are created, and writes save the database as it was when they were
+    /// started.
+    fn write_read_async() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let path = Path::new("test_files/async_io/db.json");
+        let _ = std::fs::remove_dir_all("test_files/async_io");
+
+        runtime.block_on(async {
+            let mut checkpoint_db = CheckpointDb::read_async(path).await.unwrap();
+            assert!(checkpoint_db.is_empty());
+
+            checkpoint_db
+                .add_checkpoint(1000, "Async", ProjectId::NoId)
+                .unwrap();
+            let written = checkpoint_db.clone();
+            let write = checkpoint_db.write_async(path);
+            checkpoint_db
+                .add_checkpoint(2000, "Not written", ProjectId::NoId)
+                .unwrap();
+            write.await.unwrap();
+
+            assert_eq!(CheckpointDb::read_async(path).await.unwrap(), written);
+            assert_eq!(CheckpointDb::read(path).unwrap(), written);
+        });
+    }
+}
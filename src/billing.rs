@@ -0,0 +1,781 @@
+use crate::{
+    report::Entry, CheckpointDb, CheckpointDbError, CheckpointId, ClientId, Operation, ProjectId,
+    Result, RoundingPolicy, RoundingScope, TimeRange,
+};
+use chrono::prelude::*;
+use std::{
+    cmp::{max, Reverse},
+    collections::{BTreeMap, BTreeSet},
+    ops::AddAssign,
+};
+
+/// The tracked and billable time of one project on one day.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct BillableTime {
+    pub date: NaiveDate,
+    pub project_id: ProjectId,
+    /// The time actually tracked, in seconds.
+    pub tracked: i64,
+    /// The time to bill after rounding and project minimums, in seconds.
+    pub billable: i64,
+}
+
+/// Billable time in a `TimeRange`, with one line per project and day.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct BillingReport {
+    pub range: TimeRange,
+    pub lines: Vec<BillableTime>,
+    pub tracked: i64,
+    pub billable: i64,
+}
+
+/// Durations in seconds and the amount earned from them.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Earnings {
+    pub tracked: i64,
+    pub billable: i64,
+    /// The earned amount in the smallest unit of the currency.
+    pub amount: i64,
+    /// The part of the amount that is paid on top of the base amount because of the
+    /// `PayRule`s of the database.
+    pub premium: i64,
+}
+
+impl Earnings {
+    /// Returns the amount earned at the plain hourly rates, without premiums.
+    pub fn base(&self) -> i64 {
+        self.amount - self.premium
+    }
+}
+
+impl AddAssign for Earnings {
+    fn add_assign(&mut self, other: Earnings) {
+        self.tracked += other.tracked;
+        self.billable += other.billable;
+        self.amount += other.amount;
+        self.premium += other.premium;
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct ProjectEarnings {
+    pub project_id: ProjectId,
+    pub client_id: Option<ClientId>,
+    /// The hourly rate of the project. Time on checkpoints with their own rate is earned at
+    /// that rate instead.
+    pub rate: Option<i64>,
+    pub currency: Option<String>,
+    pub earnings: Earnings,
+}
+
+/// The earnings from a client's projects in one currency.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct ClientEarnings {
+    pub client_id: Option<ClientId>,
+    pub currency: Option<String>,
+    pub earnings: Earnings,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct CurrencyEarnings {
+    pub currency: Option<String>,
+    pub earnings: Earnings,
+}
+
+/// Earnings in a `TimeRange` per project, per client and in total. Amounts in different
+/// currencies are never added together, so clients and totals are split up per currency.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct EarningsReport {
+    pub range: TimeRange,
+    pub projects: Vec<ProjectEarnings>,
+    pub clients: Vec<ClientEarnings>,
+    pub totals: Vec<CurrencyEarnings>,
+}
+
+impl EarningsReport {
+    /// Returns the total amount converted to the given currency, or `None` if the
+    /// `ExchangeRates` lack a rate that is needed.
+    pub fn total_in(&self, currency: &str, exchange_rates: &ExchangeRates) -> Option<i64> {
+        self.totals
+            .iter()
+            .map(|total| {
+                exchange_rates.convert(total.earnings.amount, total.currency.as_deref()?, currency)
+            })
+            .sum()
+    }
+}
+
+/// The time tracked with one message.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct TaskTotal {
+    pub message: String,
+    pub duration: i64,
+}
+
+/// Time and earnings of all projects of a client in a `TimeRange`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct ClientReport {
+    pub client_id: ClientId,
+    pub range: TimeRange,
+    pub projects: Vec<ProjectEarnings>,
+    pub totals: Vec<CurrencyEarnings>,
+    /// The tasks the most time was spent on, longest first.
+    pub top_tasks: Vec<TaskTotal>,
+    /// The billable time of all time that has not been invoiced yet, per currency.
+    pub uninvoiced: Vec<CurrencyEarnings>,
+}
+
+/// A user-supplied table of conversion rates between currencies.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExchangeRates {
+    rates: BTreeMap<(String, String), f64>,
+}
+
+impl ExchangeRates {
+    pub fn new() -> ExchangeRates {
+        ExchangeRates::default()
+    }
+
+    /// Sets how many units of `to` one unit of `from` is worth. Fails with
+    /// `CheckpointDbError::InvalidInput` if the rate isn't a positive number.
+    pub fn insert(&mut self, from: &str, to: &str, rate: f64) -> Result<()> {
+        if !(rate.is_finite() && rate > 0.0) {
+            return Err(CheckpointDbError::InvalidInput(format!(
+                "{} is not an exchange rate from {} to {}",
+                rate, from, to
+            )));
+        }
+        self.rates.insert((from.to_string(), to.to_string()), rate);
+        Ok(())
+    }
+
+    /// Converts an amount from one currency to another, using the inverse of the opposite rate
+    /// if there is no direct one.
+    pub fn convert(&self, amount: i64, from: &str, to: &str) -> Option<i64> {
+        if from == to {
+            return Some(amount);
+        }
+
+        let rate = match self.rates.get(&(from.to_string(), to.to_string())) {
+            Some(rate) => *rate,
+            None => 1.0 / self.rates.get(&(to.to_string(), from.to_string()))?,
+        };
+        Some((amount as f64 * rate).round() as i64)
+    }
+}
+
+impl CheckpointDb {
+    /// Returns a `BillingReport` for the given `TimeRange`. Durations are rounded according to
+    /// the given `RoundingPolicy`, and each project's minimum billable duration is applied at
+    /// the same scope as the rounding: to every entry, or to every project and day.
+    pub fn billing_report(
+        &self,
+        range: &TimeRange,
+        rounding: Option<&RoundingPolicy>,
+    ) -> BillingReport {
+        self.billing_report_of(self.entries_in(range), range, rounding)
+    }
+
+    /// Returns a `BillingReport` of the time on billable projects in the given `TimeRange` that
+    /// has not been invoiced yet.
+    pub fn uninvoiced_report(
+        &self,
+        range: &TimeRange,
+        rounding: Option<&RoundingPolicy>,
+    ) -> BillingReport {
+        let entries = self.entries_in(range).filter(move |entry| {
+            entry.checkpoint.invoice.is_none()
+                && self
+                    .project_from_project_id(entry.checkpoint.project_id)
+                    .is_some_and(|project| project.billable)
+        });
+        self.billing_report_of(entries, range, rounding)
+    }
+
+    /// Marks the `Checkpoint` identified by the given `CheckpointId` as billed on the given
+    /// invoice. Fails if the checkpoint has already been invoiced.
+    pub fn mark_invoiced(&mut self, checkpoint_id: &CheckpointId, invoice: &str) -> Result<()> {
+        let timestamp = self.resolve_checkpoint(checkpoint_id)?;
+        self.apply(Operation::MarkInvoiced {
+            timestamp,
+            invoice: invoice.to_string(),
+        })
+    }
+
+    /// Sets the hourly rate of a checkpoint, which overrides the rate of its project, or removes
+    /// it with `None`.
+    pub fn set_checkpoint_rate(
+        &mut self,
+        checkpoint_id: &CheckpointId,
+        rate: Option<i64>,
+    ) -> Result<()> {
+        let timestamp = self.resolve_checkpoint(checkpoint_id)?;
+        self.apply(Operation::SetCheckpointRate { timestamp, rate })
+    }
+
+    /// Marks all checkpoints billed on the given invoice as paid, and returns how many there
+    /// were.
+    pub fn mark_invoice_paid(&mut self, invoice: &str) -> Result<usize> {
+        let count = self
+            .checkpoints
+            .values()
+            .filter(|checkpoint| checkpoint.invoice.as_deref() == Some(invoice))
+            .count();
+        self.apply(Operation::MarkInvoicePaid {
+            invoice: invoice.to_string(),
+        })?;
+        Ok(count)
+    }
+
+    fn billing_report_of<'a, I>(
+        &self,
+        entries: I,
+        range: &TimeRange,
+        rounding: Option<&RoundingPolicy>,
+    ) -> BillingReport
+    where
+        I: Iterator<Item = Entry<'a>> + Clone,
+    {
+        let scope = rounding.map_or(RoundingScope::Entry, |rounding| rounding.scope);
+        let tracked = self.project_days(entries.clone(), scope, |_, duration| duration);
+        let billable = self.project_days(entries, scope, |project_id, duration| {
+            self.billable_duration(project_id, duration, rounding)
+        });
+
+        let lines: Vec<BillableTime> = tracked
+            .into_iter()
+            .zip(billable.into_values())
+            .map(|(((date, project_id), tracked), billable)| BillableTime {
+                date,
+                project_id,
+                tracked,
+                billable,
+            })
+            .collect();
+
+        BillingReport {
+            range: *range,
+            tracked: lines.iter().map(|line| line.tracked).sum(),
+            billable: lines.iter().map(|line| line.billable).sum(),
+            lines,
+        }
+    }
+
+    /// Returns an `EarningsReport` for the given `TimeRange`. Only time on billable projects
+    /// counts as billable, and the amounts are calculated from the billable time and the
+    /// projects' rates.
+    pub fn earnings_report(
+        &self,
+        range: &TimeRange,
+        rounding: Option<&RoundingPolicy>,
+    ) -> EarningsReport {
+        self.earnings_report_of(self.entries_in(range), range, rounding)
+    }
+
+    /// Returns a `ClientReport` for all of the given client's projects in the given
+    /// `TimeRange`.
+    pub fn client_report(
+        &self,
+        client_id: ClientId,
+        range: &TimeRange,
+        rounding: Option<&RoundingPolicy>,
+    ) -> Result<ClientReport> {
+        if self.client_from_client_id(client_id).is_none() {
+            return Err(CheckpointDbError::ClientNotFound { id: client_id });
+        }
+
+        let is_client_project = |project_id: ProjectId| {
+            self.project_from_project_id(project_id)
+                .is_some_and(|project| project.client_id == Some(client_id))
+        };
+        let entries = self
+            .entries_in(range)
+            .filter(move |entry| is_client_project(entry.checkpoint.project_id));
+        let report = self.earnings_report_of(entries.clone(), range, rounding);
+
+        let mut tasks: BTreeMap<&str, i64> = BTreeMap::new();
+        for entry in entries {
+            *tasks.entry(entry.checkpoint.message.trim()).or_insert(0) += entry.duration;
+        }
+        let mut top_tasks: Vec<TaskTotal> = tasks
+            .into_iter()
+            .map(|(message, duration)| TaskTotal {
+                message: message.to_string(),
+                duration,
+            })
+            .collect();
+        top_tasks.sort_by_key(|task| Reverse(task.duration));
+
+        let all_time = TimeRange::new(i64::MIN, i64::MAX);
+        let uninvoiced = self.entries_in(&all_time).filter(move |entry| {
+            entry.checkpoint.invoice.is_none() && is_client_project(entry.checkpoint.project_id)
+        });
+
+        Ok(ClientReport {
+            client_id,
+            range: *range,
+            projects: report.projects,
+            totals: report.totals,
+            top_tasks,
+            uninvoiced: self
+                .earnings_report_of(uninvoiced, &all_time, rounding)
+                .totals,
+        })
+    }
+
+    fn earnings_report_of<'a, I>(
+        &self,
+        entries: I,
+        range: &TimeRange,
+        rounding: Option<&RoundingPolicy>,
+    ) -> EarningsReport
+    where
+        I: Iterator<Item = Entry<'a>> + Clone,
+    {
+        // Time at different rates is billed apart, as if it were on different projects.
+        let mut lines = Vec::new();
+        let checkpoint_rates: BTreeSet<Option<i64>> =
+            entries.clone().map(|entry| entry.checkpoint.rate).collect();
+        for checkpoint_rate in checkpoint_rates {
+            let entries = entries
+                .clone()
+                .filter(move |entry| entry.checkpoint.rate == checkpoint_rate);
+            for mut line in self.billing_report_of(entries, range, rounding).lines {
+                let project = self.project_from_project_id(line.project_id);
+                if !project.is_some_and(|project| project.billable) {
+                    line.billable = 0;
+                }
+                let rate = checkpoint_rate.or(project.and_then(|project| project.rate));
+                lines.push((line, rate));
+            }
+        }
+
+        let mut day_billable: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+        for (line, _) in &lines {
+            *day_billable.entry(line.date).or_default() += line.billable;
+        }
+
+        let mut project_rates: BTreeMap<(ProjectId, Option<i64>), (Earnings, f64)> =
+            BTreeMap::new();
+        for (line, rate) in lines {
+            let (earnings, premium) = project_rates.entry((line.project_id, rate)).or_default();
+            *earnings += Earnings {
+                tracked: line.tracked,
+                billable: line.billable,
+                ..Earnings::default()
+            };
+            if let Some(rate) = rate {
+                *premium += self.premium(rate, line.date, line.billable, day_billable[&line.date]);
+            }
+        }
+
+        let mut projects: BTreeMap<ProjectId, Earnings> = BTreeMap::new();
+        for ((project_id, rate), (mut earnings, premium)) in project_rates {
+            earnings.premium = premium.round() as i64;
+            earnings.amount =
+                rate.map_or(0, |rate| amount(rate, earnings.billable)) + earnings.premium;
+            *projects.entry(project_id).or_default() += earnings;
+        }
+
+        let projects: Vec<ProjectEarnings> = projects
+            .into_iter()
+            .map(|(project_id, earnings)| {
+                let project = self.project_from_project_id(project_id);
+                let rate = project.and_then(|project| project.rate);
+
+                ProjectEarnings {
+                    project_id,
+                    client_id: project.and_then(|project| project.client_id),
+                    rate,
+                    currency: self.project_currency(project_id).map(str::to_string),
+                    earnings,
+                }
+            })
+            .collect();
+
+        let mut clients: BTreeMap<(Option<ClientId>, Option<String>), Earnings> = BTreeMap::new();
+        let mut totals: BTreeMap<Option<String>, Earnings> = BTreeMap::new();
+        for project in &projects {
+            let currency = project.currency.clone();
+            *clients
+                .entry((project.client_id, currency.clone()))
+                .or_default() += project.earnings;
+            *totals.entry(currency).or_default() += project.earnings;
+        }
+
+        EarningsReport {
+            range: *range,
+            projects,
+            clients: clients
+                .into_iter()
+                .map(|((client_id, currency), earnings)| ClientEarnings {
+                    client_id,
+                    currency,
+                    earnings,
+                })
+                .collect(),
+            totals: totals
+                .into_iter()
+                .map(|(currency, earnings)| CurrencyEarnings { currency, earnings })
+                .collect(),
+        }
+    }
+
+    /// Returns the currency that project rates are given in, like "EUR".
+    pub fn currency(&self) -> Option<&str> {
+        self.currency.as_deref()
+    }
+
+    pub fn set_currency(&mut self, currency: Option<&str>) -> Result<()> {
+        self.apply(Operation::SetCurrency {
+            currency: currency.map(str::to_string),
+        })
+    }
+
+    /// Sets the hourly rate of a project in the smallest unit of its currency, or removes it
+    /// with `None`.
+    pub fn set_project_rate(&mut self, project_id: ProjectId, rate: Option<i64>) -> Result<()> {
+        self.apply(Operation::SetProjectRate { project_id, rate })
+    }
+
+    /// Sets the shortest duration in seconds billed for any piece of work on a project.
+    pub fn set_project_minimum_billable(
+        &mut self,
+        project_id: ProjectId,
+        minimum_billable: Option<i64>,
+    ) -> Result<()> {
+        self.apply(Operation::SetProjectMinimumBillable {
+            project_id,
+            minimum_billable,
+        })
+    }
+
+    pub fn set_project_billable(&mut self, project_id: ProjectId, billable: bool) -> Result<()> {
+        self.apply(Operation::SetProjectBillable {
+            project_id,
+            billable,
+        })
+    }
+
+    /// Sets the currency of a project's rate, or makes it use the database's currency with
+    /// `None`.
+    pub fn set_project_currency(
+        &mut self,
+        project_id: ProjectId,
+        currency: Option<&str>,
+    ) -> Result<()> {
+        self.apply(Operation::SetProjectCurrency {
+            project_id,
+            currency: currency.map(str::to_string),
+        })
+    }
+
+    /// Returns the currency of the given project's rate, which is the currency of the database
+    /// unless the project has its own.
+    pub fn project_currency(&self, project_id: ProjectId) -> Option<&str> {
+        self.project_from_project_id(project_id)
+            .and_then(|project| project.currency.as_deref())
+            .or(self.currency.as_deref())
+    }
+
+    /// Applies rounding and the minimum billable duration of the given project to a duration.
+    pub(crate) fn billable_duration(
+        &self,
+        project_id: ProjectId,
+        duration: i64,
+        rounding: Option<&RoundingPolicy>,
+    ) -> i64 {
+        let rounded = rounding.map_or(duration, |rounding| rounding.round(duration));
+        let minimum = self
+            .project_from_project_id(project_id)
+            .and_then(|project| project.minimum_billable);
+
+        match minimum {
+            Some(minimum) if duration > 0 => max(rounded, minimum),
+            _ => rounded,
+        }
+    }
+}
+
+/// Returns the amount earned in the given number of seconds at the given hourly rate, rounded to
+/// the nearest unit.
+pub(crate) fn amount(rate: i64, seconds: i64) -> i64 {
+    let amount = i128::from(rate) * i128::from(seconds);
+    ((amount + amount.signum() * 1800) / 3600) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Checkpoint, Invoice, RoundingMode};
+
+    #[test]
+    /// Short entries on a project with a minimum billable duration are billed at the minimum,
+    /// while the tracked time stays untouched.
+    fn minimum_billable() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Project", "prj").unwrap();
+        checkpoint_db
+            .set_project_minimum_billable(project_id, Some(15 * 60))
+            .unwrap();
+
+        let start = Utc
+            .with_ymd_and_hms(2021, 6, 15, 12, 0, 0)
+            .unwrap()
+            .timestamp();
+        for minutes in [0, 5, 35] {
+            checkpoint_db
+                .add_checkpoint(start + minutes * 60, "Work", project_id)
+                .unwrap();
+        }
+
+        let range = TimeRange::new(start, start + 3600);
+        let report = checkpoint_db.billing_report(&range, None);
+        assert_eq!(report.tracked, 35 * 60);
+        assert_eq!(report.billable, 45 * 60);
+
+        let rounding = RoundingPolicy::new(RoundingMode::Up, 30, RoundingScope::ProjectDay);
+        let report = checkpoint_db.billing_report(&range, Some(&rounding));
+        assert_eq!(report.tracked, 35 * 60);
+        assert_eq!(report.billable, 60 * 60);
+    }
+
+    #[test]
+    /// Earnings are summed per project and client, and non-billable projects earn nothing.
+    fn earnings() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let client_id = checkpoint_db.add_client("Client").unwrap();
+        let billed_id = checkpoint_db.add_project("Billed", "bld").unwrap();
+        let internal_id = checkpoint_db.add_project("Internal", "int").unwrap();
+        checkpoint_db
+            .set_project_client(billed_id, Some(client_id))
+            .unwrap();
+        checkpoint_db
+            .set_project_rate(billed_id, Some(6000))
+            .unwrap();
+        checkpoint_db
+            .set_project_rate(internal_id, Some(6000))
+            .unwrap();
+        checkpoint_db
+            .set_project_billable(internal_id, false)
+            .unwrap();
+
+        let start = Utc
+            .with_ymd_and_hms(2021, 6, 15, 12, 0, 0)
+            .unwrap()
+            .timestamp();
+        checkpoint_db
+            .add_checkpoint(start, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 90 * 60, "Work", billed_id)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 120 * 60, "Meeting", internal_id)
+            .unwrap();
+
+        let range = TimeRange::new(start, start + 3 * 3600);
+        let report = checkpoint_db.earnings_report(&range, None);
+        assert_eq!(report.totals.len(), 1);
+        assert_eq!(report.totals[0].earnings.tracked, 120 * 60);
+        assert_eq!(report.totals[0].earnings.billable, 90 * 60);
+        assert_eq!(report.totals[0].earnings.amount, 9000);
+        assert_eq!(
+            report.clients,
+            vec![
+                ClientEarnings {
+                    client_id: None,
+                    currency: None,
+                    earnings: Earnings {
+                        tracked: 30 * 60,
+                        billable: 0,
+                        amount: 0,
+                        premium: 0,
+                    },
+                },
+                ClientEarnings {
+                    client_id: Some(client_id),
+                    currency: None,
+                    earnings: Earnings {
+                        tracked: 90 * 60,
+                        billable: 90 * 60,
+                        amount: 9000,
+                        premium: 0,
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    /// Checkpoints with their own rate are earned and invoiced at it instead of the project's.
+    fn rate_override() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let client_id = checkpoint_db.add_client("Client").unwrap();
+        let project_id = checkpoint_db.add_project("Project", "prj").unwrap();
+        checkpoint_db
+            .set_project_client(project_id, Some(client_id))
+            .unwrap();
+        checkpoint_db
+            .set_project_rate(project_id, Some(6000))
+            .unwrap();
+
+        let start = Utc
+            .with_ymd_and_hms(2021, 6, 15, 12, 0, 0)
+            .unwrap()
+            .timestamp();
+        checkpoint_db
+            .add_checkpoint(start, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 3600, "Work", project_id)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint_full(
+                start + 5400,
+                Checkpoint::builder()
+                    .message("Urgent fix")
+                    .project(project_id)
+                    .rate(12000),
+            )
+            .unwrap();
+
+        let range = TimeRange::new(start + 1, start + 3 * 3600);
+        let report = checkpoint_db.earnings_report(&range, None);
+        assert_eq!(report.projects[0].rate, Some(6000));
+        assert_eq!(report.projects[0].earnings.billable, 5400);
+        assert_eq!(report.projects[0].earnings.amount, 6000 + 6000);
+
+        let invoice = Invoice::builder(client_id, range)
+            .preview(&checkpoint_db)
+            .unwrap();
+        let rates: Vec<_> = invoice.lines.iter().map(|line| line.rate).collect();
+        assert_eq!(rates, [Some(6000), Some(12000)]);
+        assert_eq!(invoice.subtotal, 12000);
+
+        checkpoint_db
+            .set_checkpoint_rate(&CheckpointId::Timestamp(start + 5400), None)
+            .unwrap();
+        let report = checkpoint_db.earnings_report(&range, None);
+        assert_eq!(report.projects[0].earnings.amount, 9000);
+    }
+
+    #[test]
+    /// Amounts in different currencies are kept apart, and can be added up with exchange rates.
+    fn currencies() {
+        let mut checkpoint_db = CheckpointDb::new();
+        checkpoint_db.set_currency(Some("EUR")).unwrap();
+        let euro_id = checkpoint_db.add_project("Euro", "eur").unwrap();
+        let krona_id = checkpoint_db.add_project("Krona", "sek").unwrap();
+        checkpoint_db
+            .set_project_rate(euro_id, Some(10000))
+            .unwrap();
+        checkpoint_db
+            .set_project_rate(krona_id, Some(100000))
+            .unwrap();
+        checkpoint_db
+            .set_project_currency(krona_id, Some("SEK"))
+            .unwrap();
+
+        let start = Utc
+            .with_ymd_and_hms(2021, 6, 15, 12, 0, 0)
+            .unwrap()
+            .timestamp();
+        checkpoint_db
+            .add_checkpoint(start, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 3600, "Work", euro_id)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 7200, "Work", krona_id)
+            .unwrap();
+
+        let report = checkpoint_db.earnings_report(&TimeRange::new(start, start + 7201), None);
+        let amounts: Vec<(Option<&str>, i64)> = report
+            .totals
+            .iter()
+            .map(|total| (total.currency.as_deref(), total.earnings.amount))
+            .collect();
+        assert_eq!(amounts, vec![(Some("EUR"), 10000), (Some("SEK"), 100000)]);
+
+        let mut exchange_rates = ExchangeRates::new();
+        assert_eq!(report.total_in("EUR", &exchange_rates), None);
+        assert!(exchange_rates.insert("EUR", "SEK", 0.0).is_err());
+        assert!(exchange_rates.insert("EUR", "SEK", f64::NAN).is_err());
+        exchange_rates.insert("EUR", "SEK", 10.0).unwrap();
+        assert_eq!(report.total_in("EUR", &exchange_rates), Some(20000));
+        assert_eq!(report.total_in("SEK", &exchange_rates), Some(200000));
+    }
+
+    #[test]
+    /// A client report only covers the client's projects, and its uninvoiced balance shrinks
+    /// when time gets invoiced.
+    fn client_report() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let client_id = checkpoint_db.add_client("Client").unwrap();
+        let project_id = checkpoint_db.add_project("Project", "prj").unwrap();
+        let other_id = checkpoint_db.add_project("Other", "oth").unwrap();
+        checkpoint_db
+            .set_project_client(project_id, Some(client_id))
+            .unwrap();
+        checkpoint_db
+            .set_project_rate(project_id, Some(3600))
+            .unwrap();
+
+        let start = Utc
+            .with_ymd_and_hms(2021, 6, 15, 12, 0, 0)
+            .unwrap()
+            .timestamp();
+        checkpoint_db
+            .add_checkpoint(start, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 600, "Email", project_id)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 2400, "Coding", project_id)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 3000, "Email", other_id)
+            .unwrap();
+
+        let range = TimeRange::new(start, start + 3600);
+        let report = checkpoint_db
+            .client_report(client_id, &range, None)
+            .unwrap();
+        assert_eq!(report.projects.len(), 1);
+        assert_eq!(report.totals[0].earnings.amount, 2400);
+        assert_eq!(report.uninvoiced[0].earnings.amount, 2400);
+        assert_eq!(
+            report.top_tasks,
+            vec![
+                TaskTotal {
+                    message: "Coding".to_string(),
+                    duration: 1800,
+                },
+                TaskTotal {
+                    message: "Email".to_string(),
+                    duration: 600,
+                },
+            ]
+        );
+
+        checkpoint_db
+            .mark_invoiced(&CheckpointId::Timestamp(start + 600), "1")
+            .unwrap();
+        let report = checkpoint_db
+            .client_report(client_id, &range, None)
+            .unwrap();
+        assert_eq!(report.uninvoiced[0].earnings.amount, 1800);
+    }
+}
@@ -0,0 +1,65 @@
+//! Runs a sync server that devices push and pull their operations through, with
+//! `SyncClient::sync`.
+//!
+//! Usage:
+//!
+//! - `tt-sync <state file> add-device <name> <token>` lets a device sync with the token.
+//! - `tt-sync <state file> serve [address]` serves the API on the address, which defaults to
+//!   `127.0.0.1:8643`.
+
+use std::env;
+use std::process::exit;
+use std::sync::{Arc, Mutex};
+use time_track::{sync_router, SyncServer};
+
+const USAGE: &str = "usage: tt-sync <state file> (add-device <name> <token> | serve [address])";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let Some((path, command)) = args.split_first() else {
+        eprintln!("{}", USAGE);
+        exit(2);
+    };
+
+    let mut server = match SyncServer::open(path) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("could not read {}: {}", path, e);
+            exit(1);
+        }
+    };
+
+    match command {
+        ["add-device", name, token] => {
+            if let Err(e) = server.add_device(name, token) {
+                eprintln!("could not add {}: {}", name, e);
+                exit(1);
+            }
+        }
+        ["serve", address @ ..] if address.len() <= 1 => {
+            let address = address.first().copied().unwrap_or("127.0.0.1:8643");
+            serve(server, address);
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+    }
+}
+
+fn serve(server: SyncServer, address: &str) {
+    let router = sync_router(Arc::new(Mutex::new(server)));
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_io()
+        .build()
+        .expect("could not start the runtime");
+    let result = runtime.block_on(async {
+        let listener = tokio::net::TcpListener::bind(address).await?;
+        axum::serve(listener, router).await
+    });
+    if let Err(e) = result {
+        eprintln!("could not serve on {}: {}", address, e);
+        exit(1);
+    }
+}
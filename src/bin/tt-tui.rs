@@ -0,0 +1,314 @@
+//! An interactive time tracker for the terminal, built on the library.
+//!
+//! The database is the file given as the first argument, or in `TIME_TRACK_DB`, or
+//! `~/.time_track.json`. The timeline lists the checkpoints latest first, one screen at a time,
+//! and the top line times the current task, which is everything since the latest checkpoint.
+//!
+//! Keys: `j`/`k` or the arrows move, `PageUp`/`PageDown` move a screen, `a` adds a checkpoint
+//! now, `p` picks the project of the selected checkpoint, `d` removes it and `q` quits.
+
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Clear, List, ListItem, ListState, Paragraph},
+    DefaultTerminal, Frame,
+};
+use std::{env, path::PathBuf, process, time::Duration};
+use time_track::{
+    CheckpointDb, CheckpointId, CheckpointKind, ProjectId, Result, Timestamp, TrackedDuration,
+};
+
+/// What the keys currently do.
+enum Mode {
+    Timeline,
+    /// Typing the message of a new checkpoint.
+    Adding(String),
+    /// Picking a project from the list, where the first entry is no project.
+    PickingProject(ListState),
+}
+
+struct App {
+    checkpoint_db: CheckpointDb,
+    path: PathBuf,
+    /// The position of the selected checkpoint, where 0 is the latest.
+    selected: usize,
+    /// The position of the first checkpoint on the screen.
+    offset: usize,
+    /// The number of checkpoints that fit on the screen, from the last draw.
+    page_size: usize,
+    mode: Mode,
+    error: Option<String>,
+}
+
+fn main() {
+    let path = env::args_os()
+        .nth(1)
+        .or_else(|| env::var_os("TIME_TRACK_DB"))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            let home = env::var_os("HOME").unwrap_or_default();
+            PathBuf::from(home).join(".time_track.json")
+        });
+    let checkpoint_db = match CheckpointDb::read(&path) {
+        Ok(checkpoint_db) => checkpoint_db,
+        Err(e) => {
+            eprintln!("tt-tui: {}", e);
+            process::exit(1);
+        }
+    };
+
+    let mut app = App {
+        checkpoint_db,
+        path,
+        selected: 0,
+        offset: 0,
+        page_size: 1,
+        mode: Mode::Timeline,
+        error: None,
+    };
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    if let Err(e) = result {
+        eprintln!("tt-tui: {}", e);
+        process::exit(1);
+    }
+}
+
+impl App {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            // Redraw every second even without input to keep the timer running.
+            if !event::poll(Duration::from_secs(1))? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            self.error = None;
+            let mode = std::mem::replace(&mut self.mode, Mode::Timeline);
+            self.mode = match mode {
+                Mode::Timeline => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    KeyCode::Char('j') | KeyCode::Down => self.select(self.selected as i64 + 1),
+                    KeyCode::Char('k') | KeyCode::Up => self.select(self.selected as i64 - 1),
+                    KeyCode::PageDown => self.select((self.selected + self.page_size) as i64),
+                    KeyCode::PageUp => self.select(self.selected as i64 - self.page_size as i64),
+                    KeyCode::Char('a') => Mode::Adding(String::new()),
+                    KeyCode::Char('p') if !self.checkpoint_db.is_empty() => {
+                        Mode::PickingProject(ListState::default().with_selected(Some(0)))
+                    }
+                    KeyCode::Char('d') => {
+                        let id = CheckpointId::Position(self.selected);
+                        if self.checkpoint_db.remove_checkpoint(&id).is_some() {
+                            self.save();
+                        }
+                        self.select(self.selected as i64)
+                    }
+                    _ => Mode::Timeline,
+                },
+                Mode::Adding(mut message) => match key.code {
+                    KeyCode::Esc => Mode::Timeline,
+                    KeyCode::Enter => {
+                        let result = self.checkpoint_db.add_checkpoint(
+                            Timestamp::now(),
+                            &message,
+                            ProjectId::NoId,
+                        );
+                        self.handle(result);
+                        self.select(0)
+                    }
+                    KeyCode::Backspace => {
+                        message.pop();
+                        Mode::Adding(message)
+                    }
+                    KeyCode::Char(c) => {
+                        message.push(c);
+                        Mode::Adding(message)
+                    }
+                    _ => Mode::Adding(message),
+                },
+                Mode::PickingProject(mut state) => match key.code {
+                    KeyCode::Esc => Mode::Timeline,
+                    KeyCode::Char('j') | KeyCode::Down => {
+                        state.select_next();
+                        Mode::PickingProject(state)
+                    }
+                    KeyCode::Char('k') | KeyCode::Up => {
+                        state.select_previous();
+                        Mode::PickingProject(state)
+                    }
+                    KeyCode::Enter => {
+                        let project_id = state
+                            .selected()
+                            .and_then(|index| index.checked_sub(1))
+                            .and_then(|index| self.checkpoint_db.projects().nth(index))
+                            .map_or(ProjectId::NoId, |(project_id, _)| project_id);
+                        let result = self.checkpoint_db.set_checkpoint_project(
+                            CheckpointId::Position(self.selected),
+                            project_id,
+                        );
+                        self.handle(result);
+                        Mode::Timeline
+                    }
+                    _ => Mode::PickingProject(state),
+                },
+            };
+        }
+    }
+
+    /// Selects the checkpoint at the position, kept within the timeline, and scrolls to it.
+    fn select(&mut self, position: i64) -> Mode {
+        let last = self.checkpoint_db.len().saturating_sub(1);
+        self.selected = position.clamp(0, last as i64) as usize;
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + self.page_size {
+            self.offset = self.selected + 1 - self.page_size;
+        }
+        Mode::Timeline
+    }
+
+    /// Saves the database after a successful change, or shows why it failed.
+    fn handle(&mut self, result: Result<()>) {
+        match result {
+            Ok(()) => self.save(),
+            Err(e) => self.error = Some(e.to_string()),
+        }
+    }
+
+    fn save(&mut self) {
+        if let Err(e) = self.checkpoint_db.write(&self.path) {
+            self.error = Some(format!("could not save: {}", e));
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [timer_area, timeline_area, footer_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        frame.render_widget(Paragraph::new(self.timer()), timer_area);
+        self.draw_timeline(frame, timeline_area);
+
+        let footer = match (&self.mode, &self.error) {
+            (_, Some(error)) => error.clone(),
+            (Mode::Adding(message), None) => format!("New checkpoint: {}_", message),
+            (Mode::PickingProject(_), None) => "Enter picks the project, Esc cancels".to_string(),
+            (Mode::Timeline, None) => {
+                "a add  p project  d remove  j/k move  PageUp/PageDown page  q quit".to_string()
+            }
+        };
+        frame.render_widget(Paragraph::new(footer), footer_area);
+
+        if let Mode::PickingProject(state) = &mut self.mode {
+            let items: Vec<ListItem> = std::iter::once(ListItem::new("(no project)"))
+                .chain(self.checkpoint_db.projects().map(|(_, project)| {
+                    ListItem::new(format!("{:<10} {}", project.short_name, project.long_name))
+                }))
+                .collect();
+            let area = centered(timeline_area, 40, items.len() as u16 + 2);
+            frame.render_widget(Clear, area);
+            frame.render_stateful_widget(
+                List::new(items)
+                    .block(Block::bordered().title("Project"))
+                    .highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+                area,
+                state,
+            );
+        }
+    }
+
+    /// Describes the current task, which has been going on since the latest checkpoint.
+    fn timer(&self) -> String {
+        match self.checkpoint_db.checkpoints().next_back() {
+            Some((timestamp, checkpoint)) => format!(
+                "Current task: {} since {} (after \"{}\")",
+                TrackedDuration(Timestamp::now().seconds() - timestamp),
+                local_time(Timestamp(timestamp)),
+                checkpoint.message
+            ),
+            None => "No checkpoints yet, press a to add one".to_string(),
+        }
+    }
+
+    /// Draws the page of the timeline that the selected checkpoint is on.
+    fn draw_timeline(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(format!(
+            "Timeline ({} checkpoints)",
+            self.checkpoint_db.len()
+        ));
+        self.page_size = usize::from(block.inner(area).height).max(1);
+        self.select(self.selected as i64);
+
+        // Take one more than fits, to know the duration of the oldest one on the page.
+        let page: Vec<_> = self
+            .checkpoint_db
+            .checkpoints()
+            .rev()
+            .skip(self.offset)
+            .take(self.page_size + 1)
+            .collect();
+        let lines: Vec<Line> = page
+            .iter()
+            .zip(page.iter().skip(1).map(Some).chain(Some(None)))
+            .take(self.page_size)
+            .enumerate()
+            .map(|(index, ((timestamp, checkpoint), previous))| {
+                let duration = previous.map_or(0, |(previous, _)| timestamp - previous);
+                let project = self
+                    .checkpoint_db
+                    .project_from_project_id(checkpoint.project_id)
+                    .map_or("-", |project| project.short_name.as_str());
+                let text = format!(
+                    "{}  {:>6}  {:<10} {}{}",
+                    local_time(Timestamp(*timestamp)),
+                    TrackedDuration(duration).to_string(),
+                    project,
+                    checkpoint.message,
+                    if checkpoint.kind == CheckpointKind::Break {
+                        " [break]"
+                    } else {
+                        ""
+                    }
+                );
+                if self.offset + index == self.selected {
+                    Line::styled(text, Style::new().add_modifier(Modifier::REVERSED))
+                } else {
+                    Line::raw(text)
+                }
+            })
+            .collect();
+
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
+
+fn centered(area: Rect, width: u16, height: u16) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+fn local_time(timestamp: Timestamp) -> String {
+    match timestamp.to_local() {
+        Some(time) => time.format("%Y-%m-%d %H:%M").to_string(),
+        None => timestamp.to_string(),
+    }
+}
@@ -0,0 +1,341 @@
+//! A command line time tracker built on the library.
+//!
+//! The database is the file given with `--db`, or in `TIME_TRACK_DB`, or `~/.time_track.json`.
+//! Times can be written like `now`, `2h ago`, `yesterday 14:30` or `monday 9am`, and
+//! checkpoints are picked by position, where `0` is the latest, or with `@<timestamp>`. `~`
+//! before a position is allowed but expands to a directory in most shells unless quoted.
+
+use chrono::Local;
+use clap::{Parser, Subcommand};
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process,
+};
+use time_track::{
+    Category, Checkpoint, CheckpointDb, CheckpointDbError, CheckpointId, CheckpointKind, ProjectId,
+    Result, TimeRange, Timestamp,
+};
+
+#[derive(Parser)]
+#[command(name = "tt", about = "Tracks time with checkpoints")]
+struct Cli {
+    /// The database file.
+    #[arg(long, env = "TIME_TRACK_DB")]
+    db: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Adds a checkpoint for what was done since the previous one.
+    Add {
+        message: String,
+        /// The short name or id of the project.
+        #[arg(short, long)]
+        project: Option<String>,
+        /// When the checkpoint is, instead of now.
+        #[arg(long)]
+        at: Option<String>,
+        /// Marks the time since the previous checkpoint as a break.
+        #[arg(long = "break")]
+        is_break: bool,
+        #[arg(short, long = "tag")]
+        tags: Vec<String>,
+        /// Work, meeting, admin, learning or break.
+        #[arg(short, long)]
+        category: Option<Category>,
+        /// The hourly rate in cents, if it differs from the project's.
+        #[arg(long)]
+        rate: Option<i64>,
+        /// Adds the checkpoint even if it's in the future or before the earliest allowed time.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Lists the checkpoints in a range, latest first.
+    Log {
+        /// The start of the range, today by default.
+        #[arg(long)]
+        from: Option<String>,
+        /// The end of the range, now by default.
+        #[arg(long)]
+        to: Option<String>,
+    },
+    /// Lists the projects, or adds one.
+    Projects {
+        #[command(subcommand)]
+        command: Option<ProjectsCommand>,
+    },
+    /// Sums the tracked time per day and project in a range.
+    Report {
+        /// The start of the range, today by default.
+        #[arg(long)]
+        from: Option<String>,
+        /// The end of the range, now by default.
+        #[arg(long)]
+        to: Option<String>,
+        /// Sums the time per category and project instead.
+        #[arg(long)]
+        by_category: bool,
+    },
+    /// Changes a checkpoint.
+    Edit {
+        #[arg(value_parser = parse_checkpoint_id)]
+        checkpoint: CheckpointId,
+        #[arg(short, long)]
+        message: Option<String>,
+        /// The short name or id of the project, or "" for none.
+        #[arg(short, long)]
+        project: Option<String>,
+        #[arg(long = "break", conflicts_with = "work")]
+        is_break: bool,
+        #[arg(long)]
+        work: bool,
+        /// Replaces the tags.
+        #[arg(short, long = "tag")]
+        tags: Option<Vec<String>>,
+        /// The category, or "" for none.
+        #[arg(short, long)]
+        category: Option<String>,
+    },
+    /// Holds the database open and answers requests about it on a Unix socket.
+    #[cfg(all(feature = "daemon", unix))]
+    Daemon {
+        #[arg(long, env = "TIME_TRACK_SOCKET")]
+        socket: PathBuf,
+    },
+    /// Removes a checkpoint.
+    Remove {
+        #[arg(value_parser = parse_checkpoint_id)]
+        checkpoint: CheckpointId,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProjectsCommand {
+    /// Adds a project.
+    Add {
+        long_name: String,
+        short_name: String,
+    },
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let path = cli.db.unwrap_or_else(|| {
+        let home = env::var_os("HOME").unwrap_or_default();
+        PathBuf::from(home).join(".time_track.json")
+    });
+
+    if let Err(e) = run(cli.command, &path) {
+        eprintln!("tt: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(command: Command, path: &Path) -> Result<()> {
+    #[cfg(all(feature = "daemon", unix))]
+    if let Command::Daemon { socket } = &command {
+        return time_track::Daemon::open(path)?.serve(socket);
+    }
+
+    let mut db_file = CheckpointDb::options().create(true).open(path)?;
+    let checkpoint_db = db_file.checkpoint_db_mut();
+
+    match command {
+        Command::Add {
+            message,
+            project,
+            at,
+            is_break,
+            tags,
+            category,
+            rate,
+            force,
+        } => {
+            let mut checkpoint = Checkpoint::builder().message(&message);
+            if let Some(project) = project {
+                checkpoint = checkpoint.project(checkpoint_db.resolve_project(&project)?);
+            }
+            if is_break {
+                checkpoint = checkpoint.kind(CheckpointKind::Break);
+            }
+            for tag in &tags {
+                checkpoint = checkpoint.tag(tag);
+            }
+            if let Some(category) = category {
+                checkpoint = checkpoint.category(category);
+            }
+            if let Some(rate) = rate {
+                checkpoint = checkpoint.rate(rate);
+            }
+            let time = match at {
+                Some(at) => parse_time(&at)?,
+                None => Timestamp::now(),
+            };
+            if force {
+                checkpoint_db.force_add_checkpoint(time, checkpoint)?;
+            } else {
+                checkpoint_db.add_checkpoint_full(time, checkpoint)?;
+            }
+            db_file.save_delta()?;
+        }
+        Command::Log { from, to } => {
+            let range = parse_range(from, to)?;
+            for log in checkpoint_db.log_between_times(range.start - 1, range.end) {
+                println!(
+                    "~{:<4} {}  {:>6}  {:<10} {}{}",
+                    log.position,
+                    local_time(log.timestamp),
+                    log.duration.unwrap_or_default().to_string(),
+                    project_name(checkpoint_db, log.checkpoint.project_id),
+                    log.checkpoint.message,
+                    if log.checkpoint.kind == CheckpointKind::Break {
+                        " [break]"
+                    } else {
+                        ""
+                    }
+                );
+            }
+        }
+        Command::Projects { command: None } => {
+            for (project_id, project) in checkpoint_db.projects() {
+                println!(
+                    "{:<4} {:<10} {}",
+                    project_id, project.short_name, project.long_name
+                );
+            }
+        }
+        Command::Projects {
+            command:
+                Some(ProjectsCommand::Add {
+                    long_name,
+                    short_name,
+                }),
+        } => {
+            let project_id = checkpoint_db.add_project(&long_name, &short_name)?;
+            db_file.save_delta()?;
+            println!("added project {}", project_id);
+        }
+        Command::Report {
+            from,
+            to,
+            by_category: true,
+        } => {
+            for total in checkpoint_db.category_totals(&parse_range(from, to)?) {
+                let name = total
+                    .category
+                    .map_or("none".to_string(), |category| category.to_string());
+                println!("{:<10}  {:>6}", name, total.duration.to_string());
+                for project in &total.projects {
+                    println!(
+                        "    {:<10} {:>6}",
+                        project_name(checkpoint_db, project.project_id),
+                        project.duration.to_string()
+                    );
+                }
+            }
+        }
+        Command::Report { from, to, .. } => {
+            let report = checkpoint_db.report(&parse_range(from, to)?, None);
+            for day in &report.days {
+                println!(
+                    "{}  {:>6}",
+                    checkpoint_db.format_date(day.date),
+                    day.duration.to_string()
+                );
+                for project in &day.projects {
+                    println!(
+                        "    {:<10} {:>6}",
+                        project_name(checkpoint_db, project.project_id),
+                        project.duration.to_string()
+                    );
+                }
+            }
+            println!("total       {:>6}", report.duration.to_string());
+        }
+        Command::Edit {
+            checkpoint,
+            message,
+            project,
+            is_break,
+            work,
+            tags,
+            category,
+        } => {
+            if !checkpoint.exists(checkpoint_db) {
+                return Err(CheckpointDbError::CheckpointNotFound { id: checkpoint });
+            }
+            if let Some(project) = project {
+                let project_id = checkpoint_db.resolve_project(&project)?;
+                checkpoint_db.set_checkpoint_project(checkpoint, project_id)?;
+            }
+            if is_break || work {
+                let kind = if is_break {
+                    CheckpointKind::Break
+                } else {
+                    CheckpointKind::Work
+                };
+                checkpoint_db.set_checkpoint_kind(checkpoint, kind)?;
+            }
+            if let Some(tags) = tags {
+                checkpoint_db.set_checkpoint_tags(checkpoint, tags)?;
+            }
+            if let Some(category) = category {
+                let category = match category.as_str() {
+                    "" => None,
+                    category => Some(category.parse()?),
+                };
+                checkpoint_db.set_checkpoint_category(checkpoint, category)?;
+            }
+            if let Some(message) = message {
+                checkpoint_db.set_checkpoint_message(checkpoint, &message)?;
+            }
+            db_file.save_delta()?;
+        }
+        #[cfg(all(feature = "daemon", unix))]
+        Command::Daemon { .. } => unreachable!("the daemon is started before reading"),
+        Command::Remove { checkpoint } => {
+            if checkpoint_db.remove_checkpoint(&checkpoint).is_none() {
+                return Err(CheckpointDbError::CheckpointNotFound { id: checkpoint });
+            }
+            db_file.save_delta()?;
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_checkpoint_id(id: &str) -> Result<CheckpointId> {
+    match id.parse() {
+        Ok(position) => Ok(CheckpointId::Position(position)),
+        Err(_) => id.parse(),
+    }
+}
+
+fn parse_time(time: &str) -> Result<Timestamp> {
+    Timestamp::parse_natural(time, &Local::now())
+}
+
+fn parse_range(from: Option<String>, to: Option<String>) -> Result<TimeRange> {
+    let from = parse_time(from.as_deref().unwrap_or("today"))?;
+    let to = match to {
+        Some(to) => parse_time(&to)?,
+        None => Timestamp(Timestamp::now().seconds() + 1),
+    };
+    Ok(TimeRange::new(from.seconds(), to.seconds()))
+}
+
+fn local_time(timestamp: Timestamp) -> String {
+    match timestamp.to_local() {
+        Some(time) => time.format("%Y-%m-%d %H:%M").to_string(),
+        None => timestamp.to_string(),
+    }
+}
+
+fn project_name(checkpoint_db: &CheckpointDb, project_id: ProjectId) -> &str {
+    checkpoint_db
+        .project_from_project_id(project_id)
+        .map_or("-", |project| project.short_name.as_str())
+}
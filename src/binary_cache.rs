@@ -0,0 +1,48 @@
+use crate::{CheckpointDb, Result};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::Hasher,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// What a cache file starts with, followed by the hash of the JSON it was made from and the
+/// database as MessagePack.
+const MAGIC: &[u8; 4] = b"ttc1";
+
+/// Returns the database in the cache file next to the JSON file at the path, if it was made
+/// from exactly that JSON. Caches that can't be read are treated as missing.
+pub(crate) fn read_cache(path: &Path, json: &[u8]) -> Option<CheckpointDb> {
+    let cache = fs::read(cache_path(path)).ok()?;
+    let rest = cache.strip_prefix(MAGIC)?;
+    let (hash, database) = rest.split_first_chunk::<8>()?;
+    if u64::from_le_bytes(*hash) != hash_json(json) {
+        return None;
+    }
+    rmp_serde::from_slice(database).ok()
+}
+
+/// Writes the database to the cache file next to the JSON file at the path, which it was
+/// read from or written as.
+pub(crate) fn write_cache(path: &Path, json: &[u8], checkpoint_db: &CheckpointDb) -> Result<()> {
+    let mut cache = MAGIC.to_vec();
+    cache.extend_from_slice(&hash_json(json).to_le_bytes());
+    rmp_serde::encode::write_named(&mut cache, checkpoint_db).map_err(io::Error::other)?;
+    fs::write(cache_path(path), cache)?;
+    Ok(())
+}
+
+/// Hashes the JSON of a database. The hash may differ between versions of Rust, which only
+/// means that the cache is made again.
+fn hash_json(json: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(json);
+    hasher.finish()
+}
+
+pub(crate) fn cache_path(path: &Path) -> PathBuf {
+    let mut cache = path.as_os_str().to_owned();
+    cache.push(".cache");
+    PathBuf::from(cache)
+}
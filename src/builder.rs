@@ -0,0 +1,102 @@
+use crate::{Category, Checkpoint, CheckpointKind, Pomodoro, ProjectId};
+
+/// Builds a `Checkpoint` to add with `CheckpointDb::add_checkpoint_full`. Anything not set is
+/// left as in `Checkpoint::default()`, a work checkpoint without a message or a project.
+#[derive(Clone, Debug, Default)]
+pub struct CheckpointBuilder {
+    checkpoint: Checkpoint,
+}
+
+impl Checkpoint {
+    pub fn builder() -> CheckpointBuilder {
+        CheckpointBuilder::default()
+    }
+}
+
+impl CheckpointBuilder {
+    pub fn message(mut self, message: &str) -> CheckpointBuilder {
+        self.checkpoint.message = message.into();
+        self
+    }
+
+    pub fn project(mut self, project_id: ProjectId) -> CheckpointBuilder {
+        self.checkpoint.project_id = project_id;
+        self
+    }
+
+    pub fn kind(mut self, kind: CheckpointKind) -> CheckpointBuilder {
+        self.checkpoint.kind = kind;
+        self
+    }
+
+    pub fn category(mut self, category: Category) -> CheckpointBuilder {
+        self.checkpoint.category = Some(category);
+        self
+    }
+
+    pub fn pomodoro(mut self, pomodoro: Pomodoro) -> CheckpointBuilder {
+        self.checkpoint.pomodoro = Some(pomodoro);
+        self
+    }
+
+    /// Adds a tag, keeping the tags added before it.
+    pub fn tag(mut self, tag: &str) -> CheckpointBuilder {
+        self.checkpoint.tags.push(tag.to_string());
+        self
+    }
+
+    /// Sets the hourly rate of the checkpoint, which overrides the project's rate.
+    pub fn rate(mut self, rate: i64) -> CheckpointBuilder {
+        self.checkpoint.rate = Some(rate);
+        self
+    }
+
+    pub fn external_id(mut self, external_id: &str) -> CheckpointBuilder {
+        self.checkpoint.external_id = Some(external_id.to_string());
+        self
+    }
+
+    pub fn build(self) -> Checkpoint {
+        self.checkpoint
+    }
+}
+
+impl From<CheckpointBuilder> for Checkpoint {
+    fn from(builder: CheckpointBuilder) -> Checkpoint {
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckpointDb, CheckpointId};
+
+    #[test]
+    /// Built checkpoints are added with the set fields, and their projects are validated.
+    fn add_checkpoint_full() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Website", "web").unwrap();
+        checkpoint_db
+            .add_checkpoint_full(
+                1000,
+                Checkpoint::builder()
+                    .message("Lunch")
+                    .project(project_id)
+                    .kind(CheckpointKind::Break)
+                    .tag("food")
+                    .tag("team"),
+            )
+            .unwrap();
+
+        let checkpoint = checkpoint_db
+            .get_checkpoint(&CheckpointId::Timestamp(1000))
+            .unwrap();
+        assert_eq!(&*checkpoint.message, "Lunch");
+        assert_eq!(checkpoint.kind, CheckpointKind::Break);
+        assert_eq!(checkpoint.tags, ["food", "team"]);
+        assert!(checkpoint_db
+            .add_checkpoint_full(2000, Checkpoint::builder().project(ProjectId::Id(9)))
+            .is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{
+    Checkpoint, CheckpointDb, CheckpointId, ProjectId, ProjectTotal, Result, Timestamp,
+    TrackedDuration,
+};
+use chrono::prelude::*;
+use std::collections::BTreeMap;
+
+/// Wraps a `CheckpointDb` and memoizes the per-project totals of each day, so that repeatedly
+/// querying the same days doesn't mean summing up their checkpoints every time.
+///
+/// Mutations made through the wrapper only invalidate the days they affect. Any mutation made
+/// through `checkpoint_db_mut` invalidates every day.
+#[derive(Clone, Debug, Default)]
+pub struct CachedCheckpointDb {
+    checkpoint_db: CheckpointDb,
+    days: BTreeMap<NaiveDate, Vec<ProjectTotal>>,
+}
+
+impl CachedCheckpointDb {
+    pub fn new(checkpoint_db: CheckpointDb) -> CachedCheckpointDb {
+        CachedCheckpointDb {
+            checkpoint_db,
+            days: BTreeMap::new(),
+        }
+    }
+
+    pub fn checkpoint_db(&self) -> &CheckpointDb {
+        &self.checkpoint_db
+    }
+
+    /// Returns a mutable reference to the wrapped `CheckpointDb`, clearing the whole cache.
+    pub fn checkpoint_db_mut(&mut self) -> &mut CheckpointDb {
+        self.days.clear();
+        &mut self.checkpoint_db
+    }
+
+    pub fn into_inner(self) -> CheckpointDb {
+        self.checkpoint_db
+    }
+
+    pub fn add_checkpoint<T: Into<Timestamp>>(
+        &mut self,
+        time: T,
+        message: &str,
+        project_id: ProjectId,
+    ) -> Result<()> {
+        let time = time.into();
+        self.checkpoint_db
+            .add_checkpoint(time, message, project_id)?;
+        self.invalidate(time.seconds());
+        Ok(())
+    }
+
+    pub fn add_checkpoint_full<T: Into<Timestamp>, C: Into<Checkpoint>>(
+        &mut self,
+        time: T,
+        checkpoint: C,
+    ) -> Result<()> {
+        let time = time.into();
+        self.checkpoint_db.add_checkpoint_full(time, checkpoint)?;
+        self.invalidate(time.seconds());
+        Ok(())
+    }
+
+    pub fn remove_checkpoint(&mut self, checkpoint_id: &CheckpointId) -> Option<Checkpoint> {
+        let timestamp = checkpoint_id.to_timestamp(&self.checkpoint_db)?;
+        // Invalidate before removing, while the following checkpoint can still be found.
+        self.invalidate(timestamp);
+        self.checkpoint_db.remove_checkpoint(checkpoint_id)
+    }
+
+    pub fn set_checkpoint_project(
+        &mut self,
+        checkpoint_id: CheckpointId,
+        project_id: ProjectId,
+    ) -> Result<()> {
+        self.checkpoint_db
+            .set_checkpoint_project(checkpoint_id, project_id)?;
+        if let Some(timestamp) = checkpoint_id.to_timestamp(&self.checkpoint_db) {
+            self.days.remove(&self.checkpoint_db.date_of(timestamp));
+        }
+        Ok(())
+    }
+
+    /// Forgets the totals of the days affected by a change at the given timestamp: the day of
+    /// the timestamp itself, and the day of the following checkpoint, whose duration depends on
+    /// the changed one.
+    pub fn invalidate(&mut self, timestamp: i64) {
+        self.days.remove(&self.checkpoint_db.date_of(timestamp));
+        if let Some((next, _)) = self
+            .checkpoint_db
+            .checkpoints
+            .range(timestamp.saturating_add(1)..)
+            .next()
+        {
+            self.days.remove(&self.checkpoint_db.date_of(*next));
+        }
+    }
+
+    /// Returns the total time of each project on the day of the date.
+    pub fn day_totals(&mut self, date: NaiveDate) -> &[ProjectTotal] {
+        let checkpoint_db = &self.checkpoint_db;
+        self.days.entry(date).or_insert_with(|| {
+            checkpoint_db
+                .report(&checkpoint_db.day_range(date), None)
+                .projects
+        })
+    }
+
+    /// Returns the total time of each project from the first to the last given date,
+    /// inclusive.
+    pub fn totals(&mut self, first: NaiveDate, last: NaiveDate) -> Vec<ProjectTotal> {
+        let mut totals: BTreeMap<ProjectId, TrackedDuration> = BTreeMap::new();
+        for date in first.iter_days().take_while(|date| *date <= last) {
+            for total in self.day_totals(date) {
+                *totals.entry(total.project_id).or_default() += total.duration;
+            }
+        }
+
+        totals
+            .into_iter()
+            .map(|(project_id, duration)| ProjectTotal {
+                project_id,
+                duration,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TimeRange;
+
+    #[test]
+    /// Adding a checkpoint changes the cached totals of its day and of the day of the
+    /// checkpoint after it.
+    fn invalidation() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Project", "prj").unwrap();
+        let monday = NaiveDate::from_ymd_opt(2021, 6, 14).unwrap();
+        let tuesday = monday.succ_opt().unwrap();
+        let monday_noon = TimeRange::day(monday).start + 12 * 3600;
+        let tuesday_noon = TimeRange::day(tuesday).start + 12 * 3600;
+        checkpoint_db
+            .add_checkpoint(monday_noon, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(tuesday_noon, "Work", project_id)
+            .unwrap();
+
+        let mut cached = CachedCheckpointDb::new(checkpoint_db);
+        assert_eq!(
+            cached.totals(monday, tuesday)[1].duration,
+            TrackedDuration(24 * 3600)
+        );
+
+        cached
+            .add_checkpoint(monday_noon + 3600, "Lunch", ProjectId::NoId)
+            .unwrap();
+        assert_eq!(cached.day_totals(monday)[0].duration, TrackedDuration(3600));
+        assert_eq!(
+            cached.day_totals(tuesday)[0].duration,
+            TrackedDuration(23 * 3600)
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use crate::{
+    ical::{ical_uid, read_ical_events, write_ical_line, ICAL_UID_SUFFIX},
+    CheckpointDb, CheckpointDbError, CheckpointKind, IcalProjectRule, ImportSummary,
+    ImportedCheckpoint, Result, TimeRange,
+};
+use base64::Engine;
+use chrono::prelude::*;
+
+/// The tag of the checkpoints pulled from a CalDAV calendar, which are never pushed back.
+pub const CALDAV_TAG: &str = "caldav";
+
+/// A CalDAV calendar, like a Google Calendar, to sync checkpoints with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CalDavCalendar {
+    /// The URL of the calendar collection.
+    pub url: String,
+    pub username: String,
+    pub password: String,
+    /// Events with this keyword in their summary or categories, in any case, are pulled as
+    /// checkpoints. Nothing is pulled without one.
+    pub pull_keyword: Option<String>,
+    /// Decides the projects of pulled events.
+    pub rules: Vec<IcalProjectRule>,
+}
+
+impl CalDavCalendar {
+    pub fn new(url: &str, username: &str, password: &str) -> CalDavCalendar {
+        CalDavCalendar {
+            url: url.to_string(),
+            username: username.to_string(),
+            password: password.to_string(),
+            pull_keyword: None,
+            rules: Vec::new(),
+        }
+    }
+
+    /// Returns the URL of the resource with the given name in the calendar.
+    fn resource_url(&self, name: &str) -> String {
+        format!("{}/{}", self.url.trim_end_matches('/'), name)
+    }
+
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        let credentials = format!("{}:{}", self.username, self.password);
+        let credentials = base64::engine::general_purpose::STANDARD.encode(credentials);
+        ureq::request(method, url).set("Authorization", &format!("Basic {}", credentials))
+    }
+}
+
+impl CheckpointDb {
+    /// Pushes the work in the given `TimeRange` to the calendar as events, replacing the events
+    /// pushed for the same checkpoints before. Returns the number of pushed events.
+    pub fn push_caldav(&self, calendar: &CalDavCalendar, range: &TimeRange) -> Result<usize> {
+        let mut pushed = 0;
+        for entry in self.entries_in(range) {
+            if entry.duration == 0
+                || entry.checkpoint.kind == CheckpointKind::Break
+                || entry.checkpoint.tags.iter().any(|tag| tag == CALDAV_TAG)
+            {
+                continue;
+            }
+
+            let mut body = Vec::new();
+            write_ical_line(&mut body, "BEGIN:VCALENDAR")
+                .and_then(|_| write_ical_line(&mut body, "VERSION:2.0"))
+                .and_then(|_| write_ical_line(&mut body, "PRODID:-//time_track//time_track//EN"))
+                .and_then(|_| self.write_ical_event(&mut body, &entry))
+                .and_then(|_| write_ical_line(&mut body, "END:VCALENDAR"))?;
+            let body = String::from_utf8_lossy(&body);
+
+            let url = calendar.resource_url(&format!("{}.ics", ical_uid(entry.timestamp)));
+            calendar
+                .request("PUT", &url)
+                .set("Content-Type", "text/calendar; charset=utf-8")
+                .send_string(&body)?;
+            pushed += 1;
+        }
+
+        Ok(pushed)
+    }
+
+    /// Pulls the events in the given `TimeRange` that have the calendar's `pull_keyword` and
+    /// imports them as checkpoints tagged with `CALDAV_TAG`. Events pushed by `push_caldav` are
+    /// skipped. If `dry_run` is set, nothing is added and the returned summary tells what would
+    /// have been.
+    pub fn pull_caldav(
+        &mut self,
+        calendar: &CalDavCalendar,
+        range: &TimeRange,
+        dry_run: bool,
+    ) -> Result<ImportSummary> {
+        let keyword = match &calendar.pull_keyword {
+            Some(keyword) => keyword.to_lowercase(),
+            None => return self.import(Vec::new(), dry_run),
+        };
+
+        let query = format!(
+            r#"<?xml version="1.0" encoding="utf-8" ?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop><C:calendar-data/></D:prop>
+  <C:filter>
+    <C:comp-filter name="VCALENDAR">
+      <C:comp-filter name="VEVENT">
+        <C:time-range start="{}" end="{}"/>
+      </C:comp-filter>
+    </C:comp-filter>
+  </C:filter>
+</C:calendar-query>"#,
+            caldav_time(range.start)?,
+            caldav_time(range.end)?
+        );
+        let response = calendar
+            .request("REPORT", &calendar.url)
+            .set("Depth", "1")
+            .set("Content-Type", "application/xml; charset=utf-8")
+            .send_string(&query)?
+            .into_string()?;
+
+        let mut checkpoints = Vec::new();
+        for data in calendar_data(&response) {
+            for event in read_ical_events(&data).map_err(CheckpointDbError::InvalidInput)? {
+                let has_keyword = event.summary.to_lowercase().contains(&keyword)
+                    || event
+                        .categories
+                        .iter()
+                        .any(|category| category.to_lowercase().contains(&keyword));
+                if !has_keyword || event.uid.ends_with(ICAL_UID_SUFFIX) {
+                    continue;
+                }
+
+                let checkpoint = event
+                    .to_imported(&calendar.rules)
+                    .map_err(CheckpointDbError::InvalidInput)?;
+                checkpoints.push(ImportedCheckpoint {
+                    tags: vec![CALDAV_TAG.to_string()],
+                    ..checkpoint
+                });
+            }
+        }
+
+        self.import(checkpoints, dry_run)
+    }
+}
+
+fn caldav_time(timestamp: i64) -> Result<String> {
+    Utc.timestamp_opt(timestamp, 0)
+        .single()
+        .map(|time| time.format("%Y%m%dT%H%M%SZ").to_string())
+        .ok_or_else(|| CheckpointDbError::InvalidInput("timestamp out of range".to_string()))
+}
+
+/// Returns the contents of the `calendar-data` elements of a multistatus response, whatever
+/// prefix their namespace has.
+fn calendar_data(xml: &str) -> Vec<String> {
+    let mut data = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '>' || c == '/')
+            .unwrap_or(rest.len());
+        let name = &rest[..name_end];
+        if !(name == "calendar-data" || name.ends_with(":calendar-data")) {
+            continue;
+        }
+
+        let Some(open_end) = rest.find('>') else {
+            break;
+        };
+        if rest[..open_end].ends_with('/') {
+            continue;
+        }
+        let contents = &rest[open_end + 1..];
+        let Some(close) = contents.find(&format!("</{}>", name)) else {
+            break;
+        };
+        data.push(unescape_xml(&contents[..close]));
+        rest = &contents[close..];
+    }
+    data
+}
+
+/// Replaces the predefined and numeric character references of XML, and unwraps CDATA.
+fn unescape_xml(text: &str) -> String {
+    let text = text.trim();
+    if let Some(cdata) = text
+        .strip_prefix("<![CDATA[")
+        .and_then(|text| text.strip_suffix("]]>"))
+    {
+        return cdata.to_string();
+    }
+
+    let mut unescaped = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let character = match &rest[1..end] {
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "amp" => Some('&'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            reference => reference
+                .strip_prefix("#x")
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| reference.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match character {
+            Some(character) => {
+                unescaped.push(character);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Finds the calendar data of each response, with any namespace prefix.
+    fn read_calendar_data() {
+        let xml = r#"<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+              <d:response><d:propstat><d:prop>
+                <cal:calendar-data>BEGIN:VCALENDAR&#13;
+SUMMARY:Fix &lt;header&gt; &amp; footer&#13;
+END:VCALENDAR</cal:calendar-data>
+              </d:prop></d:propstat></d:response>
+              <d:response><d:propstat><d:prop>
+                <calendar-data xmlns="urn:ietf:params:xml:ns:caldav"><![CDATA[BEGIN:VCALENDAR]]></calendar-data>
+              </d:prop></d:propstat></d:response>
+            </d:multistatus>"#;
+
+        assert_eq!(
+            calendar_data(xml),
+            vec![
+                "BEGIN:VCALENDAR\r\nSUMMARY:Fix <header> & footer\r\nEND:VCALENDAR".to_string(),
+                "BEGIN:VCALENDAR".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{
+    CheckpointDb, CheckpointDbError, CheckpointId, Operation, ProjectId, ProjectTotal, Result,
+    TimeRange, TrackedDuration,
+};
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+/// What sort of time a `Checkpoint` ends, independent of its project, like a meeting on a
+/// project.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Category {
+    Work,
+    Meeting,
+    Admin,
+    Learning,
+    Break,
+}
+
+impl Category {
+    pub const ALL: [Category; 5] = [
+        Category::Work,
+        Category::Meeting,
+        Category::Admin,
+        Category::Learning,
+        Category::Break,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::Work => "work",
+            Category::Meeting => "meeting",
+            Category::Admin => "admin",
+            Category::Learning => "learning",
+            Category::Break => "break",
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Category {
+    type Err = CheckpointDbError;
+
+    /// Parses the name of a category, ignoring case.
+    fn from_str(s: &str) -> Result<Category> {
+        Category::ALL
+            .into_iter()
+            .find(|category| category.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                CheckpointDbError::InvalidInput(format!(
+                    "{:?} is not a category, expected one of work, meeting, admin, learning or \
+                     break",
+                    s
+                ))
+            })
+    }
+}
+
+/// The time tracked in a category, in total and per project.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct CategoryTotal {
+    /// `None` for checkpoints without a category.
+    pub category: Option<Category>,
+    pub projects: Vec<ProjectTotal>,
+    pub duration: TrackedDuration,
+}
+
+impl CheckpointDb {
+    pub fn set_checkpoint_category(
+        &mut self,
+        checkpoint_id: CheckpointId,
+        category: Option<Category>,
+    ) -> Result<()> {
+        let timestamp = self.resolve_checkpoint(&checkpoint_id)?;
+        self.apply(Operation::SetCheckpointCategory {
+            timestamp,
+            category,
+        })
+    }
+
+    /// Sums the time tracked in the given `TimeRange` per category and project, with the
+    /// uncategorized time first and then the categories in order.
+    pub fn category_totals(&self, range: &TimeRange) -> Vec<CategoryTotal> {
+        let mut durations: BTreeMap<(Option<Category>, ProjectId), i64> = BTreeMap::new();
+        for entry in self.entries_in(range) {
+            *durations
+                .entry((entry.checkpoint.category, entry.checkpoint.project_id))
+                .or_default() += entry.duration;
+        }
+
+        let mut totals: Vec<CategoryTotal> = Vec::new();
+        for ((category, project_id), duration) in durations {
+            let duration = TrackedDuration(duration);
+            let project_total = ProjectTotal {
+                project_id,
+                duration,
+            };
+            match totals.last_mut() {
+                Some(total) if total.category == category => {
+                    total.projects.push(project_total);
+                    total.duration += duration;
+                }
+                _ => totals.push(CategoryTotal {
+                    category,
+                    projects: vec![project_total],
+                    duration,
+                }),
+            }
+        }
+        totals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Checkpoint;
+
+    #[test]
+    /// Time is summed per category and project, and categories are parsed and changed.
+    fn category_totals() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let website = checkpoint_db.add_project("Website", "web").unwrap();
+        let shop = checkpoint_db.add_project("Shop", "shop").unwrap();
+        checkpoint_db
+            .add_checkpoint(0, "", ProjectId::NoId)
+            .unwrap();
+        let checkpoints = [
+            (600, website, Some(Category::Meeting)),
+            (1800, website, Some(Category::Work)),
+            (2400, shop, Some(Category::Meeting)),
+            (3000, shop, None),
+        ];
+        for (time, project_id, category) in checkpoints {
+            let mut checkpoint = Checkpoint::builder().project(project_id);
+            if let Some(category) = category {
+                checkpoint = checkpoint.category(category);
+            }
+            checkpoint_db.add_checkpoint_full(time, checkpoint).unwrap();
+        }
+
+        let range = TimeRange::new(1, 3600);
+        let totals = checkpoint_db.category_totals(&range);
+        let categories: Vec<_> = totals.iter().map(|total| total.category).collect();
+        assert_eq!(
+            categories,
+            [None, Some(Category::Work), Some(Category::Meeting)]
+        );
+        assert_eq!(totals[2].duration, TrackedDuration(1200));
+        assert_eq!(
+            totals[2].projects,
+            [
+                ProjectTotal {
+                    project_id: website,
+                    duration: TrackedDuration(600)
+                },
+                ProjectTotal {
+                    project_id: shop,
+                    duration: TrackedDuration(600)
+                }
+            ]
+        );
+
+        assert_eq!("Meeting".parse::<Category>().unwrap(), Category::Meeting);
+        assert!("call".parse::<Category>().is_err());
+        checkpoint_db
+            .set_checkpoint_category(CheckpointId::Timestamp(3000), Some(Category::Admin))
+            .unwrap();
+        let totals = checkpoint_db.category_totals(&range);
+        assert_eq!(totals[0].category, Some(Category::Work));
+        assert_eq!(totals[2].category, Some(Category::Admin));
+        assert_eq!(totals.len(), 3);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{
+    csv::{header_column, parse_csv, parse_local_time},
+    import::map_project,
+    toggl::split_tags,
+    ImportedCheckpoint, Importer,
+};
+use std::collections::BTreeMap;
+
+/// Reads the CSV of Clockify's detailed report.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClockifyImporter {
+    /// Maps Clockify project names to the short names of projects in the database. Projects
+    /// without a mapping keep their Clockify name as short name.
+    pub projects: BTreeMap<String, String>,
+    /// A chrono format string for the date columns, which depend on the workspace settings.
+    pub date_format: String,
+    /// A chrono format string for the time columns, which depend on the workspace settings.
+    pub time_format: String,
+}
+
+impl Default for ClockifyImporter {
+    /// An importer for Clockify's default date and time formats.
+    fn default() -> ClockifyImporter {
+        ClockifyImporter {
+            projects: BTreeMap::new(),
+            date_format: "%m/%d/%Y".to_string(),
+            time_format: "%I:%M:%S %p".to_string(),
+        }
+    }
+}
+
+impl Importer for ClockifyImporter {
+    fn read(&self, input: &str) -> Result<Vec<ImportedCheckpoint>, String> {
+        let rows = parse_csv(input, ',');
+        let header = rows.first().ok_or("the file is empty")?;
+        let column = |name: &str| header_column(header, name);
+        let project = column("Project")?;
+        let description = column("Description")?;
+        let start_date = column("Start Date")?;
+        let start_time = column("Start Time")?;
+        let end_date = column("End Date")?;
+        let end_time = column("End Time")?;
+        let tags = column("Tags").ok();
+        let format = format!("{} {}", self.date_format, self.time_format);
+
+        rows.iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, row)| row.iter().any(|field| !field.is_empty()))
+            .map(|(i, row)| {
+                let field = |column: usize| row.get(column).map_or("", |field| field.trim());
+                let local_time = |date: usize, time: usize| {
+                    let value = format!("{} {}", field(date), field(time));
+                    parse_local_time(&value, &format)
+                        .map_err(|message| format!("row {}: {}", i + 1, message))
+                };
+
+                Ok(ImportedCheckpoint {
+                    timestamp: local_time(end_date, end_time)?,
+                    message: field(description).to_string(),
+                    project: map_project(&self.projects, field(project)),
+                    tags: tags.map_or_else(Vec::new, |tags| split_tags(field(tags))),
+                    start: Some(local_time(start_date, start_time)?),
+                    external_id: None,
+                    kind: None,
+                })
+            })
+            .collect()
+    }
+}
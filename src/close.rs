@@ -0,0 +1,191 @@
+use crate::{
+    Checkpoint, CheckpointDb, CheckpointKind, ProjectId, Result, TimeRange, Timestamp,
+    TrackedDuration,
+};
+use chrono::NaiveDate;
+use std::{cmp::min, fmt};
+
+/// How `CheckpointDb::close_day` checks a day.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct CloseDayOptions {
+    /// Work checkpoints ending more than this many seconds after the previous checkpoint are
+    /// reported, since the time was probably not all spent on them. 3 hours by default.
+    pub max_gap: i64,
+    /// Whether to add a break checkpoint as end marker if the day doesn't have one. Off by
+    /// default.
+    pub insert_end_marker: bool,
+}
+
+impl Default for CloseDayOptions {
+    fn default() -> CloseDayOptions {
+        CloseDayOptions {
+            max_gap: 3 * 3600,
+            insert_end_marker: false,
+        }
+    }
+}
+
+/// Something that should be fixed before a day is done.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub enum DayProblem {
+    /// Nothing was tracked on the day.
+    NoCheckpoints,
+    /// A work checkpoint ends a long time without any other checkpoint.
+    LongGap { timestamp: Timestamp, duration: i64 },
+    /// A work checkpoint has no project.
+    NoProject { timestamp: Timestamp },
+    /// The last checkpoint of the day isn't a break, so the day isn't marked as ended.
+    NoEndMarker,
+}
+
+impl fmt::Display for DayProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DayProblem::NoCheckpoints => write!(f, "nothing was tracked"),
+            DayProblem::LongGap {
+                timestamp,
+                duration,
+            } => write!(
+                f,
+                "the checkpoint at {} ends {} without other checkpoints",
+                timestamp,
+                TrackedDuration::from(*duration)
+            ),
+            DayProblem::NoProject { timestamp } => {
+                write!(f, "the checkpoint at {} has no project", timestamp)
+            }
+            DayProblem::NoEndMarker => write!(f, "the day doesn't end with a break"),
+        }
+    }
+}
+
+/// The result of closing a day: a checklist of its problems.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct DayClosing {
+    pub date: NaiveDate,
+    pub problems: Vec<DayProblem>,
+    /// The break checkpoint that was added as end marker, if one was.
+    pub end_marker: Option<Timestamp>,
+}
+
+impl DayClosing {
+    /// Whether the day has no problems.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl CheckpointDb {
+    /// Checks that the local day is complete, and returns its problems. Meant to be run at the
+    /// end of every day, like by a scheduled job.
+    ///
+    /// If `options.insert_end_marker` is set and the day doesn't end with a break, a break
+    /// checkpoint is added at `now`, or at the last second of the day if `now` is later.
+    pub fn close_day(
+        &mut self,
+        date: NaiveDate,
+        now: i64,
+        options: &CloseDayOptions,
+    ) -> Result<DayClosing> {
+        let range = TimeRange::day(date);
+        let mut closing = DayClosing {
+            date,
+            problems: Vec::new(),
+            end_marker: None,
+        };
+
+        for entry in self.entries_in(&range) {
+            if entry.checkpoint.kind == CheckpointKind::Break {
+                continue;
+            }
+            let timestamp = Timestamp(entry.timestamp);
+            if entry.duration > options.max_gap {
+                closing.problems.push(DayProblem::LongGap {
+                    timestamp,
+                    duration: entry.duration,
+                });
+            }
+            if entry.checkpoint.project_id == ProjectId::NoId {
+                closing.problems.push(DayProblem::NoProject { timestamp });
+            }
+        }
+
+        match self.checkpoints.range(range.start..range.end).next_back() {
+            None => closing.problems.push(DayProblem::NoCheckpoints),
+            Some((_, checkpoint)) if checkpoint.kind == CheckpointKind::Break => (),
+            Some((last, _)) => {
+                let end = min(now, range.end - 1);
+                if options.insert_end_marker && end > *last {
+                    self.add_checkpoint_full(
+                        end,
+                        Checkpoint::builder().kind(CheckpointKind::Break),
+                    )?;
+                    closing.end_marker = Some(Timestamp(end));
+                } else {
+                    closing.problems.push(DayProblem::NoEndMarker);
+                }
+            }
+        }
+
+        Ok(closing)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Gaps, checkpoints without projects and a missing end marker are reported, and the end
+    /// marker can be added.
+    fn close_day() {
+        let date = NaiveDate::from_ymd_opt(2021, 6, 15).unwrap();
+        let morning = TimeRange::day(date).start + 9 * 3600;
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Website", "web").unwrap();
+        checkpoint_db
+            .add_checkpoint_full(morning, Checkpoint::builder().kind(CheckpointKind::Break))
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(morning + 3600, "Styling", project_id)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(morning + 6 * 3600, "Meeting", ProjectId::NoId)
+            .unwrap();
+
+        let evening = morning + 9 * 3600;
+        let closing = checkpoint_db
+            .close_day(date, evening, &CloseDayOptions::default())
+            .unwrap();
+        assert_eq!(
+            closing.problems,
+            [
+                DayProblem::LongGap {
+                    timestamp: Timestamp(morning + 6 * 3600),
+                    duration: 5 * 3600,
+                },
+                DayProblem::NoProject {
+                    timestamp: Timestamp(morning + 6 * 3600),
+                },
+                DayProblem::NoEndMarker,
+            ]
+        );
+
+        let options = CloseDayOptions {
+            insert_end_marker: true,
+            ..CloseDayOptions::default()
+        };
+        let closing = checkpoint_db.close_day(date, evening, &options).unwrap();
+        assert_eq!(closing.end_marker, Some(Timestamp(evening)));
+        assert_eq!(closing.problems.len(), 2);
+        assert!(!closing.is_ok());
+        assert!(checkpoint_db
+            .close_day(date.succ_opt().unwrap(), evening, &options)
+            .unwrap()
+            .problems
+            .contains(&DayProblem::NoCheckpoints));
+    }
+}
@@ -0,0 +1,368 @@
+use crate::{
+    CheckpointDb, CheckpointDbError, ImportSummary, ImportedCheckpoint, Result, TimeRange,
+};
+use chrono::prelude::*;
+use std::io::{self, Read, Write};
+
+/// Options for `CheckpointDb::export_csv`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvOptions {
+    pub delimiter: char,
+    /// Whether to start with a row of column names.
+    pub header: bool,
+    /// A chrono format string for the timestamp column, which otherwise contains seconds since
+    /// the Unix epoch.
+    pub timestamp_format: Option<String>,
+    pub date_format: String,
+    pub time_format: String,
+}
+
+impl Default for CsvOptions {
+    fn default() -> CsvOptions {
+        CsvOptions {
+            delimiter: ',',
+            header: true,
+            timestamp_format: None,
+            date_format: "%Y-%m-%d".to_string(),
+            time_format: "%H:%M:%S".to_string(),
+        }
+    }
+}
+
+/// Describes which columns of a CSV file hold what, for `CheckpointDb::import_csv`. Columns
+/// are counted from zero.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvMapping {
+    pub delimiter: char,
+    /// Whether the first row holds column names and should be skipped.
+    pub header: bool,
+    /// The column holding the time of each checkpoint.
+    pub timestamp: usize,
+    /// A chrono format string for the local time in the timestamp column, which otherwise
+    /// holds seconds since the Unix epoch.
+    pub timestamp_format: Option<String>,
+    /// A column holding the date, for files where the timestamp column only holds the time of
+    /// day. The date and time are joined with a space before being parsed with
+    /// `timestamp_format`.
+    pub date: Option<usize>,
+    /// A column holding durations in seconds. If there is one, the timestamp is taken to be the
+    /// start of the tracked time and the checkpoint is placed at its end.
+    pub duration: Option<usize>,
+    /// A column holding project short names.
+    pub project: Option<usize>,
+    pub message: Option<usize>,
+}
+
+impl Default for CsvMapping {
+    /// The mapping of the files written by `CheckpointDb::export_csv` with default options.
+    fn default() -> CsvMapping {
+        CsvMapping {
+            delimiter: ',',
+            header: true,
+            timestamp: 0,
+            timestamp_format: None,
+            date: None,
+            duration: None,
+            project: Some(4),
+            message: Some(6),
+        }
+    }
+}
+
+const CSV_HEADER: [&str; 7] = [
+    "timestamp",
+    "date",
+    "time",
+    "duration",
+    "project_short_name",
+    "project_long_name",
+    "message",
+];
+
+impl CheckpointDb {
+    /// Writes the checkpoints in the given `TimeRange` as CSV, one row per checkpoint with its
+    /// timestamp, local date and time, duration in seconds, project names and message.
+    pub fn export_csv<W: Write>(
+        &self,
+        mut writer: W,
+        range: &TimeRange,
+        options: &CsvOptions,
+    ) -> Result<()> {
+        if options.header {
+            write_csv_row(&mut writer, &CSV_HEADER, options.delimiter)?;
+        }
+
+        for entry in self.entries_in(range) {
+            let time = Local
+                .timestamp_opt(entry.timestamp, 0)
+                .single()
+                .ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidData, "timestamp out of range")
+                })?;
+            let project = self.project_from_project_id(entry.checkpoint.project_id);
+
+            let timestamp = match &options.timestamp_format {
+                Some(format) => time.format(format).to_string(),
+                None => entry.timestamp.to_string(),
+            };
+            let date = time.format(&options.date_format).to_string();
+            let time_of_day = time.format(&options.time_format).to_string();
+            let duration = entry.duration.to_string();
+
+            write_csv_row(
+                &mut writer,
+                &[
+                    &timestamp,
+                    &date,
+                    &time_of_day,
+                    &duration,
+                    project.map_or("", |project| &project.short_name),
+                    project.map_or("", |project| &project.long_name),
+                    &entry.checkpoint.message,
+                ],
+                options.delimiter,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads checkpoints from CSV according to the given `CsvMapping` and imports them. If
+    /// `dry_run` is set, nothing is added and the returned summary tells what would have been.
+    pub fn import_csv<R: Read>(
+        &mut self,
+        mut reader: R,
+        mapping: &CsvMapping,
+        dry_run: bool,
+    ) -> Result<ImportSummary> {
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+
+        let rows = parse_csv(&input, mapping.delimiter);
+        let skip = if mapping.header { 1 } else { 0 };
+        let checkpoints = rows
+            .iter()
+            .enumerate()
+            .skip(skip)
+            .filter(|(_, row)| row.iter().any(|field| !field.is_empty()))
+            .map(|(i, row)| {
+                read_csv_checkpoint(row, mapping).map_err(|message| {
+                    CheckpointDbError::InvalidInput(format!("row {}: {}", i + 1, message))
+                })
+            })
+            .collect::<Result<Vec<ImportedCheckpoint>>>()?;
+
+        self.import(checkpoints, dry_run)
+    }
+}
+
+/// Reads one `ImportedCheckpoint` from a row according to the `CsvMapping`.
+fn read_csv_checkpoint(row: &[String], mapping: &CsvMapping) -> Result<ImportedCheckpoint, String> {
+    let column = |column: usize| {
+        row.get(column)
+            .map(|field| field.trim())
+            .ok_or_else(|| format!("there is no column {}", column))
+    };
+
+    let timestamp = column(mapping.timestamp)?;
+    let timestamp = match &mapping.timestamp_format {
+        Some(format) => {
+            let value = match mapping.date {
+                Some(date) => format!("{} {}", column(date)?, timestamp),
+                None => timestamp.to_string(),
+            };
+            parse_local_time(&value, format)?
+        }
+        None => timestamp
+            .parse::<i64>()
+            .map_err(|e| format!("could not parse the timestamp {:?}: {}", timestamp, e))?,
+    };
+
+    let (start, timestamp) = match mapping.duration {
+        Some(duration) => {
+            let duration = column(duration)?;
+            let duration = duration
+                .parse::<i64>()
+                .map_err(|e| format!("could not parse the duration {:?}: {}", duration, e))?;
+            if duration <= 0 {
+                return Err(format!("the duration {} is not positive", duration));
+            }
+            (Some(timestamp), timestamp + duration)
+        }
+        None => (None, timestamp),
+    };
+
+    Ok(ImportedCheckpoint {
+        timestamp,
+        message: match mapping.message {
+            Some(message) => column(message)?.to_string(),
+            None => String::new(),
+        },
+        project: match mapping.project {
+            Some(project) => Some(column(project)?)
+                .filter(|short_name| !short_name.is_empty())
+                .map(str::to_string),
+            None => None,
+        },
+        tags: Vec::new(),
+        start,
+        external_id: None,
+        kind: None,
+    })
+}
+
+/// Returns the index of the column with the given name in a header row.
+pub(crate) fn header_column(header: &[String], name: &str) -> Result<usize, String> {
+    header
+        .iter()
+        .position(|field| field.trim() == name)
+        .ok_or_else(|| format!("there is no {:?} column", name))
+}
+
+/// Parses a local date and time with the given chrono format string into a timestamp.
+pub(crate) fn parse_local_time(value: &str, format: &str) -> Result<i64, String> {
+    let time = NaiveDateTime::parse_from_str(value, format)
+        .map_err(|e| format!("could not parse the time {:?}: {}", value, e))?;
+    Local
+        .from_local_datetime(&time)
+        .earliest()
+        .map(|time| time.timestamp())
+        .ok_or_else(|| format!("the time {:?} does not exist", value))
+}
+
+/// Splits CSV into rows of fields, handling quoted fields.
+pub(crate) fn parse_csv(input: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if quoted {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => quoted = false,
+                _ => field.push(c),
+            }
+        } else if c == '"' {
+            quoted = true;
+        } else if c == delimiter {
+            row.push(std::mem::take(&mut field));
+        } else if c == '\n' {
+            row.push(std::mem::take(&mut field));
+            rows.push(std::mem::take(&mut row));
+        } else if c != '\r' {
+            field.push(c);
+        }
+    }
+
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    rows
+}
+
+/// Writes one CSV row, quoting the fields that need it.
+pub(crate) fn write_csv_row<W: Write>(
+    writer: &mut W,
+    fields: &[&str],
+    delimiter: char,
+) -> io::Result<()> {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            write!(writer, "{}", delimiter)?;
+        }
+
+        if field.contains([delimiter, '"', '\n', '\r']) {
+            write!(writer, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            write!(writer, "{}", field)?;
+        }
+    }
+    writeln!(writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProjectId;
+
+    #[test]
+    /// Exports with a custom delimiter, and checks that fields containing it get quoted.
+    fn export_csv() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Project", "prj").unwrap();
+        checkpoint_db
+            .add_checkpoint(1000, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(1600, "Fix; \"the\" bug", project_id)
+            .unwrap();
+
+        let options = CsvOptions {
+            delimiter: ';',
+            header: false,
+            ..CsvOptions::default()
+        };
+        let mut output = Vec::new();
+        checkpoint_db
+            .export_csv(&mut output, &TimeRange::new(1500, 2000), &options)
+            .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let date = Local.timestamp_opt(1600, 0).unwrap();
+        assert_eq!(
+            output,
+            format!(
+                "1600;{};{};600;prj;Project;\"Fix; \"\"the\"\" bug\"\n",
+                date.format("%Y-%m-%d"),
+                date.format("%H:%M:%S")
+            )
+        );
+    }
+
+    #[test]
+    /// Imports intervals given as a start time and a duration, first as a dry run, and refuses
+    /// ones that don't last.
+    fn import_csv() {
+        let input = "when,minutes,project,what\n\
+                     2021-06-15 09:00,3600,prj,\"Planning, mostly\"\n\
+                     2021-06-15 10:00,1800,,Email\n";
+        let mapping = CsvMapping {
+            timestamp: 0,
+            timestamp_format: Some("%Y-%m-%d %H:%M".to_string()),
+            duration: Some(1),
+            project: Some(2),
+            message: Some(3),
+            ..CsvMapping::default()
+        };
+
+        let mut checkpoint_db = CheckpointDb::new();
+        let summary = checkpoint_db
+            .import_csv(input.as_bytes(), &mapping, true)
+            .unwrap();
+        assert!(checkpoint_db.checkpoints.is_empty());
+        assert_eq!(summary.new_projects, vec!["prj".to_string()]);
+        assert_eq!(summary.checkpoints[0].message, "Planning, mostly");
+        assert_eq!(summary.breaks.len(), 1);
+
+        checkpoint_db
+            .import_csv(input.as_bytes(), &mapping, false)
+            .unwrap();
+        assert_eq!(checkpoint_db.checkpoints.len(), 3);
+        assert!(checkpoint_db.project_id_from_short_name("prj").is_some());
+
+        // An interval can't end before it starts.
+        let input = "when,minutes,project,what\n2021-06-16 09:00,-60,,Email\n";
+        let error = checkpoint_db
+            .import_csv(input.as_bytes(), &mapping, false)
+            .unwrap_err();
+        assert!(error.to_string().contains("row 2"));
+    }
+}
@@ -0,0 +1,278 @@
+use crate::{
+    CheckpointDb, CheckpointDbError, DbFile, LogCheckpoint, Operation, Report, Result, Status,
+    TimeRange, Timestamp,
+};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::Path,
+    sync::{Arc, PoisonError, RwLock},
+    thread,
+};
+
+/// A request to a `Daemon`, sent as a line of JSON.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum DaemonRequest {
+    /// Changes the database and saves it. A change that can't be saved isn't made.
+    Apply {
+        operation: Operation,
+    },
+    /// Asks for the `Status` at a time, usually now.
+    Status {
+        now: Timestamp,
+    },
+    Log {
+        range: TimeRange,
+    },
+    Report {
+        range: TimeRange,
+    },
+}
+
+/// The answer to a `DaemonRequest`, sent as a line of JSON.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "response", rename_all = "snake_case")]
+pub enum DaemonResponse {
+    /// The operation was applied and saved.
+    Applied,
+    /// The status, which is missing if there are no checkpoints.
+    Status {
+        status: Option<Status>,
+    },
+    Log {
+        log: Vec<LogCheckpoint>,
+    },
+    Report {
+        report: Report,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Holds a database open and answers requests about it on a Unix socket, so that short-lived
+/// programs don't each have to read the whole file, and changes from several of them can't
+/// overwrite each other.
+///
+/// Each connection is served on its own thread. Changes are applied and saved one at a time,
+/// by appending them to the delta file like `DbFile::save_delta`, while reads can happen at the
+/// same time.
+#[derive(Clone, Debug)]
+pub struct Daemon {
+    db_file: Arc<RwLock<DbFile>>,
+}
+
+impl Daemon {
+    /// Reads the database at the path, which changes are saved to, creating it if there is no
+    /// file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Daemon> {
+        let db_file = CheckpointDb::options().create(true).open(path)?;
+        Ok(Daemon {
+            db_file: Arc::new(RwLock::new(db_file)),
+        })
+    }
+
+    /// Listens on the socket until it fails, replacing any socket file left at the path.
+    pub fn serve<P: AsRef<Path>>(&self, socket_path: P) -> Result<()> {
+        let socket_path = socket_path.as_ref();
+        match std::fs::remove_file(socket_path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            _ => (),
+        }
+
+        let listener = UnixListener::bind(socket_path)?;
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let daemon = self.clone();
+            thread::spawn(move || daemon.handle(stream));
+        }
+        Ok(())
+    }
+
+    /// Answers requests on the connection until it's closed.
+    pub fn handle(&self, stream: UnixStream) -> Result<()> {
+        let mut writer = stream.try_clone()?;
+        for line in BufReader::new(stream).lines() {
+            let response = match serde_json::from_str(&line?) {
+                Ok(request) => self.respond(request),
+                Err(e) => DaemonResponse::Error {
+                    message: format!("invalid request: {}", e),
+                },
+            };
+            serde_json::to_writer(&mut writer, &response)?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn respond(&self, request: DaemonRequest) -> DaemonResponse {
+        match request {
+            DaemonRequest::Apply { operation } => {
+                let mut db_file = self.db_file.write().unwrap_or_else(PoisonError::into_inner);
+                // A change that couldn't be saved is dropped, so that it isn't saved by the next
+                // request instead.
+                let result = db_file.checkpoint_db_mut().apply(operation).and_then(|()| {
+                    match db_file.save_delta() {
+                        Ok(()) => Ok(()),
+                        Err(e) => db_file.revert().and(Err(e)),
+                    }
+                });
+                match result {
+                    Ok(()) => DaemonResponse::Applied,
+                    Err(e) => DaemonResponse::Error {
+                        message: e.to_string(),
+                    },
+                }
+            }
+            DaemonRequest::Status { now } => DaemonResponse::Status {
+                status: self.read(|checkpoint_db| checkpoint_db.current_status(now.seconds())),
+            },
+            DaemonRequest::Log { range } => DaemonResponse::Log {
+                log: self.read(|checkpoint_db| {
+                    checkpoint_db.get_log_between_times(range.start, range.end)
+                }),
+            },
+            DaemonRequest::Report { range } => DaemonResponse::Report {
+                report: self.read(|checkpoint_db| checkpoint_db.report(&range, None)),
+            },
+        }
+    }
+
+    /// Runs a function with the database locked for reading.
+    fn read<T, F: FnOnce(&CheckpointDb) -> T>(&self, f: F) -> T {
+        let db_file = self.db_file.read().unwrap_or_else(PoisonError::into_inner);
+        f(db_file.checkpoint_db())
+    }
+}
+
+/// A connection to a `Daemon`.
+#[derive(Debug)]
+pub struct DaemonClient {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+}
+
+impl DaemonClient {
+    pub fn connect<P: AsRef<Path>>(socket_path: P) -> Result<DaemonClient> {
+        UnixStream::connect(socket_path)?.try_into()
+    }
+
+    /// Sends a request and waits for the answer. Errors from the daemon are returned as
+    /// `DaemonResponse::Error`.
+    pub fn request(&mut self, request: &DaemonRequest) -> Result<DaemonResponse> {
+        serde_json::to_writer(&mut self.writer, request)?;
+        self.writer.write_all(b"\n")?;
+
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        Ok(serde_json::from_str(&line)?)
+    }
+
+    /// Applies an operation to the daemon's database, failing with
+    /// `CheckpointDbError::InvalidInput` if the daemon refused it.
+    pub fn apply(&mut self, operation: Operation) -> Result<()> {
+        match self.request(&DaemonRequest::Apply { operation })? {
+            DaemonResponse::Applied => Ok(()),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    pub fn status(&mut self, now: Timestamp) -> Result<Option<Status>> {
+        match self.request(&DaemonRequest::Status { now })? {
+            DaemonResponse::Status { status } => Ok(status),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    /// Returns the checkpoints in the range, like `CheckpointDb::get_log_between_times`.
+    pub fn log(&mut self, range: TimeRange) -> Result<Vec<LogCheckpoint>> {
+        match self.request(&DaemonRequest::Log { range })? {
+            DaemonResponse::Log { log } => Ok(log),
+            response => Err(unexpected(response)),
+        }
+    }
+
+    pub fn report(&mut self, range: TimeRange) -> Result<Report> {
+        match self.request(&DaemonRequest::Report { range })? {
+            DaemonResponse::Report { report } => Ok(report),
+            response => Err(unexpected(response)),
+        }
+    }
+}
+
+impl TryFrom<UnixStream> for DaemonClient {
+    type Error = CheckpointDbError;
+
+    fn try_from(stream: UnixStream) -> Result<DaemonClient> {
+        Ok(DaemonClient {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        })
+    }
+}
+
+fn unexpected(response: DaemonResponse) -> CheckpointDbError {
+    match response {
+        DaemonResponse::Error { message } => CheckpointDbError::InvalidInput(message),
+        response => CheckpointDbError::InvalidInput(format!(
+            "unexpected response from the daemon: {:?}",
+            response
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Checkpoint;
+
+    #[test]
+    /// Changes sent to the daemon are saved, and failed ones are reported back.
+    fn daemon_requests() {
+        let path = Path::new("test_files/daemon/db.json");
+        let _ = std::fs::remove_dir_all("test_files/daemon");
+        let daemon = Daemon::open(path).unwrap();
+        let (client_stream, daemon_stream) = UnixStream::pair().unwrap();
+        let server = thread::spawn(move || daemon.handle(daemon_stream));
+
+        let mut client = DaemonClient::try_from(client_stream).unwrap();
+        for timestamp in [1000, 1600] {
+            client
+                .apply(Operation::AddCheckpoint {
+                    timestamp: Timestamp(timestamp),
+                    checkpoint: Checkpoint::builder().message("Work").build(),
+                })
+                .unwrap();
+        }
+        assert!(client
+            .apply(Operation::RemoveCheckpoint {
+                timestamp: Timestamp(5000),
+            })
+            .is_err());
+
+        let status = client.status(Timestamp(2000)).unwrap().unwrap();
+        assert_eq!(status.elapsed, 400);
+        let report = client.report(TimeRange::new(0, 5000)).unwrap();
+        assert_eq!(report.duration.seconds(), 600);
+        assert_eq!(client.log(TimeRange::new(0, 5000)).unwrap().len(), 2);
+        assert_eq!(CheckpointDb::read(path).unwrap().len(), 2);
+        assert!(crate::options::delta_path(path).exists());
+
+        // A change that can't be saved isn't kept either.
+        std::fs::remove_file("test_files/daemon/db.json.lock").unwrap();
+        std::fs::create_dir("test_files/daemon/db.json.lock").unwrap();
+        assert!(client
+            .apply(Operation::AddCheckpoint {
+                timestamp: Timestamp(3000),
+                checkpoint: Checkpoint::default(),
+            })
+            .is_err());
+        assert_eq!(client.log(TimeRange::new(0, 5000)).unwrap().len(), 2);
+
+        drop(client);
+        server.join().unwrap().unwrap();
+    }
+}
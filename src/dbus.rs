@@ -0,0 +1,113 @@
+use crate::{Result, SharedCheckpointDb, Timestamp};
+use std::path::PathBuf;
+use zbus::{fdo, interface, object_server::SignalEmitter};
+
+/// The well-known name taken on the session bus by `serve_dbus`.
+pub const DBUS_NAME: &str = "com.orsvarn.TimeTrack";
+/// The path of the object that `serve_dbus` serves `DbusService` at.
+pub const DBUS_PATH: &str = "/com/orsvarn/TimeTrack";
+
+/// The `com.orsvarn.TimeTrack1` D-Bus interface, for desktop environments and other programs
+/// to read the status and add checkpoints without running a command.
+///
+/// - `Status() -> (b, x, s, x, s)` returns whether there are any checkpoints, then the
+///   timestamp, message and seconds since the latest one, and the short name of its project,
+///   which is empty for none.
+/// - `AddCheckpoint(s message, s project) -> x` adds a checkpoint now, in the project with the
+///   short name or id, or none if it's empty, and returns its timestamp.
+/// - The `CheckpointAdded(x timestamp)` signal is sent for checkpoints added through it.
+#[derive(Clone, Debug)]
+pub struct DbusService {
+    checkpoint_db: SharedCheckpointDb,
+    path: Option<PathBuf>,
+}
+
+impl DbusService {
+    /// Serves the database, saving it to `path` after every change when given one.
+    pub fn new(checkpoint_db: SharedCheckpointDb, path: Option<PathBuf>) -> DbusService {
+        DbusService {
+            checkpoint_db,
+            path,
+        }
+    }
+
+    fn add(&self, message: &str, project: &str) -> Result<Timestamp> {
+        let mut checkpoint_db = self.checkpoint_db.write();
+        let project_id = checkpoint_db.resolve_project(project)?;
+        let timestamp = Timestamp::now();
+        checkpoint_db.add_checkpoint(timestamp, message, project_id)?;
+        if let Some(path) = &self.path {
+            checkpoint_db.write(path)?;
+        }
+        Ok(timestamp)
+    }
+}
+
+#[interface(name = "com.orsvarn.TimeTrack1")]
+impl DbusService {
+    fn status(&self) -> (bool, i64, String, i64, String) {
+        let checkpoint_db = self.checkpoint_db.read();
+        match checkpoint_db.current_status(Timestamp::now().seconds()) {
+            Some(status) => (
+                true,
+                status.latest.timestamp.seconds(),
+                status.latest.checkpoint.message.to_string(),
+                status.elapsed,
+                checkpoint_db
+                    .project_from_project_id(status.project_id)
+                    .map(|project| project.short_name.clone())
+                    .unwrap_or_default(),
+            ),
+            None => (false, 0, String::new(), 0, String::new()),
+        }
+    }
+
+    async fn add_checkpoint(
+        &self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        message: &str,
+        project: &str,
+    ) -> fdo::Result<i64> {
+        let timestamp = self
+            .add(message, project)
+            .map_err(|e| fdo::Error::InvalidArgs(e.to_string()))?
+            .seconds();
+        DbusService::checkpoint_added(&emitter, timestamp).await?;
+        Ok(timestamp)
+    }
+
+    #[zbus(signal)]
+    async fn checkpoint_added(emitter: &SignalEmitter<'_>, timestamp: i64) -> zbus::Result<()>;
+}
+
+/// Serves the database on the session bus under `DBUS_NAME`, for as long as the returned
+/// connection is kept.
+pub fn serve_dbus(service: DbusService) -> Result<zbus::blocking::Connection> {
+    Ok(zbus::blocking::connection::Builder::session()?
+        .name(DBUS_NAME)?
+        .serve_at(DBUS_PATH, service)?
+        .build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CheckpointDb;
+
+    #[test]
+    /// Checkpoints added through the service show up in its status.
+    fn status_after_add() {
+        let mut checkpoint_db = CheckpointDb::new();
+        checkpoint_db.add_project("Website", "web").unwrap();
+        let service = DbusService::new(checkpoint_db.into(), None);
+        assert!(!service.status().0);
+
+        let timestamp = service.add("Styling", "web").unwrap();
+        assert!(service.add("", "wbe").is_err());
+        let (has_checkpoint, latest, message, _, project) = service.status();
+        assert!(has_checkpoint);
+        assert_eq!(latest, timestamp.seconds());
+        assert_eq!(message, "Styling");
+        assert_eq!(project, "web");
+    }
+}
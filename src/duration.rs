@@ -0,0 +1,258 @@
+use crate::{CheckpointDbError, Result};
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Neg, Sub, SubAssign},
+    str::FromStr,
+};
+
+/// An amount of tracked time in seconds, like the duration of a checkpoint or a report total.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json", serde(transparent))]
+pub struct TrackedDuration(pub i64);
+
+impl TrackedDuration {
+    pub const ZERO: TrackedDuration = TrackedDuration(0);
+
+    pub fn from_hours_minutes(hours: i64, minutes: i64) -> TrackedDuration {
+        TrackedDuration(hours * 3600 + minutes * 60)
+    }
+
+    pub fn seconds(self) -> i64 {
+        self.0
+    }
+
+    /// Returns the whole hours and the remaining minutes, rounded to the nearest minute. Both
+    /// are negative for negative durations.
+    pub fn as_hours_minutes(self) -> (i64, i64) {
+        let minutes = (self.0.abs() + 30) / 60 * self.0.signum();
+        (minutes / 60, minutes % 60)
+    }
+
+    /// Returns the duration in decimal hours, like 1.5 for an hour and a half.
+    pub fn as_hours(self) -> f64 {
+        self.0 as f64 / 3600.0
+    }
+
+    /// Formats the duration with a pattern where `H` is the hours, `M` the minutes and `S` the
+    /// seconds, and doubling a letter pads it to two digits, like `H:MM` for `1:05` or
+    /// `HH:MM:SS` for `01:05:00`. Without seconds in the pattern, the duration is rounded to the
+    /// nearest minute. Negative durations start with a minus sign.
+    pub fn format(self, pattern: &str) -> String {
+        let mut seconds = self.0.abs();
+        if !pattern.contains('S') {
+            seconds = (seconds + 30) / 60 * 60;
+        }
+        let sign = if self.0 < 0 && seconds > 0 { "-" } else { "" };
+        let values = [
+            ('H', seconds / 3600),
+            ('M', seconds % 3600 / 60),
+            ('S', seconds % 60),
+        ];
+
+        let mut formatted = sign.to_string();
+        let mut chars = pattern.chars().peekable();
+        while let Some(c) = chars.next() {
+            match values.iter().find(|(letter, _)| *letter == c) {
+                Some((_, value)) if chars.peek() == Some(&c) => {
+                    chars.next();
+                    formatted.push_str(&format!("{:02}", value));
+                }
+                Some((_, value)) => formatted.push_str(&value.to_string()),
+                None => formatted.push(c),
+            }
+        }
+        formatted
+    }
+}
+
+/// Writes the duration as hours and minutes, like `12:05`.
+impl fmt::Display for TrackedDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.format("H:MM"))
+    }
+}
+
+impl FromStr for TrackedDuration {
+    type Err = CheckpointDbError;
+
+    /// Parses amounts of time with units like `1h30m`, `90min`, `1.5 hours` or `2d`, and
+    /// clock-like durations like `0:45` or `1:30:15`. A leading minus sign makes the duration
+    /// negative.
+    fn from_str(s: &str) -> Result<TrackedDuration> {
+        let invalid = || CheckpointDbError::InvalidInput(format!("{:?} is not a duration", s));
+        let text: String = s.split_whitespace().collect::<String>().to_lowercase();
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(text) => (true, text),
+            None => (false, text.as_str()),
+        };
+        let seconds = if text.contains(':') {
+            parse_clock(text)
+        } else {
+            parse_amounts(text)
+        }
+        .ok_or_else(invalid)?;
+
+        Ok(TrackedDuration(if negative { -seconds } else { seconds }))
+    }
+}
+
+/// Parses `H:MM` or `H:MM:SS` into seconds.
+fn parse_clock(text: &str) -> Option<i64> {
+    let parts: Vec<&str> = text.split(':').collect();
+    let (hours, minutes, seconds) = match parts.as_slice() {
+        [hours, minutes] => (hours, minutes, &"00"),
+        [hours, minutes, seconds] => (hours, minutes, seconds),
+        _ => return None,
+    };
+    let sixtieths = |part: &str| match part.parse::<i64>() {
+        Ok(value) if part.len() == 2 && value < 60 => Some(value),
+        _ => None,
+    };
+    if hours.is_empty() || !hours.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let hours: i64 = hours.parse().ok()?;
+    hours
+        .checked_mul(3600)?
+        .checked_add(sixtieths(minutes)? * 60 + sixtieths(seconds)?)
+}
+
+/// Parses amounts followed by units, like `1h30m`, into seconds.
+fn parse_amounts(text: &str) -> Option<i64> {
+    if text.is_empty() {
+        return None;
+    }
+
+    let mut seconds = 0;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let number_length = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let amount: f64 = rest[..number_length].parse().ok()?;
+        rest = &rest[number_length..];
+        let unit_length = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let unit = match &rest[..unit_length] {
+            "s" | "sec" | "secs" | "second" | "seconds" => 1.0,
+            "m" | "min" | "mins" | "minute" | "minutes" => 60.0,
+            "h" | "hr" | "hrs" | "hour" | "hours" => 3600.0,
+            "d" | "day" | "days" => 86400.0,
+            "w" | "week" | "weeks" => 7.0 * 86400.0,
+            _ => return None,
+        };
+        rest = &rest[unit_length..];
+
+        let amount = (amount * unit).round();
+        if !amount.is_finite() || amount > i64::MAX as f64 {
+            return None;
+        }
+        seconds = i64::checked_add(seconds, amount as i64)?;
+    }
+    Some(seconds)
+}
+
+/// Drops any fraction of a second.
+#[cfg(feature = "time")]
+impl From<time::Duration> for TrackedDuration {
+    fn from(duration: time::Duration) -> TrackedDuration {
+        TrackedDuration(duration.whole_seconds())
+    }
+}
+
+#[cfg(feature = "time")]
+impl From<TrackedDuration> for time::Duration {
+    fn from(duration: TrackedDuration) -> time::Duration {
+        time::Duration::seconds(duration.0)
+    }
+}
+
+impl From<i64> for TrackedDuration {
+    fn from(seconds: i64) -> TrackedDuration {
+        TrackedDuration(seconds)
+    }
+}
+
+impl From<TrackedDuration> for i64 {
+    fn from(duration: TrackedDuration) -> i64 {
+        duration.0
+    }
+}
+
+impl Add for TrackedDuration {
+    type Output = TrackedDuration;
+
+    fn add(self, other: TrackedDuration) -> TrackedDuration {
+        TrackedDuration(self.0 + other.0)
+    }
+}
+
+impl AddAssign for TrackedDuration {
+    fn add_assign(&mut self, other: TrackedDuration) {
+        self.0 += other.0;
+    }
+}
+
+impl Sub for TrackedDuration {
+    type Output = TrackedDuration;
+
+    fn sub(self, other: TrackedDuration) -> TrackedDuration {
+        TrackedDuration(self.0 - other.0)
+    }
+}
+
+impl SubAssign for TrackedDuration {
+    fn sub_assign(&mut self, other: TrackedDuration) {
+        self.0 -= other.0;
+    }
+}
+
+impl Neg for TrackedDuration {
+    type Output = TrackedDuration;
+
+    fn neg(self) -> TrackedDuration {
+        TrackedDuration(-self.0)
+    }
+}
+
+impl Sum for TrackedDuration {
+    fn sum<I: Iterator<Item = TrackedDuration>>(iter: I) -> TrackedDuration {
+        iter.fold(TrackedDuration::ZERO, Add::add)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Rounds to minutes unless the pattern has seconds, and keeps the sign.
+    fn format() {
+        let duration = TrackedDuration::from_hours_minutes(1, 5) + TrackedDuration(40);
+        assert_eq!(duration.to_string(), "1:06");
+        assert_eq!(duration.format("HH:MM:SS"), "01:05:40");
+        assert_eq!((-duration).format("Hh Mm"), "-1h 6m");
+        assert_eq!(duration.as_hours_minutes(), (1, 6));
+        assert_eq!(TrackedDuration(5400).as_hours(), 1.5);
+    }
+
+    #[test]
+    /// Durations are parsed with units or like a clock.
+    fn from_str() {
+        let parse = |s: &str| s.parse::<TrackedDuration>().unwrap().seconds();
+        assert_eq!(parse("1h30m"), 5400);
+        assert_eq!(parse("90min"), 5400);
+        assert_eq!(parse("1.5 hours"), 5400);
+        assert_eq!(parse("0:45"), 2700);
+        assert_eq!(parse("1:30:15"), 5415);
+        assert_eq!(parse("-10m"), -600);
+
+        for s in ["", "45", "1:5", "0:60", "1h30", "h", "1.2.3h", ":30"] {
+            assert!(s.parse::<TrackedDuration>().is_err(), "{}", s);
+        }
+    }
+}
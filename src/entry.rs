@@ -0,0 +1,143 @@
+use crate::{
+    Checkpoint, CheckpointDb, CheckpointId, CheckpointKind, Operation, Pomodoro, ProjectId, Result,
+    Timestamp,
+};
+
+/// A checkpoint that exists in a `CheckpointDb`, borrowed for changing it. Changes are applied
+/// as operations, so they go through the same validation as the `set_checkpoint_*` functions.
+#[derive(Debug)]
+pub struct CheckpointEntry<'a> {
+    checkpoint_db: &'a mut CheckpointDb,
+    timestamp: Timestamp,
+}
+
+impl CheckpointDb {
+    /// Returns the checkpoint at the given `CheckpointId` for changing it, or `None` if there is
+    /// no such checkpoint.
+    pub fn checkpoint_entry(
+        &mut self,
+        checkpoint_id: &CheckpointId,
+    ) -> Option<CheckpointEntry<'_>> {
+        let timestamp = Timestamp(checkpoint_id.to_timestamp(self)?);
+        Some(CheckpointEntry {
+            checkpoint_db: self,
+            timestamp,
+        })
+    }
+}
+
+impl CheckpointEntry<'_> {
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp.seconds()
+    }
+
+    pub fn checkpoint(&self) -> &Checkpoint {
+        &self.checkpoint_db.checkpoints[&self.timestamp.seconds()]
+    }
+
+    pub fn set_message(&mut self, message: &str) -> Result<&mut Self> {
+        self.apply(Operation::SetCheckpointMessage {
+            timestamp: self.timestamp,
+            message: message.to_string(),
+        })
+    }
+
+    /// Moves the checkpoint to a project, failing if the project doesn't exist.
+    pub fn set_project(&mut self, project_id: ProjectId) -> Result<&mut Self> {
+        self.apply(Operation::SetCheckpointProject {
+            timestamp: self.timestamp,
+            project_id,
+        })
+    }
+
+    pub fn set_kind(&mut self, kind: CheckpointKind) -> Result<&mut Self> {
+        self.apply(Operation::SetCheckpointKind {
+            timestamp: self.timestamp,
+            kind,
+        })
+    }
+
+    pub fn set_pomodoro(&mut self, pomodoro: Option<Pomodoro>) -> Result<&mut Self> {
+        self.apply(Operation::SetCheckpointPomodoro {
+            timestamp: self.timestamp,
+            pomodoro,
+        })
+    }
+
+    pub fn set_tags(&mut self, tags: Vec<String>) -> Result<&mut Self> {
+        self.apply(Operation::SetCheckpointTags {
+            timestamp: self.timestamp,
+            tags,
+        })
+    }
+
+    /// Removes the checkpoint from the database and returns it. Like any other change, this
+    /// fails if the checkpoint is in a locked timesheet.
+    pub fn remove(self) -> Result<Checkpoint> {
+        let checkpoint = self.checkpoint().clone();
+        self.checkpoint_db.apply(Operation::RemoveCheckpoint {
+            timestamp: self.timestamp,
+        })?;
+        Ok(checkpoint)
+    }
+
+    fn apply(&mut self, operation: Operation) -> Result<&mut Self> {
+        self.checkpoint_db.apply(operation)?;
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    /// Changes go through validation, and the entry can remove its checkpoint.
+    fn checkpoint_entry() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Website", "web").unwrap();
+        checkpoint_db
+            .add_checkpoint(1000, "", ProjectId::NoId)
+            .unwrap();
+
+        let mut entry = checkpoint_db
+            .checkpoint_entry(&CheckpointId::Position(0))
+            .unwrap();
+        entry
+            .set_message("Fix the header")
+            .unwrap()
+            .set_tags(vec!["bug".to_string()])
+            .unwrap();
+        entry.set_project(project_id).unwrap();
+        assert!(entry.set_project(ProjectId::Id(7)).is_err());
+        assert_eq!(entry.checkpoint().project_id, project_id);
+        assert_eq!(&*entry.remove().unwrap().message, "Fix the header");
+        assert!(checkpoint_db.is_empty());
+    }
+
+    #[test]
+    /// Checkpoints in a submitted timesheet can't be changed or removed through an entry.
+    fn locked_entry() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let date = NaiveDate::from_ymd_opt(2021, 6, 16).unwrap();
+        let noon = checkpoint_db.day_range(date).start + 12 * 3600;
+        checkpoint_db
+            .add_checkpoint(noon, "Work", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db.submit_timesheet(date, None).unwrap();
+
+        let mut entry = checkpoint_db
+            .checkpoint_entry(&CheckpointId::Timestamp(noon))
+            .unwrap();
+        assert!(entry.set_message("Changed").is_err());
+        assert!(entry.remove().is_err());
+        assert_eq!(
+            &*checkpoint_db
+                .get_checkpoint(&CheckpointId::Timestamp(noon))
+                .unwrap()
+                .message,
+            "Work"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{CheckpointId, ClientId, ExpenseId, ProjectId, TimeRange, Timestamp};
+use std::{error, fmt, io};
+
+/// The ways operations on a `CheckpointDb` can fail.
+#[derive(Debug)]
+pub enum CheckpointDbError {
+    ProjectNotFound {
+        id: ProjectId,
+    },
+    /// No project has the short name or id, but some have similar names.
+    UnknownProject {
+        name: String,
+        suggestions: Vec<String>,
+    },
+    ClientNotFound {
+        id: ClientId,
+    },
+    CheckpointNotFound {
+        id: CheckpointId,
+    },
+    ExpenseNotFound {
+        id: ExpenseId,
+    },
+    /// There already is a checkpoint at the time.
+    CheckpointExists {
+        timestamp: Timestamp,
+    },
+    /// The time is in the future or before the earliest time allowed by the settings.
+    ImplausibleTimestamp {
+        timestamp: Timestamp,
+    },
+    /// Another project already has the short name.
+    DuplicateShortName {
+        name: String,
+    },
+    /// Another client already has the name.
+    DuplicateClientName {
+        name: String,
+    },
+    /// The checkpoint or expense has already been billed on an invoice.
+    AlreadyInvoiced {
+        invoice: String,
+    },
+    /// The invoice number has already been given to another invoice, possibly by another
+    /// frontend.
+    InvoiceNumberTaken {
+        number: u32,
+    },
+    /// The checkpoint is in a timesheet that has been submitted or approved.
+    TimesheetLocked {
+        range: TimeRange,
+    },
+    /// An argument or the data being imported isn't valid.
+    InvalidInput(String),
+    Io(io::Error),
+    #[cfg(feature = "json")]
+    Serde(serde_json::Error),
+    #[cfg(feature = "templates")]
+    Template(handlebars::RenderError),
+    #[cfg(any(feature = "caldav", feature = "sync", feature = "webhooks"))]
+    Http(Box<ureq::Error>),
+    #[cfg(feature = "dbus")]
+    Dbus(zbus::Error),
+    #[cfg(feature = "lua")]
+    Lua(mlua::Error),
+}
+
+impl fmt::Display for CheckpointDbError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ProjectNotFound { id } => write!(f, "could not find the project {}", id),
+            Self::UnknownProject { name, suggestions } => {
+                write!(f, "could not find the project {:?}", name)?;
+                if !suggestions.is_empty() {
+                    write!(f, ", did you mean {}?", suggestions.join(", "))?;
+                }
+                Ok(())
+            }
+            Self::ClientNotFound { id } => write!(f, "could not find the client {}", id),
+            Self::CheckpointNotFound { id } => write!(f, "could not find the checkpoint {}", id),
+            Self::ExpenseNotFound { id } => write!(f, "could not find the expense {}", id),
+            Self::CheckpointExists { timestamp } => {
+                write!(f, "there already is a checkpoint at {}", timestamp)
+            }
+            Self::ImplausibleTimestamp { timestamp } => write!(
+                f,
+                "{} is in the future or before the earliest allowed time",
+                timestamp
+            ),
+            Self::DuplicateShortName { name } => {
+                write!(f, "a project with the short name {:?} already exists", name)
+            }
+            Self::DuplicateClientName { name } => {
+                write!(f, "a client with the name {:?} already exists", name)
+            }
+            Self::AlreadyInvoiced { invoice } => {
+                write!(f, "it has already been invoiced on {}", invoice)
+            }
+            Self::InvoiceNumberTaken { number } => {
+                write!(f, "the invoice number {} has already been used", number)
+            }
+            Self::TimesheetLocked { range } => write!(
+                f,
+                "the checkpoint is in the submitted timesheet from {} to {}",
+                range.start, range.end
+            ),
+            Self::InvalidInput(message) => write!(f, "{}", message),
+            Self::Io(e) => write!(f, "{}", e),
+            #[cfg(feature = "json")]
+            Self::Serde(e) => write!(f, "{}", e),
+            #[cfg(feature = "templates")]
+            Self::Template(e) => write!(f, "{}", e),
+            #[cfg(any(feature = "caldav", feature = "sync", feature = "webhooks"))]
+            Self::Http(e) => write!(f, "{}", e),
+            #[cfg(feature = "dbus")]
+            Self::Dbus(e) => write!(f, "{}", e),
+            #[cfg(feature = "lua")]
+            Self::Lua(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for CheckpointDbError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            #[cfg(feature = "json")]
+            Self::Serde(e) => Some(e),
+            #[cfg(feature = "templates")]
+            Self::Template(e) => Some(e),
+            #[cfg(any(feature = "caldav", feature = "sync", feature = "webhooks"))]
+            Self::Http(e) => Some(e.as_ref()),
+            #[cfg(feature = "dbus")]
+            Self::Dbus(e) => Some(e),
+            #[cfg(feature = "lua")]
+            Self::Lua(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CheckpointDbError {
+    fn from(error: io::Error) -> CheckpointDbError {
+        CheckpointDbError::Io(error)
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for CheckpointDbError {
+    fn from(error: serde_json::Error) -> CheckpointDbError {
+        CheckpointDbError::Serde(error)
+    }
+}
+
+#[cfg(any(feature = "caldav", feature = "sync", feature = "webhooks"))]
+impl From<ureq::Error> for CheckpointDbError {
+    fn from(error: ureq::Error) -> CheckpointDbError {
+        CheckpointDbError::Http(Box::new(error))
+    }
+}
+
+#[cfg(feature = "dbus")]
+impl From<zbus::Error> for CheckpointDbError {
+    fn from(error: zbus::Error) -> CheckpointDbError {
+        CheckpointDbError::Dbus(error)
+    }
+}
+
+#[cfg(feature = "lua")]
+impl From<mlua::Error> for CheckpointDbError {
+    fn from(error: mlua::Error) -> CheckpointDbError {
+        CheckpointDbError::Lua(error)
+    }
+}
+
+/// The result of operations on a `CheckpointDb`.
+pub type Result<T, E = CheckpointDbError> = std::result::Result<T, E>;
@@ -0,0 +1,134 @@
+use crate::{CheckpointDb, ClientId, ExpenseId, ProjectId, Timestamp};
+use std::{fmt, sync::Arc};
+
+/// A change made to a `CheckpointDb`, passed to the functions registered with `on_change`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub enum ChangeEvent {
+    /// A checkpoint was added, possibly replacing one at the same time.
+    CheckpointAdded {
+        timestamp: Timestamp,
+    },
+    CheckpointChanged {
+        timestamp: Timestamp,
+    },
+    CheckpointRemoved {
+        timestamp: Timestamp,
+    },
+    ProjectAdded {
+        project_id: ProjectId,
+    },
+    ProjectChanged {
+        project_id: ProjectId,
+    },
+    ProjectRemoved {
+        project_id: ProjectId,
+    },
+    ClientAdded {
+        client_id: ClientId,
+    },
+    ClientChanged {
+        client_id: ClientId,
+    },
+    ClientRemoved {
+        client_id: ClientId,
+    },
+    /// The settings changed, or something else kept for the whole database, like its currency
+    /// or pay rules.
+    SettingsChanged,
+    /// The state of the timesheet of the week starting at the timestamp changed.
+    TimesheetChanged {
+        start: Timestamp,
+    },
+    ExpenseAdded {
+        expense_id: ExpenseId,
+    },
+    ExpenseChanged {
+        expense_id: ExpenseId,
+    },
+    ExpenseRemoved {
+        expense_id: ExpenseId,
+    },
+    /// The whole database was replaced, like when syncing, so anything may have changed.
+    Replaced,
+}
+
+type Listener = Arc<dyn Fn(&ChangeEvent) + Send + Sync>;
+
+/// The functions registered with `on_change`. They aren't part of the data, so they aren't
+/// saved, copied by `clone` or compared by `==`.
+#[derive(Default)]
+pub(crate) struct Listeners(Vec<Listener>);
+
+impl Listeners {
+    pub(crate) fn emit(&self, event: ChangeEvent) {
+        for listener in &self.0 {
+            listener(&event);
+        }
+    }
+}
+
+impl Clone for Listeners {
+    fn clone(&self) -> Listeners {
+        Listeners::default()
+    }
+}
+
+impl PartialEq for Listeners {
+    fn eq(&self, _other: &Listeners) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for Listeners {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} listeners", self.0.len())
+    }
+}
+
+impl CheckpointDb {
+    /// Registers a function to call with every change made to the database, after the change
+    /// has been made.
+    ///
+    /// The functions aren't copied when the database is cloned.
+    pub fn on_change<F: Fn(&ChangeEvent) + Send + Sync + 'static>(&mut self, listener: F) {
+        self.listeners.0.push(Arc::new(listener));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CheckpointId;
+    use std::sync::Mutex;
+
+    #[test]
+    /// Listeners are told about changes in the order they're made.
+    fn on_change() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        checkpoint_db.on_change(move |event| recorded.lock().unwrap().push(*event));
+
+        let project_id = checkpoint_db.add_project("Website", "web").unwrap();
+        checkpoint_db.add_checkpoint(1000, "", project_id).unwrap();
+        checkpoint_db.remove_project(project_id).unwrap();
+        checkpoint_db.remove_checkpoint(&CheckpointId::Timestamp(1000));
+        assert!(checkpoint_db
+            .set_checkpoint_tags(CheckpointId::Timestamp(1000), Vec::new())
+            .is_err());
+
+        let timestamp = Timestamp(1000);
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                ChangeEvent::ProjectAdded { project_id },
+                ChangeEvent::CheckpointAdded { timestamp },
+                ChangeEvent::CheckpointChanged { timestamp },
+                ChangeEvent::ProjectRemoved { project_id },
+                ChangeEvent::CheckpointRemoved { timestamp },
+            ]
+        );
+        assert_eq!(checkpoint_db.clone(), checkpoint_db);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::{CheckpointDb, CheckpointDbError, Operation, ProjectId, Result, TimeRange};
+use chrono::prelude::*;
+use std::{
+    fmt::{self, Display},
+    path::PathBuf,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ExpenseId(pub u32);
+
+impl Display for ExpenseId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Money spent on a project, like travel or licenses, which can be billed to its client on
+/// invoices along with the time.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Expense {
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub date: NaiveDate,
+    pub project_id: ProjectId,
+    /// The amount in the smallest unit of the currency, like cents.
+    pub amount: i64,
+    /// The currency of the amount, if it differs from the project's currency.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub currency: Option<String>,
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "String::is_empty")
+    )]
+    pub note: String,
+    /// The path of a scan or photo of the receipt.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub receipt: Option<PathBuf>,
+    /// Whether the expense is billed to the client of the project.
+    #[cfg_attr(feature = "json", serde(default = "crate::default_billable"))]
+    pub billable: bool,
+    /// The number of the invoice the expense has been billed on.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub invoice: Option<String>,
+}
+
+impl Expense {
+    /// Returns a billable expense without a note or a receipt, in the project's currency.
+    pub fn new(date: NaiveDate, project_id: ProjectId, amount: i64) -> Expense {
+        Expense {
+            date,
+            project_id,
+            amount,
+            currency: None,
+            note: String::new(),
+            receipt: None,
+            billable: true,
+            invoice: None,
+        }
+    }
+}
+
+impl CheckpointDb {
+    pub fn add_expense(&mut self, expense: Expense) -> Result<ExpenseId> {
+        let number = (0..=u32::MAX)
+            .find(|number| !self.expenses.contains_key(number))
+            .ok_or_else(|| {
+                CheckpointDbError::InvalidInput("There is no room for more expenses".to_string())
+            })?;
+        self.apply(Operation::AddExpense {
+            expense_id: ExpenseId(number),
+            expense,
+        })?;
+        Ok(ExpenseId(number))
+    }
+
+    pub fn remove_expense(&mut self, expense_id: ExpenseId) -> Result<Expense> {
+        let expense = self
+            .expense(expense_id)
+            .cloned()
+            .ok_or(CheckpointDbError::ExpenseNotFound { id: expense_id })?;
+        self.apply(Operation::RemoveExpense { expense_id })?;
+        Ok(expense)
+    }
+
+    pub fn expense(&self, expense_id: ExpenseId) -> Option<&Expense> {
+        self.expenses.get(&expense_id.0)
+    }
+
+    pub fn expenses(&self) -> impl Iterator<Item = (ExpenseId, &Expense)> {
+        self.expenses
+            .iter()
+            .map(|(id, expense)| (ExpenseId(*id), expense))
+    }
+
+    /// Returns the expenses on the days that start in the given `TimeRange`, by date.
+    pub fn expenses_in(&self, range: &TimeRange) -> Vec<(ExpenseId, &Expense)> {
+        let mut expenses: Vec<_> = self
+            .expenses()
+            .filter(|(_, expense)| range.contains(self.day_range(expense.date).start))
+            .collect();
+        expenses.sort_by_key(|(expense_id, expense)| (expense.date, *expense_id));
+        expenses
+    }
+
+    /// Returns the currency of the expense, which is the currency of its project unless it has
+    /// its own.
+    pub fn expense_currency<'a>(&'a self, expense: &'a Expense) -> Option<&'a str> {
+        expense
+            .currency
+            .as_deref()
+            .or_else(|| self.project_currency(expense.project_id))
+    }
+
+    /// Marks the expense as billed on the given invoice. Fails if it has already been
+    /// invoiced.
+    pub fn mark_expense_invoiced(&mut self, expense_id: ExpenseId, invoice: &str) -> Result<()> {
+        self.apply(Operation::MarkExpenseInvoiced {
+            expense_id,
+            invoice: invoice.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Invoice;
+
+    #[test]
+    /// Billable expenses of the client's projects are invoiced once, along with the time.
+    fn invoice_expenses() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let client_id = checkpoint_db.add_client("Client").unwrap();
+        let project_id = checkpoint_db.add_project("Project", "prj").unwrap();
+        checkpoint_db
+            .set_project_client(project_id, Some(client_id))
+            .unwrap();
+        checkpoint_db
+            .set_project_rate(project_id, Some(6000))
+            .unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2024, 6, 3).unwrap();
+        let start = checkpoint_db.day_range(date).start;
+        checkpoint_db
+            .add_checkpoint(start + 8 * 3600, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 9 * 3600, "Work", project_id)
+            .unwrap();
+        let mut train = Expense::new(date, project_id, 4500);
+        train.note = "Train tickets".to_string();
+        let train_id = checkpoint_db.add_expense(train).unwrap();
+        let mut lunch = Expense::new(date, project_id, 1200);
+        lunch.billable = false;
+        checkpoint_db.add_expense(lunch).unwrap();
+        assert!(checkpoint_db
+            .add_expense(Expense::new(date, ProjectId::Id(9), 100))
+            .is_err());
+
+        let range = checkpoint_db.day_range(date);
+        assert_eq!(checkpoint_db.expenses_in(&range).len(), 2);
+        let invoice = Invoice::builder(client_id, range)
+            .build(&mut checkpoint_db)
+            .unwrap();
+        assert_eq!(invoice.lines.len(), 2);
+        assert_eq!(invoice.lines[1].description, "Train tickets");
+        assert_eq!(invoice.lines[1].expense_id, Some(train_id));
+        assert_eq!(invoice.subtotal, 6000 + 4500);
+        assert_eq!(
+            checkpoint_db.expense(train_id).unwrap().invoice.as_deref(),
+            Some(invoice.number.as_str())
+        );
+        assert!(checkpoint_db
+            .mark_expense_invoiced(train_id, "other")
+            .is_err());
+
+        let invoice = Invoice::builder(client_id, range)
+            .preview(&checkpoint_db)
+            .unwrap();
+        assert!(invoice.lines.is_empty());
+        checkpoint_db.remove_expense(train_id).unwrap();
+        assert!(checkpoint_db.expense(train_id).is_none());
+    }
+}
@@ -0,0 +1,262 @@
+use crate::{CheckpointDb, CheckpointDbError, CheckpointKind, ProjectId};
+use std::{
+    ffi::{c_char, CStr, CString},
+    path::Path,
+    ptr,
+};
+
+/// The result of a call through the C interface. The values won't change between versions.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TtStatus {
+    Ok = 0,
+    /// A pointer that must not be null was null.
+    NullArgument = 1,
+    /// A string wasn't valid UTF-8.
+    InvalidUtf8 = 2,
+    ProjectNotFound = 3,
+    /// The file couldn't be read or written.
+    Io = 4,
+    /// The file isn't a valid database.
+    InvalidFile = 5,
+    Other = 6,
+    /// There already is a checkpoint at the time.
+    CheckpointExists = 7,
+}
+
+impl From<CheckpointDbError> for TtStatus {
+    fn from(error: CheckpointDbError) -> TtStatus {
+        match error {
+            CheckpointDbError::ProjectNotFound { .. } => TtStatus::ProjectNotFound,
+            CheckpointDbError::CheckpointExists { .. } => TtStatus::CheckpointExists,
+            CheckpointDbError::Io(_) => TtStatus::Io,
+            CheckpointDbError::Serde(_) => TtStatus::InvalidFile,
+            _ => TtStatus::Other,
+        }
+    }
+}
+
+/// A checkpoint returned by `tt_db_query_range`.
+#[repr(C)]
+pub struct TtCheckpoint {
+    pub timestamp: i64,
+    /// The seconds since the checkpoint before it, which is 0 for the oldest checkpoint.
+    pub duration: i64,
+    /// The id of the project, or -1 if it has none.
+    pub project_id: i32,
+    pub is_break: bool,
+    /// A null-terminated UTF-8 string owned by the list the checkpoint is in.
+    pub message: *mut c_char,
+}
+
+/// Checkpoints returned by `tt_db_query_range`, from the latest to the oldest. Must be freed
+/// with `tt_checkpoint_list_free`.
+#[repr(C)]
+pub struct TtCheckpointList {
+    pub checkpoints: *mut TtCheckpoint,
+    pub len: usize,
+}
+
+/// Returns a new empty database, which must be freed with `tt_db_free`.
+#[no_mangle]
+pub extern "C" fn tt_db_new() -> *mut CheckpointDb {
+    Box::into_raw(Box::new(CheckpointDb::new()))
+}
+
+/// Reads the database at the path, creating an empty one if there is no file, and stores it in
+/// `out`. It must be freed with `tt_db_free`. `out` is left untouched on failure.
+///
+/// # Safety
+///
+/// `path` must be a null-terminated string and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn tt_db_open(path: *const c_char, out: *mut *mut CheckpointDb) -> TtStatus {
+    if out.is_null() {
+        return TtStatus::NullArgument;
+    }
+    let path = match str_from_c(path) {
+        Ok(path) => path,
+        Err(status) => return status,
+    };
+
+    match CheckpointDb::read(Path::new(path)) {
+        Ok(checkpoint_db) => {
+            *out = Box::into_raw(Box::new(checkpoint_db));
+            TtStatus::Ok
+        }
+        Err(e) => e.into(),
+    }
+}
+
+/// Writes the database to the path.
+///
+/// # Safety
+///
+/// `db` must come from `tt_db_new` or `tt_db_open` and `path` must be a null-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn tt_db_write(db: *const CheckpointDb, path: *const c_char) -> TtStatus {
+    let Some(checkpoint_db) = db.as_ref() else {
+        return TtStatus::NullArgument;
+    };
+    let path = match str_from_c(path) {
+        Ok(path) => path,
+        Err(status) => return status,
+    };
+
+    match checkpoint_db.write(Path::new(path)) {
+        Ok(()) => TtStatus::Ok,
+        Err(e) => e.into(),
+    }
+}
+
+/// Adds a checkpoint at the timestamp, failing with `TtStatus::CheckpointExists` if there
+/// already is one there. A negative `project_id` means no project.
+///
+/// # Safety
+///
+/// `db` must come from `tt_db_new` or `tt_db_open` and `message` must be a null-terminated
+/// string.
+#[no_mangle]
+pub unsafe extern "C" fn tt_db_add_checkpoint(
+    db: *mut CheckpointDb,
+    timestamp: i64,
+    message: *const c_char,
+    project_id: i32,
+) -> TtStatus {
+    let Some(checkpoint_db) = db.as_mut() else {
+        return TtStatus::NullArgument;
+    };
+    let message = match str_from_c(message) {
+        Ok(message) => message,
+        Err(status) => return status,
+    };
+    let project_id = if project_id < 0 {
+        ProjectId::NoId
+    } else {
+        match u16::try_from(project_id) {
+            Ok(id) => ProjectId::Id(id),
+            Err(_) => return TtStatus::ProjectNotFound,
+        }
+    };
+
+    match checkpoint_db.add_checkpoint(timestamp, message, project_id) {
+        Ok(()) => TtStatus::Ok,
+        Err(e) => e.into(),
+    }
+}
+
+/// Returns the checkpoints between two timestamps, like `CheckpointDb::get_log_between_times`.
+/// A null `db` gives an empty list.
+///
+/// # Safety
+///
+/// `db` must be null or come from `tt_db_new` or `tt_db_open`.
+#[no_mangle]
+pub unsafe extern "C" fn tt_db_query_range(
+    db: *const CheckpointDb,
+    start: i64,
+    end: i64,
+) -> TtCheckpointList {
+    let Some(checkpoint_db) = db.as_ref() else {
+        return TtCheckpointList {
+            checkpoints: ptr::null_mut(),
+            len: 0,
+        };
+    };
+
+    let checkpoints: Box<[TtCheckpoint]> = checkpoint_db
+        .get_log_between_times(start, end)
+        .into_iter()
+        .map(|log| TtCheckpoint {
+            timestamp: log.timestamp.seconds(),
+            duration: log.duration.map_or(0, |duration| duration.seconds()),
+            project_id: match log.checkpoint.project_id {
+                ProjectId::Id(id) => i32::from(id),
+                ProjectId::NoId => -1,
+            },
+            is_break: log.checkpoint.kind == CheckpointKind::Break,
+            message: CString::new(log.checkpoint.message.replace('\0', ""))
+                .unwrap_or_default()
+                .into_raw(),
+        })
+        .collect();
+    let len = checkpoints.len();
+    TtCheckpointList {
+        checkpoints: Box::into_raw(checkpoints).cast(),
+        len,
+    }
+}
+
+/// Frees a list returned by `tt_db_query_range`, including its messages.
+///
+/// # Safety
+///
+/// `list` must come from `tt_db_query_range` and not have been freed already.
+#[no_mangle]
+pub unsafe extern "C" fn tt_checkpoint_list_free(list: TtCheckpointList) {
+    if list.checkpoints.is_null() {
+        return;
+    }
+    let checkpoints = Box::from_raw(ptr::slice_from_raw_parts_mut(list.checkpoints, list.len));
+    for checkpoint in checkpoints.iter() {
+        drop(CString::from_raw(checkpoint.message));
+    }
+}
+
+/// Frees a database. Null is ignored.
+///
+/// # Safety
+///
+/// `db` must be null or come from `tt_db_new` or `tt_db_open`, and not have been freed
+/// already.
+#[no_mangle]
+pub unsafe extern "C" fn tt_db_free(db: *mut CheckpointDb) {
+    if !db.is_null() {
+        drop(Box::from_raw(db));
+    }
+}
+
+unsafe fn str_from_c<'a>(string: *const c_char) -> Result<&'a str, TtStatus> {
+    if string.is_null() {
+        return Err(TtStatus::NullArgument);
+    }
+    CStr::from_ptr(string)
+        .to_str()
+        .map_err(|_| TtStatus::InvalidUtf8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Checkpoints added through the C interface can be queried and freed again.
+    fn add_and_query() {
+        let message = CString::new("Writing bindings").unwrap();
+        unsafe {
+            let db = tt_db_new();
+            assert_eq!(
+                tt_db_add_checkpoint(db, 1000, message.as_ptr(), -1),
+                TtStatus::Ok
+            );
+            assert_eq!(
+                tt_db_add_checkpoint(db, 2000, message.as_ptr(), 3),
+                TtStatus::ProjectNotFound
+            );
+            assert_eq!(
+                tt_db_add_checkpoint(db, 2000, ptr::null(), -1),
+                TtStatus::NullArgument
+            );
+
+            let list = tt_db_query_range(db, 0, 5000);
+            assert_eq!(list.len, 1);
+            let checkpoint = &*list.checkpoints;
+            assert_eq!(checkpoint.timestamp, 1000);
+            assert_eq!(checkpoint.project_id, -1);
+            assert_eq!(CStr::from_ptr(checkpoint.message), message.as_c_str());
+            tt_checkpoint_list_free(list);
+            tt_db_free(db);
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use crate::{Checkpoint, CheckpointDb, CheckpointDbError, Result, TimeRange, Timestamp};
+use chrono::prelude::*;
+use std::collections::BTreeMap;
+
+/// A standard working day, which `CheckpointDb::fill_range` adds to each working day of a
+/// range.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TemplateDay {
+    /// The checkpoints of the day at their wall clock times.
+    pub checkpoints: Vec<(NaiveTime, Checkpoint)>,
+    /// The weekdays that are filled, Monday to Friday by default.
+    pub weekdays: Vec<Weekday>,
+    /// The dates that aren't filled, like holidays and days off.
+    pub holidays: Vec<NaiveDate>,
+}
+
+impl Default for TemplateDay {
+    fn default() -> TemplateDay {
+        TemplateDay {
+            checkpoints: Vec::new(),
+            weekdays: vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ],
+            holidays: Vec::new(),
+        }
+    }
+}
+
+impl TemplateDay {
+    pub fn new() -> TemplateDay {
+        TemplateDay::default()
+    }
+
+    /// Adds a checkpoint at the time of day, keeping the ones added before it.
+    pub fn checkpoint<C: Into<Checkpoint>>(
+        mut self,
+        time: NaiveTime,
+        checkpoint: C,
+    ) -> TemplateDay {
+        self.checkpoints.push((time, checkpoint.into()));
+        self
+    }
+
+    pub fn holiday(mut self, date: NaiveDate) -> TemplateDay {
+        self.holidays.push(date);
+        self
+    }
+
+    fn fills(&self, date: NaiveDate) -> bool {
+        self.weekdays.contains(&date.weekday()) && !self.holidays.contains(&date)
+    }
+}
+
+impl CheckpointDb {
+    /// Adds the checkpoints of the template day to every day that starts in the range, on the
+    /// template's weekdays and not on its holidays, and returns the dates that were filled.
+    /// Days that already have checkpoints are left as they are. Fails at the first day whose
+    /// checkpoints can't all be added, like one in a submitted timesheet, without adding any of
+    /// them, keeping the days filled before it.
+    pub fn fill_range(
+        &mut self,
+        range: &TimeRange,
+        template: &TemplateDay,
+    ) -> Result<Vec<NaiveDate>> {
+        let mut date = self.date_of(range.start);
+        if self.day_range(date).start < range.start {
+            date = date.succ_opt().unwrap_or(date);
+        }
+
+        let mut filled = Vec::new();
+        loop {
+            let day = self.day_range(date);
+            if day.start >= range.end {
+                break;
+            }
+            let is_empty = self.checkpoints.range(day.start..day.end).next().is_none();
+            if template.fills(date) && is_empty {
+                let checkpoints = self.template_checkpoints(date, template)?;
+                for (timestamp, checkpoint) in checkpoints {
+                    self.add_checkpoint_full(timestamp, checkpoint)?;
+                }
+                filled.push(date);
+            }
+            match date.succ_opt() {
+                Some(next) => date = next,
+                None => break,
+            }
+        }
+        Ok(filled)
+    }
+
+    /// Returns the checkpoints of the template day on the date, failing if any of them can't be
+    /// added to the database.
+    fn template_checkpoints(
+        &self,
+        date: NaiveDate,
+        template: &TemplateDay,
+    ) -> Result<BTreeMap<Timestamp, Checkpoint>> {
+        let mut checkpoints = BTreeMap::new();
+        for (time, checkpoint) in &template.checkpoints {
+            let seconds = i64::from(time.num_seconds_from_midnight());
+            let timestamp = Timestamp(self.time_on(date, seconds));
+            self.check_plausible(timestamp)?;
+            self.check_unlocked(timestamp.seconds())?;
+            // Adding a checkpoint changes the duration of the next one.
+            if let Some((next, _)) = self.checkpoints.range(timestamp.seconds() + 1..).next() {
+                self.check_unlocked(*next)?;
+            }
+            self.check_project(checkpoint.project_id)?;
+            // With a day boundary, times before it are outside of the day that was checked to
+            // be empty.
+            if self.checkpoints.contains_key(&timestamp.seconds())
+                || checkpoints.insert(timestamp, checkpoint.clone()).is_some()
+            {
+                return Err(CheckpointDbError::CheckpointExists { timestamp });
+            }
+        }
+        Ok(checkpoints)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckpointKind, ProjectId, ReportTimeZone, Settings};
+
+    #[test]
+    /// Working days are filled with the template, but weekends, holidays and days with
+    /// checkpoints aren't.
+    fn fill_range() {
+        let mut checkpoint_db = CheckpointDb::new();
+        checkpoint_db
+            .set_settings(Settings {
+                time_zone: ReportTimeZone::Utc,
+                ..Settings::default()
+            })
+            .unwrap();
+        let project_id = checkpoint_db.add_project("Project", "prj").unwrap();
+        let date = |day| NaiveDate::from_ymd_opt(2024, 6, day).unwrap();
+        let time = |hour| NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+        let template = TemplateDay::new()
+            .checkpoint(time(9), Checkpoint::builder().message("Start"))
+            .checkpoint(
+                time(12),
+                Checkpoint::builder().message("Work").project(project_id),
+            )
+            .checkpoint(
+                time(13),
+                Checkpoint::builder()
+                    .message("Lunch")
+                    .kind(CheckpointKind::Break),
+            )
+            .checkpoint(
+                time(17),
+                Checkpoint::builder().message("Work").project(project_id),
+            )
+            .holiday(date(5));
+
+        // Something happened on Thursday.
+        let thursday = checkpoint_db.day_range(date(6)).start;
+        checkpoint_db
+            .add_checkpoint(thursday + 10 * 3600, "Conference", ProjectId::NoId)
+            .unwrap();
+
+        // From Monday to Sunday.
+        let range = TimeRange::new(
+            checkpoint_db.day_range(date(3)).start,
+            checkpoint_db.day_range(date(10)).start,
+        );
+        let filled = checkpoint_db.fill_range(&range, &template).unwrap();
+        assert_eq!(filled, [date(3), date(4), date(7)]);
+        let report = checkpoint_db.report(&range, None);
+        let project = report
+            .projects
+            .iter()
+            .find(|project| project.project_id == project_id)
+            .unwrap();
+        assert_eq!(project.duration.0, 3 * 7 * 3600);
+        let tuesday = checkpoint_db.day_range(date(4));
+        assert_eq!(
+            checkpoint_db
+                .checkpoints
+                .range(tuesday.start..tuesday.end)
+                .count(),
+            4
+        );
+
+        // Filling again doesn't add anything.
+        assert!(checkpoint_db
+            .fill_range(&range, &template)
+            .unwrap()
+            .is_empty());
+
+        // A day whose last checkpoint can't be added is left empty.
+        let broken = template.checkpoint(time(18), Checkpoint::builder().project(ProjectId::Id(9)));
+        let next_week = TimeRange::new(range.end, checkpoint_db.day_range(date(17)).start);
+        assert!(checkpoint_db.fill_range(&next_week, &broken).is_err());
+        assert!(checkpoint_db
+            .checkpoints
+            .range(range.end..)
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    /// With a day boundary, template times before it can land on existing checkpoints, which
+    /// leaves the day empty.
+    fn fill_before_day_boundary() {
+        let mut checkpoint_db = CheckpointDb::new();
+        checkpoint_db
+            .set_settings(Settings {
+                time_zone: ReportTimeZone::Utc,
+                day_boundary: 4 * 3600,
+                ..Settings::default()
+            })
+            .unwrap();
+        let time = |hour| NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+        let template = TemplateDay::new()
+            .checkpoint(time(1), Checkpoint::builder().message("Late"))
+            .checkpoint(time(2), Checkpoint::builder().message("Later"))
+            .checkpoint(time(9), Checkpoint::builder().message("Start"));
+        let monday = NaiveDate::from_ymd_opt(2024, 6, 3).unwrap();
+        let day = checkpoint_db.day_range(monday);
+        checkpoint_db
+            .add_checkpoint(day.start - 2 * 3600, "Sunday night", ProjectId::NoId)
+            .unwrap();
+
+        let error = checkpoint_db.fill_range(&day, &template).unwrap_err();
+        assert!(matches!(error, CheckpointDbError::CheckpointExists { .. }));
+        assert_eq!(checkpoint_db.len(), 1);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::{CheckpointDb, CheckpointDbError, ImportSummary, ImportedCheckpoint, Importer, Result};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Separates the fields of each commit in the log read by `GitImporter`.
+const FIELD_SEPARATOR: char = '\u{1f}';
+
+/// Creates a checkpoint for each commit in a git repository made by an author, at the commit
+/// time with the subject as message.
+///
+/// The log is read by running `git`, so it has to be installed. Commits pushed to GitHub or
+/// GitLab can be imported from any clone of the repository.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GitImporter {
+    pub repository: PathBuf,
+    /// The name or email address of the author whose commits are imported.
+    pub author: String,
+    /// The short name of the project the repository's commits belong to.
+    pub project: Option<String>,
+}
+
+impl GitImporter {
+    pub fn new(repository: PathBuf, author: &str) -> GitImporter {
+        GitImporter {
+            repository,
+            author: author.to_string(),
+            project: None,
+        }
+    }
+
+    /// Runs `git log` in the repository and returns the output in the form `read` expects.
+    pub fn log(&self) -> Result<String, String> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(&self.repository)
+            .args([
+                "log",
+                "--all",
+                "--no-merges",
+                "--format=%ct%x1f%ae%x1f%an%x1f%s",
+            ])
+            .output()
+            .map_err(|e| format!("could not run git: {}", e))?;
+
+        if !output.status.success() {
+            return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+        }
+        String::from_utf8(output.stdout).map_err(|e| e.to_string())
+    }
+}
+
+impl Importer for GitImporter {
+    /// Reads the output of `git log --format=%ct%x1f%ae%x1f%an%x1f%s`: the commit time, the
+    /// author's email address and name, and the subject, separated by the unit separator.
+    fn read(&self, input: &str) -> Result<Vec<ImportedCheckpoint>, String> {
+        let mut checkpoints = Vec::new();
+        for (i, line) in input.lines().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.splitn(4, FIELD_SEPARATOR).collect();
+            let [time, email, name, subject] = fields[..] else {
+                return Err(format!("line {}: expected 4 fields", i + 1));
+            };
+            if !email.eq_ignore_ascii_case(&self.author) && name != self.author {
+                continue;
+            }
+
+            checkpoints.push(ImportedCheckpoint {
+                timestamp: time
+                    .parse()
+                    .map_err(|e| format!("line {}: could not parse the time: {}", i + 1, e))?,
+                message: subject.to_string(),
+                project: self.project.clone(),
+                tags: Vec::new(),
+                start: None,
+                external_id: None,
+                kind: None,
+            });
+        }
+        Ok(checkpoints)
+    }
+}
+
+impl CheckpointDb {
+    /// Imports the commits of a git repository with the given `GitImporter`. If `dry_run` is
+    /// set, nothing is added and the returned summary tells what would have been.
+    pub fn import_git(&mut self, importer: &GitImporter, dry_run: bool) -> Result<ImportSummary> {
+        let log = importer.log().map_err(CheckpointDbError::InvalidInput)?;
+        self.import_from(log.as_bytes(), importer, dry_run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Only the commits of the configured author are imported, by email address or name.
+    fn read_git_log() {
+        let log = "1623751200\u{1f}jo@example.com\u{1f}Jo\u{1f}Fix the header\n\
+                   1623747600\u{1f}sam@example.com\u{1f}Sam\u{1f}Add a footer\n\
+                   1623744000\u{1f}jo@work.example.com\u{1f}Jo\u{1f}Start the website\n";
+        let mut importer = GitImporter::new(PathBuf::from("."), "JO@example.com");
+        importer.project = Some("web".to_string());
+
+        let checkpoints = importer.read(log).unwrap();
+        assert_eq!(checkpoints.len(), 1);
+        assert_eq!(checkpoints[0].timestamp, 1_623_751_200);
+        assert_eq!(checkpoints[0].message, "Fix the header");
+        assert_eq!(checkpoints[0].project.as_deref(), Some("web"));
+
+        importer.author = "Jo".to_string();
+        assert_eq!(importer.read(log).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,134 @@
+use crate::{CheckpointDb, Operation, ProjectId, Result};
+use chrono::prelude::*;
+use std::cmp::max;
+
+/// How far along a goal is, in seconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct GoalProgress {
+    pub goal: i64,
+    pub achieved: i64,
+    /// The time left until the goal is reached, or zero if it has been reached.
+    pub remaining: i64,
+}
+
+impl GoalProgress {
+    pub fn new(goal: i64, achieved: i64) -> GoalProgress {
+        GoalProgress {
+            goal,
+            achieved,
+            remaining: max(goal - achieved, 0),
+        }
+    }
+
+    /// Returns the achieved part of the goal, which is 1 or more once the goal is reached.
+    pub fn fraction(&self) -> f64 {
+        if self.goal <= 0 {
+            1.0
+        } else {
+            self.achieved as f64 / self.goal as f64
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct ProjectGoalProgress {
+    pub project_id: ProjectId,
+    pub progress: GoalProgress,
+}
+
+/// The progress towards the daily goals on one day.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct DailyProgress {
+    pub date: NaiveDate,
+    /// The progress towards the database's daily goal, if it has one.
+    pub total: Option<GoalProgress>,
+    /// The progress of every project that has a daily goal.
+    pub projects: Vec<ProjectGoalProgress>,
+}
+
+impl CheckpointDb {
+    /// Returns the time in seconds to track each day, if there is a goal.
+    pub fn daily_goal(&self) -> Option<i64> {
+        self.daily_goal
+    }
+
+    pub fn set_daily_goal(&mut self, daily_goal: Option<i64>) -> Result<()> {
+        self.apply(Operation::SetDailyGoal { daily_goal })
+    }
+
+    /// Sets the time in seconds to spend on a project each day.
+    pub fn set_project_daily_goal(
+        &mut self,
+        project_id: ProjectId,
+        daily_goal: Option<i64>,
+    ) -> Result<()> {
+        self.apply(Operation::SetProjectDailyGoal {
+            project_id,
+            daily_goal,
+        })
+    }
+
+    /// Returns the progress towards the daily goals on the given local date.
+    pub fn goal_progress(&self, date: NaiveDate) -> DailyProgress {
+        let report = self.report(&self.day_range(date), None);
+        let achieved = |project_id: ProjectId| {
+            report
+                .projects
+                .iter()
+                .find(|total| total.project_id == project_id)
+                .map_or(0, |total| total.duration.seconds())
+        };
+
+        DailyProgress {
+            date,
+            total: self
+                .daily_goal
+                .map(|goal| GoalProgress::new(goal, report.duration.seconds())),
+            projects: self
+                .projects
+                .iter()
+                .filter_map(|(id, project)| {
+                    let project_id = ProjectId::Id(*id);
+                    project.daily_goal.map(|goal| ProjectGoalProgress {
+                        project_id,
+                        progress: GoalProgress::new(goal, achieved(project_id)),
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TimeRange;
+
+    #[test]
+    fn goal_progress() {
+        let mut checkpoint_db = CheckpointDb::new();
+        checkpoint_db.set_daily_goal(Some(8 * 3600)).unwrap();
+        let project_id = checkpoint_db.add_project("Project", "prj").unwrap();
+        checkpoint_db
+            .set_project_daily_goal(project_id, Some(2 * 3600))
+            .unwrap();
+
+        let date = NaiveDate::from_ymd_opt(2021, 6, 15).unwrap();
+        let start = TimeRange::day(date).start + 9 * 3600;
+        checkpoint_db
+            .add_checkpoint(start, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 3 * 3600, "Work", project_id)
+            .unwrap();
+
+        let progress = checkpoint_db.goal_progress(date);
+        assert_eq!(progress.total, Some(GoalProgress::new(8 * 3600, 3 * 3600)));
+        assert_eq!(progress.total.unwrap().remaining, 5 * 3600);
+        assert_eq!(progress.projects[0].progress.remaining, 0);
+        assert_eq!(progress.projects[0].progress.fraction(), 1.5);
+    }
+}
@@ -0,0 +1,127 @@
+use crate::{
+    csv::{header_column, parse_csv},
+    import::map_project,
+    report::local_midnight,
+    ImportedCheckpoint, Importer,
+};
+use chrono::prelude::*;
+use std::collections::BTreeMap;
+
+/// Reads the CSV of Harvest's detailed time report.
+///
+/// Harvest only records the hours of each entry, not when they were worked, so the entries of
+/// each day are placed one after another from `day_start`, in the order of the file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HarvestImporter {
+    /// Maps Harvest project names to the short names of projects in the database. Projects
+    /// without a mapping keep their Harvest name as short name.
+    pub projects: BTreeMap<String, String>,
+    /// The local time of day the first entry of each day starts at.
+    pub day_start: NaiveTime,
+}
+
+impl Default for HarvestImporter {
+    fn default() -> HarvestImporter {
+        HarvestImporter {
+            projects: BTreeMap::new(),
+            day_start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+        }
+    }
+}
+
+impl Importer for HarvestImporter {
+    /// The notes of each entry become its message, or the task if there are no notes. The task
+    /// is also kept as a tag.
+    fn read(&self, input: &str) -> Result<Vec<ImportedCheckpoint>, String> {
+        let rows = parse_csv(input, ',');
+        let header = rows.first().ok_or("the file is empty")?;
+        let column = |name: &str| header_column(header, name);
+        let date = column("Date")?;
+        let project = column("Project")?;
+        let task = column("Task")?;
+        let notes = column("Notes")?;
+        let hours = column("Hours")?;
+        let day_start = self.day_start.signed_duration_since(NaiveTime::MIN);
+
+        let mut ends: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+        rows.iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, row)| row.iter().any(|field| !field.is_empty()))
+            .map(|(i, row)| {
+                let field = |column: usize| row.get(column).map_or("", |field| field.trim());
+                let error = |message: String| format!("row {}: {}", i + 1, message);
+
+                let day = NaiveDate::parse_from_str(field(date), "%Y-%m-%d")
+                    .map_err(|e| error(format!("could not parse the date: {}", e)))?;
+                let hours = field(hours)
+                    .parse::<f64>()
+                    .map_err(|e| error(format!("could not parse the hours: {}", e)))?;
+                let start = *ends
+                    .entry(day)
+                    .or_insert_with(|| local_midnight(day) + day_start.num_seconds());
+                let end = start + (hours * 3600.0).round() as i64;
+                ends.insert(day, end);
+
+                let task = field(task);
+                Ok(ImportedCheckpoint {
+                    timestamp: end,
+                    message: match field(notes) {
+                        "" => task.to_string(),
+                        notes => notes.to_string(),
+                    },
+                    project: map_project(&self.projects, field(project)),
+                    tags: if task.is_empty() {
+                        Vec::new()
+                    } else {
+                        vec![task.to_string()]
+                    },
+                    start: Some(start),
+                    external_id: None,
+                    kind: None,
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckpointDb, ClockifyImporter};
+
+    #[test]
+    /// Imports the same day from Harvest and Clockify, through the `Importer` trait.
+    fn import_harvest_and_clockify() {
+        let harvest = "Date,Client,Project,Project Code,Task,Notes,Hours\n\
+                       2021-06-15,Acme,Website,,Design,,1.5\n\
+                       2021-06-15,Acme,Website,,Development,Fix the header,0.5\n";
+        let clockify = "Project,Client,Description,Task,User,Tags,Start Date,Start Time,\
+                        End Date,End Time\n\
+                        Website,Acme,Design,,Jo,Design,06/15/2021,09:00:00 AM,\
+                        06/15/2021,10:30:00 AM\n\
+                        Website,Acme,Fix the header,,Jo,Development,06/15/2021,10:30:00 AM,\
+                        06/15/2021,11:00:00 AM\n";
+        let importers: [(&str, &dyn Importer); 2] = [
+            (harvest, &HarvestImporter::default()),
+            (clockify, &ClockifyImporter::default()),
+        ];
+
+        let start = Local
+            .with_ymd_and_hms(2021, 6, 15, 9, 0, 0)
+            .unwrap()
+            .timestamp();
+        for (input, importer) in importers {
+            let mut checkpoint_db = CheckpointDb::new();
+            let summary = checkpoint_db
+                .import_from(input.as_bytes(), importer, false)
+                .unwrap();
+            assert_eq!(summary.breaks, vec![start]);
+            assert_eq!(summary.new_projects, vec!["Website"]);
+
+            let checkpoint = &checkpoint_db.checkpoints[&(start + 7200)];
+            assert_eq!(&*checkpoint.message, "Fix the header");
+            assert_eq!(checkpoint.tags, vec!["Development"]);
+        }
+    }
+}
@@ -0,0 +1,431 @@
+use crate::{
+    report::Entry, CheckpointDb, CheckpointDbError, ImportSummary, ImportedCheckpoint, Result,
+    TimeRange,
+};
+use chrono::prelude::*;
+use std::io::{self, Read, Write};
+
+/// A rule deciding which project imported iCalendar events belong to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IcalProjectRule {
+    /// Events in the calendar with the given name belong to the project.
+    Calendar { name: String, project: String },
+    /// Events with the keyword in their summary, in any case, belong to the project.
+    Keyword { keyword: String, project: String },
+}
+
+impl IcalProjectRule {
+    /// Returns the short name of the project if the rule matches.
+    fn matches(&self, calendar: Option<&str>, summary: &str) -> Option<&str> {
+        match self {
+            IcalProjectRule::Calendar { name, project } => {
+                (calendar == Some(name.as_str())).then_some(project.as_str())
+            }
+            IcalProjectRule::Keyword { keyword, project } => summary
+                .to_lowercase()
+                .contains(&keyword.to_lowercase())
+                .then_some(project.as_str()),
+        }
+    }
+}
+
+impl CheckpointDb {
+    /// Writes the checkpoints in the given `TimeRange` as an iCalendar file. Each checkpoint
+    /// becomes an event from the checkpoint before it to the checkpoint itself, with the
+    /// message as summary and the project's name as category.
+    pub fn export_ical<W: Write>(&self, mut writer: W, range: &TimeRange) -> Result<()> {
+        write_ical_line(&mut writer, "BEGIN:VCALENDAR")?;
+        write_ical_line(&mut writer, "VERSION:2.0")?;
+        write_ical_line(&mut writer, "PRODID:-//time_track//time_track//EN")?;
+
+        for entry in self.entries_in(range).filter(|entry| entry.duration > 0) {
+            self.write_ical_event(&mut writer, &entry)?;
+        }
+
+        write_ical_line(&mut writer, "END:VCALENDAR")?;
+        Ok(())
+    }
+
+    /// Reads the events of an iCalendar file and imports them as checkpoints at the end of each
+    /// event, with the summary as message. The project of each event is decided by the first
+    /// matching `IcalProjectRule`. If `dry_run` is set, nothing is added and the returned
+    /// summary tells what would have been.
+    pub fn import_ical<R: Read>(
+        &mut self,
+        mut reader: R,
+        rules: &[IcalProjectRule],
+        dry_run: bool,
+    ) -> Result<ImportSummary> {
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+
+        let checkpoints = read_ical_events(&input)
+            .and_then(|events| {
+                events
+                    .iter()
+                    .map(|event| event.to_imported(rules))
+                    .collect::<Result<Vec<ImportedCheckpoint>, String>>()
+            })
+            .map_err(CheckpointDbError::InvalidInput)?;
+
+        self.import(checkpoints, dry_run)
+    }
+
+    /// Writes the checkpoint of an entry as an event from the checkpoint before it to the
+    /// checkpoint itself.
+    pub(crate) fn write_ical_event<W: Write>(
+        &self,
+        writer: &mut W,
+        entry: &Entry,
+    ) -> io::Result<()> {
+        let start = ical_time(entry.timestamp - entry.duration)?;
+        let end = ical_time(entry.timestamp)?;
+
+        write_ical_line(writer, "BEGIN:VEVENT")?;
+        write_ical_line(writer, &format!("UID:{}", ical_uid(entry.timestamp)))?;
+        write_ical_line(writer, &format!("DTSTAMP:{}", end))?;
+        write_ical_line(writer, &format!("DTSTART:{}", start))?;
+        write_ical_line(writer, &format!("DTEND:{}", end))?;
+        write_ical_line(
+            writer,
+            &format!("SUMMARY:{}", escape_ical(&entry.checkpoint.message)),
+        )?;
+        if let Some(project) = self.project_from_project_id(entry.checkpoint.project_id) {
+            write_ical_line(
+                writer,
+                &format!("CATEGORIES:{}", escape_ical(&project.long_name)),
+            )?;
+        }
+        write_ical_line(writer, "END:VEVENT")
+    }
+}
+
+/// The suffix of the UIDs of the events written for checkpoints.
+pub(crate) const ICAL_UID_SUFFIX: &str = "@time_track";
+
+/// Returns the UID of the event written for the checkpoint at the given timestamp.
+pub(crate) fn ical_uid(timestamp: i64) -> String {
+    format!("{}{}", timestamp, ICAL_UID_SUFFIX)
+}
+
+/// The properties of a VEVENT needed to import it.
+#[derive(Default)]
+pub(crate) struct IcalEvent {
+    pub(crate) uid: String,
+    /// The name of the calendar the event is in.
+    pub(crate) calendar: Option<String>,
+    pub(crate) summary: String,
+    pub(crate) categories: Vec<String>,
+    start: Option<Option<i64>>,
+    end: Option<Option<i64>>,
+    duration: Option<String>,
+}
+
+/// Reads the events of the calendars in an iCalendar file.
+pub(crate) fn read_ical_events(input: &str) -> Result<Vec<IcalEvent>, String> {
+    let mut events = Vec::new();
+    let mut calendar: Option<String> = None;
+    let mut event: Option<IcalEvent> = None;
+    for line in unfold_ical(input) {
+        let (name, value) = match line.split_once(':') {
+            Some((name, value)) => (name, value),
+            None => continue,
+        };
+        // Parameters like TZID come after a semicolon in the name.
+        let (name, parameters) = name.split_once(';').unwrap_or((name, ""));
+
+        match (name.to_uppercase().as_str(), &mut event) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VCALENDAR") => calendar = None,
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VEVENT") => {
+                event = Some(IcalEvent {
+                    calendar: calendar.clone(),
+                    ..IcalEvent::default()
+                });
+            }
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VEVENT") => {
+                events.extend(event.take());
+            }
+            ("X-WR-CALNAME", None) => calendar = Some(unescape_ical(value)),
+            ("UID", Some(current)) => current.uid = value.to_string(),
+            ("DTSTART", Some(current)) => {
+                current.start = Some(parse_ical_time(value, parameters));
+            }
+            ("DTEND", Some(current)) => current.end = Some(parse_ical_time(value, parameters)),
+            ("DURATION", Some(current)) => current.duration = Some(value.to_string()),
+            ("SUMMARY", Some(current)) => current.summary = unescape_ical(value),
+            ("CATEGORIES", Some(current)) => current
+                .categories
+                .extend(split_ical_list(value).iter().map(|c| unescape_ical(c))),
+            _ => (),
+        }
+    }
+    Ok(events)
+}
+
+impl IcalEvent {
+    /// Converts the event to a checkpoint at its end, with the project of the first matching
+    /// rule.
+    pub(crate) fn to_imported(
+        &self,
+        rules: &[IcalProjectRule],
+    ) -> Result<ImportedCheckpoint, String> {
+        let (start, end) = self.times()?;
+        let project = rules
+            .iter()
+            .find_map(|rule| rule.matches(self.calendar.as_deref(), &self.summary))
+            .map(str::to_string);
+
+        Ok(ImportedCheckpoint {
+            timestamp: end,
+            message: self.summary.clone(),
+            project,
+            tags: Vec::new(),
+            start: Some(start),
+            external_id: Some(self.uid.clone()).filter(|uid| !uid.is_empty()),
+            kind: None,
+        })
+    }
+
+    /// Returns the start and end timestamps of the event.
+    fn times(&self) -> Result<(i64, i64), String> {
+        let start = match self.start {
+            Some(Some(start)) => start,
+            Some(None) => return Err(format!("could not parse the start of {:?}", self.summary)),
+            None => return Err(format!("the event {:?} has no start", self.summary)),
+        };
+
+        let end = match (self.end, &self.duration) {
+            (Some(Some(end)), _) => end,
+            (None, Some(duration)) => {
+                start
+                    + parse_ical_duration(duration).ok_or_else(|| {
+                        format!("could not parse the duration of {:?}", self.summary)
+                    })?
+            }
+            _ => return Err(format!("could not parse the end of {:?}", self.summary)),
+        };
+
+        Ok((start, end))
+    }
+}
+
+/// Joins folded lines back together.
+fn unfold_ical(input: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in input.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+/// Parses an iCalendar date or date-time. Times without a trailing `Z` are taken to be local,
+/// even if they have a TZID parameter.
+fn parse_ical_time(value: &str, parameters: &str) -> Option<i64> {
+    if parameters.to_uppercase().contains("VALUE=DATE") && !value.contains('T') {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some(crate::report::local_midnight(date));
+    }
+
+    match value.strip_suffix('Z') {
+        Some(utc) => NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S")
+            .ok()
+            .map(|time| time.and_utc().timestamp()),
+        None => {
+            let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+            Local
+                .from_local_datetime(&time)
+                .earliest()
+                .map(|time| time.timestamp())
+        }
+    }
+}
+
+/// Parses an iCalendar duration like `PT1H30M` into seconds.
+fn parse_ical_duration(value: &str) -> Option<i64> {
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(value) => (-1, value),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let mut value = value.strip_prefix('P')?;
+
+    let mut seconds = 0;
+    let mut in_time = false;
+    while !value.is_empty() {
+        if let Some(rest) = value.strip_prefix('T') {
+            in_time = true;
+            value = rest;
+            continue;
+        }
+
+        let digits = value.find(|c: char| !c.is_ascii_digit())?;
+        let number: i64 = value[..digits].parse().ok()?;
+        let unit = match (value[digits..].chars().next()?, in_time) {
+            ('W', false) => 7 * 24 * 3600,
+            ('D', false) => 24 * 3600,
+            ('H', true) => 3600,
+            ('M', true) => 60,
+            ('S', true) => 1,
+            _ => return None,
+        };
+        seconds += number * unit;
+        value = &value[digits + 1..];
+    }
+
+    Some(sign * seconds)
+}
+
+/// Splits a list value on the commas that aren't escaped.
+fn split_ical_list(value: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ',' => {
+                items.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    items.push(&value[start..]);
+    items
+}
+
+/// Reverses `escape_ical`.
+fn unescape_ical(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => unescaped.push('\n'),
+                Some(other) => unescaped.push(other),
+                None => (),
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+/// Formats a timestamp as an iCalendar UTC date-time.
+fn ical_time(timestamp: i64) -> io::Result<String> {
+    Utc.timestamp_opt(timestamp, 0)
+        .single()
+        .map(|time| time.format("%Y%m%dT%H%M%SZ").to_string())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "timestamp out of range"))
+}
+
+/// Escapes the characters that have special meaning in iCalendar text values.
+fn escape_ical(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+        .replace('\r', "")
+}
+
+/// Writes a content line, folding it so that no line is longer than 75 bytes.
+pub(crate) fn write_ical_line<W: Write>(writer: &mut W, line: &str) -> io::Result<()> {
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            writer.write_all(b"\r\n ")?;
+            length = 1;
+        }
+        write!(writer, "{}", c)?;
+        length += c.len_utf8();
+    }
+    writer.write_all(b"\r\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProjectId;
+
+    #[test]
+    fn export_ical() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Project, Inc", "prj").unwrap();
+        let start = Utc
+            .with_ymd_and_hms(2021, 6, 15, 9, 0, 0)
+            .unwrap()
+            .timestamp();
+        checkpoint_db
+            .add_checkpoint(start, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 3600, "Planning", project_id)
+            .unwrap();
+
+        let mut output = Vec::new();
+        checkpoint_db
+            .export_ical(&mut output, &TimeRange::new(start, start + 7200))
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert_eq!(output.matches("BEGIN:VEVENT").count(), 1);
+        assert!(output.contains("DTSTART:20210615T090000Z\r\n"));
+        assert!(output.contains("DTEND:20210615T100000Z\r\n"));
+        assert!(output.contains("SUMMARY:Planning\r\n"));
+        assert!(output.contains("CATEGORIES:Project\\, Inc\r\n"));
+    }
+
+    #[test]
+    /// Imports events from two calendars, assigning projects by calendar name and by keyword.
+    fn import_ical() {
+        let input = "BEGIN:VCALENDAR\r\n\
+                     X-WR-CALNAME:Work\r\n\
+                     BEGIN:VEVENT\r\n\
+                     DTSTART:20210615T090000Z\r\n\
+                     DTEND:20210615T100000Z\r\n\
+                     SUMMARY:Stand-up\\, planning\r\n\
+                     END:VEVENT\r\n\
+                     END:VCALENDAR\r\n\
+                     BEGIN:VCALENDAR\r\n\
+                     BEGIN:VEVENT\r\n\
+                     DTSTART:20210615T110000Z\r\n\
+                     DURATION:PT30M\r\n\
+                     SUMMARY:Dentist for the\r\n  teeth\r\n\
+                     END:VEVENT\r\n\
+                     END:VCALENDAR\r\n";
+        let rules = [
+            IcalProjectRule::Calendar {
+                name: "Work".to_string(),
+                project: "wrk".to_string(),
+            },
+            IcalProjectRule::Keyword {
+                keyword: "dentist".to_string(),
+                project: "hlt".to_string(),
+            },
+        ];
+
+        let mut checkpoint_db = CheckpointDb::new();
+        let summary = checkpoint_db
+            .import_ical(input.as_bytes(), &rules, false)
+            .unwrap();
+        assert_eq!(summary.new_projects, vec!["wrk", "hlt"]);
+
+        let start = Utc
+            .with_ymd_and_hms(2021, 6, 15, 9, 0, 0)
+            .unwrap()
+            .timestamp();
+        let checkpoint = &checkpoint_db.checkpoints[&(start + 3600)];
+        assert_eq!(&*checkpoint.message, "Stand-up, planning");
+        let checkpoint = &checkpoint_db.checkpoints[&(start + 9000)];
+        assert_eq!(&*checkpoint.message, "Dentist for the teeth");
+        assert_eq!(
+            checkpoint.project_id,
+            checkpoint_db.project_id_from_short_name("hlt").unwrap()
+        );
+        assert_eq!(checkpoint_db.checkpoints.len(), 4);
+    }
+}
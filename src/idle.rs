@@ -0,0 +1,201 @@
+use crate::{Checkpoint, CheckpointDb, CheckpointKind, Operation, Result, TimeRange, Timestamp};
+
+/// Tells how long the user has been idle, usually from the operating system. The probes live
+/// in frontends, which know the system they run on.
+pub trait IdleSource {
+    /// Returns the seconds since the user last used the keyboard or mouse.
+    fn idle_seconds(&self) -> Result<i64>;
+}
+
+/// Turns regular samples of an `IdleSource` into idle periods.
+#[derive(Clone, Debug)]
+pub struct IdleMonitor<S> {
+    source: S,
+    /// Idle times shorter than this aren't counted.
+    min_idle: i64,
+    idle_since: Option<i64>,
+}
+
+impl<S: IdleSource> IdleMonitor<S> {
+    /// Watches the source for idle times of at least `min_idle` seconds.
+    pub fn new(source: S, min_idle: i64) -> IdleMonitor<S> {
+        IdleMonitor {
+            source,
+            min_idle,
+            idle_since: None,
+        }
+    }
+
+    /// Samples the source, and returns the idle period that ended if the user has come back.
+    pub fn poll(&mut self, now: i64) -> Result<Option<TimeRange>> {
+        let idle = self.source.idle_seconds()?;
+        if idle >= self.min_idle {
+            self.idle_since.get_or_insert(now - idle);
+            return Ok(None);
+        }
+        Ok(self
+            .idle_since
+            .take()
+            .map(|start| TimeRange::new(start, now - idle)))
+    }
+}
+
+/// How idle periods are turned into breaks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct IdlePolicy {
+    /// Idle periods shorter than this many seconds are counted as work. 5 minutes by default.
+    pub min_idle: i64,
+    /// Idle periods at least this many seconds long are made into breaks without asking, or
+    /// `None` to always ask. 30 minutes by default.
+    pub auto_break_after: Option<i64>,
+}
+
+impl Default for IdlePolicy {
+    fn default() -> IdlePolicy {
+        IdlePolicy {
+            min_idle: 5 * 60,
+            auto_break_after: Some(30 * 60),
+        }
+    }
+}
+
+/// A break proposed for an idle period.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct IdleBreak {
+    pub period: TimeRange,
+    /// The operations that make the period a break: a checkpoint at its start for the work
+    /// before it, and a break checkpoint at its end.
+    pub operations: Vec<Operation>,
+    /// Whether the policy lets the break be made without asking the user.
+    pub automatic: bool,
+}
+
+impl CheckpointDb {
+    /// Returns the breaks to make of the idle periods according to the policy.
+    ///
+    /// The work before an idle period is ended at its start, with the message and project of
+    /// the checkpoint after it, or of the latest checkpoint if it's in the running timer.
+    /// Periods with checkpoints in them were not really idle, and periods before the first
+    /// checkpoint aren't tracked, so neither get a break.
+    pub fn idle_breaks(&self, periods: &[TimeRange], policy: &IdlePolicy) -> Vec<IdleBreak> {
+        periods
+            .iter()
+            .filter(|period| period.end - period.start >= policy.min_idle)
+            .filter_map(|period| {
+                if self
+                    .checkpoints
+                    .range(period.start + 1..period.end)
+                    .next()
+                    .is_some()
+                {
+                    return None;
+                }
+                self.checkpoints.range(..=period.start).next_back()?;
+                let (_, work) = self
+                    .checkpoints
+                    .range(period.end..)
+                    .next()
+                    .or_else(|| self.checkpoints.iter().next_back())?;
+
+                let mut operations = Vec::new();
+                if !self.checkpoints.contains_key(&period.start) {
+                    operations.push(Operation::AddCheckpoint {
+                        timestamp: Timestamp(period.start),
+                        checkpoint: Checkpoint {
+                            message: work.message.clone(),
+                            project_id: work.project_id,
+                            tags: work.tags.clone(),
+                            ..Checkpoint::default()
+                        },
+                    });
+                }
+                let end = Timestamp(period.end);
+                operations.push(match self.checkpoints.get(&period.end) {
+                    Some(_) => Operation::SetCheckpointKind {
+                        timestamp: end,
+                        kind: CheckpointKind::Break,
+                    },
+                    None => Operation::AddCheckpoint {
+                        timestamp: end,
+                        checkpoint: Checkpoint::builder().kind(CheckpointKind::Break).build(),
+                    },
+                });
+
+                Some(IdleBreak {
+                    period: *period,
+                    operations,
+                    automatic: policy
+                        .auto_break_after
+                        .is_some_and(|after| period.end - period.start >= after),
+                })
+            })
+            .collect()
+    }
+
+    /// Makes the breaks that the policy allows without asking, and returns the others for the
+    /// user to confirm by applying their operations.
+    pub fn apply_idle_breaks(
+        &mut self,
+        periods: &[TimeRange],
+        policy: &IdlePolicy,
+    ) -> Result<Vec<IdleBreak>> {
+        let (automatic, proposed): (Vec<IdleBreak>, Vec<IdleBreak>) = self
+            .idle_breaks(periods, policy)
+            .into_iter()
+            .partition(|idle_break| idle_break.automatic);
+        for operation in automatic.into_iter().flat_map(|b| b.operations) {
+            self.apply(operation)?;
+        }
+        Ok(proposed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProjectId;
+    use std::cell::Cell;
+
+    struct FakeIdle(Cell<i64>);
+
+    impl IdleSource for FakeIdle {
+        fn idle_seconds(&self) -> Result<i64> {
+            Ok(self.0.get())
+        }
+    }
+
+    #[test]
+    /// Long idle periods become breaks, short ones are proposed, and tiny ones are ignored.
+    fn idle_periods_become_breaks() {
+        let mut monitor = IdleMonitor::new(FakeIdle(Cell::new(0)), 60);
+        assert_eq!(monitor.poll(1000).unwrap(), None);
+        monitor.source.0.set(600);
+        assert_eq!(monitor.poll(1600).unwrap(), None);
+        monitor.source.0.set(5);
+        let long = monitor.poll(5000).unwrap().unwrap();
+        assert_eq!(long, TimeRange::new(1000, 4995));
+
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Website", "web").unwrap();
+        checkpoint_db
+            .add_checkpoint(0, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(8000, "Styling", project_id)
+            .unwrap();
+
+        let periods = [long, TimeRange::new(6000, 6600), TimeRange::new(7000, 7010)];
+        let proposed = checkpoint_db
+            .apply_idle_breaks(&periods, &IdlePolicy::default())
+            .unwrap();
+        assert_eq!(proposed.len(), 1);
+        assert_eq!(proposed[0].period, TimeRange::new(6000, 6600));
+
+        assert_eq!(&*checkpoint_db.checkpoints[&1000].message, "Styling");
+        assert_eq!(checkpoint_db.checkpoints[&1000].project_id, project_id);
+        assert_eq!(checkpoint_db.checkpoints[&4995].kind, CheckpointKind::Break);
+        assert_eq!(checkpoint_db.len(), 4);
+    }
+}
@@ -0,0 +1,301 @@
+use crate::{
+    Checkpoint, CheckpointDb, CheckpointDbError, CheckpointId, CheckpointKind, ProjectId, Result,
+};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Read;
+
+/// A checkpoint read from another format, before it's added to a database.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct ImportedCheckpoint {
+    pub timestamp: i64,
+    pub message: String,
+    /// The short name of the checkpoint's project.
+    pub project: Option<String>,
+    pub tags: Vec<String>,
+    /// The start of the tracked interval, for formats that store intervals. A break checkpoint
+    /// is added at the start unless there already is a checkpoint there, so that the time
+    /// before the interval isn't counted towards it.
+    pub start: Option<i64>,
+    /// The id of the time entry in the other format, if it has one.
+    pub external_id: Option<String>,
+    /// The kind of the checkpoint, for formats that store it. Otherwise a new checkpoint is
+    /// work, and an updated one keeps its kind.
+    #[cfg_attr(feature = "json", serde(default))]
+    pub kind: Option<CheckpointKind>,
+}
+
+/// What an import added to the database, or would have added in a dry run.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct ImportSummary {
+    pub checkpoints: Vec<ImportedCheckpoint>,
+    /// The number of checkpoints that didn't exist yet.
+    pub created: usize,
+    /// The number of checkpoints that already existed and were changed.
+    pub updated: usize,
+    /// The number of checkpoints that already existed as they are.
+    pub skipped: usize,
+    /// The short names of the projects that didn't exist and were created.
+    pub new_projects: Vec<String>,
+    /// The timestamps of the break checkpoints added at the start of intervals.
+    pub breaks: Vec<i64>,
+}
+
+/// Reads the time entries exported by another time tracker.
+pub trait Importer {
+    /// Converts the time entries in the input to checkpoints at the end of each entry.
+    fn read(&self, input: &str) -> Result<Vec<ImportedCheckpoint>, String>;
+}
+
+/// Returns the short name a project name from another tracker maps to, which is the name itself
+/// unless `projects` says otherwise.
+pub(crate) fn map_project(projects: &BTreeMap<String, String>, name: &str) -> Option<String> {
+    if name.is_empty() {
+        return None;
+    }
+    Some(projects.get(name).map_or(name, String::as_str).to_string())
+}
+
+impl CheckpointDb {
+    /// Adds the given checkpoints, creating projects for any unknown short names. If `dry_run`
+    /// is set, the database is left untouched and the summary tells what would have been done.
+    ///
+    /// A checkpoint with the same external id, or else the same timestamp, as an existing one
+    /// updates it if they differ and is skipped otherwise, so that importing the same data
+    /// again doesn't change anything. If any of it can't be added, like a checkpoint in a
+    /// submitted week, none of it is.
+    pub fn import(
+        &mut self,
+        checkpoints: Vec<ImportedCheckpoint>,
+        dry_run: bool,
+    ) -> Result<ImportSummary> {
+        let mut new_projects: Vec<String> = Vec::new();
+        for short_name in checkpoints.iter().filter_map(|c| c.project.as_deref()) {
+            if self.project_id_from_short_name(short_name).is_none()
+                && !new_projects.iter().any(|name| name == short_name)
+            {
+                new_projects.push(short_name.to_string());
+            }
+        }
+
+        let ends: BTreeSet<i64> = checkpoints.iter().map(|c| c.timestamp).collect();
+        let breaks: BTreeSet<i64> = checkpoints
+            .iter()
+            .filter_map(|c| c.start)
+            .filter(|start| !ends.contains(start) && !self.checkpoints.contains_key(start))
+            .collect();
+
+        let external_ids: BTreeMap<&str, i64> = self
+            .checkpoints
+            .iter()
+            .filter_map(|(timestamp, c)| Some((c.external_id.as_deref()?, *timestamp)))
+            .collect();
+        let existing: Vec<Option<i64>> = checkpoints
+            .iter()
+            .map(|checkpoint| {
+                checkpoint
+                    .external_id
+                    .as_deref()
+                    .and_then(|external_id| external_ids.get(external_id).copied())
+                    .or_else(|| {
+                        Some(checkpoint.timestamp)
+                            .filter(|timestamp| self.checkpoints.contains_key(timestamp))
+                    })
+            })
+            .collect();
+
+        let mut summary = ImportSummary::default();
+        for (checkpoint, existing) in checkpoints.iter().zip(&existing) {
+            match existing {
+                Some(timestamp) if self.is_imported(*timestamp, checkpoint) => summary.skipped += 1,
+                Some(_) => summary.updated += 1,
+                None => summary.created += 1,
+            }
+        }
+
+        if !dry_run {
+            // Like an invoice, the import is made on a copy first, so that one that fails
+            // halfway, like on a submitted week, leaves the database as it was.
+            self.clone()
+                .add_imported(&checkpoints, &existing, &new_projects, &breaks)?;
+            self.add_imported(&checkpoints, &existing, &new_projects, &breaks)?;
+        }
+
+        Ok(ImportSummary {
+            checkpoints,
+            new_projects,
+            breaks: breaks.into_iter().collect(),
+            ..summary
+        })
+    }
+
+    /// Adds the projects, breaks and checkpoints of an import, replacing the existing
+    /// checkpoints the imported ones update.
+    fn add_imported(
+        &mut self,
+        checkpoints: &[ImportedCheckpoint],
+        existing: &[Option<i64>],
+        new_projects: &[String],
+        breaks: &BTreeSet<i64>,
+    ) -> Result<()> {
+        for short_name in new_projects {
+            self.add_project(short_name, short_name)?;
+        }
+        for start in breaks {
+            self.add_or_replace_checkpoint(
+                *start,
+                Checkpoint::builder().kind(CheckpointKind::Break),
+            )?;
+        }
+        for (checkpoint, existing) in checkpoints.iter().zip(existing) {
+            // Every project has been created above, so the lookup can't fail.
+            let project_id = checkpoint
+                .project
+                .as_deref()
+                .and_then(|short_name| self.project_id_from_short_name(short_name))
+                .unwrap_or(ProjectId::NoId);
+            // An updated checkpoint keeps its invoice, and moves if its time changed.
+            let previous = existing
+                .and_then(|timestamp| self.remove_checkpoint(&CheckpointId::Timestamp(timestamp)));
+            let mut added = Checkpoint {
+                message: checkpoint.message.as_str().into(),
+                project_id,
+                tags: checkpoint.tags.clone(),
+                external_id: checkpoint.external_id.clone(),
+                ..Checkpoint::default()
+            };
+            if let Some(previous) = previous {
+                added.kind = previous.kind;
+                added.pomodoro = previous.pomodoro;
+                added.invoice = previous.invoice;
+                added.paid = previous.paid;
+            }
+            if let Some(kind) = checkpoint.kind {
+                added.kind = kind;
+            }
+            self.add_or_replace_checkpoint(checkpoint.timestamp, added)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the checkpoint at the timestamp is the same as an imported checkpoint.
+    fn is_imported(&self, timestamp: i64, imported: &ImportedCheckpoint) -> bool {
+        let Some(checkpoint) = self.checkpoints.get(&timestamp) else {
+            return false;
+        };
+        let project_id = match imported.project.as_deref() {
+            Some(short_name) => match self.project_id_from_short_name(short_name) {
+                Some(project_id) => project_id,
+                None => return false,
+            },
+            None => ProjectId::NoId,
+        };
+
+        timestamp == imported.timestamp
+            && *checkpoint.message == *imported.message
+            && checkpoint.project_id == project_id
+            && checkpoint.tags == imported.tags
+            && checkpoint.external_id == imported.external_id
+            && imported.kind.is_none_or(|kind| kind == checkpoint.kind)
+    }
+
+    /// Reads an export of another time tracker with the given `Importer` and imports its time
+    /// entries. If `dry_run` is set, nothing is added and the returned summary tells what would
+    /// have been.
+    pub fn import_from<R: Read, I: Importer + ?Sized>(
+        &mut self,
+        mut reader: R,
+        importer: &I,
+        dry_run: bool,
+    ) -> Result<ImportSummary> {
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+
+        let checkpoints = importer
+            .read(&input)
+            .map_err(CheckpointDbError::InvalidInput)?;
+        self.import(checkpoints, dry_run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    /// Importing the same entries again skips them, and entries with a known external id update
+    /// their checkpoint even if it moved.
+    fn import_again() {
+        let imported = |timestamp: i64, message: &str| ImportedCheckpoint {
+            timestamp,
+            message: message.to_string(),
+            project: Some("web".to_string()),
+            tags: Vec::new(),
+            start: Some(1000),
+            external_id: Some("entry-1".to_string()),
+            kind: None,
+        };
+        let mut checkpoint_db = CheckpointDb::new();
+
+        let summary = checkpoint_db
+            .import(vec![imported(2000, "Fix the header")], false)
+            .unwrap();
+        assert_eq!(
+            (summary.created, summary.updated, summary.skipped),
+            (1, 0, 0)
+        );
+        let summary = checkpoint_db
+            .import(vec![imported(2000, "Fix the header")], false)
+            .unwrap();
+        assert_eq!(
+            (summary.created, summary.updated, summary.skipped),
+            (0, 0, 1)
+        );
+        assert!(summary.breaks.is_empty());
+
+        let summary = checkpoint_db
+            .import(vec![imported(2500, "Fix the footer")], false)
+            .unwrap();
+        assert_eq!(
+            (summary.created, summary.updated, summary.skipped),
+            (0, 1, 0)
+        );
+        assert_eq!(checkpoint_db.checkpoints.len(), 2);
+        assert_eq!(&*checkpoint_db.checkpoints[&2500].message, "Fix the footer");
+    }
+
+    #[test]
+    /// An import that fails halfway, like when it moves a checkpoint into a submitted week,
+    /// doesn't change anything.
+    fn import_fails_whole() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let entry = |timestamp: i64, project: &str| ImportedCheckpoint {
+            timestamp,
+            message: "Fix the header".to_string(),
+            project: Some(project.to_string()),
+            tags: Vec::new(),
+            start: None,
+            external_id: Some("entry-1".to_string()),
+            kind: None,
+        };
+        checkpoint_db
+            .import(vec![entry(2000, "web")], false)
+            .unwrap();
+        let submitted = 14 * 86400;
+        checkpoint_db
+            .add_checkpoint(submitted + 3600, "Email", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .submit_timesheet(NaiveDate::from_ymd_opt(1970, 1, 15).unwrap(), None)
+            .unwrap();
+        let before = checkpoint_db.clone();
+
+        let error = checkpoint_db
+            .import(vec![entry(submitted + 7200, "app")], false)
+            .unwrap_err();
+        assert!(matches!(error, CheckpointDbError::TimesheetLocked { .. }));
+        assert_eq!(checkpoint_db, before);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::{Checkpoint, CheckpointDb};
+#[cfg(feature = "json")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    collections::{hash_map::DefaultHasher, BTreeMap, BTreeSet},
+    hash::{Hash, Hasher},
+    sync::OnceLock,
+};
+
+/// Which checkpoints have each tag and each word in their messages, so that tag filters and
+/// searches don't have to go through every checkpoint. They're built the first time they're
+/// needed and dropped whenever a checkpoint's message or tags may change. Once built, they're
+/// saved with the database. Like the listeners they aren't compared by `==`.
+///
+/// Saved indexes may have been written by an older version, or the file may have been edited
+/// without them, so they're only used if the fingerprint saved with them still matches the
+/// checkpoints. This is checked the first time they're needed, and they're built again if it
+/// doesn't.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Indexes {
+    saved: Option<IndexData>,
+    saved_matches: OnceLock<bool>,
+    built: OnceLock<IndexData>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+struct IndexData {
+    /// The `index_fingerprint` of the checkpoints the indexes were built from.
+    #[cfg_attr(feature = "json", serde(default))]
+    fingerprint: u64,
+    tags: BTreeMap<String, BTreeSet<i64>>,
+    words: BTreeMap<String, BTreeSet<i64>>,
+}
+
+impl Indexes {
+    pub(crate) fn clear(&mut self) {
+        *self = Indexes::default();
+    }
+
+    #[cfg(feature = "json")]
+    pub(crate) fn is_unbuilt(&self) -> bool {
+        self.saved.is_none() && self.built.get().is_none()
+    }
+}
+
+impl PartialEq for Indexes {
+    fn eq(&self, _other: &Indexes) -> bool {
+        true
+    }
+}
+
+#[cfg(feature = "json")]
+impl Serialize for Indexes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.built
+            .get()
+            .or(self.saved.as_ref())
+            .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "json")]
+impl<'de> Deserialize<'de> for Indexes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Indexes, D::Error> {
+        Ok(Indexes {
+            saved: Option::<IndexData>::deserialize(deserializer)?,
+            ..Indexes::default()
+        })
+    }
+}
+
+/// The timestamps of all checkpoints in order, so that checkpoints can be found by position
+/// without going through the ones after them. Built the first time a position is needed and
+/// dropped whenever a checkpoint is added or removed.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Timestamps(OnceLock<Vec<i64>>);
+
+impl Timestamps {
+    pub(crate) fn clear(&mut self) {
+        self.0.take();
+    }
+}
+
+impl PartialEq for Timestamps {
+    fn eq(&self, _other: &Timestamps) -> bool {
+        true
+    }
+}
+
+/// Splits a text into lowercase words, leaving out punctuation.
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+impl CheckpointDb {
+    /// Returns the timestamp of the checkpoint at the position, counted from the latest one.
+    pub(crate) fn timestamp_at(&self, position: usize) -> Option<i64> {
+        let timestamps = self.timestamps();
+        let index = timestamps.len().checked_sub(position + 1)?;
+        Some(timestamps[index])
+    }
+
+    /// Returns the position of the checkpoint at the timestamp, counted from the latest one.
+    pub(crate) fn position_of(&self, timestamp: i64) -> Option<usize> {
+        let timestamps = self.timestamps();
+        let index = timestamps.binary_search(&timestamp).ok()?;
+        Some(timestamps.len() - 1 - index)
+    }
+
+    /// Returns the number of checkpoints at or after the timestamp.
+    pub(crate) fn count_from(&self, timestamp: i64) -> usize {
+        let timestamps = self.timestamps();
+        timestamps.len() - timestamps.partition_point(|time| *time < timestamp)
+    }
+
+    fn timestamps(&self) -> &[i64] {
+        self.timestamps
+            .0
+            .get_or_init(|| self.checkpoints.keys().copied().collect())
+    }
+
+    /// Returns a hash of the timestamps, messages and tags of the checkpoints, which is all the
+    /// indexes are built from. The hash isn't guaranteed to stay the same between versions of
+    /// Rust, in which case the saved indexes are just built again.
+    fn index_fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for (timestamp, checkpoint) in &self.checkpoints {
+            timestamp.hash(&mut hasher);
+            checkpoint.message.hash(&mut hasher);
+            checkpoint.tags.hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
+    fn index_data(&self) -> &IndexData {
+        if let Some(saved) = &self.indexes.saved {
+            let matches = self
+                .indexes
+                .saved_matches
+                .get_or_init(|| saved.fingerprint == self.index_fingerprint());
+            if *matches {
+                return saved;
+            }
+        }
+        self.indexes.built.get_or_init(|| {
+            let mut data = IndexData {
+                fingerprint: self.index_fingerprint(),
+                ..IndexData::default()
+            };
+            for (timestamp, checkpoint) in &self.checkpoints {
+                for tag in &checkpoint.tags {
+                    data.tags.entry(tag.clone()).or_default().insert(*timestamp);
+                }
+                for word in words(&checkpoint.message) {
+                    data.words.entry(word).or_default().insert(*timestamp);
+                }
+            }
+            data
+        })
+    }
+
+    /// Returns the checkpoints with the tag, in order.
+    pub fn tagged(&self, tag: &str) -> impl Iterator<Item = (i64, &Checkpoint)> {
+        self.index_data()
+            .tags
+            .get(tag)
+            .into_iter()
+            .flatten()
+            .filter_map(|timestamp| Some((*timestamp, self.checkpoints.get(timestamp)?)))
+    }
+
+    /// Returns the checkpoints whose messages have every word of the query, in order. Case and
+    /// punctuation are ignored.
+    pub fn search(&self, query: &str) -> Vec<(i64, &Checkpoint)> {
+        let data = self.index_data();
+        let Some(mut matches) = words(query)
+            .map(|word| data.words.get(&word))
+            .collect::<Option<Vec<_>>>()
+        else {
+            return Vec::new();
+        };
+        matches.sort_by_key(|timestamps| timestamps.len());
+        let Some((fewest, rest)) = matches.split_first() else {
+            return Vec::new();
+        };
+        fewest
+            .iter()
+            .filter(|timestamp| rest.iter().all(|other| other.contains(timestamp)))
+            .filter_map(|timestamp| Some((*timestamp, self.checkpoints.get(timestamp)?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckpointId, ProjectId};
+
+    #[test]
+    /// Tags and words are found, and the indexes follow changes.
+    fn tag_and_word_indexes() {
+        let mut checkpoint_db = CheckpointDb::new();
+        checkpoint_db
+            .add_checkpoint(1000, "Fix the header", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(2000, "Review: header, footer", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .set_checkpoint_tags(CheckpointId::Timestamp(2000), vec!["review".to_string()])
+            .unwrap();
+
+        let timestamps = |found: Vec<(i64, &Checkpoint)>| {
+            found
+                .into_iter()
+                .map(|(timestamp, _)| timestamp)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(timestamps(checkpoint_db.search("HEADER")), [1000, 2000]);
+        assert_eq!(timestamps(checkpoint_db.search("header footer")), [2000]);
+        assert!(checkpoint_db.search("sidebar header").is_empty());
+        assert_eq!(timestamps(checkpoint_db.tagged("review").collect()), [2000]);
+
+        checkpoint_db
+            .add_checkpoint(3000, "Footer", ProjectId::NoId)
+            .unwrap();
+        assert_eq!(timestamps(checkpoint_db.search("footer")), [2000, 3000]);
+
+        #[cfg(feature = "json")]
+        {
+            let json = serde_json::to_string(&checkpoint_db).unwrap();
+            assert!(json.contains("\"indexes\""));
+            let read: CheckpointDb = serde_json::from_str(&json).unwrap();
+            assert!(!read.indexes.is_unbuilt());
+            assert_eq!(timestamps(read.tagged("review").collect()), [2000]);
+
+            // Indexes that don't match the checkpoints any more are built again.
+            let mut edited: serde_json::Value = serde_json::from_str(&json).unwrap();
+            edited["checkpoints"]["1000"]["message"] = "Fix the sidebar".into();
+            let read: CheckpointDb = serde_json::from_value(edited).unwrap();
+            assert!(read.search("header").iter().all(|(time, _)| *time != 1000));
+            assert_eq!(timestamps(read.search("sidebar")), [1000]);
+            assert_eq!(timestamps(read.tagged("review").collect()), [2000]);
+        }
+    }
+
+    #[test]
+    /// Positions and timestamps are converted both ways, and follow added and removed
+    /// checkpoints.
+    fn positions() {
+        let mut checkpoint_db = CheckpointDb::new();
+        for time in [1000, 2000, 3000] {
+            checkpoint_db
+                .add_checkpoint(time, "", ProjectId::NoId)
+                .unwrap();
+        }
+        assert_eq!(
+            CheckpointId::Position(0).to_timestamp(&checkpoint_db),
+            Some(3000)
+        );
+        assert_eq!(
+            CheckpointId::Timestamp(1000).to_position(&checkpoint_db),
+            Some(2)
+        );
+        assert_eq!(CheckpointId::Position(3).to_timestamp(&checkpoint_db), None);
+        assert_eq!(
+            CheckpointId::Timestamp(1500).to_position(&checkpoint_db),
+            None
+        );
+
+        checkpoint_db
+            .add_checkpoint(2500, "", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db.remove_checkpoint(&CheckpointId::Position(0));
+        assert_eq!(
+            CheckpointId::Position(0).to_timestamp(&checkpoint_db),
+            Some(2500)
+        );
+        assert_eq!(
+            CheckpointId::Timestamp(1000).to_position(&checkpoint_db),
+            Some(2)
+        );
+        assert_eq!(checkpoint_db.count_from(2000), 2);
+    }
+}
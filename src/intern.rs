@@ -0,0 +1,155 @@
+#[cfg(feature = "json")]
+use crate::Checkpoint;
+use crate::CheckpointDb;
+#[cfg(feature = "json")]
+use serde::{Deserialize, Deserializer};
+#[cfg(feature = "json")]
+use std::collections::BTreeMap;
+use std::{collections::HashSet, fmt, sync::Arc};
+
+/// The fewest messages that are kept before the ones that are no longer used are dropped.
+const MIN_RELEASE_AT: usize = 1024;
+
+/// The distinct checkpoint messages of a database, so that checkpoints with the same message
+/// share one string. Like the listeners they aren't part of the data, so they aren't saved or
+/// compared by `==`.
+#[derive(Clone, Default)]
+pub(crate) struct Messages {
+    shared: HashSet<Arc<str>>,
+    /// The number of messages at which the ones no checkpoint uses anymore are dropped, which is
+    /// twice as many as were used the last time, so that it takes constant time on average.
+    release_at: usize,
+}
+
+impl PartialEq for Messages {
+    fn eq(&self, _other: &Messages) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for Messages {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} messages", self.shared.len())
+    }
+}
+
+impl Messages {
+    /// Returns the shared string equal to the message.
+    pub(crate) fn intern(&mut self, message: &str) -> Arc<str> {
+        match self.shared.get(message) {
+            Some(shared) => Arc::clone(shared),
+            None => {
+                if self.shared.len() >= self.release_at {
+                    self.release();
+                }
+                let shared: Arc<str> = message.into();
+                self.shared.insert(Arc::clone(&shared));
+                shared
+            }
+        }
+    }
+
+    /// Drops the messages that only this table holds, like those of removed checkpoints.
+    fn release(&mut self) {
+        self.shared.retain(|message| Arc::strong_count(message) > 1);
+        self.release_at = MIN_RELEASE_AT.max(2 * self.shared.len());
+    }
+}
+
+impl CheckpointDb {
+    /// Returns the messages of the database, finding them first if the database was just read.
+    pub(crate) fn messages(&mut self) -> &mut Messages {
+        if self.messages.shared.is_empty() {
+            self.messages.shared = self
+                .checkpoints
+                .values()
+                .map(|checkpoint| Arc::clone(&checkpoint.message))
+                .collect();
+        }
+        &mut self.messages
+    }
+}
+
+/// Reads the checkpoints of a database with one string per distinct message.
+#[cfg(feature = "json")]
+pub(crate) fn deserialize_checkpoints<'de, D>(
+    deserializer: D,
+) -> Result<BTreeMap<i64, Checkpoint>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let mut checkpoints = BTreeMap::<i64, Checkpoint>::deserialize(deserializer)?;
+    let mut messages = Messages::default();
+    for checkpoint in checkpoints.values_mut() {
+        checkpoint.message = messages.intern(&checkpoint.message);
+    }
+    Ok(checkpoints)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckpointId, ProjectId};
+
+    #[test]
+    /// Checkpoints with the same message share it, whether they're added or read.
+    fn shared_messages() {
+        let mut checkpoint_db = CheckpointDb::new();
+        for timestamp in [1000, 2000, 3000] {
+            checkpoint_db
+                .add_checkpoint(timestamp, "Styling", ProjectId::NoId)
+                .unwrap();
+        }
+        checkpoint_db
+            .add_checkpoint(4000, "Email", ProjectId::NoId)
+            .unwrap();
+        let messages = |checkpoint_db: &CheckpointDb| {
+            checkpoint_db
+                .checkpoints
+                .values()
+                .map(|checkpoint| Arc::clone(&checkpoint.message))
+                .collect::<Vec<_>>()
+        };
+
+        let added = messages(&checkpoint_db);
+        assert!(Arc::ptr_eq(&added[0], &added[2]));
+        assert!(!Arc::ptr_eq(&added[0], &added[3]));
+
+        #[cfg(feature = "json")]
+        {
+            let json = serde_json::to_string(&checkpoint_db).unwrap();
+            let mut read: CheckpointDb = serde_json::from_str(&json).unwrap();
+            read.add_checkpoint(5000, "Styling", ProjectId::NoId)
+                .unwrap();
+            let read_messages = messages(&read);
+            assert!(Arc::ptr_eq(&read_messages[0], &read_messages[1]));
+            assert!(Arc::ptr_eq(&read_messages[0], &read_messages[4]));
+            assert_eq!(read, {
+                let mut expected = checkpoint_db.clone();
+                expected
+                    .add_checkpoint(5000, "Styling", ProjectId::NoId)
+                    .unwrap();
+                expected
+            });
+        }
+    }
+
+    #[test]
+    /// Messages that no checkpoint uses anymore are dropped.
+    fn released_messages() {
+        let mut checkpoint_db = CheckpointDb::new();
+        checkpoint_db
+            .add_checkpoint(1000, "Styling", ProjectId::NoId)
+            .unwrap();
+        for n in 0..10 * MIN_RELEASE_AT {
+            checkpoint_db
+                .set_checkpoint_message(CheckpointId::Timestamp(1000), &n.to_string())
+                .unwrap();
+        }
+        assert!(checkpoint_db.messages.shared.len() <= MIN_RELEASE_AT);
+        assert_eq!(
+            &*checkpoint_db.checkpoints[&1000].message,
+            (10 * MIN_RELEASE_AT - 1).to_string()
+        );
+    }
+}
@@ -0,0 +1,638 @@
+use crate::{
+    billing::amount, operation::check_tax_rate, report::Entry, CheckpointDb, CheckpointDbError,
+    ClientId, Expense, ExpenseId, Operation, ProjectId, Result, RoundingPolicy, RoundingScope,
+    TimeRange, Timestamp,
+};
+use chrono::prelude::*;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    iter,
+};
+
+/// How the time on an `Invoice` is split into lines.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InvoiceGrouping {
+    /// One line per project.
+    Project,
+    /// One line per project and day.
+    Day,
+}
+
+/// How invoice numbers are written. `{number}` in the pattern stands for the number in the
+/// sequence, padded with zeros to `digits` digits, and `{year}` for the year the invoice is
+/// made in, so `{year}-{number}` with 4 digits gives numbers like `2024-0042`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct InvoiceNumbering {
+    pub pattern: String,
+    pub digits: usize,
+}
+
+impl Default for InvoiceNumbering {
+    fn default() -> InvoiceNumbering {
+        InvoiceNumbering {
+            pattern: "{number}".to_string(),
+            digits: 1,
+        }
+    }
+}
+
+impl InvoiceNumbering {
+    #[cfg(feature = "json")]
+    pub(crate) fn is_default(&self) -> bool {
+        *self == InvoiceNumbering::default()
+    }
+
+    pub fn format(&self, number: u32, year: i32) -> String {
+        self.pattern.replace("{year}", &year.to_string()).replace(
+            "{number}",
+            &format!("{:0digits$}", number, digits = self.digits),
+        )
+    }
+}
+
+/// How the tax of an `Invoice` is rounded to whole units of the currency.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TaxRounding {
+    /// The tax of each line is rounded, and the tax at each rate is their sum.
+    Line,
+    /// The tax at each rate is calculated from the net amount of its lines and rounded once.
+    #[default]
+    Total,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct InvoiceLine {
+    pub description: String,
+    pub project_id: ProjectId,
+    /// The day the line covers, if the invoice is grouped by day.
+    pub date: Option<NaiveDate>,
+    /// The billed duration in seconds.
+    pub duration: i64,
+    pub rate: Option<i64>,
+    /// The net amount, before tax.
+    pub amount: i64,
+    /// The tax rate of the line as a fraction, like `0.25` for 25%.
+    pub tax_rate: f64,
+    /// The tax of the line, rounded on its own.
+    pub tax: i64,
+    /// The expense the line bills, if it isn't for time.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub expense_id: Option<ExpenseId>,
+}
+
+/// An invoice for a client, ready to be rendered. Amounts are in the smallest unit of the
+/// currency.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Invoice {
+    pub number: String,
+    pub client_id: ClientId,
+    pub range: TimeRange,
+    pub currency: Option<String>,
+    pub lines: Vec<InvoiceLine>,
+    /// The net amount, before tax.
+    pub subtotal: i64,
+    /// The tax rate of lines whose project and client have none, as a fraction.
+    pub tax_rate: f64,
+    /// The net amount and tax at each of the tax rates of the lines, from the lowest rate.
+    pub taxes: Vec<TaxTotal>,
+    pub tax: i64,
+    /// The gross amount, with tax.
+    pub total: i64,
+}
+
+/// The lines of an `Invoice` with the same tax rate.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct TaxTotal {
+    pub rate: f64,
+    pub net: i64,
+    pub tax: i64,
+}
+
+/// Builds an `Invoice` for all billable time and expenses of a client's projects in a
+/// `TimeRange`.
+#[derive(Clone, Debug)]
+pub struct InvoiceBuilder {
+    client_id: ClientId,
+    range: TimeRange,
+    grouping: InvoiceGrouping,
+    tax_rate: f64,
+    tax_rounding: TaxRounding,
+    rounding: Option<RoundingPolicy>,
+}
+
+impl Invoice {
+    pub fn builder(client_id: ClientId, range: TimeRange) -> InvoiceBuilder {
+        InvoiceBuilder {
+            client_id,
+            range,
+            grouping: InvoiceGrouping::Project,
+            tax_rate: 0.0,
+            tax_rounding: TaxRounding::Total,
+            rounding: None,
+        }
+    }
+}
+
+impl InvoiceBuilder {
+    pub fn grouping(mut self, grouping: InvoiceGrouping) -> InvoiceBuilder {
+        self.grouping = grouping;
+        self
+    }
+
+    /// Sets the tax rate of lines for projects that have no tax rate of their own, when the
+    /// client has none either. No tax by default.
+    pub fn tax_rate(mut self, tax_rate: f64) -> InvoiceBuilder {
+        self.tax_rate = tax_rate;
+        self
+    }
+
+    pub fn tax_rounding(mut self, tax_rounding: TaxRounding) -> InvoiceBuilder {
+        self.tax_rounding = tax_rounding;
+        self
+    }
+
+    pub fn rounding(mut self, rounding: RoundingPolicy) -> InvoiceBuilder {
+        self.rounding = Some(rounding);
+        self
+    }
+
+    /// Returns the invoice that `build` would create, without numbering it or marking any
+    /// checkpoints as invoiced. Fails with `CheckpointDbError::InvalidInput` if the tax rate is
+    /// negative or not a number.
+    pub fn preview(&self, checkpoint_db: &CheckpointDb) -> Result<Invoice> {
+        check_tax_rate(Some(self.tax_rate))?;
+        let Some(client) = checkpoint_db.client_from_client_id(self.client_id) else {
+            return Err(CheckpointDbError::ClientNotFound { id: self.client_id });
+        };
+        let tax_rate = |project_id: ProjectId| {
+            checkpoint_db
+                .project_from_project_id(project_id)
+                .and_then(|project| project.tax_rate)
+                .or(client.tax_rate)
+                .unwrap_or(self.tax_rate)
+        };
+
+        let scope = self
+            .rounding
+            .map_or(RoundingScope::Entry, |rounding| rounding.scope);
+        // Time at different rates gets lines of its own.
+        let checkpoint_rates: BTreeSet<Option<i64>> = self
+            .covered_entries(checkpoint_db)
+            .map(|entry| entry.checkpoint.rate)
+            .collect();
+        let mut lines: BTreeMap<(ProjectId, Option<NaiveDate>, Option<i64>), i64> = BTreeMap::new();
+        for checkpoint_rate in checkpoint_rates {
+            let project_days = checkpoint_db.project_days(
+                self.covered_entries(checkpoint_db)
+                    .filter(|entry| entry.checkpoint.rate == checkpoint_rate),
+                scope,
+                |project_id, duration| {
+                    checkpoint_db.billable_duration(project_id, duration, self.rounding.as_ref())
+                },
+            );
+            for ((date, project_id), duration) in project_days {
+                let date = match self.grouping {
+                    InvoiceGrouping::Project => None,
+                    InvoiceGrouping::Day => Some(date),
+                };
+                *lines
+                    .entry((project_id, date, checkpoint_rate))
+                    .or_insert(0) += duration;
+            }
+        }
+
+        let mut lines: Vec<InvoiceLine> = lines
+            .into_iter()
+            .map(|((project_id, date, checkpoint_rate), duration)| {
+                let project = checkpoint_db.project_from_project_id(project_id);
+                let rate = checkpoint_rate.or(project.and_then(|project| project.rate));
+                let name = project.map_or("", |project| project.long_name.as_str());
+
+                InvoiceLine {
+                    description: match date {
+                        Some(date) => format!("{} {}", date, name),
+                        None => name.to_string(),
+                    },
+                    project_id,
+                    date,
+                    duration,
+                    rate,
+                    amount: rate.map_or(0, |rate| amount(rate, duration)),
+                    tax_rate: tax_rate(project_id),
+                    tax: 0,
+                    expense_id: None,
+                }
+            })
+            .collect();
+        let mut currencies: Vec<Option<&str>> = lines
+            .iter()
+            .map(|line| checkpoint_db.project_currency(line.project_id))
+            .collect();
+
+        for (expense_id, expense) in self.covered_expenses(checkpoint_db) {
+            currencies.push(checkpoint_db.expense_currency(expense));
+            lines.push(InvoiceLine {
+                description: expense.note.clone(),
+                project_id: expense.project_id,
+                date: Some(expense.date),
+                duration: 0,
+                rate: None,
+                amount: expense.amount,
+                tax_rate: tax_rate(expense.project_id),
+                tax: 0,
+                expense_id: Some(expense_id),
+            });
+        }
+
+        let mut currencies = currencies.into_iter();
+        let currency = currencies
+            .next()
+            .unwrap_or(checkpoint_db.currency.as_deref());
+        if currencies.any(|other| other != currency) {
+            return Err(CheckpointDbError::InvalidInput(
+                "the client's projects and expenses are billed in different currencies".to_string(),
+            ));
+        }
+
+        let mut taxes: Vec<TaxTotal> = Vec::new();
+        for line in &mut lines {
+            line.tax = tax(line.amount, line.tax_rate);
+            let index = match taxes.iter().position(|total| total.rate == line.tax_rate) {
+                Some(index) => index,
+                None => {
+                    taxes.push(TaxTotal {
+                        rate: line.tax_rate,
+                        net: 0,
+                        tax: 0,
+                    });
+                    taxes.len() - 1
+                }
+            };
+            taxes[index].net += line.amount;
+            taxes[index].tax += line.tax;
+        }
+        if self.tax_rounding == TaxRounding::Total {
+            for total in &mut taxes {
+                total.tax = tax(total.net, total.rate);
+            }
+        }
+        taxes.sort_by(|a, b| a.rate.total_cmp(&b.rate));
+
+        let subtotal: i64 = taxes.iter().map(|total| total.net).sum();
+        let tax: i64 = taxes.iter().map(|total| total.tax).sum();
+
+        Ok(Invoice {
+            number: String::new(),
+            client_id: self.client_id,
+            range: self.range,
+            currency: currency.map(str::to_string),
+            lines,
+            subtotal,
+            tax_rate: self.tax_rate,
+            taxes,
+            tax,
+            total: subtotal + tax,
+        })
+    }
+
+    /// Creates the invoice, gives it the next number in the database's invoice sequence and
+    /// marks the covered checkpoints and expenses as invoiced.
+    ///
+    /// Either all of this is done or, if any of it fails, none of it is. The changes are made to
+    /// a copy of the database first, and only made to the database once they all succeeded.
+    /// Like `CheckpointDb::next_invoice_number`, build invoices within `DbFile::exclusive` when
+    /// other frontends write to the same file.
+    pub fn build(self, checkpoint_db: &mut CheckpointDb) -> Result<Invoice> {
+        let mut invoice = self.preview(checkpoint_db)?;
+        if invoice.lines.is_empty() {
+            return Err(CheckpointDbError::InvalidInput(
+                "there is nothing to invoice in the given range".to_string(),
+            ));
+        }
+
+        let number = checkpoint_db
+            .last_invoice_number
+            .checked_add(1)
+            .ok_or_else(|| {
+                CheckpointDbError::InvalidInput("there are no invoice numbers left".to_string())
+            })?;
+        invoice.number = checkpoint_db.format_invoice_number(number);
+
+        let covered: Vec<i64> = self
+            .covered_entries(checkpoint_db)
+            .map(|entry| entry.timestamp)
+            .collect();
+        let operations: Vec<Operation> = iter::once(Operation::AllocateInvoiceNumber { number })
+            .chain(
+                covered
+                    .into_iter()
+                    .map(|timestamp| Operation::MarkInvoiced {
+                        timestamp: Timestamp(timestamp),
+                        invoice: invoice.number.clone(),
+                    }),
+            )
+            .chain(
+                invoice
+                    .lines
+                    .iter()
+                    .filter_map(|line| line.expense_id)
+                    .map(|expense_id| Operation::MarkExpenseInvoiced {
+                        expense_id,
+                        invoice: invoice.number.clone(),
+                    }),
+            )
+            .collect();
+
+        let mut checked = checkpoint_db.clone();
+        for operation in &operations {
+            checked.apply(operation.clone())?;
+        }
+        for operation in operations {
+            checkpoint_db.apply(operation)?;
+        }
+        Ok(invoice)
+    }
+
+    /// Returns the entries in the range that belong to billable projects of the client and have
+    /// not been invoiced yet.
+    fn covered_entries<'a>(
+        &self,
+        checkpoint_db: &'a CheckpointDb,
+    ) -> impl Iterator<Item = Entry<'a>> + 'a {
+        let client_id = self.client_id;
+        checkpoint_db.entries_in(&self.range).filter(move |entry| {
+            entry.checkpoint.invoice.is_none()
+                && checkpoint_db
+                    .project_from_project_id(entry.checkpoint.project_id)
+                    .is_some_and(|project| project.billable && project.client_id == Some(client_id))
+        })
+    }
+
+    /// Returns the billable expenses in the range that belong to the client's projects and have
+    /// not been invoiced yet.
+    fn covered_expenses<'a>(
+        &self,
+        checkpoint_db: &'a CheckpointDb,
+    ) -> Vec<(ExpenseId, &'a Expense)> {
+        let mut expenses = checkpoint_db.expenses_in(&self.range);
+        expenses.retain(|(_, expense)| {
+            expense.billable
+                && expense.invoice.is_none()
+                && checkpoint_db
+                    .project_from_project_id(expense.project_id)
+                    .is_some_and(|project| project.client_id == Some(self.client_id))
+        });
+        expenses
+    }
+}
+
+impl CheckpointDb {
+    /// Sets the tax rate of a client's invoices as a fraction, like `0.25` for 25%, or removes
+    /// it with `None`.
+    pub fn set_client_tax_rate(
+        &mut self,
+        client_id: ClientId,
+        tax_rate: Option<f64>,
+    ) -> Result<()> {
+        self.apply(Operation::SetClientTaxRate {
+            client_id,
+            tax_rate,
+        })
+    }
+
+    /// Sets the tax rate of a project's invoice lines, which overrides the client's and the
+    /// invoice's, or removes it with `None`.
+    pub fn set_project_tax_rate(
+        &mut self,
+        project_id: ProjectId,
+        tax_rate: Option<f64>,
+    ) -> Result<()> {
+        self.apply(Operation::SetProjectTaxRate {
+            project_id,
+            tax_rate,
+        })
+    }
+
+    pub fn invoice_numbering(&self) -> &InvoiceNumbering {
+        &self.invoice_numbering
+    }
+
+    /// Replaces the invoice numbering, failing with `CheckpointDbError::InvalidInput` if the
+    /// pattern has no `{number}`.
+    pub fn set_invoice_numbering(&mut self, invoice_numbering: InvoiceNumbering) -> Result<()> {
+        self.apply(Operation::SetInvoiceNumbering { invoice_numbering })
+    }
+
+    /// Returns the number of the most recently generated invoice.
+    pub fn last_invoice_number(&self) -> u32 {
+        self.last_invoice_number
+    }
+
+    /// Takes the next number in the invoice sequence and writes it with the
+    /// `invoice_numbering`. Taking a number is an operation, so it's saved in deltas and
+    /// synced. When other frontends write to the same file, take it within
+    /// `DbFile::exclusive`, so that it's the next number in the file rather than in this copy.
+    pub fn next_invoice_number(&mut self) -> Result<String> {
+        let number = self.last_invoice_number.checked_add(1).ok_or_else(|| {
+            CheckpointDbError::InvalidInput("there are no invoice numbers left".to_string())
+        })?;
+        self.apply(Operation::AllocateInvoiceNumber { number })?;
+        Ok(self.format_invoice_number(number))
+    }
+
+    /// Writes the number with the `invoice_numbering`, in the current year of the settings'
+    /// time zone.
+    fn format_invoice_number(&self, number: u32) -> String {
+        let year = self.date_of(Timestamp::now().seconds()).year();
+        self.invoice_numbering.format(number, year)
+    }
+}
+
+/// Returns the tax on the net amount at the rate, rounded to the nearest unit.
+fn tax(net: i64, rate: f64) -> i64 {
+    (net as f64 * rate).round() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Builds an invoice with tax, and checks that the covered checkpoints get marked with its
+    /// number.
+    fn build_invoice() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let client_id = checkpoint_db.add_client("Client").unwrap();
+        let project_id = checkpoint_db.add_project("Project", "prj").unwrap();
+        checkpoint_db
+            .set_project_client(project_id, Some(client_id))
+            .unwrap();
+        checkpoint_db
+            .set_project_rate(project_id, Some(10000))
+            .unwrap();
+
+        let start = Utc
+            .with_ymd_and_hms(2021, 6, 15, 12, 0, 0)
+            .unwrap()
+            .timestamp();
+        checkpoint_db
+            .add_checkpoint(start, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 3600, "Work", project_id)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 5400, "More work", project_id)
+            .unwrap();
+
+        let range = TimeRange::new(start, start + 2 * 3600);
+        for tax_rate in [-0.25, f64::NAN] {
+            assert!(Invoice::builder(client_id, range)
+                .tax_rate(tax_rate)
+                .build(&mut checkpoint_db)
+                .is_err());
+        }
+        let invoice = Invoice::builder(client_id, range)
+            .tax_rate(0.25)
+            .build(&mut checkpoint_db)
+            .unwrap();
+        assert_eq!(invoice.number, "1");
+        assert_eq!(invoice.lines.len(), 1);
+        assert_eq!(invoice.lines[0].duration, 5400);
+        assert_eq!(invoice.subtotal, 15000);
+        assert_eq!(invoice.tax, 3750);
+        assert_eq!(invoice.total, 18750);
+
+        let invoiced = checkpoint_db
+            .checkpoints
+            .values()
+            .filter(|checkpoint| checkpoint.invoice.as_deref() == Some("1"))
+            .count();
+        assert_eq!(invoiced, 2);
+
+        // The same time can't be invoiced twice.
+        assert!(Invoice::builder(client_id, range)
+            .build(&mut checkpoint_db)
+            .is_err());
+        assert_eq!(checkpoint_db.uninvoiced_report(&range, None).billable, 0);
+        assert_eq!(checkpoint_db.mark_invoice_paid("1").unwrap(), 2);
+
+        // An invoice that can't be given a number leaves everything as it was.
+        checkpoint_db
+            .add_checkpoint(start + 3 * 3600, "Later work", project_id)
+            .unwrap();
+        checkpoint_db
+            .apply(Operation::AllocateInvoiceNumber { number: u32::MAX })
+            .unwrap();
+        let before = checkpoint_db.clone();
+        assert!(
+            Invoice::builder(client_id, TimeRange::new(start, start + 4 * 3600))
+                .build(&mut checkpoint_db)
+                .is_err()
+        );
+        assert_eq!(checkpoint_db, before);
+    }
+
+    #[test]
+    /// Projects use their own tax rate or the client's, and tax is rounded per line or per
+    /// rate.
+    fn taxes() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let client_id = checkpoint_db.add_client("Client").unwrap();
+        checkpoint_db
+            .set_client_tax_rate(client_id, Some(0.25))
+            .unwrap();
+        let start = Utc
+            .with_ymd_and_hms(2021, 6, 15, 12, 0, 0)
+            .unwrap()
+            .timestamp();
+        checkpoint_db
+            .add_checkpoint(start, "Start", ProjectId::NoId)
+            .unwrap();
+        for (i, (rate, tax_rate)) in [(1005, None), (1005, None), (10000, Some(0.07))]
+            .into_iter()
+            .enumerate()
+        {
+            let project_id = checkpoint_db
+                .add_project(&format!("Project {}", i), &format!("p{}", i))
+                .unwrap();
+            checkpoint_db
+                .set_project_client(project_id, Some(client_id))
+                .unwrap();
+            checkpoint_db
+                .set_project_rate(project_id, Some(rate))
+                .unwrap();
+            checkpoint_db
+                .set_project_tax_rate(project_id, tax_rate)
+                .unwrap();
+            checkpoint_db
+                .add_checkpoint(start + (i as i64 + 1) * 3600, "Work", project_id)
+                .unwrap();
+        }
+
+        let range = TimeRange::new(start, start + 4 * 3600);
+        let invoice = Invoice::builder(client_id, range)
+            .preview(&checkpoint_db)
+            .unwrap();
+        assert_eq!(invoice.lines[0].tax, 251);
+        assert_eq!(
+            invoice.taxes,
+            [
+                TaxTotal {
+                    rate: 0.07,
+                    net: 10000,
+                    tax: 700,
+                },
+                TaxTotal {
+                    rate: 0.25,
+                    net: 2010,
+                    tax: 503,
+                },
+            ]
+        );
+        assert_eq!(invoice.subtotal, 12010);
+        assert_eq!(invoice.total, 12010 + 1203);
+
+        let invoice = Invoice::builder(client_id, range)
+            .tax_rounding(TaxRounding::Line)
+            .preview(&checkpoint_db)
+            .unwrap();
+        assert_eq!(invoice.tax, 1202);
+    }
+
+    #[test]
+    /// Invoice numbers follow the numbering, and a number can't be taken twice.
+    fn invoice_numbers() {
+        let mut checkpoint_db = CheckpointDb::new();
+        checkpoint_db
+            .set_invoice_numbering(InvoiceNumbering {
+                pattern: "{year}-{number}".to_string(),
+                digits: 4,
+            })
+            .unwrap();
+        checkpoint_db
+            .apply(Operation::AllocateInvoiceNumber { number: 41 })
+            .unwrap();
+        let year = checkpoint_db.date_of(Timestamp::now().seconds()).year();
+        assert_eq!(
+            checkpoint_db.next_invoice_number().unwrap(),
+            format!("{}-0042", year)
+        );
+        assert_eq!(checkpoint_db.last_invoice_number(), 42);
+        assert_eq!(InvoiceNumbering::default().format(7, year), "7");
+
+        // Another frontend that hasn't seen the number tries to take it too.
+        assert!(matches!(
+            checkpoint_db.apply(Operation::AllocateInvoiceNumber { number: 42 }),
+            Err(CheckpointDbError::InvoiceNumberTaken { number: 42 })
+        ));
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{
+    CheckpointDb, CheckpointDbError, CheckpointKind, ImportSummary, ImportedCheckpoint, Result,
+    TimeRange,
+};
+use std::{
+    io::{BufRead, Write},
+    mem,
+};
+
+/// The number of lines `CheckpointDb::import_jsonl` imports at once.
+const BATCH_SIZE: usize = 1000;
+
+/// A line of JSON Lines, holding one checkpoint.
+#[derive(Debug, Serialize, Deserialize)]
+struct JsonLine {
+    timestamp: i64,
+    /// The time tracked by the checkpoint, which is only written for convenience and is
+    /// ignored when importing.
+    #[serde(default, skip_deserializing)]
+    duration: i64,
+    /// The short name of the checkpoint's project.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "CheckpointKind::is_work")]
+    kind: CheckpointKind,
+}
+
+impl CheckpointDb {
+    /// Writes the checkpoints in the given `TimeRange` as JSON Lines, one JSON object per line
+    /// with the timestamp, duration, project short name, message, tags and kind of a
+    /// checkpoint.
+    pub fn export_jsonl<W: Write>(&self, mut writer: W, range: &TimeRange) -> Result<()> {
+        for entry in self.entries_in(range) {
+            let line = JsonLine {
+                timestamp: entry.timestamp,
+                duration: entry.duration,
+                project: self
+                    .project_from_project_id(entry.checkpoint.project_id)
+                    .map(|project| project.short_name.clone()),
+                message: entry.checkpoint.message.to_string(),
+                tags: entry.checkpoint.tags.clone(),
+                kind: entry.checkpoint.kind,
+            };
+            serde_json::to_writer(&mut writer, &line)?;
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads JSON Lines as written by `export_jsonl` and imports them in batches of 1000
+    /// lines, so that the input never has to fit in memory as a whole. Blank lines are skipped.
+    /// If `dry_run` is set, nothing is added and the returned summary tells what would have
+    /// been.
+    ///
+    /// The summary doesn't list the imported checkpoints. A line that can't be read or
+    /// imported fails the import, keeping the batches before its own.
+    pub fn import_jsonl<R: BufRead>(&mut self, reader: R, dry_run: bool) -> Result<ImportSummary> {
+        if dry_run {
+            // The batches have to see the ones before them to tell what they would do.
+            return self.clone().import_jsonl(reader, false);
+        }
+
+        let mut summary = ImportSummary::default();
+        let mut batch = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let error = |message: String| {
+                CheckpointDbError::InvalidInput(format!("line {}: {}", i + 1, message))
+            };
+
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line: JsonLine = serde_json::from_str(&line).map_err(|e| error(e.to_string()))?;
+
+            batch.push(ImportedCheckpoint {
+                timestamp: line.timestamp,
+                message: line.message,
+                project: line.project,
+                tags: line.tags,
+                start: None,
+                external_id: None,
+                kind: Some(line.kind),
+            });
+            if batch.len() == BATCH_SIZE {
+                add_batch(&mut summary, self.import(mem::take(&mut batch), false)?);
+            }
+        }
+        add_batch(&mut summary, self.import(batch, false)?);
+
+        Ok(summary)
+    }
+}
+
+/// Adds what a batch of an import did to the summary of the whole import, leaving out its
+/// checkpoints.
+fn add_batch(summary: &mut ImportSummary, batch: ImportSummary) {
+    summary.created += batch.created;
+    summary.updated += batch.updated;
+    summary.skipped += batch.skipped;
+    summary.new_projects.extend(batch.new_projects);
+    summary.breaks.extend(batch.breaks);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Checkpoint, ProjectId};
+
+    #[test]
+    /// Exports one checkpoint per line and imports them into another database, keeping breaks.
+    fn jsonl_round_trip() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Website", "web").unwrap();
+        checkpoint_db
+            .add_checkpoint(1000, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(1600, "Fix the\nheader", project_id)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint_full(2000, Checkpoint::builder().kind(CheckpointKind::Break))
+            .unwrap();
+
+        let mut output = Vec::new();
+        checkpoint_db
+            .export_jsonl(&mut output, &TimeRange::new(0, 3000))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output.clone()).unwrap(),
+            "{\"timestamp\":1000,\"duration\":0,\"message\":\"Start\"}\n\
+             {\"timestamp\":1600,\"duration\":600,\"project\":\"web\",\"message\":\"Fix the\\nheader\"}\n\
+             {\"timestamp\":2000,\"duration\":400,\"message\":\"\",\"kind\":\"Break\"}\n"
+        );
+
+        let mut imported = CheckpointDb::new();
+        let summary = imported.import_jsonl(&output[..], true).unwrap();
+        assert_eq!(summary.created, 3);
+        assert!(imported.is_empty());
+        let summary = imported.import_jsonl(&output[..], false).unwrap();
+        assert_eq!(summary.new_projects, vec!["web"]);
+        assert!(summary.checkpoints.is_empty());
+        assert_eq!(&*imported.checkpoints[&1600].message, "Fix the\nheader");
+        assert_eq!(imported.checkpoints[&2000].kind, CheckpointKind::Break);
+    }
+}
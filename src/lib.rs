@@ -1,56 +1,214 @@
+#[cfg(feature = "json")]
 #[macro_use]
 extern crate serde_derive;
 extern crate chrono;
+#[cfg(feature = "json")]
 extern crate serde;
+#[cfg(feature = "json")]
 extern crate serde_json;
 
-use chrono::prelude::*;
+#[cfg(feature = "plugins")]
+#[doc(hidden)]
+pub use inventory;
+
+mod activity;
+mod analytics;
+#[cfg(feature = "json")]
+mod anonymize;
+#[cfg(feature = "tokio")]
+mod async_io;
+mod billing;
+#[cfg(feature = "binary_cache")]
+mod binary_cache;
+mod builder;
+mod cache;
+#[cfg(feature = "caldav")]
+mod caldav;
+mod category;
+mod clockify;
+mod close;
+mod csv;
+#[cfg(all(feature = "daemon", unix))]
+mod daemon;
+#[cfg(feature = "dbus")]
+mod dbus;
+mod duration;
+mod entry;
+mod error;
+mod events;
+mod expense;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod fill;
+mod git;
+mod goals;
+mod harvest;
+mod ical;
+mod idle;
+mod import;
+mod index;
+mod intern;
+mod invoice;
+#[cfg(feature = "json")]
+mod jsonl;
+mod locale;
+#[cfg(feature = "lua")]
+mod lua;
+mod metrics;
+#[cfg(feature = "mmap")]
+mod mmap;
+#[cfg(feature = "natural")]
+mod natural;
+mod operation;
+#[cfg(feature = "json")]
+mod options;
+mod org;
+mod pay;
+mod period;
+mod plugin;
+mod pomodoro;
+mod privacy;
+#[cfg(feature = "time_track_py")]
+mod python;
+mod reminders;
+mod render;
+mod report;
+mod resolve;
+#[cfg(feature = "rest")]
+mod rest;
+#[cfg(feature = "json")]
+mod schema;
+mod settings;
+mod shared;
+mod statusline;
+#[cfg(feature = "json")]
+mod stream;
+#[cfg(feature = "sync")]
+mod sync;
+mod team;
+#[cfg(feature = "templates")]
+mod template;
+mod timeclock;
+mod timesheet;
+mod timestamp;
+#[cfg(feature = "json")]
+mod timewarrior;
+mod toggl;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "webhooks")]
+mod webhooks;
+#[cfg(feature = "json")]
+mod workspace;
+
+pub use activity::{ActivitySample, ActivitySource, ActivitySummarizer};
+pub use analytics::{
+    Comparison, DurationChange, Forecast, MessageFrequency, ProjectActivity, ProjectChange,
+    ProjectForecast, ProjectShare, Status,
+};
+pub use billing::{
+    BillableTime, BillingReport, ClientEarnings, ClientReport, CurrencyEarnings, Earnings,
+    EarningsReport, ExchangeRates, ProjectEarnings, TaskTotal,
+};
+pub use builder::CheckpointBuilder;
+pub use cache::CachedCheckpointDb;
+#[cfg(feature = "caldav")]
+pub use caldav::{CalDavCalendar, CALDAV_TAG};
+pub use category::{Category, CategoryTotal};
+pub use clockify::ClockifyImporter;
+pub use close::{CloseDayOptions, DayClosing, DayProblem};
+pub use csv::{CsvMapping, CsvOptions};
+#[cfg(all(feature = "daemon", unix))]
+pub use daemon::{Daemon, DaemonClient, DaemonRequest, DaemonResponse};
+#[cfg(feature = "dbus")]
+pub use dbus::{serve_dbus, DbusService, DBUS_NAME, DBUS_PATH};
+pub use duration::TrackedDuration;
+pub use entry::CheckpointEntry;
+pub use error::{CheckpointDbError, Result};
+pub use events::ChangeEvent;
+pub use expense::{Expense, ExpenseId};
+pub use fill::TemplateDay;
+pub use git::GitImporter;
+pub use goals::{DailyProgress, GoalProgress, ProjectGoalProgress};
+pub use harvest::HarvestImporter;
+pub use ical::IcalProjectRule;
+pub use idle::{IdleBreak, IdleMonitor, IdlePolicy, IdleSource};
+pub use import::{ImportSummary, ImportedCheckpoint, Importer};
+pub use invoice::{
+    Invoice, InvoiceBuilder, InvoiceGrouping, InvoiceLine, InvoiceNumbering, TaxRounding, TaxTotal,
+};
+pub use locale::{DateFormat, DateFormatter};
+#[cfg(feature = "lua")]
+pub use lua::LuaHooks;
+#[cfg(feature = "mmap")]
+pub use mmap::MappedDb;
+pub use operation::Operation;
+#[cfg(feature = "json")]
+pub use options::{BatchGuard, DbFile, Format, OpenOptions};
+pub use pay::PayRule;
+pub use period::Period;
+#[cfg(feature = "plugins")]
+pub use plugin::Plugin;
+pub use plugin::{Exporter, PluginRegistry};
+pub use pomodoro::{Pomodoro, PomodoroCount, PomodoroStats};
+pub use privacy::{DataExport, ExportedCheckpoint, PurgeConfirmation, PurgeTarget};
+#[cfg(feature = "time_track_py")]
+pub use python::PyCheckpointDb;
+#[cfg(feature = "notifications")]
+pub use reminders::DesktopNotifier;
+pub use reminders::{Notifier, Reminder, ReminderSchedule, WorkingHours};
+pub use render::{HtmlRenderer, MarkdownRenderer, ReportRenderer};
+pub use report::{
+    DayTotal, ProjectTotal, Report, RoundingMode, RoundingPolicy, RoundingScope, TimeRange,
+};
+#[cfg(feature = "rest")]
+pub use rest::rest_router;
+pub use settings::{ReportTimeZone, Settings};
+pub use shared::SharedCheckpointDb;
+#[cfg(feature = "json")]
+pub use stream::{read_checkpoints, DbWriter};
+#[cfg(feature = "sync")]
+pub use sync::{
+    sync_router, SyncClient, SyncRequest, SyncResponse, SyncServer, SyncState, SyncSummary,
+    SyncedOperation,
+};
+pub use team::{Team, TeamClientTotal, TeamProjectTotal, TeamReport};
+#[cfg(feature = "templates")]
+pub use template::HandlebarsRenderer;
+pub use timesheet::{Timesheet, TimesheetState};
+pub use timestamp::Timestamp;
+#[cfg(feature = "json")]
+pub use timewarrior::TimewarriorImporter;
+pub use toggl::{TogglFormat, TogglImporter};
+#[cfg(feature = "wasm")]
+pub use wasm::{BrowserStorage, WasmCheckpointDb};
+#[cfg(feature = "webhooks")]
+pub use webhooks::{sign, Webhook, WebhookPayload, Webhooks, WEBHOOK_SIGNATURE_HEADER};
+#[cfg(feature = "json")]
+pub use workspace::{Workspace, WorkspaceManifest, WorkspaceReport};
+
+use events::Listeners;
+use index::{Indexes, Timestamps};
+use intern::Messages;
+use locale::CustomFormatter;
+#[cfg(feature = "json")]
+use options::Journal;
+#[cfg(feature = "json")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::{
     cmp::{max, min},
     collections::BTreeMap,
-    error,
     fmt::{self, Display},
-    fs::{self, File},
-    io,
-    path::Path,
+    str::FromStr,
+    sync::Arc,
 };
+#[cfg(feature = "json")]
+use std::{mem, path::Path};
 
-#[derive(Clone)]
-pub struct CheckpointDbError {
-    error_kind: ErrorKind,
-    message: String,
-}
-
-#[derive(Debug, Clone)]
-pub enum ErrorKind {
-    AlreadyExists,
-    InvalidInput,
-}
-
-impl fmt::Display for CheckpointDbError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}: {}", self.error_kind, self.message)
-    }
-}
-
-impl fmt::Debug for CheckpointDbError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}: {}", self.error_kind, self.message)
-    }
-}
-
-impl std::error::Error for CheckpointDbError {
-    fn description(&self) -> &str {
-        &self.message
-    }
-
-    fn cause(&self) -> Option<&dyn error::Error> {
-        // Generic error, underlying cause isn't tracked.
-        None
-    }
-}
-
-#[derive(Clone, Copy, Debug)]
+/// Refers to a checkpoint either by its timestamp or by its position counted from the latest
+/// checkpoint, which is at position 0. As a string, and when serialized, a timestamp is written
+/// like `@1717171717` and a position like `~3`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum CheckpointId {
     Timestamp(i64),
     Position(usize),
@@ -62,12 +220,7 @@ impl CheckpointId {
     pub fn to_timestamp(&self, checkpoint_db: &CheckpointDb) -> Option<i64> {
         match self {
             CheckpointId::Timestamp(t) => checkpoint_db.checkpoints.get(t).map(|_| *t),
-            CheckpointId::Position(pos) => checkpoint_db
-                .checkpoints
-                .iter()
-                .rev()
-                .nth(*pos)
-                .map(|(time, _checkpoint)| *time),
+            CheckpointId::Position(pos) => checkpoint_db.timestamp_at(*pos),
         }
     }
 
@@ -75,23 +228,8 @@ impl CheckpointId {
     /// of the `CheckpointId` as `Option<usize>`. Otherwise return `None`.
     pub fn to_position(&self, checkpoint_db: &CheckpointDb) -> Option<usize> {
         match self {
-            CheckpointId::Timestamp(t) => checkpoint_db
-                .checkpoints
-                .iter()
-                .rev()
-                .enumerate()
-                .find(|(_, (time, _checkpoint))| t == *time)
-                .map(|(i, (_, _))| i),
-            CheckpointId::Position(pos) => {
-                let checkpoint = checkpoint_db
-                    .checkpoints
-                    .iter()
-                    .rev()
-                    .nth(*pos)
-                    .map(|(_time, checkpoint)| checkpoint);
-
-                checkpoint.map(|_| *pos)
-            }
+            CheckpointId::Timestamp(t) => checkpoint_db.position_of(*t),
+            CheckpointId::Position(pos) => checkpoint_db.timestamp_at(*pos).map(|_| *pos),
         }
     }
 
@@ -100,8 +238,65 @@ impl CheckpointId {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+impl Display for CheckpointId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timestamp(timestamp) => write!(f, "at {}", timestamp),
+            Self::Position(position) => write!(f, "at position {}", position),
+        }
+    }
+}
+
+impl FromStr for CheckpointId {
+    type Err = CheckpointDbError;
+
+    /// Parses `@<timestamp>` or `~<position>`.
+    fn from_str(s: &str) -> Result<CheckpointId> {
+        let invalid = || {
+            CheckpointDbError::InvalidInput(format!(
+                "{:?} is not a checkpoint, expected @<timestamp> or ~<position>",
+                s
+            ))
+        };
+        if let Some(timestamp) = s.strip_prefix('@') {
+            timestamp
+                .parse()
+                .map(CheckpointId::Timestamp)
+                .map_err(|_| invalid())
+        } else if let Some(position) = s.strip_prefix('~') {
+            position
+                .parse()
+                .map(CheckpointId::Position)
+                .map_err(|_| invalid())
+        } else {
+            Err(invalid())
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl Serialize for CheckpointId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        match self {
+            Self::Timestamp(timestamp) => serializer.collect_str(&format_args!("@{}", timestamp)),
+            Self::Position(position) => serializer.collect_str(&format_args!("~{}", position)),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+impl<'de> Deserialize<'de> for CheckpointId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(de::Error::custom)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub enum ProjectId {
+    #[default]
     NoId,
     Id(u16),
 }
@@ -115,144 +310,453 @@ impl Display for ProjectId {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ClientId(pub u16);
+
+impl Display for ClientId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// What kind of time a `Checkpoint` ends.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum CheckpointKind {
+    #[default]
+    Work,
+    Break,
+}
+
+impl CheckpointKind {
+    #[cfg(feature = "json")]
+    fn is_work(&self) -> bool {
+        *self == CheckpointKind::Work
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Checkpoint {
-    pub message: String,
+    pub message: Arc<str>,
     pub project_id: ProjectId,
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "CheckpointKind::is_work")
+    )]
+    pub kind: CheckpointKind,
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub category: Option<Category>,
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub pomodoro: Option<Pomodoro>,
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub tags: Vec<String>,
+    /// The hourly rate of the time the checkpoint ends, in place of the project's rate.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub rate: Option<i64>,
+    /// The number of the invoice the checkpoint has been billed on.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub invoice: Option<String>,
+    /// Whether the invoice the checkpoint has been billed on is paid.
+    #[cfg_attr(feature = "json", serde(default, skip_serializing_if = "is_false"))]
+    pub paid: bool,
+    /// The id the checkpoint has in the tracker or calendar it was imported from, used to
+    /// recognize it when importing again.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub external_id: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[cfg(feature = "json")]
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Project {
     pub long_name: String,
     pub short_name: String,
+    /// The shortest duration in seconds billed for any piece of work on the project.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub minimum_billable: Option<i64>,
+    /// The hourly rate of the project in the smallest unit of the currency, like cents.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub rate: Option<i64>,
+    /// Whether time tracked on the project can be billed.
+    #[cfg_attr(feature = "json", serde(default = "default_billable"))]
+    pub billable: bool,
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub client_id: Option<ClientId>,
+    /// The currency of the project's rate, if it differs from the database's currency.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub currency: Option<String>,
+    /// The time in seconds that may be spent on the project each month.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub budget: Option<i64>,
+    /// The time in seconds to spend on the project each day.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub daily_goal: Option<i64>,
+    /// The tax rate of invoices for the project as a fraction, like `0.25` for 25%, if it
+    /// differs from the client's.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub tax_rate: Option<f64>,
+}
+
+#[cfg(feature = "json")]
+pub(crate) fn default_billable() -> bool {
+    true
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "json", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Client {
+    pub name: String,
+    /// The tax rate of invoices for the client as a fraction, like `0.25` for 25%.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub tax_rate: Option<f64>,
+}
+
+/// All projects, clients and checkpoints. The default is an empty database.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "json", derive(Deserialize, Serialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct CheckpointDb {
-    pub projects: BTreeMap<u16, Project>,
-    pub checkpoints: BTreeMap<i64, Checkpoint>,
+    projects: BTreeMap<u16, Project>,
+    #[cfg_attr(
+        feature = "json",
+        serde(deserialize_with = "intern::deserialize_checkpoints")
+    )]
+    checkpoints: BTreeMap<i64, Checkpoint>,
+    #[cfg_attr(feature = "json", serde(default))]
+    clients: BTreeMap<u16, Client>,
+    /// The currency that project rates are given in, like "EUR".
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    currency: Option<String>,
+    /// The number of the most recently generated invoice.
+    #[cfg_attr(feature = "json", serde(default))]
+    last_invoice_number: u32,
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "InvoiceNumbering::is_default")
+    )]
+    invoice_numbering: InvoiceNumbering,
+    /// The time in seconds to track each day.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    daily_goal: Option<i64>,
+    /// The rules for paying more for some time, like overtime, used by earnings reports.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pay_rules: Vec<PayRule>,
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Settings::is_default")
+    )]
+    settings: Settings,
+    /// The reviews of weeks by the timestamp they start at.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    timesheets: BTreeMap<i64, Timesheet>,
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    expenses: BTreeMap<u32, Expense>,
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Indexes::is_unbuilt")
+    )]
+    #[cfg_attr(feature = "schema", schemars(skip))]
+    indexes: Indexes,
+    #[cfg_attr(feature = "json", serde(skip))]
+    timestamps: Timestamps,
+    #[cfg_attr(feature = "json", serde(skip))]
+    messages: Messages,
+    #[cfg(feature = "json")]
+    #[serde(skip)]
+    journal: Journal,
+    #[cfg_attr(feature = "json", serde(skip))]
+    listeners: Listeners,
+    #[cfg_attr(feature = "json", serde(skip))]
+    date_formatter: CustomFormatter,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
 pub struct LogCheckpoint {
-    pub timestamp: i64,
+    pub timestamp: Timestamp,
     pub checkpoint: Checkpoint,
-    pub duration: Option<i64>,
+    pub duration: Option<TrackedDuration>,
     pub position: usize,
 }
 
-impl CheckpointDb {
-    fn new() -> CheckpointDb {
-        CheckpointDb {
-            projects: BTreeMap::new(),
-            checkpoints: BTreeMap::new(),
+/// Like `LogCheckpoint`, but borrows the checkpoint from the database instead of copying it.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct LogCheckpointRef<'a> {
+    pub timestamp: Timestamp,
+    pub checkpoint: &'a Checkpoint,
+    pub duration: Option<TrackedDuration>,
+    pub position: usize,
+}
+
+impl From<LogCheckpointRef<'_>> for LogCheckpoint {
+    fn from(log: LogCheckpointRef<'_>) -> LogCheckpoint {
+        LogCheckpoint {
+            timestamp: log.timestamp,
+            checkpoint: log.checkpoint.clone(),
+            duration: log.duration,
+            position: log.position,
         }
     }
+}
 
-    pub fn read(path: &Path) -> io::Result<CheckpointDb> {
-        match File::open(path) {
-            Ok(file) => {
-                let checkpoint_db = serde_json::from_reader(file)?;
-                Ok(checkpoint_db)
-            }
-            Err(e) => {
-                if e.kind() == io::ErrorKind::NotFound {
-                    let checkpoint_db = CheckpointDb::new();
-                    checkpoint_db.write(path)?;
-                    Ok(checkpoint_db)
-                } else {
-                    Err(e)
-                }
-            }
-        }
+impl CheckpointDb {
+    /// Creates an empty database, like `CheckpointDb::default`.
+    pub fn new() -> CheckpointDb {
+        CheckpointDb::default()
     }
 
-    pub fn write(&self, path: &Path) -> io::Result<()> {
-        let write_dir = path.parent().expect("Invalid database location");
-        if !write_dir.exists() {
-            fs::create_dir_all(write_dir)?;
-        }
+    /// Replaces the data of the database with that of another one, keeping what isn't part of
+    /// the data, like the listeners and the date formatter. As the change isn't an operation, a
+    /// `DbFile` writes the whole database the next time it's saved.
+    #[cfg(feature = "json")]
+    pub(crate) fn replace_data(&mut self, mut other: CheckpointDb) {
+        other.listeners = mem::take(&mut self.listeners);
+        other.date_formatter = mem::take(&mut self.date_formatter);
+        other.journal = mem::take(&mut self.journal);
+        other.journal.rewrite();
+        *self = other;
+        self.listeners.emit(ChangeEvent::Replaced);
+    }
+
+    /// Reads the database at the path, creating an empty one if there is no file. A file that
+    /// can't be read fails with `CheckpointDbError::Io` and one that isn't a valid database
+    /// with `CheckpointDbError::Serde`. Use `CheckpointDb::options` to not create missing files.
+    #[cfg(feature = "json")]
+    pub fn read(path: &Path) -> Result<CheckpointDb> {
+        Ok(CheckpointDb::options()
+            .create(true)
+            .open(path)?
+            .into_inner())
+    }
 
-        let file = File::create(path)?;
-        serde_json::to_writer_pretty(&file, self)?;
-        Ok(())
+    /// Writes the whole database to the path, and removes the delta file that `DbFile::save_delta`
+    /// may have left next to it, as the written database has its operations.
+    #[cfg(feature = "json")]
+    pub fn write(&self, path: &Path) -> Result<()> {
+        options::write_db(self, path, Format::Json)
     }
 
-    pub fn add_checkpoint(
+    /// Adds a checkpoint at the given time, which can be given as seconds or as a chrono
+    /// `DateTime`. Fails with `CheckpointDbError::CheckpointExists` if there already is a
+    /// checkpoint at the time, use `add_or_replace_checkpoint` to replace it, and with
+    /// `CheckpointDbError::ImplausibleTimestamp` if the settings don't allow the time, use
+    /// `force_add_checkpoint` to add it anyway.
+    pub fn add_checkpoint<T: Into<Timestamp>>(
         &mut self,
-        time: i64,
+        time: T,
         message: &str,
         project_id: ProjectId,
-    ) -> Result<(), CheckpointDbError> {
-        if let ProjectId::Id(project_id) = project_id {
-            if !self.projects.contains_key(&project_id) {
-                return Err(CheckpointDbError {
-                    error_kind: ErrorKind::InvalidInput,
-                    message: "the given project id does not exist".to_string(),
-                });
-            }
-        }
+    ) -> Result<()> {
+        self.add_checkpoint_full(
+            time,
+            Checkpoint::builder().message(message).project(project_id),
+        )
+    }
 
-        let message = message.to_string();
-        let checkpoint = Checkpoint {
-            message,
-            project_id,
-        };
-        self.checkpoints.insert(time, checkpoint);
-        Ok(())
+    /// Adds a checkpoint with any of its fields set, usually made with `Checkpoint::builder`.
+    /// Like `add_checkpoint`, it fails if there already is a checkpoint at the time, if the
+    /// settings don't allow the time or if the project doesn't exist.
+    pub fn add_checkpoint_full<T: Into<Timestamp>, C: Into<Checkpoint>>(
+        &mut self,
+        time: T,
+        checkpoint: C,
+    ) -> Result<()> {
+        let timestamp = time.into();
+        self.check_plausible(timestamp)?;
+        self.force_add_checkpoint(timestamp, checkpoint)
+    }
+
+    /// Adds a checkpoint like `add_checkpoint_full`, even if the settings don't allow the time,
+    /// like when the time was confirmed to be right.
+    pub fn force_add_checkpoint<T: Into<Timestamp>, C: Into<Checkpoint>>(
+        &mut self,
+        time: T,
+        checkpoint: C,
+    ) -> Result<()> {
+        self.apply(Operation::AddCheckpoint {
+            timestamp: time.into(),
+            checkpoint: checkpoint.into(),
+        })
+    }
+
+    /// Adds a checkpoint like `add_checkpoint_full`, replacing any checkpoint at the same time,
+    /// and returns the replaced one.
+    pub fn add_or_replace_checkpoint<T: Into<Timestamp>, C: Into<Checkpoint>>(
+        &mut self,
+        time: T,
+        checkpoint: C,
+    ) -> Result<Option<Checkpoint>> {
+        let timestamp = time.into();
+        self.check_plausible(timestamp)?;
+        let replaced = self.checkpoints.get(&timestamp.seconds()).cloned();
+        self.apply(Operation::ReplaceCheckpoint {
+            timestamp,
+            checkpoint: checkpoint.into(),
+        })?;
+        Ok(replaced)
+    }
+
+    /// Returns the timestamps and checkpoints from the oldest to the latest.
+    pub fn checkpoints(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (i64, &Checkpoint)> + ExactSizeIterator {
+        self.checkpoints
+            .iter()
+            .map(|(timestamp, checkpoint)| (*timestamp, checkpoint))
+    }
+
+    /// Returns the ids and projects, ordered by id.
+    pub fn projects(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (ProjectId, &Project)> + ExactSizeIterator {
+        self.projects
+            .iter()
+            .map(|(id, project)| (ProjectId::Id(*id), project))
+    }
+
+    /// Returns the ids and clients, ordered by id.
+    pub fn clients(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (ClientId, &Client)> + ExactSizeIterator {
+        self.clients
+            .iter()
+            .map(|(id, client)| (ClientId(*id), client))
+    }
+
+    /// Returns the number of checkpoints.
+    pub fn len(&self) -> usize {
+        self.checkpoints.len()
+    }
+
+    /// Whether there are no checkpoints.
+    pub fn is_empty(&self) -> bool {
+        self.checkpoints.is_empty()
     }
 
     /// Removes and returns the `Checkpoint` identified by the given `CheckpointId`.
     pub fn remove_checkpoint(&mut self, checkpoint_id: &CheckpointId) -> Option<Checkpoint> {
-        let timestamp = checkpoint_id.to_timestamp(self);
-
-        if let Some(t) = timestamp {
-            self.checkpoints.remove(&t)
-        } else {
-            None
-        }
+        let timestamp = Timestamp(checkpoint_id.to_timestamp(self)?);
+        let checkpoint = self.checkpoints.get(&timestamp.seconds())?.clone();
+        self.apply(Operation::RemoveCheckpoint { timestamp }).ok()?;
+        Some(checkpoint)
     }
 
-    /// Takes a start `DateTime<Local>` and an end `DateTime<Local>` and returns a `Vec<LogCheckpoint>`
-    /// containing all `LogCheckpoint`s between those two `DateTime<Local>`s.
-    pub fn get_log_between_times(
+    /// Returns the `LogCheckpoint`s of all checkpoints between two times, which can be given as
+    /// `Timestamp`s, seconds or chrono `DateTime`s.
+    pub fn get_log_between_times<S: Into<Timestamp>, E: Into<Timestamp>>(
         &self,
-        time_start: &chrono::DateTime<Local>,
-        time_end: &chrono::DateTime<Local>,
+        time_start: S,
+        time_end: E,
     ) -> Vec<LogCheckpoint> {
-        let timestamp_early = min(time_start, time_end).timestamp();
-        let timestamp_late = max(time_start, time_end).timestamp();
+        self.log_between_times(time_start, time_end)
+            .map(LogCheckpoint::from)
+            .collect()
+    }
 
-        self.checkpoints
-            .iter()
-            .rev()
-            .filter(|&(time, _)| *time > timestamp_early && *time < timestamp_late)
-            .map(|(time, checkpoint)| LogCheckpoint {
-                timestamp: *time,
-                checkpoint: checkpoint.clone(),
-                duration: self.get_checkpoint_duration(&CheckpointId::Timestamp(*time)),
-                position: self
-                    .checkpoints
-                    .iter()
-                    .rev()
-                    .position(|(t, _)| t == time)
-                    .expect("Could not find an checkpoint at the given position"),
+    /// Like `get_log_between_times`, but borrows the checkpoints instead of copying them, latest
+    /// first.
+    pub fn log_between_times<S: Into<Timestamp>, E: Into<Timestamp>>(
+        &self,
+        time_start: S,
+        time_end: E,
+    ) -> impl Iterator<Item = LogCheckpointRef<'_>> {
+        let (time_start, time_end) = (time_start.into().seconds(), time_end.into().seconds());
+        let timestamp_early = min(time_start, time_end);
+        let timestamp_late = max(time_start, time_end);
+
+        let mut position = self.count_from(timestamp_late);
+        let mut older = self.checkpoints.range(..timestamp_late).rev().peekable();
+        std::iter::from_fn(move || {
+            let (time, checkpoint) = older.next().filter(|(time, _)| **time > timestamp_early)?;
+            let duration = older.peek().map_or(0, |(previous, _)| time - *previous);
+            position += 1;
+            Some(LogCheckpointRef {
+                timestamp: Timestamp(*time),
+                checkpoint,
+                duration: Some(TrackedDuration(duration)),
+                position: position - 1,
             })
-            .collect()
+        })
     }
 
     /// Returns the `LogCheckpoint` for the given `CheckpointId`.
     pub fn get_log(&self, checkpoint_id: &CheckpointId) -> Option<LogCheckpoint> {
-        let checkpoint = match self.get_checkpoint(checkpoint_id) {
-            Some(x) => x,
-            None => return None,
-        };
+        let checkpoint = self.get_checkpoint(checkpoint_id)?;
         let duration = self.get_checkpoint_duration(checkpoint_id);
-        let timestamp = checkpoint_id.to_timestamp(self).unwrap();
-        let position = checkpoint_id.to_position(self).unwrap();
+        let timestamp = checkpoint_id.to_timestamp(self)?;
+        let position = checkpoint_id.to_position(self)?;
 
         Some(LogCheckpoint {
-            timestamp,
+            timestamp: Timestamp(timestamp),
             checkpoint: checkpoint.clone(),
             duration,
             position,
@@ -261,37 +765,26 @@ impl CheckpointDb {
 
     /// Returns the checkpoint at the given `CheckpointId`.
     pub fn get_checkpoint(&self, checkpoint_id: &CheckpointId) -> Option<&Checkpoint> {
-        match checkpoint_id.to_timestamp(self) {
-            Some(timestamp) => Some(&self.checkpoints[&timestamp]),
-            None => None,
-        }
+        checkpoint_id
+            .to_timestamp(self)
+            .and_then(|timestamp| self.checkpoints.get(&timestamp))
     }
 
     /// Gets the duration of the input `CheckpointId`.
-    pub fn get_checkpoint_duration(&self, checkpoint_id: &CheckpointId) -> Option<i64> {
-        if !checkpoint_id.exists(self) {
-            return None;
-        }
-
-        let current_checkpoint_timestamp = checkpoint_id.to_timestamp(self).unwrap();
-        let current_checkpoint_position = checkpoint_id.to_position(self).unwrap();
+    pub fn get_checkpoint_duration(&self, checkpoint_id: &CheckpointId) -> Option<TrackedDuration> {
+        let current_checkpoint_timestamp = checkpoint_id.to_timestamp(self)?;
+        let current_checkpoint_position = checkpoint_id.to_position(self)?;
         let preceeding_checkpoint_position =
             CheckpointId::Position(current_checkpoint_position + 1);
 
         if let Some(preceeding_checkpoint_timestamp) =
             preceeding_checkpoint_position.to_timestamp(self)
         {
-            Some(current_checkpoint_timestamp - preceeding_checkpoint_timestamp)
+            Some(TrackedDuration(
+                current_checkpoint_timestamp - preceeding_checkpoint_timestamp,
+            ))
         } else {
-            Some(0)
-        }
-    }
-
-    /// Returns a mutable reference to the `Checkpoint` identified by `CheckpointId`.
-    pub fn get_checkpoint_mut(&mut self, checkpoint_id: &CheckpointId) -> Option<&mut Checkpoint> {
-        match checkpoint_id.to_timestamp(self) {
-            Some(timestamp) => self.checkpoints.get_mut(&timestamp),
-            None => None,
+            Some(TrackedDuration::ZERO)
         }
     }
 
@@ -299,103 +792,72 @@ impl CheckpointDb {
         &mut self,
         checkpoint_id: CheckpointId,
         project_id: ProjectId,
-    ) -> Result<(), CheckpointDbError> {
-        if let ProjectId::Id(project_id) = project_id {
-            if !self.projects.contains_key(&project_id) {
-                return Err(CheckpointDbError {
-                    error_kind: ErrorKind::InvalidInput,
-                    message: "could not find the given project_id".to_string(),
-                });
-            }
-        }
-
-        if let Some(checkpoint) = self.get_checkpoint_mut(&checkpoint_id) {
-            checkpoint.project_id = project_id;
-            Ok(())
-        } else {
-            Err(CheckpointDbError {
-                error_kind: ErrorKind::InvalidInput,
-                message: "could not find the given checkpoint_id".to_string(),
-            })
-        }
+    ) -> Result<()> {
+        let timestamp = self.resolve_checkpoint(&checkpoint_id)?;
+        self.apply(Operation::SetCheckpointProject {
+            timestamp,
+            project_id,
+        })
     }
 
-    pub fn add_project(
+    pub fn set_checkpoint_kind(
         &mut self,
-        long_name: &str,
-        short_name: &str,
-    ) -> Result<ProjectId, CheckpointDbError> {
-        let short_name = short_name.to_string();
-        let long_name = long_name.to_string();
-
-        if short_name.is_empty() {
-            return Err(CheckpointDbError {
-                error_kind: ErrorKind::InvalidInput,
-                message: "You need to have a short name for the project".to_string(),
-            });
-        }
-        if long_name.is_empty() {
-            return Err(CheckpointDbError {
-                error_kind: ErrorKind::InvalidInput,
-                message: "You need to have a long name for the project".to_string(),
-            });
-        }
-        for existing_project in self.projects.values() {
-            if existing_project.short_name == short_name {
-                return Err(CheckpointDbError {
-                    error_kind: ErrorKind::AlreadyExists,
-                    message: "A project with this short name already exists".to_string(),
-                });
-            }
-        }
-
-        let mut project_id = ProjectId::NoId;
-
-        // Ignoring lint because this needs to be a two-step process, and the lint doesn't
-        // understand that.
-        #[allow(unknown_lints)]
-        #[allow(clippy::map_entry)]
-        for number in 0.. {
-            if !self.projects.contains_key(&number) {
-                self.projects.insert(
-                    number,
-                    Project {
-                        short_name,
-                        long_name,
-                    },
-                );
-                project_id = ProjectId::Id(number);
-                break;
-            }
-        }
+        checkpoint_id: CheckpointId,
+        kind: CheckpointKind,
+    ) -> Result<()> {
+        let timestamp = self.resolve_checkpoint(&checkpoint_id)?;
+        self.apply(Operation::SetCheckpointKind { timestamp, kind })
+    }
 
-        Ok(project_id)
+    pub fn set_checkpoint_pomodoro(
+        &mut self,
+        checkpoint_id: CheckpointId,
+        pomodoro: Option<Pomodoro>,
+    ) -> Result<()> {
+        let timestamp = self.resolve_checkpoint(&checkpoint_id)?;
+        self.apply(Operation::SetCheckpointPomodoro {
+            timestamp,
+            pomodoro,
+        })
     }
 
-    pub fn remove_project(&mut self, project_id: ProjectId) -> Result<(), CheckpointDbError> {
-        if let ProjectId::Id(project_id) = project_id {
-            self.projects.remove(&project_id);
-        } else {
-            return Err(CheckpointDbError {
-                error_kind: ErrorKind::InvalidInput,
-                message: "That ProjectId does not exist".to_string(),
-            });
-        }
+    pub fn set_checkpoint_message(
+        &mut self,
+        checkpoint_id: CheckpointId,
+        message: &str,
+    ) -> Result<()> {
+        let timestamp = self.resolve_checkpoint(&checkpoint_id)?;
+        self.apply(Operation::SetCheckpointMessage {
+            timestamp,
+            message: message.to_string(),
+        })
+    }
 
-        // Remove the project from all checkpoints where it's used.
-        let affected_checkpoint_times: Vec<i64> = self
-            .checkpoints
-            .iter()
-            .filter(|(_, e)| project_id == e.project_id)
-            .map(|(t, _)| *t)
-            .collect();
+    pub fn set_checkpoint_tags(
+        &mut self,
+        checkpoint_id: CheckpointId,
+        tags: Vec<String>,
+    ) -> Result<()> {
+        let timestamp = self.resolve_checkpoint(&checkpoint_id)?;
+        self.apply(Operation::SetCheckpointTags { timestamp, tags })
+    }
 
-        for time in affected_checkpoint_times {
-            let checkpoint = self.checkpoints.get_mut(&time).unwrap();
-            checkpoint.project_id = ProjectId::NoId;
-        }
+    pub fn add_project(&mut self, long_name: &str, short_name: &str) -> Result<ProjectId> {
+        let id = (0..=u16::MAX)
+            .find(|number| !self.projects.contains_key(number))
+            .ok_or_else(|| {
+                CheckpointDbError::InvalidInput("There is no room for more projects".to_string())
+            })?;
+        self.apply(Operation::AddProject {
+            id,
+            long_name: long_name.to_string(),
+            short_name: short_name.to_string(),
+        })?;
+        Ok(ProjectId::Id(id))
+    }
 
-        Ok(())
+    pub fn remove_project(&mut self, project_id: ProjectId) -> Result<()> {
+        self.apply(Operation::RemoveProject { project_id })
     }
 
     pub fn project_id_from_short_name(&self, short_name: &str) -> Option<ProjectId> {
@@ -417,20 +879,99 @@ impl CheckpointDb {
             None
         }
     }
+
+    pub(crate) fn project_from_project_id_mut(
+        &mut self,
+        project_id: ProjectId,
+    ) -> Option<&mut Project> {
+        if let ProjectId::Id(project_id) = project_id {
+            self.projects.get_mut(&project_id)
+        } else {
+            None
+        }
+    }
+
+    pub fn add_client(&mut self, name: &str) -> Result<ClientId> {
+        let number = (0..=u16::MAX)
+            .find(|number| !self.clients.contains_key(number))
+            .ok_or_else(|| {
+                CheckpointDbError::InvalidInput("There is no room for more clients".to_string())
+            })?;
+        self.apply(Operation::AddClient {
+            client_id: ClientId(number),
+            name: name.to_string(),
+        })?;
+        Ok(ClientId(number))
+    }
+
+    /// Removes the client and unassigns it from all of its projects.
+    pub fn remove_client(&mut self, client_id: ClientId) -> Result<Client> {
+        let client = self
+            .clients
+            .get(&client_id.0)
+            .cloned()
+            .ok_or(CheckpointDbError::ClientNotFound { id: client_id })?;
+        self.apply(Operation::RemoveClient { client_id })?;
+        Ok(client)
+    }
+
+    /// Renames the client. The name can't be empty or used by another client.
+    pub fn rename_client(&mut self, client_id: ClientId, name: &str) -> Result<()> {
+        self.apply(Operation::RenameClient {
+            client_id,
+            name: name.to_string(),
+        })
+    }
+
+    pub fn set_project_client(
+        &mut self,
+        project_id: ProjectId,
+        client_id: Option<ClientId>,
+    ) -> Result<()> {
+        self.apply(Operation::SetProjectClient {
+            project_id,
+            client_id,
+        })
+    }
+
+    /// Renames the project. The short name can't be empty or used by another project.
+    pub fn rename_project(
+        &mut self,
+        project_id: ProjectId,
+        long_name: &str,
+        short_name: &str,
+    ) -> Result<()> {
+        self.apply(Operation::RenameProject {
+            project_id,
+            long_name: long_name.to_string(),
+            short_name: short_name.to_string(),
+        })
+    }
+
+    pub fn client_from_client_id(&self, client_id: ClientId) -> Option<&Client> {
+        self.clients.get(&client_id.0)
+    }
+
+    pub(crate) fn client_from_client_id_mut(&mut self, client_id: ClientId) -> Option<&mut Client> {
+        self.clients.get_mut(&client_id.0)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "json")]
+    use std::fs;
 
     #[test]
+    #[cfg(feature = "json")]
     /// Creates a simple database, writes it to a file, loads the written file
     /// and checks that the contents are the same as the original data.
     fn write_read_db() {
         let file_name = Path::new("test_files/read_write_test.json");
         let mut checkpoint_db = CheckpointDb::new();
 
-        let time_now = Utc::now().timestamp();
+        let time_now = Timestamp::now().seconds();
 
         let zro_id = checkpoint_db.add_project("Zeroeth", "zro").unwrap();
         checkpoint_db.add_project("First", "frs").unwrap();
@@ -438,7 +979,10 @@ mod tests {
 
         // Adding a project with a short name that already exists should not work.
         assert!(
-            checkpoint_db.add_project("Duplicate", "scn").is_err(),
+            matches!(
+                checkpoint_db.add_project("Duplicate", "scn"),
+                Err(CheckpointDbError::DuplicateShortName { name }) if name == "scn"
+            ),
             "Adding a duplicate project didn't fail, but it should"
         );
 
@@ -459,8 +1003,16 @@ mod tests {
                     .get_checkpoint(&CheckpointId::Timestamp(time))
                     .unwrap(),
                 Checkpoint {
-                    message: message.to_string(),
+                    message: message.into(),
                     project_id: ProjectId::NoId,
+                    invoice: None,
+                    paid: false,
+                    kind: CheckpointKind::Work,
+                    category: None,
+                    pomodoro: None,
+                    rate: None,
+                    tags: Vec::new(),
+                    external_id: None,
                 }
             );
         }
@@ -486,9 +1038,148 @@ mod tests {
             .remove_checkpoint(&CheckpointId::Timestamp(time_now + 2))
             .is_some());
 
-        assert!(checkpoint_db.write(&file_name).is_ok());
+        assert!(checkpoint_db.write(file_name).is_ok());
+        assert!(checkpoint_db.write(Path::new("/")).is_err());
 
-        let checkpoint_db_read = CheckpointDb::read(&file_name).unwrap();
+        let checkpoint_db_read = CheckpointDb::read(file_name).unwrap();
         assert_eq!(checkpoint_db, checkpoint_db_read);
+        assert_eq!(
+            checkpoint_db_read.len(),
+            checkpoint_db_read.checkpoints().count()
+        );
+        assert_eq!(
+            checkpoint_db_read.projects().next().map(|(id, _)| id),
+            Some(zro_id)
+        );
+
+        // A corrupt file is a serde error rather than an I/O error.
+        fs::write(file_name, "{").unwrap();
+        assert!(matches!(
+            CheckpointDb::read(file_name),
+            Err(CheckpointDbError::Serde(_))
+        ));
+    }
+
+    #[test]
+    /// Checkpoint ids are parsed from and serialized to `@<timestamp>` and `~<position>`.
+    fn checkpoint_id_from_str() {
+        assert_eq!(
+            "@1717171717".parse::<CheckpointId>().unwrap(),
+            CheckpointId::Timestamp(1717171717)
+        );
+        assert_eq!(
+            "~3".parse::<CheckpointId>().unwrap(),
+            CheckpointId::Position(3)
+        );
+        assert!("3".parse::<CheckpointId>().is_err());
+        assert!("~-1".parse::<CheckpointId>().is_err());
+
+        #[cfg(feature = "json")]
+        {
+            let json = serde_json::to_string(&CheckpointId::Timestamp(-5)).unwrap();
+            assert_eq!(json, r#""@-5""#);
+            assert_eq!(
+                serde_json::from_str::<CheckpointId>(&json).unwrap(),
+                CheckpointId::Timestamp(-5)
+            );
+        }
+    }
+
+    #[test]
+    /// The log between two times leaves out the ends, and has the durations and positions of
+    /// the checkpoints in the whole database.
+    fn log_between_times() {
+        let mut checkpoint_db = CheckpointDb::new();
+        for time in [1000, 1600, 2500, 4000] {
+            checkpoint_db
+                .add_checkpoint(time, "", ProjectId::NoId)
+                .unwrap();
+        }
+
+        let log: Vec<_> = checkpoint_db.log_between_times(1000, 4000).collect();
+        let timestamps: Vec<_> = log.iter().map(|log| log.timestamp.seconds()).collect();
+        assert_eq!(timestamps, [2500, 1600]);
+        assert_eq!(log[0].duration, Some(TrackedDuration(900)));
+        assert_eq!(log[1].position, 2);
+        assert!(std::ptr::eq(
+            log[0].checkpoint,
+            checkpoint_db
+                .get_checkpoint(&CheckpointId::Position(1))
+                .unwrap()
+        ));
+        assert_eq!(
+            checkpoint_db
+                .get_log_between_times(4001, 0)
+                .iter()
+                .map(|log| (log.position, log.duration))
+                .collect::<Vec<_>>(),
+            [
+                (0, Some(TrackedDuration(1500))),
+                (1, Some(TrackedDuration(900))),
+                (2, Some(TrackedDuration(600))),
+                (3, Some(TrackedDuration(0))),
+            ]
+        );
+    }
+
+    #[test]
+    /// Adding a checkpoint where there already is one fails, unless it's meant to replace it.
+    fn duplicate_timestamps() {
+        let mut checkpoint_db = CheckpointDb::new();
+        checkpoint_db
+            .add_checkpoint(1000, "Styling", ProjectId::NoId)
+            .unwrap();
+        assert!(matches!(
+            checkpoint_db.add_checkpoint(1000, "Email", ProjectId::NoId),
+            Err(CheckpointDbError::CheckpointExists {
+                timestamp: Timestamp(1000)
+            })
+        ));
+
+        let replaced = checkpoint_db
+            .add_or_replace_checkpoint(1000, Checkpoint::builder().message("Email"))
+            .unwrap();
+        assert_eq!(&*replaced.unwrap().message, "Styling");
+        assert!(checkpoint_db
+            .add_or_replace_checkpoint(2000, Checkpoint::default())
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            &*checkpoint_db
+                .get_checkpoint(&CheckpointId::Timestamp(1000))
+                .unwrap()
+                .message,
+            "Email"
+        );
+    }
+
+    #[test]
+    /// Projects can be renamed, but not to an empty or taken short name.
+    fn rename_project() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let web_id = checkpoint_db.add_project("Website", "web").unwrap();
+        checkpoint_db.add_project("Email", "eml").unwrap();
+
+        assert!(checkpoint_db.rename_project(web_id, "Website", "").is_err());
+        assert!(matches!(
+            checkpoint_db.rename_project(web_id, "Website", "eml"),
+            Err(CheckpointDbError::DuplicateShortName { name }) if name == "eml"
+        ));
+        checkpoint_db
+            .rename_project(web_id, "Web shop", "web")
+            .unwrap();
+        checkpoint_db.rename_project(web_id, "Shop", "shp").unwrap();
+        assert_eq!(
+            checkpoint_db.project_id_from_short_name("shp"),
+            Some(web_id)
+        );
+        assert_eq!(
+            checkpoint_db
+                .project_from_project_id(web_id)
+                .unwrap()
+                .long_name,
+            "Shop"
+        );
+        assert!(checkpoint_db.set_project_rate(web_id, Some(-100)).is_err());
     }
 }
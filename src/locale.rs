@@ -0,0 +1,151 @@
+use crate::CheckpointDb;
+use chrono::prelude::*;
+use std::{fmt, sync::Arc};
+
+/// How dates are written in rendered reports, following the usual conventions of different
+/// places.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum DateFormat {
+    /// `2024-06-01`.
+    #[default]
+    Iso,
+    /// `01.06.2024`.
+    DayMonthYear,
+    /// `06/01/2024`.
+    MonthDayYear,
+}
+
+/// Writes dates and weekdays for reports, for languages and conventions that `DateFormat`
+/// doesn't cover. Set with `CheckpointDb::set_date_formatter`.
+pub trait DateFormatter: Send + Sync {
+    fn format_date(&self, date: NaiveDate) -> String;
+
+    /// Writes the weekday, as its English abbreviation like `Mon` by default.
+    fn format_weekday(&self, weekday: Weekday) -> String {
+        weekday.to_string()
+    }
+}
+
+impl DateFormat {
+    #[cfg(feature = "json")]
+    pub(crate) fn is_iso(&self) -> bool {
+        *self == DateFormat::Iso
+    }
+}
+
+impl DateFormatter for DateFormat {
+    fn format_date(&self, date: NaiveDate) -> String {
+        let format = match self {
+            DateFormat::Iso => "%Y-%m-%d",
+            DateFormat::DayMonthYear => "%d.%m.%Y",
+            DateFormat::MonthDayYear => "%m/%d/%Y",
+        };
+        date.format(format).to_string()
+    }
+}
+
+/// The formatter set with `set_date_formatter`. Like the listeners it isn't part of the data,
+/// so it isn't saved or compared by `==`.
+#[derive(Clone, Default)]
+pub(crate) struct CustomFormatter(Option<Arc<dyn DateFormatter>>);
+
+impl PartialEq for CustomFormatter {
+    fn eq(&self, _other: &CustomFormatter) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for CustomFormatter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Some(_) => write!(f, "custom date formatter"),
+            None => write!(f, "no date formatter"),
+        }
+    }
+}
+
+impl CheckpointDb {
+    /// Makes reports write dates with the formatter instead of the `date_format` of the
+    /// settings. The formatter is kept when the database is cloned, but isn't saved.
+    pub fn set_date_formatter<F: DateFormatter + 'static>(&mut self, formatter: F) {
+        self.date_formatter.0 = Some(Arc::new(formatter));
+    }
+
+    /// Writes the date the way reports do.
+    pub fn format_date(&self, date: NaiveDate) -> String {
+        self.date_formatter().format_date(date)
+    }
+
+    /// Writes the date with its weekday, like `Sat 2024-06-01`, the way reports write days.
+    pub fn format_day(&self, date: NaiveDate) -> String {
+        let formatter = self.date_formatter();
+        format!(
+            "{} {}",
+            formatter.format_weekday(date.weekday()),
+            formatter.format_date(date)
+        )
+    }
+
+    fn date_formatter(&self) -> &dyn DateFormatter {
+        match &self.date_formatter.0 {
+            Some(formatter) => formatter.as_ref(),
+            None => &self.settings.date_format,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ProjectId, Settings, TimeRange};
+
+    struct German;
+
+    impl DateFormatter for German {
+        fn format_date(&self, date: NaiveDate) -> String {
+            DateFormat::DayMonthYear.format_date(date)
+        }
+
+        fn format_weekday(&self, weekday: Weekday) -> String {
+            ["Mo", "Di", "Mi", "Do", "Fr", "Sa", "So"][weekday.num_days_from_monday() as usize]
+                .to_string()
+        }
+    }
+
+    #[test]
+    /// Reports write dates the way the settings or the formatter say, and weeks start on the
+    /// week start.
+    fn date_formats() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let date = NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+        assert_eq!(checkpoint_db.format_day(date), "Sat 2024-06-01");
+
+        checkpoint_db
+            .set_settings(Settings {
+                week_start: Weekday::Sat,
+                date_format: DateFormat::MonthDayYear,
+                ..Settings::default()
+            })
+            .unwrap();
+        assert_eq!(checkpoint_db.format_date(date), "06/01/2024");
+        let range = checkpoint_db.week_range(date);
+        assert_eq!(range.start, TimeRange::day(date).start);
+        checkpoint_db
+            .add_checkpoint(range.start + 3600, "", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(range.start + 7200, "", ProjectId::NoId)
+            .unwrap();
+        let report = checkpoint_db.report(&range, None);
+        assert_eq!(report.days[0].date, date);
+        assert!(checkpoint_db
+            .render_markdown(&report)
+            .contains("| Sat 06/01/2024 |"));
+
+        checkpoint_db.set_date_formatter(German);
+        assert_eq!(checkpoint_db.format_day(date), "Sa 01.06.2024");
+        assert_eq!(checkpoint_db.clone().format_date(date), "01.06.2024");
+    }
+}
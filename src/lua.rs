@@ -0,0 +1,131 @@
+use crate::{CheckpointDb, CheckpointDbError, Operation, Result};
+use mlua::{Function, Lua, LuaSerdeExt, Value};
+use std::path::Path;
+
+/// Runs a Lua script around the operations applied with `apply`, so that users can change
+/// what happens to their database without compiling anything.
+///
+/// The script can define two global functions, which are given the operation as a table shaped
+/// like its JSON, such as `{ AddCheckpoint = { timestamp = 1717171717, checkpoint = { ... } } }`:
+///
+/// - `before(operation)` is called before the operation is applied. It can return nothing to
+///   apply it as it is, an operation to apply that one instead, or `false` and a message to
+///   refuse it.
+/// - `after(operation)` is called after the operation has been applied.
+///
+/// ```lua
+/// function before(operation)
+///     local add = operation.AddCheckpoint
+///     if add and add.checkpoint.message:find("review") then
+///         add.checkpoint.tags = { "review" }
+///         return operation
+///     end
+///     if add and tonumber(os.date("%H", add.timestamp)) < 7 then
+///         return false, "that's before working hours"
+///     end
+/// end
+/// ```
+#[derive(Debug)]
+pub struct LuaHooks {
+    lua: Lua,
+}
+
+impl LuaHooks {
+    /// Runs the script, which defines the hooks.
+    pub fn new(script: &str) -> Result<LuaHooks> {
+        let lua = Lua::new();
+        lua.load(script).exec()?;
+        Ok(LuaHooks { lua })
+    }
+
+    /// Reads and runs the script in the file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<LuaHooks> {
+        let path = path.as_ref();
+        let lua = Lua::new();
+        lua.load(path).exec()?;
+        Ok(LuaHooks { lua })
+    }
+
+    /// Returns the operation that `before` makes of the operation, failing with
+    /// `CheckpointDbError::InvalidInput` if it refuses it.
+    pub fn before(&self, operation: Operation) -> Result<Operation> {
+        let Some(before) = self.hook("before")? else {
+            return Ok(operation);
+        };
+        let (result, message): (Value, Option<String>) =
+            before.call(self.lua.to_value(&operation)?)?;
+        match result {
+            Value::Nil => Ok(operation),
+            Value::Boolean(false) => Err(CheckpointDbError::InvalidInput(
+                message.unwrap_or_else(|| "refused by a hook".to_string()),
+            )),
+            result => Ok(self.lua.from_value(result)?),
+        }
+    }
+
+    /// Runs `before` on the operation, applies what it returns and runs `after` on that.
+    pub fn apply(&self, checkpoint_db: &mut CheckpointDb, operation: Operation) -> Result<()> {
+        let operation = self.before(operation)?;
+        checkpoint_db.apply(operation.clone())?;
+        if let Some(after) = self.hook("after")? {
+            after.call::<_, ()>(self.lua.to_value(&operation)?)?;
+        }
+        Ok(())
+    }
+
+    fn hook(&self, name: &str) -> Result<Option<Function<'_>>> {
+        Ok(self.lua.globals().get(name)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Checkpoint, Timestamp};
+
+    #[test]
+    /// Hooks can change operations, refuse them and see the ones that were applied.
+    fn scripted_hooks() {
+        let hooks = LuaHooks::new(
+            r#"
+            applied = 0
+            function before(operation)
+                local add = operation.AddCheckpoint
+                if add and add.checkpoint.message:find("review") then
+                    add.checkpoint.tags = { "review" }
+                    return operation
+                end
+                if add and add.timestamp < 1000 then
+                    return false, "too early"
+                end
+            end
+            function after(operation)
+                applied = applied + 1
+            end
+            "#,
+        )
+        .unwrap();
+        let add = |timestamp: i64, message: &str| Operation::AddCheckpoint {
+            timestamp: Timestamp(timestamp),
+            checkpoint: Checkpoint::builder().message(message).build(),
+        };
+
+        let mut checkpoint_db = CheckpointDb::new();
+        hooks
+            .apply(&mut checkpoint_db, add(1000, "Code review"))
+            .unwrap();
+        hooks
+            .apply(&mut checkpoint_db, add(2000, "Styling"))
+            .unwrap();
+        match hooks.apply(&mut checkpoint_db, add(500, "Styling")) {
+            Err(CheckpointDbError::InvalidInput(message)) => assert_eq!(message, "too early"),
+            result => panic!("{:?}", result),
+        }
+
+        assert_eq!(checkpoint_db.checkpoints[&1000].tags, ["review"]);
+        assert!(checkpoint_db.checkpoints[&2000].tags.is_empty());
+        assert_eq!(checkpoint_db.len(), 2);
+        let applied: i64 = hooks.lua.globals().get("applied").unwrap();
+        assert_eq!(applied, 2);
+    }
+}
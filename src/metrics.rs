@@ -0,0 +1,114 @@
+use crate::{CheckpointDb, ProjectId, TimeRange};
+use std::fmt::Write;
+
+impl CheckpointDb {
+    /// Returns metrics about the tracked time in the Prometheus text exposition format: the
+    /// seconds tracked today, the seconds tracked today and in total per project, and the
+    /// seconds since the latest checkpoint.
+    pub fn prometheus_metrics(&self, now: i64) -> String {
+        let today = self.report(&self.day_range(self.date_of(now)), None);
+        let total = self.report(&TimeRange::new(i64::MIN, i64::MAX), None);
+        let mut metrics = String::new();
+
+        write_metric_header(
+            &mut metrics,
+            "time_track_today_seconds",
+            "gauge",
+            "Seconds tracked today.",
+        );
+        let _ = writeln!(
+            metrics,
+            "time_track_today_seconds {}",
+            today.duration.seconds()
+        );
+
+        write_metric_header(
+            &mut metrics,
+            "time_track_project_today_seconds",
+            "gauge",
+            "Seconds tracked today per project.",
+        );
+        for project in &today.projects {
+            let _ = writeln!(
+                metrics,
+                "time_track_project_today_seconds{{project=\"{}\"}} {}",
+                self.metric_project_label(project.project_id),
+                project.duration.seconds()
+            );
+        }
+
+        write_metric_header(
+            &mut metrics,
+            "time_track_project_seconds_total",
+            "counter",
+            "Seconds tracked in total per project.",
+        );
+        for project in &total.projects {
+            let _ = writeln!(
+                metrics,
+                "time_track_project_seconds_total{{project=\"{}\"}} {}",
+                self.metric_project_label(project.project_id),
+                project.duration.seconds()
+            );
+        }
+
+        if let Some((latest, _)) = self.checkpoints.iter().next_back() {
+            write_metric_header(
+                &mut metrics,
+                "time_track_seconds_since_last_checkpoint",
+                "gauge",
+                "Seconds since the latest checkpoint.",
+            );
+            let _ = writeln!(
+                metrics,
+                "time_track_seconds_since_last_checkpoint {}",
+                now - latest
+            );
+        }
+
+        metrics
+    }
+
+    /// Returns the short name of a project escaped for a label value, or an empty string for
+    /// time without a project.
+    fn metric_project_label(&self, project_id: ProjectId) -> String {
+        self.project_from_project_id(project_id)
+            .map_or("", |project| &project.short_name)
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    }
+}
+
+fn write_metric_header(metrics: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(metrics, "# HELP {} {}", name, help);
+    let _ = writeln!(metrics, "# TYPE {} {}", name, kind);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Exposes today's and all time totals per project.
+    fn prometheus_metrics() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Website", "web").unwrap();
+        let midnight = TimeRange::day(chrono::NaiveDate::from_ymd_opt(2021, 6, 15).unwrap()).start;
+        checkpoint_db
+            .add_checkpoint(midnight - 3600, "", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(midnight - 1800, "", project_id)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(midnight + 3600, "", project_id)
+            .unwrap();
+
+        let metrics = checkpoint_db.prometheus_metrics(midnight + 4000);
+        assert!(metrics.contains("\ntime_track_today_seconds 5400\n"));
+        assert!(metrics.contains("\ntime_track_project_today_seconds{project=\"web\"} 5400\n"));
+        assert!(metrics.contains("\ntime_track_project_seconds_total{project=\"web\"} 7200\n"));
+        assert!(metrics.contains("\ntime_track_seconds_since_last_checkpoint 400\n"));
+    }
+}
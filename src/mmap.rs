@@ -0,0 +1,107 @@
+use crate::{CheckpointDb, Result, TimeRange};
+use memmap2::Mmap;
+use serde_json::value::RawValue;
+use std::{collections::BTreeMap, fs::File, path::Path};
+
+/// A database file mapped into memory, for reading a part of a large database without copying
+/// the whole file to the heap. Checkpoints outside the part that's read are only borrowed from
+/// the file, never parsed.
+#[derive(Debug)]
+pub struct MappedDb {
+    map: Mmap,
+}
+
+impl MappedDb {
+    /// Maps the database file at the path.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<MappedDb> {
+        let file = File::open(path)?;
+        // Safety: the map is only read. If another process changes the file while it's
+        // mapped, reading it can at worst fail or give a mix of both versions.
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(MappedDb { map })
+    }
+
+    /// Returns the number of checkpoints in the file.
+    pub fn len(&self) -> Result<usize> {
+        Ok(self.raw_checkpoints()?.1.len())
+    }
+
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Returns a database with everything in the file except the checkpoints outside the
+    /// range. The last checkpoint before the range is kept, so that the durations of the
+    /// checkpoints in the range, and reports over the range, are the same as in the full
+    /// database.
+    pub fn read_range(&self, range: &TimeRange) -> Result<CheckpointDb> {
+        let (fields, checkpoints) = self.raw_checkpoints()?;
+        let before = checkpoints.range(..range.start).next_back();
+        let kept = before
+            .into_iter()
+            .chain(checkpoints.range(range.start..std::cmp::max(range.start, range.end)));
+
+        // Put the parts that are kept back together, and only parse those. The indexes are
+        // left out, since they refer to the checkpoints that aren't kept.
+        let mut json = String::from("{");
+        for (name, value) in fields
+            .iter()
+            .filter(|(name, _)| !["checkpoints", "indexes"].contains(name))
+        {
+            json.push_str(&serde_json::to_string(name)?);
+            json.push(':');
+            json.push_str(value.get());
+            json.push(',');
+        }
+        json.push_str("\"checkpoints\":{");
+        for (i, (timestamp, checkpoint)) in kept.enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str(&format!("\"{}\":{}", timestamp, checkpoint.get()));
+        }
+        json.push_str("}}");
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Splits the file into its top level fields and its checkpoints, borrowing all of them.
+    #[allow(clippy::type_complexity)]
+    fn raw_checkpoints(&self) -> Result<(BTreeMap<&str, &RawValue>, BTreeMap<i64, &RawValue>)> {
+        let fields: BTreeMap<&str, &RawValue> = serde_json::from_slice(&self.map)?;
+        let checkpoints = match fields.get("checkpoints") {
+            Some(checkpoints) => serde_json::from_str(checkpoints.get())?,
+            None => BTreeMap::new(),
+        };
+        Ok((fields, checkpoints))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// A range read from the mapped file reports the same as the full database.
+    fn mapped_range() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Website", "web").unwrap();
+        for timestamp in (0..100).map(|i| 1000 + i * 600) {
+            checkpoint_db
+                .add_checkpoint(timestamp, "Styling \"the\" header", project_id)
+                .unwrap();
+        }
+        let path = Path::new("test_files/mmap/db.json");
+        checkpoint_db.write(path).unwrap();
+
+        let mapped = MappedDb::open(path).unwrap();
+        assert_eq!(mapped.len().unwrap(), 100);
+        let range = TimeRange::new(10000, 20000);
+        let part = mapped.read_range(&range).unwrap();
+        assert_eq!(part.len(), 18);
+        assert_eq!(
+            part.report(&range, None),
+            checkpoint_db.report(&range, None)
+        );
+        assert_eq!(part.projects, checkpoint_db.projects);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{CheckpointDbError, Result, Timestamp, TrackedDuration};
+use chrono::{prelude::*, Duration};
+
+impl Timestamp {
+    /// Parses a time the way people write it, relative to `now` and in its time zone. Accepted
+    /// are `now`, durations ago like `2h ago` or `1h 30min ago`, and a day, a time of
+    /// day or both, like `yesterday 14:30`, `monday 9am`, `2024-06-01` or `17:45`.
+    ///
+    /// A day can be `today`, `yesterday`, `tomorrow`, a date, or the name of a weekday, which
+    /// is the latest such day that isn't after today. A day without a time of day is its
+    /// midnight, and a time of day without a day is today.
+    pub fn parse_natural<Tz: TimeZone>(input: &str, now: &DateTime<Tz>) -> Result<Timestamp> {
+        let input = input.trim().to_lowercase();
+        let invalid = || CheckpointDbError::InvalidInput(format!("{:?} is not a time", input));
+        let words: Vec<&str> = input.split_whitespace().collect();
+
+        match words.as_slice() {
+            [] => Err(invalid()),
+            ["now"] => Ok(Timestamp::from(now)),
+            [amounts @ .., "ago"] => {
+                let duration: TrackedDuration = amounts.concat().parse().map_err(|_| invalid())?;
+                Ok(Timestamp(now.timestamp() - duration.seconds()))
+            }
+            _ => {
+                let today = now.date_naive();
+                let (date, time) = match words.as_slice() {
+                    [day] => match parse_day(day, today) {
+                        Some(date) => (date, NaiveTime::MIN),
+                        None => (today, parse_time_of_day(day).ok_or_else(invalid)?),
+                    },
+                    [day, time] => (
+                        parse_day(day, today).ok_or_else(invalid)?,
+                        parse_time_of_day(time).ok_or_else(invalid)?,
+                    ),
+                    _ => return Err(invalid()),
+                };
+
+                now.timezone()
+                    .from_local_datetime(&date.and_time(time))
+                    .earliest()
+                    .map(Timestamp::from)
+                    .ok_or_else(|| {
+                        CheckpointDbError::InvalidInput(format!(
+                            "{:?} doesn't exist in the time zone",
+                            input
+                        ))
+                    })
+            }
+        }
+    }
+}
+
+fn parse_day(day: &str, today: NaiveDate) -> Option<NaiveDate> {
+    match day {
+        "today" => Some(today),
+        "yesterday" => today.pred_opt(),
+        "tomorrow" => today.succ_opt(),
+        _ => {
+            if let Ok(date) = NaiveDate::parse_from_str(day, "%Y-%m-%d") {
+                return Some(date);
+            }
+            let weekday: Weekday = day.parse().ok()?;
+            let days_back =
+                (today.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+            today.checked_sub_signed(Duration::days(i64::from(days_back)))
+        }
+    }
+}
+
+/// Parses 24-hour times like `14:30`, and 12-hour times like `9am` or `9:15pm`.
+fn parse_time_of_day(time: &str) -> Option<NaiveTime> {
+    let (time, pm) = match time.strip_suffix("am") {
+        Some(time) => (time, Some(false)),
+        None => match time.strip_suffix("pm") {
+            Some(time) => (time, Some(true)),
+            None => (time, None),
+        },
+    };
+    let (hour, minute) = match time.split_once(':') {
+        Some((hour, minute)) if minute.len() == 2 => (hour.parse().ok()?, minute.parse().ok()?),
+        None if pm.is_some() => (time.parse().ok()?, 0),
+        _ => return None,
+    };
+    let hour = match pm {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some(pm) => hour % 12 + if pm { 12 } else { 0 },
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Days, times of day and amounts of time ago are relative to the given time and zone.
+    fn parse_natural() {
+        let zone = FixedOffset::east_opt(2 * 3600).unwrap();
+        // A Wednesday.
+        let now = zone.with_ymd_and_hms(2024, 6, 5, 12, 0, 0).unwrap();
+        let at = |day, hour, minute| {
+            Timestamp::from(
+                zone.with_ymd_and_hms(2024, 6, day, hour, minute, 0)
+                    .unwrap(),
+            )
+        };
+
+        let parse = |input| Timestamp::parse_natural(input, &now).unwrap();
+        assert_eq!(parse("now"), Timestamp::from(now));
+        assert_eq!(parse("yesterday 14:30"), at(4, 14, 30));
+        assert_eq!(parse("Monday 9am"), at(3, 9, 0));
+        assert_eq!(parse("wednesday 12:15pm"), at(5, 12, 15));
+        assert_eq!(parse("12am"), at(5, 0, 0));
+        assert_eq!(parse("2024-06-01"), at(1, 0, 0));
+        assert_eq!(parse("2h ago"), at(5, 10, 0));
+        assert_eq!(parse("1 hour 30min ago"), at(5, 10, 30));
+
+        for input in ["", "ago", "13pm", "soon", "monday 25:00", "2 ago"] {
+            assert!(Timestamp::parse_natural(input, &now).is_err(), "{}", input);
+        }
+    }
+}
@@ -0,0 +1,874 @@
+use crate::{
+    Category, ChangeEvent, Checkpoint, CheckpointDb, CheckpointDbError, CheckpointId,
+    CheckpointKind, Client, ClientId, Expense, ExpenseId, InvoiceNumbering, PayRule, Pomodoro,
+    Project, ProjectId, Result, Settings, TimeRange, Timesheet, TimesheetState, Timestamp,
+};
+
+/// A change to a `CheckpointDb`. Every method that changes a database does so by applying an
+/// `Operation`, so a log of them can be kept, sent elsewhere and applied again to get the same
+/// database.
+///
+/// Operations refer to checkpoints by timestamp and to projects and clients by id, so that they
+/// mean the same thing no matter when they are applied.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub enum Operation {
+    /// Adds a checkpoint, failing with `CheckpointDbError::CheckpointExists` if there already
+    /// is one at the time.
+    AddCheckpoint {
+        timestamp: Timestamp,
+        checkpoint: Checkpoint,
+    },
+    /// Adds a checkpoint, replacing any checkpoint at the same time.
+    ReplaceCheckpoint {
+        timestamp: Timestamp,
+        checkpoint: Checkpoint,
+    },
+    RemoveCheckpoint {
+        timestamp: Timestamp,
+    },
+    SetCheckpointMessage {
+        timestamp: Timestamp,
+        message: String,
+    },
+    SetCheckpointProject {
+        timestamp: Timestamp,
+        project_id: ProjectId,
+    },
+    SetCheckpointKind {
+        timestamp: Timestamp,
+        kind: CheckpointKind,
+    },
+    SetCheckpointCategory {
+        timestamp: Timestamp,
+        category: Option<Category>,
+    },
+    SetCheckpointPomodoro {
+        timestamp: Timestamp,
+        pomodoro: Option<Pomodoro>,
+    },
+    SetCheckpointTags {
+        timestamp: Timestamp,
+        tags: Vec<String>,
+    },
+    SetCheckpointRate {
+        timestamp: Timestamp,
+        rate: Option<i64>,
+    },
+    /// Marks a checkpoint as billed on an invoice, failing if it already has been.
+    MarkInvoiced {
+        timestamp: Timestamp,
+        invoice: String,
+    },
+    /// Takes the number in the invoice sequence, failing unless it's after the last number
+    /// taken, so that two frontends can't give two invoices the same number.
+    AllocateInvoiceNumber {
+        number: u32,
+    },
+    /// Marks all checkpoints billed on an invoice as paid, failing if there are none.
+    MarkInvoicePaid {
+        invoice: String,
+    },
+    AddProject {
+        id: u16,
+        long_name: String,
+        short_name: String,
+    },
+    /// Removes a project and unassigns it from all of its checkpoints.
+    RemoveProject {
+        project_id: ProjectId,
+    },
+    SetProjectClient {
+        project_id: ProjectId,
+        client_id: Option<ClientId>,
+    },
+    /// Renames a project, failing if the short name is taken by another project.
+    RenameProject {
+        project_id: ProjectId,
+        long_name: String,
+        short_name: String,
+    },
+    SetProjectRate {
+        project_id: ProjectId,
+        rate: Option<i64>,
+    },
+    SetProjectMinimumBillable {
+        project_id: ProjectId,
+        minimum_billable: Option<i64>,
+    },
+    SetProjectBillable {
+        project_id: ProjectId,
+        billable: bool,
+    },
+    SetProjectCurrency {
+        project_id: ProjectId,
+        currency: Option<String>,
+    },
+    SetProjectBudget {
+        project_id: ProjectId,
+        budget: Option<i64>,
+    },
+    SetProjectDailyGoal {
+        project_id: ProjectId,
+        daily_goal: Option<i64>,
+    },
+    SetProjectTaxRate {
+        project_id: ProjectId,
+        tax_rate: Option<f64>,
+    },
+    AddClient {
+        client_id: ClientId,
+        name: String,
+    },
+    /// Removes a client and unassigns it from all of its projects.
+    RemoveClient {
+        client_id: ClientId,
+    },
+    /// Renames a client, failing if another client has the name.
+    RenameClient {
+        client_id: ClientId,
+        name: String,
+    },
+    SetClientTaxRate {
+        client_id: ClientId,
+        tax_rate: Option<f64>,
+    },
+    SetSettings {
+        settings: Settings,
+    },
+    SetCurrency {
+        currency: Option<String>,
+    },
+    SetDailyGoal {
+        daily_goal: Option<i64>,
+    },
+    SetPayRules {
+        pay_rules: Vec<PayRule>,
+    },
+    SetInvoiceNumbering {
+        invoice_numbering: InvoiceNumbering,
+    },
+    /// Moves the timesheet of a week to another state. Checkpoints in submitted and approved
+    /// timesheets can't be added, removed or changed, other than being invoiced.
+    SetTimesheetState {
+        range: TimeRange,
+        state: TimesheetState,
+        reviewer: Option<String>,
+        comment: Option<String>,
+    },
+    AddExpense {
+        expense_id: ExpenseId,
+        expense: Expense,
+    },
+    RemoveExpense {
+        expense_id: ExpenseId,
+    },
+    /// Marks an expense as billed on an invoice, failing if it already has been.
+    MarkExpenseInvoiced {
+        expense_id: ExpenseId,
+        invoice: String,
+    },
+}
+
+impl Operation {
+    /// Applies the operation to the database. A failed operation doesn't change anything.
+    pub fn apply(self, checkpoint_db: &mut CheckpointDb) -> Result<()> {
+        checkpoint_db.apply(self)
+    }
+
+    /// Returns the timestamp of the checkpoint the operation changes, if the change isn't
+    /// allowed in a locked timesheet.
+    fn locked_timestamp(&self) -> Option<Timestamp> {
+        match self {
+            Operation::AddCheckpoint { timestamp, .. }
+            | Operation::ReplaceCheckpoint { timestamp, .. }
+            | Operation::RemoveCheckpoint { timestamp }
+            | Operation::SetCheckpointMessage { timestamp, .. }
+            | Operation::SetCheckpointProject { timestamp, .. }
+            | Operation::SetCheckpointKind { timestamp, .. }
+            | Operation::SetCheckpointCategory { timestamp, .. }
+            | Operation::SetCheckpointPomodoro { timestamp, .. }
+            | Operation::SetCheckpointTags { timestamp, .. }
+            | Operation::SetCheckpointRate { timestamp, .. } => Some(*timestamp),
+            _ => None,
+        }
+    }
+}
+
+impl CheckpointDb {
+    /// Applies an operation to the database. A failed operation doesn't change anything.
+    pub fn apply(&mut self, operation: Operation) -> Result<()> {
+        self.check_operation_unlocked(&operation)?;
+        #[cfg(feature = "json")]
+        let recorded = self.journal.is_recording().then(|| operation.clone());
+
+        match operation {
+            Operation::AddCheckpoint {
+                timestamp,
+                mut checkpoint,
+            } => {
+                if self.checkpoints.contains_key(&timestamp.seconds()) {
+                    return Err(CheckpointDbError::CheckpointExists { timestamp });
+                }
+                self.check_project(checkpoint.project_id)?;
+                checkpoint.message = self.messages().intern(&checkpoint.message);
+                self.checkpoints.insert(timestamp.seconds(), checkpoint);
+                self.indexes.clear();
+                self.timestamps.clear();
+                self.listeners
+                    .emit(ChangeEvent::CheckpointAdded { timestamp });
+            }
+            Operation::ReplaceCheckpoint {
+                timestamp,
+                mut checkpoint,
+            } => {
+                self.check_project(checkpoint.project_id)?;
+                checkpoint.message = self.messages().intern(&checkpoint.message);
+                self.checkpoints.insert(timestamp.seconds(), checkpoint);
+                self.indexes.clear();
+                self.timestamps.clear();
+                self.listeners
+                    .emit(ChangeEvent::CheckpointAdded { timestamp });
+            }
+            Operation::RemoveCheckpoint { timestamp } => {
+                self.checkpoints.remove(&timestamp.seconds()).ok_or(
+                    CheckpointDbError::CheckpointNotFound {
+                        id: timestamp.into(),
+                    },
+                )?;
+                self.indexes.clear();
+                self.timestamps.clear();
+                self.listeners
+                    .emit(ChangeEvent::CheckpointRemoved { timestamp });
+            }
+            Operation::SetCheckpointMessage { timestamp, message } => {
+                let message = self.messages().intern(&message);
+                self.change_checkpoint(timestamp, |checkpoint| {
+                    checkpoint.message = message;
+                    Ok(())
+                })?;
+                self.indexes.clear();
+            }
+            Operation::SetCheckpointProject {
+                timestamp,
+                project_id,
+            } => {
+                self.check_project(project_id)?;
+                self.change_checkpoint(timestamp, |checkpoint| {
+                    checkpoint.project_id = project_id;
+                    Ok(())
+                })?;
+            }
+            Operation::SetCheckpointKind { timestamp, kind } => {
+                self.change_checkpoint(timestamp, |checkpoint| {
+                    checkpoint.kind = kind;
+                    Ok(())
+                })?;
+            }
+            Operation::SetCheckpointCategory {
+                timestamp,
+                category,
+            } => {
+                self.change_checkpoint(timestamp, |checkpoint| {
+                    checkpoint.category = category;
+                    Ok(())
+                })?;
+            }
+            Operation::SetCheckpointPomodoro {
+                timestamp,
+                pomodoro,
+            } => {
+                self.change_checkpoint(timestamp, |checkpoint| {
+                    checkpoint.pomodoro = pomodoro;
+                    Ok(())
+                })?;
+            }
+            Operation::SetCheckpointTags { timestamp, tags } => {
+                self.change_checkpoint(timestamp, |checkpoint| {
+                    checkpoint.tags = tags;
+                    Ok(())
+                })?;
+                self.indexes.clear();
+            }
+            Operation::SetCheckpointRate { timestamp, rate } => {
+                self.change_checkpoint(timestamp, |checkpoint| {
+                    checkpoint.rate = rate;
+                    Ok(())
+                })?;
+            }
+            Operation::MarkInvoiced { timestamp, invoice } => {
+                self.change_checkpoint(timestamp, |checkpoint| {
+                    if let Some(existing) = &checkpoint.invoice {
+                        return Err(CheckpointDbError::AlreadyInvoiced {
+                            invoice: existing.clone(),
+                        });
+                    }
+                    checkpoint.invoice = Some(invoice);
+                    Ok(())
+                })?;
+            }
+            Operation::MarkInvoicePaid { invoice } => {
+                if !self
+                    .checkpoints
+                    .values()
+                    .any(|checkpoint| checkpoint.invoice.as_deref() == Some(&invoice))
+                {
+                    return Err(CheckpointDbError::InvalidInput(
+                        "no checkpoints have been billed on the given invoice".to_string(),
+                    ));
+                }
+                for (timestamp, checkpoint) in self.checkpoints.iter_mut() {
+                    if checkpoint.invoice.as_deref() == Some(&invoice) {
+                        checkpoint.paid = true;
+                        self.listeners.emit(ChangeEvent::CheckpointChanged {
+                            timestamp: Timestamp(*timestamp),
+                        });
+                    }
+                }
+            }
+            Operation::AddProject {
+                id,
+                long_name,
+                short_name,
+            } => {
+                self.check_project_names(&long_name, &short_name, None)?;
+                if self.projects.contains_key(&id) {
+                    return Err(CheckpointDbError::InvalidInput(format!(
+                        "there already is a project with the id {}",
+                        id
+                    )));
+                }
+
+                self.projects.insert(
+                    id,
+                    Project {
+                        short_name,
+                        long_name,
+                        minimum_billable: None,
+                        rate: None,
+                        billable: true,
+                        client_id: None,
+                        currency: None,
+                        budget: None,
+                        daily_goal: None,
+                        tax_rate: None,
+                    },
+                );
+                self.listeners.emit(ChangeEvent::ProjectAdded {
+                    project_id: ProjectId::Id(id),
+                });
+            }
+            Operation::RemoveProject { project_id } => {
+                let ProjectId::Id(id) = project_id else {
+                    return Err(CheckpointDbError::ProjectNotFound { id: project_id });
+                };
+                self.projects.remove(&id);
+
+                // Remove the project from all checkpoints where it's used.
+                for (timestamp, checkpoint) in self.checkpoints.iter_mut() {
+                    if checkpoint.project_id == project_id {
+                        checkpoint.project_id = ProjectId::NoId;
+                        self.listeners.emit(ChangeEvent::CheckpointChanged {
+                            timestamp: Timestamp(*timestamp),
+                        });
+                    }
+                }
+
+                for (id, expense) in self.expenses.iter_mut() {
+                    if expense.project_id == project_id {
+                        expense.project_id = ProjectId::NoId;
+                        self.listeners.emit(ChangeEvent::ExpenseChanged {
+                            expense_id: ExpenseId(*id),
+                        });
+                    }
+                }
+
+                self.listeners
+                    .emit(ChangeEvent::ProjectRemoved { project_id });
+            }
+            Operation::SetProjectClient {
+                project_id,
+                client_id,
+            } => {
+                if let Some(client_id) = client_id {
+                    if !self.clients.contains_key(&client_id.0) {
+                        return Err(CheckpointDbError::ClientNotFound { id: client_id });
+                    }
+                }
+
+                self.change_project(project_id, |project| project.client_id = client_id)?;
+            }
+            Operation::RenameProject {
+                project_id,
+                long_name,
+                short_name,
+            } => {
+                let ProjectId::Id(id) = project_id else {
+                    return Err(CheckpointDbError::ProjectNotFound { id: project_id });
+                };
+                self.check_project_names(&long_name, &short_name, Some(id))?;
+                self.change_project(project_id, |project| {
+                    project.long_name = long_name;
+                    project.short_name = short_name;
+                })?;
+            }
+            Operation::SetProjectRate { project_id, rate } => {
+                check_not_negative("rate", rate)?;
+                self.change_project(project_id, |project| project.rate = rate)?;
+            }
+            Operation::SetProjectMinimumBillable {
+                project_id,
+                minimum_billable,
+            } => {
+                check_not_negative("minimum billable duration", minimum_billable)?;
+                self.change_project(project_id, |project| {
+                    project.minimum_billable = minimum_billable
+                })?;
+            }
+            Operation::SetProjectBillable {
+                project_id,
+                billable,
+            } => {
+                self.change_project(project_id, |project| project.billable = billable)?;
+            }
+            Operation::SetProjectCurrency {
+                project_id,
+                currency,
+            } => {
+                check_currency(currency.as_deref())?;
+                self.change_project(project_id, |project| project.currency = currency)?;
+            }
+            Operation::SetProjectBudget { project_id, budget } => {
+                check_not_negative("budget", budget)?;
+                self.change_project(project_id, |project| project.budget = budget)?;
+            }
+            Operation::SetProjectDailyGoal {
+                project_id,
+                daily_goal,
+            } => {
+                check_not_negative("daily goal", daily_goal)?;
+                self.change_project(project_id, |project| project.daily_goal = daily_goal)?;
+            }
+            Operation::SetProjectTaxRate {
+                project_id,
+                tax_rate,
+            } => {
+                check_tax_rate(tax_rate)?;
+                self.change_project(project_id, |project| project.tax_rate = tax_rate)?;
+            }
+            Operation::AddClient { client_id, name } => {
+                self.check_client_name(&name, None)?;
+                if self.clients.contains_key(&client_id.0) {
+                    return Err(CheckpointDbError::InvalidInput(format!(
+                        "there already is a client with the id {}",
+                        client_id
+                    )));
+                }
+
+                self.clients.insert(
+                    client_id.0,
+                    Client {
+                        name,
+                        tax_rate: None,
+                    },
+                );
+                self.listeners.emit(ChangeEvent::ClientAdded { client_id });
+            }
+            Operation::RemoveClient { client_id } => {
+                self.clients
+                    .remove(&client_id.0)
+                    .ok_or(CheckpointDbError::ClientNotFound { id: client_id })?;
+
+                for (id, project) in self.projects.iter_mut() {
+                    if project.client_id == Some(client_id) {
+                        project.client_id = None;
+                        self.listeners.emit(ChangeEvent::ProjectChanged {
+                            project_id: ProjectId::Id(*id),
+                        });
+                    }
+                }
+
+                self.listeners
+                    .emit(ChangeEvent::ClientRemoved { client_id });
+            }
+            Operation::RenameClient { client_id, name } => {
+                self.check_client_name(&name, Some(client_id))?;
+                self.change_client(client_id, |client| client.name = name)?;
+            }
+            Operation::SetClientTaxRate {
+                client_id,
+                tax_rate,
+            } => {
+                check_tax_rate(tax_rate)?;
+                self.change_client(client_id, |client| client.tax_rate = tax_rate)?;
+            }
+            Operation::SetSettings { settings } => {
+                CheckpointDb::check_settings(&settings)?;
+                self.settings = settings;
+                self.listeners.emit(ChangeEvent::SettingsChanged);
+            }
+            Operation::SetCurrency { currency } => {
+                check_currency(currency.as_deref())?;
+                self.currency = currency;
+                self.listeners.emit(ChangeEvent::SettingsChanged);
+            }
+            Operation::SetDailyGoal { daily_goal } => {
+                check_not_negative("daily goal", daily_goal)?;
+                self.daily_goal = daily_goal;
+                self.listeners.emit(ChangeEvent::SettingsChanged);
+            }
+            Operation::SetPayRules { pay_rules } => {
+                CheckpointDb::check_pay_rules(&pay_rules)?;
+                self.pay_rules = pay_rules;
+                self.listeners.emit(ChangeEvent::SettingsChanged);
+            }
+            Operation::SetInvoiceNumbering { invoice_numbering } => {
+                if !invoice_numbering.pattern.contains("{number}") {
+                    return Err(CheckpointDbError::InvalidInput(
+                        "the invoice numbering needs a {number}".to_string(),
+                    ));
+                }
+                self.invoice_numbering = invoice_numbering;
+                self.listeners.emit(ChangeEvent::SettingsChanged);
+            }
+            Operation::SetTimesheetState {
+                range,
+                state,
+                reviewer,
+                comment,
+            } => {
+                self.check_timesheet_state(&range, state, reviewer.as_deref())?;
+                self.timesheets.insert(
+                    range.start,
+                    Timesheet {
+                        end: range.end,
+                        state,
+                        reviewer,
+                        comment,
+                    },
+                );
+                self.listeners.emit(ChangeEvent::TimesheetChanged {
+                    start: Timestamp(range.start),
+                });
+            }
+            Operation::AllocateInvoiceNumber { number } => {
+                if number <= self.last_invoice_number {
+                    return Err(CheckpointDbError::InvoiceNumberTaken { number });
+                }
+                self.last_invoice_number = number;
+            }
+            Operation::AddExpense {
+                expense_id,
+                expense,
+            } => {
+                if expense.project_id != ProjectId::NoId
+                    && self.project_from_project_id(expense.project_id).is_none()
+                {
+                    return Err(CheckpointDbError::ProjectNotFound {
+                        id: expense.project_id,
+                    });
+                }
+                if self.expenses.contains_key(&expense_id.0) {
+                    return Err(CheckpointDbError::InvalidInput(format!(
+                        "there already is an expense with the id {}",
+                        expense_id
+                    )));
+                }
+
+                self.expenses.insert(expense_id.0, expense);
+                self.listeners
+                    .emit(ChangeEvent::ExpenseAdded { expense_id });
+            }
+            Operation::RemoveExpense { expense_id } => {
+                self.expenses
+                    .remove(&expense_id.0)
+                    .ok_or(CheckpointDbError::ExpenseNotFound { id: expense_id })?;
+                self.listeners
+                    .emit(ChangeEvent::ExpenseRemoved { expense_id });
+            }
+            Operation::MarkExpenseInvoiced {
+                expense_id,
+                invoice,
+            } => {
+                let expense = self
+                    .expenses
+                    .get_mut(&expense_id.0)
+                    .ok_or(CheckpointDbError::ExpenseNotFound { id: expense_id })?;
+                if let Some(existing) = &expense.invoice {
+                    return Err(CheckpointDbError::AlreadyInvoiced {
+                        invoice: existing.clone(),
+                    });
+                }
+                expense.invoice = Some(invoice);
+                self.listeners
+                    .emit(ChangeEvent::ExpenseChanged { expense_id });
+            }
+        }
+
+        #[cfg(feature = "json")]
+        if let Some(operation) = recorded {
+            self.journal.record(operation);
+        }
+        Ok(())
+    }
+
+    /// Fails with `CheckpointDbError::TimesheetLocked` if the operation changes a checkpoint in
+    /// a locked timesheet, either itself or by changing the project or client of checkpoints.
+    fn check_operation_unlocked(&self, operation: &Operation) -> Result<()> {
+        if let Some(timestamp) = operation.locked_timestamp() {
+            let timestamp = timestamp.seconds();
+            self.check_unlocked(timestamp)?;
+            // A checkpoint's duration is the time since the previous one, so adding or removing
+            // one changes the duration of the next one too.
+            let moves = match operation {
+                Operation::AddCheckpoint { .. } | Operation::RemoveCheckpoint { .. } => true,
+                Operation::ReplaceCheckpoint { .. } => !self.checkpoints.contains_key(&timestamp),
+                _ => false,
+            };
+            return match self.checkpoints.range(timestamp + 1..).next() {
+                Some((next, _)) if moves => self.check_unlocked(*next),
+                _ => Ok(()),
+            };
+        }
+        let project_ids: Vec<ProjectId> = match operation {
+            Operation::RemoveProject { project_id } => vec![*project_id],
+            Operation::SetProjectClient {
+                project_id,
+                client_id,
+            } => match self.project_from_project_id(*project_id) {
+                Some(project) if project.client_id != *client_id => vec![*project_id],
+                _ => return Ok(()),
+            },
+            Operation::RemoveClient { client_id } => self
+                .projects
+                .iter()
+                .filter(|(_, project)| project.client_id == Some(*client_id))
+                .map(|(id, _)| ProjectId::Id(*id))
+                .collect(),
+            _ => return Ok(()),
+        };
+        self.checkpoints
+            .iter()
+            .filter(|(_, checkpoint)| {
+                checkpoint.project_id != ProjectId::NoId
+                    && project_ids.contains(&checkpoint.project_id)
+            })
+            .try_for_each(|(timestamp, _)| self.check_unlocked(*timestamp))
+    }
+
+    /// Returns the timestamp of the checkpoint, or an error if there is none.
+    pub(crate) fn resolve_checkpoint(&self, checkpoint_id: &CheckpointId) -> Result<Timestamp> {
+        checkpoint_id
+            .to_timestamp(self)
+            .map(Timestamp)
+            .ok_or(CheckpointDbError::CheckpointNotFound { id: *checkpoint_id })
+    }
+
+    pub(crate) fn check_project(&self, project_id: ProjectId) -> Result<()> {
+        match project_id {
+            ProjectId::Id(id) if !self.projects.contains_key(&id) => {
+                Err(CheckpointDbError::ProjectNotFound { id: project_id })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that a project can have the names, failing if the short name is used by another
+    /// project than the one with the id.
+    fn check_project_names(
+        &self,
+        long_name: &str,
+        short_name: &str,
+        except: Option<u16>,
+    ) -> Result<()> {
+        if short_name.is_empty() {
+            return Err(CheckpointDbError::InvalidInput(
+                "You need to have a short name for the project".to_string(),
+            ));
+        }
+        if long_name.is_empty() {
+            return Err(CheckpointDbError::InvalidInput(
+                "You need to have a long name for the project".to_string(),
+            ));
+        }
+        if self.projects.iter().any(|(id, existing_project)| {
+            Some(*id) != except && existing_project.short_name == short_name
+        }) {
+            return Err(CheckpointDbError::DuplicateShortName {
+                name: short_name.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Changes the project with the given function and reports the change.
+    fn change_project<F>(&mut self, project_id: ProjectId, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Project),
+    {
+        let project = self
+            .project_from_project_id_mut(project_id)
+            .ok_or(CheckpointDbError::ProjectNotFound { id: project_id })?;
+        f(project);
+        self.listeners
+            .emit(ChangeEvent::ProjectChanged { project_id });
+        Ok(())
+    }
+
+    /// Checks that a client can have the name, failing if another client than the one with the
+    /// id has it.
+    fn check_client_name(&self, name: &str, except: Option<ClientId>) -> Result<()> {
+        if name.is_empty() {
+            return Err(CheckpointDbError::InvalidInput(
+                "You need to have a name for the client".to_string(),
+            ));
+        }
+        if self
+            .clients
+            .iter()
+            .any(|(id, client)| Some(ClientId(*id)) != except && client.name == name)
+        {
+            return Err(CheckpointDbError::DuplicateClientName {
+                name: name.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Changes the client with the given function and reports the change.
+    fn change_client<F>(&mut self, client_id: ClientId, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Client),
+    {
+        let client = self
+            .client_from_client_id_mut(client_id)
+            .ok_or(CheckpointDbError::ClientNotFound { id: client_id })?;
+        f(client);
+        self.listeners
+            .emit(ChangeEvent::ClientChanged { client_id });
+        Ok(())
+    }
+
+    /// Changes the checkpoint at the timestamp with the given function, and reports the change
+    /// unless the function fails.
+    fn change_checkpoint<F>(&mut self, timestamp: Timestamp, f: F) -> Result<()>
+    where
+        F: FnOnce(&mut Checkpoint) -> Result<()>,
+    {
+        let checkpoint = self.checkpoints.get_mut(&timestamp.seconds()).ok_or(
+            CheckpointDbError::CheckpointNotFound {
+                id: timestamp.into(),
+            },
+        )?;
+        f(checkpoint)?;
+        self.listeners
+            .emit(ChangeEvent::CheckpointChanged { timestamp });
+        Ok(())
+    }
+}
+
+fn check_not_negative(name: &str, value: Option<i64>) -> Result<()> {
+    match value {
+        Some(value) if value < 0 => Err(CheckpointDbError::InvalidInput(format!(
+            "the {} can't be negative",
+            name
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn check_currency(currency: Option<&str>) -> Result<()> {
+    if currency == Some("") {
+        return Err(CheckpointDbError::InvalidInput(
+            "the currency can't be empty".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub(crate) fn check_tax_rate(tax_rate: Option<f64>) -> Result<()> {
+    match tax_rate {
+        Some(tax_rate) if !(tax_rate >= 0.0 && tax_rate.is_finite()) => Err(
+            CheckpointDbError::InvalidInput(format!("{} is not a valid tax rate", tax_rate)),
+        ),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Applying the operations made by the methods to another database gives the same database.
+    fn replay_operations() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Website", "web").unwrap();
+        let client_id = checkpoint_db.add_client("Acme").unwrap();
+        checkpoint_db
+            .set_project_client(project_id, Some(client_id))
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(1000, "Work", project_id)
+            .unwrap();
+        checkpoint_db
+            .mark_invoiced(&CheckpointId::Position(0), "2021-001")
+            .unwrap();
+
+        let operations = vec![
+            Operation::AddProject {
+                id: 0,
+                long_name: "Website".to_string(),
+                short_name: "web".to_string(),
+            },
+            Operation::AddClient {
+                client_id,
+                name: "Acme".to_string(),
+            },
+            Operation::SetProjectClient {
+                project_id,
+                client_id: Some(client_id),
+            },
+            Operation::AddCheckpoint {
+                timestamp: Timestamp(1000),
+                checkpoint: Checkpoint::builder()
+                    .message("Work")
+                    .project(project_id)
+                    .build(),
+            },
+            Operation::MarkInvoiced {
+                timestamp: Timestamp(1000),
+                invoice: "2021-001".to_string(),
+            },
+        ];
+        let mut replayed = CheckpointDb::new();
+        for operation in operations {
+            operation.apply(&mut replayed).unwrap();
+        }
+        assert_eq!(replayed, checkpoint_db);
+
+        let failing = Operation::MarkInvoiced {
+            timestamp: Timestamp(1000),
+            invoice: "2021-002".to_string(),
+        };
+        assert!(replayed.apply(failing).is_err());
+        let existing = Operation::AddCheckpoint {
+            timestamp: Timestamp(1000),
+            checkpoint: Checkpoint::default(),
+        };
+        assert!(matches!(
+            replayed.apply(existing),
+            Err(CheckpointDbError::CheckpointExists { .. })
+        ));
+        assert_eq!(replayed, checkpoint_db);
+
+        replayed
+            .apply(Operation::ReplaceCheckpoint {
+                timestamp: Timestamp(1000),
+                checkpoint: Checkpoint::default(),
+            })
+            .unwrap();
+        assert_eq!(replayed.len(), 1);
+        assert_ne!(replayed, checkpoint_db);
+    }
+}
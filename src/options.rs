@@ -0,0 +1,840 @@
+#[cfg(feature = "binary_cache")]
+use crate::binary_cache;
+use crate::{CheckpointDb, CheckpointDbError, Operation, Result};
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions as FileOptions},
+    io::{self, BufRead, BufWriter, Write},
+    mem,
+    ops::{Deref, DerefMut},
+    path::{Path, PathBuf},
+};
+
+/// The formats a database file can be written in. Both are read the same way.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Indented JSON, which is easy to read and diff.
+    #[default]
+    Json,
+    /// JSON without any whitespace, which is smaller.
+    CompactJson,
+}
+
+/// Configures how `open` reads a database file and how `DbFile::save` writes it back,
+/// created with `CheckpointDb::options`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OpenOptions {
+    create: bool,
+    read_only: bool,
+    format: Format,
+    backups: usize,
+    compact_after: usize,
+    #[cfg(feature = "binary_cache")]
+    binary_cache: bool,
+}
+
+impl Default for OpenOptions {
+    fn default() -> OpenOptions {
+        OpenOptions {
+            create: false,
+            read_only: false,
+            format: Format::Json,
+            backups: 0,
+            compact_after: 1000,
+            #[cfg(feature = "binary_cache")]
+            binary_cache: false,
+        }
+    }
+}
+
+/// A database opened with `OpenOptions`, which remembers where and how to save it.
+#[derive(Clone, Debug)]
+pub struct DbFile {
+    checkpoint_db: CheckpointDb,
+    path: PathBuf,
+    options: OpenOptions,
+    /// The number of operations in the delta file.
+    delta_len: usize,
+    /// The number of operations in the delta file that couldn't be applied.
+    skipped: usize,
+}
+
+/// The operations applied to a database since it was last saved, recorded for a `DbFile` so
+/// that they can be appended to its delta file. Like the listeners they aren't part of the data,
+/// so they aren't saved or compared by `==`.
+#[derive(Clone, Default)]
+pub(crate) struct Journal {
+    operations: Option<Vec<Operation>>,
+    /// Whether the data was replaced in a way that operations can't describe, like by a sync,
+    /// so that the whole database has to be written.
+    rewritten: bool,
+}
+
+impl Journal {
+    fn recording() -> Journal {
+        Journal {
+            operations: Some(Vec::new()),
+            rewritten: false,
+        }
+    }
+
+    pub(crate) fn is_recording(&self) -> bool {
+        self.operations.is_some()
+    }
+
+    fn len(&self) -> usize {
+        self.operations.as_ref().map_or(0, Vec::len)
+    }
+
+    pub(crate) fn record(&mut self, operation: Operation) {
+        if let Some(operations) = &mut self.operations {
+            operations.push(operation);
+        }
+    }
+
+    pub(crate) fn rewrite(&mut self) {
+        self.rewritten = self.is_recording();
+    }
+
+    /// Takes the recorded operations and starts recording again, or returns `None` if the
+    /// whole database has to be written instead.
+    fn take(&mut self) -> Option<Vec<Operation>> {
+        let operations = self.operations.replace(Vec::new()).unwrap_or_default();
+        (!mem::take(&mut self.rewritten)).then_some(operations)
+    }
+}
+
+impl PartialEq for Journal {
+    fn eq(&self, _other: &Journal) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for Journal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.operations {
+            Some(_) if self.rewritten => write!(f, "rewritten"),
+            Some(operations) => write!(f, "{} operations", operations.len()),
+            None => write!(f, "not recording"),
+        }
+    }
+}
+
+impl CheckpointDb {
+    /// Returns the default `OpenOptions`, which open an existing file without creating one and
+    /// save it as indented JSON without backups.
+    pub fn options() -> OpenOptions {
+        OpenOptions::default()
+    }
+}
+
+impl OpenOptions {
+    /// Whether to create an empty database if there is no file at the path.
+    pub fn create(&mut self, create: bool) -> &mut OpenOptions {
+        self.create = create;
+        self
+    }
+
+    /// Whether saving should fail instead of writing to the file.
+    pub fn read_only(&mut self, read_only: bool) -> &mut OpenOptions {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn format(&mut self, format: Format) -> &mut OpenOptions {
+        self.format = format;
+        self
+    }
+
+    /// How many of the previously saved versions of the file to keep, as `<file>.1` for the
+    /// latest one up to `<file>.<backups>` for the oldest.
+    pub fn backups(&mut self, backups: usize) -> &mut OpenOptions {
+        self.backups = backups;
+        self
+    }
+
+    /// How many operations the delta file can have before `DbFile::save_delta` writes the
+    /// whole database instead. 1000 by default.
+    pub fn compact_after(&mut self, compact_after: usize) -> &mut OpenOptions {
+        self.compact_after = compact_after;
+        self
+    }
+
+    /// Whether to keep a binary copy of the database next to the file, as `<file>.cache`, which
+    /// is much faster to read. It's only read while the file is exactly what it was made from,
+    /// and made again when the file has changed.
+    #[cfg(feature = "binary_cache")]
+    pub fn binary_cache(&mut self, binary_cache: bool) -> &mut OpenOptions {
+        self.binary_cache = binary_cache;
+        self
+    }
+
+    /// Reads the database at the path, and applies the operations in its delta file if there
+    /// is one. If there is no file, an empty database is created if `create` is set, and
+    /// otherwise a `CheckpointDbError::Io` with the kind `NotFound` is returned.
+    ///
+    /// Operations in the delta file that can't be applied, like a line cut short by a crash,
+    /// are skipped rather than failing, and counted by `DbFile::skipped_operations`.
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> Result<DbFile> {
+        let path = path.as_ref();
+        let (mut checkpoint_db, delta_len, skipped) = self.read(path)?;
+        checkpoint_db.journal = Journal::recording();
+
+        Ok(DbFile {
+            checkpoint_db,
+            path: path.to_path_buf(),
+            options: self.clone(),
+            delta_len,
+            skipped,
+        })
+    }
+
+    /// Reads the database and its delta file, and returns it with the number of operations in
+    /// the delta file and how many of them were skipped.
+    fn read(&self, path: &Path) -> Result<(CheckpointDb, usize, usize)> {
+        let mut checkpoint_db = match fs::read(path) {
+            Ok(json) => self.read_json(path, &json)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && self.create => {
+                let checkpoint_db = CheckpointDb::new();
+                if !self.read_only {
+                    write_db(&checkpoint_db, path, self.format)?;
+                }
+                checkpoint_db
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let (delta_len, skipped) = match fs::read(delta_path(path)) {
+            Ok(delta) => apply_delta(&mut checkpoint_db, &delta),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (0, 0),
+            Err(e) => return Err(e.into()),
+        };
+        Ok((checkpoint_db, delta_len, skipped))
+    }
+
+    #[cfg(not(feature = "binary_cache"))]
+    fn read_json(&self, _path: &Path, json: &[u8]) -> Result<CheckpointDb> {
+        Ok(serde_json::from_slice(json)?)
+    }
+
+    #[cfg(feature = "binary_cache")]
+    fn read_json(&self, path: &Path, json: &[u8]) -> Result<CheckpointDb> {
+        if !self.binary_cache {
+            return Ok(serde_json::from_slice(json)?);
+        }
+        if let Some(checkpoint_db) = binary_cache::read_cache(path, json) {
+            return Ok(checkpoint_db);
+        }
+        let checkpoint_db = serde_json::from_slice(json)?;
+        if !self.read_only {
+            // The cache is only there to speed up reading, so the file is still opened if it
+            // can't be written.
+            let _ = binary_cache::write_cache(path, json, &checkpoint_db);
+        }
+        Ok(checkpoint_db)
+    }
+}
+
+impl DbFile {
+    pub fn checkpoint_db(&self) -> &CheckpointDb {
+        &self.checkpoint_db
+    }
+
+    pub fn checkpoint_db_mut(&mut self) -> &mut CheckpointDb {
+        &mut self.checkpoint_db
+    }
+
+    /// Returns the database, which stops recording its operations, as there is nothing to
+    /// save them to.
+    pub fn into_inner(mut self) -> CheckpointDb {
+        self.checkpoint_db.journal = Journal::default();
+        self.checkpoint_db
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the number of operations in the delta file that couldn't be applied when the
+    /// database was opened, like an invoice number that two frontends took at the same time.
+    /// They're left out when the whole database is next written.
+    pub fn skipped_operations(&self) -> usize {
+        self.skipped
+    }
+
+    /// Writes the database back to its file, first rotating the backups if there are any, which
+    /// removes the delta file. Like with `exclusive`, the file is read again first, so what
+    /// other frontends have saved is kept.
+    pub fn save(&mut self) -> Result<()> {
+        self.check_writable()?;
+        let _lock = self.lock()?;
+        self.write_whole()
+    }
+
+    /// Writes the whole database. The file is read again first, with the changes that haven't
+    /// been saved applied on top, so that operations other `DbFile`s appended to the delta file
+    /// aren't lost. Has to be called with the lock held.
+    fn write_whole(&mut self) -> Result<()> {
+        self.reload()?;
+        if self.options.backups > 0 && self.path.exists() {
+            for n in (1..self.options.backups).rev() {
+                let backup = backup_path(&self.path, n);
+                if backup.exists() {
+                    fs::rename(&backup, backup_path(&self.path, n + 1))?;
+                }
+            }
+            fs::copy(&self.path, backup_path(&self.path, 1))?;
+        }
+
+        write_db(&self.checkpoint_db, &self.path, self.options.format)?;
+        #[cfg(feature = "binary_cache")]
+        if self.options.binary_cache {
+            // Like when opening, the database is saved even if the cache can't be written.
+            let json = fs::read(&self.path)?;
+            let _ = binary_cache::write_cache(&self.path, &json, &self.checkpoint_db);
+        }
+        self.checkpoint_db.journal = Journal::recording();
+        self.delta_len = 0;
+        self.skipped = 0;
+        Ok(())
+    }
+
+    /// Appends the operations applied since the database was last saved to the delta file
+    /// `<file>.delta`, instead of writing the whole database. Once the delta file has more
+    /// operations than `OpenOptions::compact_after`, the whole database is written with `save`
+    /// instead.
+    pub fn save_delta(&mut self) -> Result<()> {
+        self.check_writable()?;
+        let _lock = self.lock()?;
+        self.append_delta()
+    }
+
+    fn append_delta(&mut self) -> Result<()> {
+        let journal = &self.checkpoint_db.journal;
+        if journal.rewritten || self.delta_len + journal.len() > self.options.compact_after {
+            return self.write_whole();
+        }
+        let operations = self.checkpoint_db.journal.take().unwrap_or_default();
+        if operations.is_empty() {
+            return Ok(());
+        }
+
+        let file = FileOptions::new()
+            .create(true)
+            .append(true)
+            .open(delta_path(&self.path))?;
+        let mut writer = BufWriter::new(file);
+        for operation in &operations {
+            serde_json::to_writer(&mut writer, operation)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        self.delta_len += operations.len();
+        Ok(())
+    }
+
+    /// Runs a function that makes several changes to the database, and then saves them all
+    /// at once with `save_delta`. The changes made before the function fails are saved too,
+    /// and its error is returned.
+    pub fn batch<T, F>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut CheckpointDb) -> Result<T>,
+    {
+        let result = f(&mut self.checkpoint_db);
+        self.save_delta()?;
+        result
+    }
+
+    /// Runs a function that changes the database while no other `DbFile` can write to the file,
+    /// and saves its changes with `save_delta` before letting them. The database is read again
+    /// first, with the changes that haven't been saved yet applied on top, so the function sees
+    /// what every other frontend has saved.
+    ///
+    /// This is needed for changes that depend on what's in the database to not be made by two
+    /// frontends at once, like taking the next invoice number:
+    ///
+    /// ```ignore
+    /// let invoice = db_file.exclusive(|checkpoint_db| builder.build(checkpoint_db))?;
+    /// ```
+    ///
+    /// Unsaved changes that can't be applied to the database as it was read are dropped. Like
+    /// with `batch`, changes made before the function fails are saved, and its error is
+    /// returned.
+    pub fn exclusive<T, F>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut CheckpointDb) -> Result<T>,
+    {
+        self.check_writable()?;
+        let _lock = self.lock()?;
+        self.reload()?;
+        let result = f(&mut self.checkpoint_db);
+        self.append_delta()?;
+        result
+    }
+
+    /// Drops the changes that haven't been saved, like one that failed to save, by reading the
+    /// database again.
+    pub fn revert(&mut self) -> Result<()> {
+        self.checkpoint_db.journal = Journal::recording();
+        self.reload()
+    }
+
+    /// Reads the database again and applies the operations that haven't been saved to it.
+    fn reload(&mut self) -> Result<()> {
+        if self.checkpoint_db.journal.rewritten {
+            // There are no operations to apply to the file as it is now, so the database is
+            // kept as it is and written whole.
+            return Ok(());
+        }
+        let (mut checkpoint_db, delta_len, skipped) = self.options.read(&self.path)?;
+        let unsaved = self.checkpoint_db.journal.take().unwrap_or_default();
+        checkpoint_db.journal = Journal::recording();
+        for operation in unsaved {
+            let _ = checkpoint_db.apply(operation);
+        }
+        let journal = mem::take(&mut checkpoint_db.journal);
+        self.checkpoint_db.replace_data(checkpoint_db);
+        self.checkpoint_db.journal = journal;
+        self.delta_len = delta_len;
+        self.skipped = skipped;
+        Ok(())
+    }
+
+    /// Locks `<file>.lock`, which every `DbFile` does while writing, until the returned file is
+    /// dropped.
+    fn lock(&self) -> Result<File> {
+        if let Some(write_dir) = self.path.parent() {
+            fs::create_dir_all(write_dir)?;
+        }
+        let mut lock_path = self.path.as_os_str().to_owned();
+        lock_path.push(".lock");
+        let file = FileOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(lock_path)?;
+        file.lock()?;
+        Ok(file)
+    }
+
+    /// Starts a batch of changes, made through the returned guard, that are saved at once with
+    /// `save_delta` when it's finished or dropped.
+    pub fn begin_batch(&mut self) -> BatchGuard<'_> {
+        BatchGuard {
+            db_file: self,
+            finished: false,
+        }
+    }
+
+    fn check_writable(&self) -> Result<()> {
+        if self.options.read_only {
+            return Err(CheckpointDbError::InvalidInput(format!(
+                "{} was opened read only",
+                self.path.display()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// A batch of changes to a `DbFile`, started with `DbFile::begin_batch`. It derefs to the
+/// database, and saves the changes when it's finished, or when it's dropped, in which case a
+/// failure to save is ignored.
+#[derive(Debug)]
+pub struct BatchGuard<'a> {
+    db_file: &'a mut DbFile,
+    finished: bool,
+}
+
+impl BatchGuard<'_> {
+    /// Saves the changes made in the batch.
+    pub fn finish(mut self) -> Result<()> {
+        self.finished = true;
+        self.db_file.save_delta()
+    }
+}
+
+impl Deref for BatchGuard<'_> {
+    type Target = CheckpointDb;
+
+    fn deref(&self) -> &CheckpointDb {
+        &self.db_file.checkpoint_db
+    }
+}
+
+impl DerefMut for BatchGuard<'_> {
+    fn deref_mut(&mut self) -> &mut CheckpointDb {
+        &mut self.db_file.checkpoint_db
+    }
+}
+
+impl Drop for BatchGuard<'_> {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.db_file.save_delta();
+        }
+    }
+}
+
+pub(crate) fn delta_path(path: &Path) -> PathBuf {
+    let mut delta = path.as_os_str().to_owned();
+    delta.push(".delta");
+    PathBuf::from(delta)
+}
+
+fn backup_path(path: &Path, n: usize) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(format!(".{}", n));
+    PathBuf::from(backup)
+}
+
+/// Writes a database to a file, creating the directories it's in if needed. The file is
+/// written to a temporary file next to it first and then renamed, after which the delta file
+/// is removed, as the whole database has its operations.
+pub(crate) fn write_db(checkpoint_db: &CheckpointDb, path: &Path, format: Format) -> Result<()> {
+    let Some(file_name) = path.file_name() else {
+        return Err(CheckpointDbError::InvalidInput(format!(
+            "{} is not a path to a file",
+            path.display()
+        )));
+    };
+    if let Some(write_dir) = path.parent() {
+        if !write_dir.exists() {
+            fs::create_dir_all(write_dir)?;
+        }
+    }
+
+    let mut temporary_name = file_name.to_owned();
+    temporary_name.push(".tmp");
+    let temporary_path = path.with_file_name(temporary_name);
+    let mut writer = BufWriter::new(File::create(&temporary_path)?);
+    match format {
+        Format::Json => serde_json::to_writer_pretty(&mut writer, checkpoint_db)?,
+        Format::CompactJson => serde_json::to_writer(&mut writer, checkpoint_db)?,
+    }
+    writer.flush()?;
+    drop(writer);
+    fs::rename(&temporary_path, path)?;
+    remove_delta(path)
+}
+
+/// Removes the delta file of the database at the path, if there is one.
+pub(crate) fn remove_delta(path: &Path) -> Result<()> {
+    match fs::remove_file(delta_path(path)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Applies the operations of a delta file to the database, and returns how many there were and
+/// how many of them were skipped because they couldn't be read or applied.
+pub(crate) fn apply_delta(checkpoint_db: &mut CheckpointDb, delta: &[u8]) -> (usize, usize) {
+    let mut delta_len = 0;
+    let mut skipped = 0;
+    for line in delta.lines() {
+        let applied = line
+            .map_err(CheckpointDbError::from)
+            .and_then(|line| Ok(serde_json::from_str(&line)?))
+            .and_then(|operation| checkpoint_db.apply(operation));
+        if applied.is_err() {
+            skipped += 1;
+        }
+        delta_len += 1;
+    }
+    (delta_len, skipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckpointId, ProjectId};
+
+    #[test]
+    /// Missing files are only created when asked to, and saving keeps the given number of
+    /// backups.
+    fn open_options() {
+        let path = Path::new("test_files/open_options/db.json");
+        let _ = fs::remove_dir_all("test_files/open_options");
+
+        let error = CheckpointDb::options().open(path).unwrap_err();
+        assert!(matches!(error, CheckpointDbError::Io(e) if e.kind() == io::ErrorKind::NotFound));
+
+        let mut db_file = CheckpointDb::options()
+            .create(true)
+            .format(Format::CompactJson)
+            .backups(2)
+            .open(path)
+            .unwrap();
+        for time in 1..=3 {
+            db_file
+                .checkpoint_db_mut()
+                .add_checkpoint(time, "", ProjectId::NoId)
+                .unwrap();
+            db_file.save().unwrap();
+        }
+
+        let mut backup = CheckpointDb::options()
+            .read_only(true)
+            .open(backup_path(path, 2))
+            .unwrap();
+        assert_eq!(backup.checkpoint_db().checkpoints.len(), 1);
+        assert!(backup.save().is_err());
+        assert!(!backup_path(path, 3).exists());
+        assert!(!fs::read_to_string(path).unwrap().contains('\n'));
+    }
+
+    #[test]
+    /// Saving a delta appends the new operations, which are applied again when opening, until
+    /// there are enough of them to write the whole database.
+    fn delta_file() {
+        let path = Path::new("test_files/delta_file/db.json");
+        let _ = fs::remove_dir_all("test_files/delta_file");
+        let open = || {
+            CheckpointDb::options()
+                .create(true)
+                .compact_after(3)
+                .open(path)
+                .unwrap()
+        };
+
+        let mut db_file = open();
+        let project_id = db_file
+            .checkpoint_db_mut()
+            .add_project("Website", "web")
+            .unwrap();
+        db_file.save().unwrap();
+        let written = fs::read_to_string(path).unwrap();
+        for time in 1..=2 {
+            db_file
+                .checkpoint_db_mut()
+                .add_checkpoint(time, "Styling", project_id)
+                .unwrap();
+            db_file.save_delta().unwrap();
+        }
+        assert_eq!(fs::read_to_string(path).unwrap(), written);
+        assert_eq!(
+            fs::read_to_string(delta_path(path))
+                .unwrap()
+                .lines()
+                .count(),
+            2
+        );
+        assert_eq!(open().checkpoint_db(), db_file.checkpoint_db());
+
+        let mut db_file = open();
+        for time in 3..=4 {
+            db_file
+                .checkpoint_db_mut()
+                .add_checkpoint(time, "Styling", project_id)
+                .unwrap();
+        }
+        db_file.save_delta().unwrap();
+        assert!(!delta_path(path).exists());
+        assert_eq!(open().checkpoint_db().len(), 4);
+    }
+
+    #[test]
+    /// Writing the whole database keeps the operations another `DbFile` saved in the meantime.
+    fn compact_keeps_other_deltas() {
+        let path = Path::new("test_files/compact_keeps_other_deltas/db.json");
+        let _ = fs::remove_dir_all("test_files/compact_keeps_other_deltas");
+        let open = || {
+            CheckpointDb::options()
+                .create(true)
+                .compact_after(3)
+                .open(path)
+                .unwrap()
+        };
+
+        let (mut first, mut second) = (open(), open());
+        second
+            .checkpoint_db_mut()
+            .add_checkpoint(1000, "Email", ProjectId::NoId)
+            .unwrap();
+        second.save_delta().unwrap();
+        for time in 2000..2004 {
+            first
+                .checkpoint_db_mut()
+                .add_checkpoint(time, "Styling", ProjectId::NoId)
+                .unwrap();
+        }
+        first.save_delta().unwrap();
+        assert!(!delta_path(path).exists());
+
+        let reopened = open();
+        assert_eq!(reopened.checkpoint_db().len(), 5);
+        assert_eq!(reopened.checkpoint_db(), first.checkpoint_db());
+
+        let mut checkpoint_db = CheckpointDb::read(path).unwrap();
+        checkpoint_db
+            .add_checkpoint(3000, "Read", ProjectId::NoId)
+            .unwrap();
+        assert!(!checkpoint_db.journal.is_recording());
+    }
+
+    #[test]
+    /// Changes to messages, clients and what's kept for the whole database are operations too,
+    /// so they are saved in deltas.
+    fn delta_changes() {
+        let path = Path::new("test_files/delta_changes/db.json");
+        let _ = fs::remove_dir_all("test_files/delta_changes");
+
+        let mut db_file = CheckpointDb::options().create(true).open(path).unwrap();
+        db_file
+            .checkpoint_db_mut()
+            .add_checkpoint(1000, "Styling", ProjectId::NoId)
+            .unwrap();
+        let client_id = db_file.checkpoint_db_mut().add_client("Acme").unwrap();
+        db_file.save().unwrap();
+
+        let checkpoint_db = db_file.checkpoint_db_mut();
+        checkpoint_db
+            .set_checkpoint_message(CheckpointId::Timestamp(1000), "Layout")
+            .unwrap();
+        checkpoint_db.rename_client(client_id, "Acme Inc").unwrap();
+        checkpoint_db.set_currency(Some("EUR")).unwrap();
+        checkpoint_db.set_daily_goal(Some(8 * 3600)).unwrap();
+        db_file.save_delta().unwrap();
+
+        assert!(delta_path(path).exists());
+        let reopened = CheckpointDb::options().open(path).unwrap();
+        assert_eq!(reopened.checkpoint_db(), db_file.checkpoint_db());
+        assert_eq!(reopened.checkpoint_db().currency(), Some("EUR"));
+    }
+
+    #[test]
+    /// Frontends that take invoice numbers exclusively get the next number in the file, and
+    /// one that takes a number that's already taken doesn't stop the file from being opened.
+    fn exclusive_invoice_numbers() {
+        let path = Path::new("test_files/exclusive_invoice_numbers/db.json");
+        let _ = fs::remove_dir_all("test_files/exclusive_invoice_numbers");
+        let open = || CheckpointDb::options().create(true).open(path).unwrap();
+
+        let mut first = open();
+        let mut second = open();
+        let mut stale = open();
+        first
+            .checkpoint_db_mut()
+            .add_checkpoint(1000, "Styling", ProjectId::NoId)
+            .unwrap();
+        for db_file in [&mut first, &mut second] {
+            db_file
+                .exclusive(|checkpoint_db| checkpoint_db.next_invoice_number())
+                .unwrap();
+        }
+        assert_eq!(first.checkpoint_db().last_invoice_number(), 1);
+        assert_eq!(second.checkpoint_db().last_invoice_number(), 2);
+        assert_eq!(second.checkpoint_db().len(), 1);
+
+        stale.checkpoint_db_mut().next_invoice_number().unwrap();
+        stale.save_delta().unwrap();
+        let reopened = open();
+        assert_eq!(reopened.skipped_operations(), 1);
+        assert_eq!(reopened.checkpoint_db().last_invoice_number(), 2);
+        assert_eq!(reopened.checkpoint_db(), second.checkpoint_db());
+    }
+
+    #[test]
+    /// Writing the whole database with `write` removes the delta file, whose operations it
+    /// already has, so they aren't applied twice when opening it.
+    fn write_after_delta() {
+        let path = Path::new("test_files/write_after_delta/db.json");
+        let _ = fs::remove_dir_all("test_files/write_after_delta");
+
+        let mut db_file = CheckpointDb::options().create(true).open(path).unwrap();
+        db_file
+            .checkpoint_db_mut()
+            .add_project("Website", "web")
+            .unwrap();
+        db_file.save_delta().unwrap();
+        assert!(delta_path(path).exists());
+
+        let mut checkpoint_db = CheckpointDb::read(path).unwrap();
+        checkpoint_db
+            .add_checkpoint(1000, "Styling", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db.write(path).unwrap();
+        assert!(!delta_path(path).exists());
+
+        assert_eq!(CheckpointDb::read(path).unwrap(), checkpoint_db);
+        let db_file = CheckpointDb::options().open(path).unwrap();
+        assert_eq!(db_file.checkpoint_db().projects().len(), 1);
+    }
+
+    #[test]
+    /// A batch saves all of its changes once, at the end.
+    fn batch() {
+        let path = Path::new("test_files/batch/db.json");
+        let _ = fs::remove_dir_all("test_files/batch");
+        let mut db_file = CheckpointDb::options().create(true).open(path).unwrap();
+
+        let error = db_file.batch(|checkpoint_db| {
+            for time in 1..=3 {
+                checkpoint_db.add_checkpoint(time, "Styling", ProjectId::NoId)?;
+                assert!(!delta_path(path).exists());
+            }
+            checkpoint_db.add_checkpoint(4, "Email", ProjectId::Id(9))
+        });
+        assert!(error.is_err());
+        assert_eq!(
+            fs::read_to_string(delta_path(path))
+                .unwrap()
+                .lines()
+                .count(),
+            3
+        );
+
+        let mut guard = db_file.begin_batch();
+        guard.add_checkpoint(5, "Email", ProjectId::NoId).unwrap();
+        guard.add_checkpoint(6, "Email", ProjectId::NoId).unwrap();
+        assert_eq!(
+            fs::read_to_string(delta_path(path))
+                .unwrap()
+                .lines()
+                .count(),
+            3
+        );
+        drop(guard);
+        let read = CheckpointDb::options().open(path).unwrap();
+        assert_eq!(read.checkpoint_db().len(), 5);
+    }
+
+    #[test]
+    #[cfg(feature = "binary_cache")]
+    /// The binary cache is read while the file is unchanged, and made again when it changes.
+    fn binary_cache() {
+        let path = Path::new("test_files/binary_cache/db.json");
+        let _ = fs::remove_dir_all("test_files/binary_cache");
+        let open = || {
+            CheckpointDb::options()
+                .create(true)
+                .binary_cache(true)
+                .open(path)
+                .unwrap()
+        };
+
+        let mut db_file = open();
+        let project_id = db_file
+            .checkpoint_db_mut()
+            .add_project("Website", "web")
+            .unwrap();
+        db_file
+            .checkpoint_db_mut()
+            .add_checkpoint(1000, "Styling", project_id)
+            .unwrap();
+        db_file.save().unwrap();
+        let json = fs::read(path).unwrap();
+        let cached = binary_cache::read_cache(path, &json).unwrap();
+        assert_eq!(&cached, db_file.checkpoint_db());
+        assert_eq!(open().checkpoint_db(), db_file.checkpoint_db());
+
+        let mut changed = cached.clone();
+        changed
+            .add_checkpoint(2000, "Email", ProjectId::NoId)
+            .unwrap();
+        changed.write(path).unwrap();
+        assert!(binary_cache::read_cache(path, &fs::read(path).unwrap()).is_none());
+        assert_eq!(open().checkpoint_db(), &changed);
+        assert!(binary_cache::read_cache(path, &fs::read(path).unwrap()).is_some());
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{CheckpointDb, CheckpointKind, ProjectId, Result, TimeRange};
+use chrono::prelude::*;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// The start and end times of a project's clocks, by message.
+type MessageClocks<'a> = BTreeMap<&'a str, Vec<(i64, i64)>>;
+
+impl CheckpointDb {
+    /// Writes the work in the given `TimeRange` as Emacs org-mode headings, one per project with
+    /// a subheading per message. Each checkpoint becomes a `CLOCK:` line in the `LOGBOOK`
+    /// drawer of its heading, newest first the way org-mode adds them.
+    pub fn export_org<W: Write>(&self, mut writer: W, range: &TimeRange) -> Result<()> {
+        let mut clocks: BTreeMap<ProjectId, MessageClocks> = BTreeMap::new();
+        for entry in self.entries_in(range) {
+            if entry.duration == 0 || entry.checkpoint.kind == CheckpointKind::Break {
+                continue;
+            }
+            clocks
+                .entry(entry.checkpoint.project_id)
+                .or_default()
+                .entry(entry.checkpoint.message.trim())
+                .or_default()
+                .push((entry.timestamp - entry.duration, entry.timestamp));
+        }
+
+        for (project_id, messages) in clocks {
+            let heading = self
+                .project_from_project_id(project_id)
+                .map_or("No project", |project| &project.long_name);
+            writeln!(writer, "* {}", org_heading(heading))?;
+
+            // Clocks without a message go directly under the project.
+            if let Some(intervals) = messages.get("") {
+                write_org_logbook(&mut writer, intervals)?;
+            }
+            for (message, intervals) in messages.iter().filter(|(message, _)| !message.is_empty()) {
+                writeln!(writer, "** {}", org_heading(message))?;
+                write_org_logbook(&mut writer, intervals)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a `LOGBOOK` drawer with a `CLOCK:` line for each interval, newest first.
+fn write_org_logbook<W: Write>(writer: &mut W, intervals: &[(i64, i64)]) -> io::Result<()> {
+    writeln!(writer, ":LOGBOOK:")?;
+    for (start, end) in intervals.iter().rev() {
+        let minutes = (end - start + 30) / 60;
+        writeln!(
+            writer,
+            "CLOCK: {}--{} => {:2}:{:02}",
+            org_time(*start)?,
+            org_time(*end)?,
+            minutes / 60,
+            minutes % 60
+        )?;
+    }
+    writeln!(writer, ":END:")
+}
+
+fn org_time(timestamp: i64) -> io::Result<String> {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|time| time.format("[%Y-%m-%d %a %H:%M]").to_string())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "timestamp out of range"))
+}
+
+/// Keeps a heading on one line.
+fn org_heading(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Groups clocks by project and message, newest first.
+    fn export_org() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Website", "web").unwrap();
+        let start = Local
+            .with_ymd_and_hms(2021, 6, 15, 9, 0, 0)
+            .unwrap()
+            .timestamp();
+        checkpoint_db
+            .add_checkpoint(start, "", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 5400, "Fix the header", project_id)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 6000, "", project_id)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 36000, "Fix the header", project_id)
+            .unwrap();
+
+        let mut output = Vec::new();
+        checkpoint_db
+            .export_org(&mut output, &TimeRange::new(0, i64::MAX))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "* Website\n\
+             :LOGBOOK:\n\
+             CLOCK: [2021-06-15 Tue 10:30]--[2021-06-15 Tue 10:40] =>  0:10\n\
+             :END:\n\
+             ** Fix the header\n\
+             :LOGBOOK:\n\
+             CLOCK: [2021-06-15 Tue 10:40]--[2021-06-15 Tue 19:00] =>  8:20\n\
+             CLOCK: [2021-06-15 Tue 09:00]--[2021-06-15 Tue 10:30] =>  1:30\n\
+             :END:\n"
+        );
+    }
+}
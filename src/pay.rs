@@ -0,0 +1,181 @@
+use crate::{CheckpointDb, CheckpointDbError, Operation, Result};
+use chrono::prelude::*;
+
+/// A rule for paying more for some billable time, like overtime or work on Sundays, which
+/// earnings reports add as a premium on top of the base amount. When several rules apply to the
+/// same time, the one with the highest multiplier is used.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum PayRule {
+    /// Time beyond `after` seconds of billable time in a day.
+    Overtime { after: i64, multiplier: f64 },
+    /// Time on the weekday.
+    Weekday {
+        #[cfg_attr(feature = "schema", schemars(with = "String"))]
+        weekday: Weekday,
+        multiplier: f64,
+    },
+    /// Time on the dates, like public holidays.
+    Dates {
+        #[cfg_attr(feature = "schema", schemars(with = "Vec<String>"))]
+        dates: Vec<NaiveDate>,
+        multiplier: f64,
+    },
+}
+
+impl CheckpointDb {
+    pub fn pay_rules(&self) -> &[PayRule] {
+        &self.pay_rules
+    }
+
+    /// Replaces the pay rules, failing with `CheckpointDbError::InvalidInput` if a multiplier is
+    /// negative or an overtime rule starts before the start of the day.
+    pub fn set_pay_rules(&mut self, pay_rules: Vec<PayRule>) -> Result<()> {
+        self.apply(Operation::SetPayRules { pay_rules })
+    }
+
+    pub(crate) fn check_pay_rules(pay_rules: &[PayRule]) -> Result<()> {
+        for rule in pay_rules {
+            let (PayRule::Overtime { multiplier, .. }
+            | PayRule::Weekday { multiplier, .. }
+            | PayRule::Dates { multiplier, .. }) = rule;
+            if !(*multiplier >= 0.0 && multiplier.is_finite()) {
+                return Err(CheckpointDbError::InvalidInput(format!(
+                    "{} is not a valid multiplier",
+                    multiplier
+                )));
+            }
+            if let PayRule::Overtime { after, .. } = rule {
+                if *after < 0 {
+                    return Err(CheckpointDbError::InvalidInput(
+                        "overtime can't start before the start of the day".to_string(),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the premium for `billable` seconds at the hourly rate on the date, out of
+    /// `day_billable` seconds billed that day. Overtime is shared out over the day's time in
+    /// proportion to its length, as billed time doesn't say when in the day it was worked.
+    pub(crate) fn premium(
+        &self,
+        rate: i64,
+        date: NaiveDate,
+        billable: i64,
+        day_billable: i64,
+    ) -> f64 {
+        if billable == 0 || day_billable <= 0 {
+            return 0.0;
+        }
+
+        let day_multiplier = self
+            .pay_rules
+            .iter()
+            .filter_map(|rule| match rule {
+                PayRule::Weekday {
+                    weekday,
+                    multiplier,
+                } if date.weekday() == *weekday => Some(*multiplier),
+                PayRule::Dates { dates, multiplier } if dates.contains(&date) => Some(*multiplier),
+                _ => None,
+            })
+            .fold(1.0, f64::max);
+        let overtime = || {
+            self.pay_rules.iter().filter_map(|rule| match rule {
+                PayRule::Overtime { after, multiplier } => Some((*after, *multiplier)),
+                _ => None,
+            })
+        };
+
+        let mut bounds: Vec<i64> = overtime()
+            .map(|(after, _)| after)
+            .filter(|after| (0..day_billable).contains(after))
+            .chain([0, day_billable])
+            .collect();
+        bounds.sort_unstable();
+        bounds.dedup();
+
+        bounds
+            .windows(2)
+            .map(|bounds| {
+                let multiplier = overtime()
+                    .filter(|(after, _)| *after <= bounds[0])
+                    .map(|(_, multiplier)| multiplier)
+                    .fold(day_multiplier, f64::max);
+                let seconds =
+                    (bounds[1] - bounds[0]) as f64 * billable as f64 / day_billable as f64;
+                rate as f64 * seconds / 3600.0 * (multiplier - 1.0)
+            })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ProjectId, ReportTimeZone, Settings, TimeRange};
+
+    #[test]
+    /// Overtime, weekends and holidays are paid extra, with the highest multiplier winning.
+    fn pay_rules() {
+        let mut checkpoint_db = CheckpointDb::new();
+        checkpoint_db
+            .set_settings(Settings {
+                time_zone: ReportTimeZone::Utc,
+                ..Settings::default()
+            })
+            .unwrap();
+        let project_id = checkpoint_db.add_project("Project", "prj").unwrap();
+        checkpoint_db
+            .set_project_rate(project_id, Some(6000))
+            .unwrap();
+        let holiday = NaiveDate::from_ymd_opt(2024, 6, 4).unwrap();
+        assert!(checkpoint_db
+            .set_pay_rules(vec![PayRule::Overtime {
+                after: 8 * 3600,
+                multiplier: -1.0,
+            }])
+            .is_err());
+        checkpoint_db
+            .set_pay_rules(vec![
+                PayRule::Overtime {
+                    after: 8 * 3600,
+                    multiplier: 1.5,
+                },
+                PayRule::Weekday {
+                    weekday: Weekday::Sun,
+                    multiplier: 2.0,
+                },
+                PayRule::Dates {
+                    dates: vec![holiday],
+                    multiplier: 2.0,
+                },
+            ])
+            .unwrap();
+
+        let hour = |day, hour| {
+            Utc.with_ymd_and_hms(2024, 6, day, hour, 0, 0)
+                .unwrap()
+                .timestamp()
+        };
+        // Ten hours on Sunday, ten on Monday and one on a holiday Tuesday.
+        for (day, hours) in [(2, 10), (3, 10), (4, 1)] {
+            checkpoint_db
+                .add_checkpoint(hour(day, 8), "Start", ProjectId::NoId)
+                .unwrap();
+            checkpoint_db
+                .add_checkpoint(hour(day, 8 + hours), "Work", project_id)
+                .unwrap();
+        }
+
+        let report = checkpoint_db.earnings_report(&TimeRange::new(hour(2, 0), hour(5, 0)), None);
+        let earnings = report.totals[0].earnings;
+        assert_eq!(earnings.billable, 21 * 3600);
+        assert_eq!(earnings.base(), 21 * 6000);
+        assert_eq!(earnings.premium, 10 * 6000 + 2 * 3000 + 6000);
+        assert_eq!(earnings.amount, earnings.base() + earnings.premium);
+    }
+}
@@ -0,0 +1,210 @@
+use crate::{CheckpointDb, CheckpointDbError, Report, Result, RoundingPolicy, TimeRange};
+use chrono::{prelude::*, Duration, Months};
+
+/// A way of dividing time into periods for reports, like weeks, fiscal years or pay periods.
+/// Periods start at the day boundary of their first day.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub enum Period {
+    Day,
+    /// Weeks starting on the week start of the settings.
+    Week,
+    Month,
+    /// The 1st to the 15th and the 16th to the end of each month.
+    SemiMonthly,
+    /// Quarters of a fiscal year starting on the first day of the month, 1 to 12.
+    FiscalQuarter {
+        start_month: u32,
+    },
+    /// Years starting on the first day of the month, 1 to 12.
+    FiscalYear {
+        start_month: u32,
+    },
+    /// The periods of a 4-4-5 calendar, where each quarter is 13 weeks split into periods of 4,
+    /// 4 and 5 weeks. Years are 52 weeks from `year_start`, so a year that needs a 53rd week
+    /// has to be given as a new `year_start`.
+    FourFourFive {
+        year_start: NaiveDate,
+    },
+    /// Periods of the number of days, like two week pay periods, counted from `start`.
+    Days {
+        start: NaiveDate,
+        days: u32,
+    },
+}
+
+impl CheckpointDb {
+    /// Returns the period that contains the date. Fails with `CheckpointDbError::InvalidInput`
+    /// if a fiscal period doesn't start in a month or a period of days is empty.
+    pub fn period_range(&self, period: Period, date: NaiveDate) -> Result<TimeRange> {
+        let (first, next) = self.period_days(period, date)?;
+        Ok(TimeRange::new(
+            self.day_range(first).start,
+            self.day_range(next).start,
+        ))
+    }
+
+    /// Returns a report for each period that overlaps the range, in order, covering only the
+    /// part of the period inside the range.
+    pub fn period_reports(
+        &self,
+        period: Period,
+        range: &TimeRange,
+        rounding: Option<&RoundingPolicy>,
+    ) -> Result<Vec<Report>> {
+        let mut reports = Vec::new();
+        let mut start = range.start;
+        while start < range.end {
+            let period_range = self.period_range(period, self.date_of(start))?;
+            let end = period_range.end.min(range.end);
+            reports.push(self.report(&TimeRange::new(start, end), rounding));
+            start = end;
+        }
+        Ok(reports)
+    }
+
+    /// Returns the first day of the period that contains the date and the first day of the
+    /// next one.
+    fn period_days(&self, period: Period, date: NaiveDate) -> Result<(NaiveDate, NaiveDate)> {
+        let first_of_month = date.with_day(1).unwrap_or(date);
+        let months = |first: NaiveDate, months: u32| {
+            (
+                first,
+                first
+                    .checked_add_months(Months::new(months))
+                    .unwrap_or(first),
+            )
+        };
+        let fiscal = |start_month: u32, length: u32| {
+            if !(1..=12).contains(&start_month) {
+                return Err(CheckpointDbError::InvalidInput(format!(
+                    "{} is not a month",
+                    start_month
+                )));
+            }
+            let offset = (date.month0() + 12 - (start_month - 1)) % length;
+            let first = first_of_month
+                .checked_sub_months(Months::new(offset))
+                .unwrap_or(first_of_month);
+            Ok(months(first, length))
+        };
+        let period_start = |start: NaiveDate, days: i64| {
+            let count = (date - start).num_days().div_euclid(days);
+            start + Duration::days(count * days)
+        };
+
+        Ok(match period {
+            Period::Day => (date, date + Duration::days(1)),
+            Period::Week => {
+                let days_since_start = date.weekday().days_since(self.week_start());
+                let first = date - Duration::days(i64::from(days_since_start));
+                (first, first + Duration::days(7))
+            }
+            Period::Month => months(first_of_month, 1),
+            Period::SemiMonthly => {
+                let middle = first_of_month + Duration::days(15);
+                if date < middle {
+                    (first_of_month, middle)
+                } else {
+                    (middle, months(first_of_month, 1).1)
+                }
+            }
+            Period::FiscalQuarter { start_month } => fiscal(start_month, 3)?,
+            Period::FiscalYear { start_month } => fiscal(start_month, 12)?,
+            Period::FourFourFive { year_start } => {
+                let year = period_start(year_start, 52 * 7);
+                let week = (date - year).num_days() / 7;
+                let quarter = year + Duration::weeks(week / 13 * 13);
+                let (from, to) = match week % 13 {
+                    0..=3 => (0, 4),
+                    4..=7 => (4, 8),
+                    _ => (8, 13),
+                };
+                (
+                    quarter + Duration::weeks(from),
+                    quarter + Duration::weeks(to),
+                )
+            }
+            Period::Days { start, days } => {
+                if days == 0 {
+                    return Err(CheckpointDbError::InvalidInput(
+                        "periods need at least one day".to_string(),
+                    ));
+                }
+                let first = period_start(start, i64::from(days));
+                (first, first + Duration::days(i64::from(days)))
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProjectId;
+
+    #[test]
+    /// Each kind of period contains the date, and reports are split at the periods.
+    fn periods() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let date = |year, month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+        let days = |period| {
+            let range = checkpoint_db
+                .period_range(period, date(2024, 5, 20))
+                .unwrap();
+            (
+                checkpoint_db.date_of(range.start),
+                checkpoint_db.date_of(range.end),
+            )
+        };
+
+        assert_eq!(days(Period::Week), (date(2024, 5, 20), date(2024, 5, 27)));
+        assert_eq!(
+            days(Period::SemiMonthly),
+            (date(2024, 5, 16), date(2024, 6, 1))
+        );
+        assert_eq!(
+            days(Period::FiscalQuarter { start_month: 4 }),
+            (date(2024, 4, 1), date(2024, 7, 1))
+        );
+        assert_eq!(
+            days(Period::FiscalYear { start_month: 7 }),
+            (date(2023, 7, 1), date(2024, 7, 1))
+        );
+        // The 21st week of the year, in the second period of the second quarter.
+        assert_eq!(
+            days(Period::FourFourFive {
+                year_start: date(2024, 1, 1)
+            }),
+            (date(2024, 4, 29), date(2024, 5, 27))
+        );
+        assert_eq!(
+            days(Period::Days {
+                start: date(2024, 1, 1),
+                days: 14
+            }),
+            (date(2024, 5, 20), date(2024, 6, 3))
+        );
+        assert!(checkpoint_db
+            .period_range(Period::FiscalYear { start_month: 13 }, date(2024, 5, 20))
+            .is_err());
+
+        checkpoint_db.set_day_boundary(4 * 3600).unwrap();
+        let start = checkpoint_db.day_range(date(2024, 5, 14)).start;
+        for hours in [1, 2, 25, 26] {
+            checkpoint_db
+                .add_checkpoint(start + hours * 3600, "", ProjectId::NoId)
+                .unwrap();
+        }
+        let range = TimeRange::new(start, start + 3 * 86400);
+        let reports = checkpoint_db
+            .period_reports(Period::Day, &range, None)
+            .unwrap();
+        assert_eq!(reports.len(), 3);
+        assert_eq!(reports[1].range, checkpoint_db.day_range(date(2024, 5, 15)));
+        assert_eq!(
+            reports.iter().map(|report| report.duration.0).sum::<i64>(),
+            checkpoint_db.report(&range, None).duration.0
+        );
+    }
+}
@@ -0,0 +1,276 @@
+use crate::{
+    CheckpointDb, ClockifyImporter, CsvOptions, HarvestImporter, HtmlRenderer, Importer,
+    MarkdownRenderer, Report, ReportRenderer, Result, TimeRange, TogglFormat, TogglImporter,
+};
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// Writes the checkpoints of a database in another format.
+///
+/// It's implemented for functions and closures with the same arguments as `export`, so that the
+/// `export_*` methods of `CheckpointDb` can be registered as they are.
+pub trait Exporter {
+    /// Writes the checkpoints in the range.
+    fn export(
+        &self,
+        checkpoint_db: &CheckpointDb,
+        writer: &mut dyn Write,
+        range: &TimeRange,
+    ) -> Result<()>;
+}
+
+impl<F: Fn(&CheckpointDb, &mut dyn Write, &TimeRange) -> Result<()>> Exporter for F {
+    fn export(
+        &self,
+        checkpoint_db: &CheckpointDb,
+        writer: &mut dyn Write,
+        range: &TimeRange,
+    ) -> Result<()> {
+        self(checkpoint_db, writer, range)
+    }
+}
+
+/// The importers, exporters and report renderers known by name, so that programs can offer
+/// formats that they, and this crate, don't know about.
+///
+/// Registering something under a name that is already taken replaces it.
+#[derive(Default)]
+pub struct PluginRegistry {
+    importers: BTreeMap<String, Box<dyn Importer>>,
+    exporters: BTreeMap<String, Box<dyn Exporter>>,
+    renderers: BTreeMap<String, Box<dyn ReportRenderer<Report>>>,
+}
+
+impl PluginRegistry {
+    /// Returns an empty registry.
+    pub fn new() -> PluginRegistry {
+        PluginRegistry::default()
+    }
+
+    /// Returns a registry with the formats of this crate that need no configuration:
+    ///
+    /// - The importers `clockify`, `harvest`, `toggl` and, with the `json` feature,
+    ///   `timewarrior`.
+    /// - The exporters `csv`, `ical`, `org`, `timeclock` and, with the `json` feature, `jsonl`
+    ///   and `timewarrior`.
+    /// - The report renderers `html` and `markdown`.
+    pub fn with_builtins() -> PluginRegistry {
+        let mut registry = PluginRegistry::new();
+
+        registry.register_importer("clockify", ClockifyImporter::default());
+        registry.register_importer("harvest", HarvestImporter::default());
+        registry.register_importer("toggl", TogglImporter::new(TogglFormat::Csv));
+        #[cfg(feature = "json")]
+        registry.register_importer("timewarrior", crate::TimewarriorImporter::default());
+
+        registry.register_exporter(
+            "csv",
+            |checkpoint_db: &CheckpointDb, writer: &mut dyn Write, range: &TimeRange| {
+                checkpoint_db.export_csv(writer, range, &CsvOptions::default())
+            },
+        );
+        registry.register_exporter(
+            "ical",
+            |checkpoint_db: &CheckpointDb, writer: &mut dyn Write, range: &TimeRange| {
+                checkpoint_db.export_ical(writer, range)
+            },
+        );
+        registry.register_exporter(
+            "org",
+            |checkpoint_db: &CheckpointDb, writer: &mut dyn Write, range: &TimeRange| {
+                checkpoint_db.export_org(writer, range)
+            },
+        );
+        registry.register_exporter(
+            "timeclock",
+            |checkpoint_db: &CheckpointDb, writer: &mut dyn Write, range: &TimeRange| {
+                checkpoint_db.export_timeclock(writer, range)
+            },
+        );
+        #[cfg(feature = "json")]
+        {
+            registry.register_exporter(
+                "jsonl",
+                |checkpoint_db: &CheckpointDb, writer: &mut dyn Write, range: &TimeRange| {
+                    checkpoint_db.export_jsonl(writer, range)
+                },
+            );
+            registry.register_exporter(
+                "timewarrior",
+                |checkpoint_db: &CheckpointDb, writer: &mut dyn Write, range: &TimeRange| {
+                    checkpoint_db.export_timewarrior(writer, range)
+                },
+            );
+        }
+
+        registry.register_renderer("html", HtmlRenderer);
+        registry.register_renderer("markdown", MarkdownRenderer);
+        registry
+    }
+
+    /// Returns the built-in formats together with those of every plugin registered with
+    /// `register_plugin!` in the crates linked into the program. Plugins are registered after
+    /// the built-in formats, so they can replace them.
+    #[cfg(feature = "plugins")]
+    pub fn discover() -> PluginRegistry {
+        let mut registry = PluginRegistry::with_builtins();
+        for plugin in inventory::iter::<Plugin> {
+            (plugin.register)(&mut registry);
+        }
+        registry
+    }
+
+    pub fn register_importer<I: Importer + 'static>(&mut self, name: &str, importer: I) {
+        self.importers.insert(name.to_string(), Box::new(importer));
+    }
+
+    pub fn register_exporter<E: Exporter + 'static>(&mut self, name: &str, exporter: E) {
+        self.exporters.insert(name.to_string(), Box::new(exporter));
+    }
+
+    pub fn register_renderer<R: ReportRenderer<Report> + 'static>(
+        &mut self,
+        name: &str,
+        renderer: R,
+    ) {
+        self.renderers.insert(name.to_string(), Box::new(renderer));
+    }
+
+    pub fn importer(&self, name: &str) -> Option<&dyn Importer> {
+        self.importers.get(name).map(Box::as_ref)
+    }
+
+    pub fn exporter(&self, name: &str) -> Option<&dyn Exporter> {
+        self.exporters.get(name).map(Box::as_ref)
+    }
+
+    pub fn renderer(&self, name: &str) -> Option<&dyn ReportRenderer<Report>> {
+        self.renderers.get(name).map(Box::as_ref)
+    }
+
+    /// Returns the names of the importers in alphabetical order.
+    pub fn importers(&self) -> impl Iterator<Item = &str> {
+        self.importers.keys().map(String::as_str)
+    }
+
+    /// Returns the names of the exporters in alphabetical order.
+    pub fn exporters(&self) -> impl Iterator<Item = &str> {
+        self.exporters.keys().map(String::as_str)
+    }
+
+    /// Returns the names of the report renderers in alphabetical order.
+    pub fn renderers(&self) -> impl Iterator<Item = &str> {
+        self.renderers.keys().map(String::as_str)
+    }
+}
+
+/// A function that registers the formats of a plugin, collected by `register_plugin!` for
+/// `PluginRegistry::discover`.
+#[cfg(feature = "plugins")]
+pub struct Plugin {
+    register: fn(&mut PluginRegistry),
+}
+
+#[cfg(feature = "plugins")]
+impl Plugin {
+    pub const fn new(register: fn(&mut PluginRegistry)) -> Plugin {
+        Plugin { register }
+    }
+}
+
+#[cfg(feature = "plugins")]
+inventory::collect!(Plugin);
+
+/// Registers a function that takes a `&mut PluginRegistry` and registers the formats of a
+/// plugin, so that `PluginRegistry::discover` finds them without the program having to know
+/// about the plugin.
+///
+/// ```ignore
+/// fn register(registry: &mut time_track::PluginRegistry) {
+///     registry.register_importer("my-tracker", MyTrackerImporter);
+/// }
+///
+/// time_track::register_plugin!(register);
+/// ```
+#[cfg(feature = "plugins")]
+#[macro_export]
+macro_rules! register_plugin {
+    ($register:path) => {
+        $crate::inventory::submit! {
+            $crate::Plugin::new($register)
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ImportedCheckpoint;
+
+    struct LinesImporter;
+
+    impl Importer for LinesImporter {
+        fn read(&self, input: &str) -> Result<Vec<ImportedCheckpoint>, String> {
+            input
+                .lines()
+                .map(|line| {
+                    let (timestamp, message) = line.split_once(' ').ok_or("missing message")?;
+                    Ok(ImportedCheckpoint {
+                        timestamp: timestamp.parse().map_err(|_| "invalid timestamp")?,
+                        message: message.to_string(),
+                        project: None,
+                        tags: Vec::new(),
+                        start: None,
+                        external_id: None,
+                        kind: None,
+                    })
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    /// Formats registered by name can be used for importing and exporting.
+    fn registered_formats() {
+        let mut registry = PluginRegistry::with_builtins();
+        registry.register_importer("lines", LinesImporter);
+        assert!(registry.importers().any(|name| name == "lines"));
+        assert_eq!(
+            registry.renderers().collect::<Vec<_>>(),
+            ["html", "markdown"]
+        );
+
+        let mut checkpoint_db = CheckpointDb::new();
+        let importer = registry.importer("lines").unwrap();
+        checkpoint_db
+            .import_from("1000 Start\n2000 Styling".as_bytes(), importer, false)
+            .unwrap();
+        assert_eq!(checkpoint_db.len(), 2);
+
+        let mut org = Vec::new();
+        registry
+            .exporter("org")
+            .unwrap()
+            .export(&checkpoint_db, &mut org, &TimeRange::new(0, 3000))
+            .unwrap();
+        assert!(String::from_utf8(org).unwrap().contains("Styling"));
+        assert!(registry.exporter("pdf").is_none());
+    }
+
+    #[cfg(feature = "plugins")]
+    fn register_lines(registry: &mut PluginRegistry) {
+        registry.register_importer("lines", LinesImporter);
+    }
+
+    #[cfg(feature = "plugins")]
+    crate::register_plugin!(register_lines);
+
+    #[cfg(feature = "plugins")]
+    #[test]
+    /// Plugins registered with the macro are found along with the built-in formats.
+    fn discovered_plugins() {
+        let registry = PluginRegistry::discover();
+        assert!(registry.importer("lines").is_some());
+        assert!(registry.importer("toggl").is_some());
+    }
+}
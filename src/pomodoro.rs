@@ -0,0 +1,106 @@
+use crate::{CheckpointDb, ProjectId, TimeRange};
+use chrono::prelude::*;
+use std::collections::BTreeMap;
+
+/// A pomodoro session that ended at a checkpoint.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Pomodoro {
+    /// The planned length of the session in seconds.
+    pub length: i64,
+    /// Whether the session was completed, rather than aborted.
+    pub completed: bool,
+}
+
+/// The number of pomodoros of one project on one day.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct PomodoroCount {
+    pub date: NaiveDate,
+    pub project_id: ProjectId,
+    pub completed: usize,
+    pub aborted: usize,
+}
+
+/// Pomodoro sessions in a `TimeRange`, counted per day and project.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct PomodoroStats {
+    pub range: TimeRange,
+    pub days: Vec<PomodoroCount>,
+    pub completed: usize,
+    pub aborted: usize,
+}
+
+impl CheckpointDb {
+    /// Returns `PomodoroStats` for the pomodoros in the given `TimeRange`.
+    pub fn pomodoro_stats(&self, range: &TimeRange) -> PomodoroStats {
+        let mut days: BTreeMap<(NaiveDate, ProjectId), (usize, usize)> = BTreeMap::new();
+        for entry in self.entries_in(range) {
+            if let Some(pomodoro) = entry.checkpoint.pomodoro {
+                let (completed, aborted) = days
+                    .entry((self.date_of(entry.timestamp), entry.checkpoint.project_id))
+                    .or_default();
+                if pomodoro.completed {
+                    *completed += 1;
+                } else {
+                    *aborted += 1;
+                }
+            }
+        }
+
+        let days: Vec<PomodoroCount> = days
+            .into_iter()
+            .map(|((date, project_id), (completed, aborted))| PomodoroCount {
+                date,
+                project_id,
+                completed,
+                aborted,
+            })
+            .collect();
+
+        PomodoroStats {
+            range: *range,
+            completed: days.iter().map(|day| day.completed).sum(),
+            aborted: days.iter().map(|day| day.aborted).sum(),
+            days,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CheckpointId;
+
+    #[test]
+    fn pomodoro_stats() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Project", "prj").unwrap();
+        let start = Utc
+            .with_ymd_and_hms(2021, 6, 15, 12, 0, 0)
+            .unwrap()
+            .timestamp();
+        for (minutes, completed) in [(25, true), (55, true), (70, false)] {
+            let time = start + minutes * 60;
+            checkpoint_db
+                .add_checkpoint(time, "Focus", project_id)
+                .unwrap();
+            checkpoint_db
+                .set_checkpoint_pomodoro(
+                    CheckpointId::Timestamp(time),
+                    Some(Pomodoro {
+                        length: 25 * 60,
+                        completed,
+                    }),
+                )
+                .unwrap();
+        }
+
+        let stats = checkpoint_db.pomodoro_stats(&TimeRange::new(start, start + 3600 * 2));
+        assert_eq!(stats.completed, 2);
+        assert_eq!(stats.aborted, 1);
+        assert_eq!(stats.days.len(), 1);
+    }
+}
@@ -0,0 +1,190 @@
+use crate::{
+    Checkpoint, CheckpointDb, CheckpointDbError, CheckpointKind, Client, Operation, Project,
+    ProjectId, Result, TimeRange, Timestamp,
+};
+use std::collections::BTreeMap;
+
+/// Everything stored about the checkpoints in a `TimeRange`, from
+/// `CheckpointDb::export_everything`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct DataExport<'a> {
+    pub range: TimeRange,
+    pub checkpoints: Vec<ExportedCheckpoint<'a>>,
+    /// The projects of the exported checkpoints, by id.
+    pub projects: BTreeMap<u16, &'a Project>,
+    /// The clients of the exported projects, by id.
+    pub clients: BTreeMap<u16, &'a Client>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+pub struct ExportedCheckpoint<'a> {
+    pub timestamp: i64,
+    pub duration: i64,
+    pub checkpoint: &'a Checkpoint,
+}
+
+/// What `CheckpointDb::purge` erases.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PurgeTarget {
+    /// The checkpoints in the range.
+    Range(TimeRange),
+    /// The project and all of its checkpoints.
+    Project(ProjectId),
+}
+
+/// What a purge would erase, along with the token that confirms it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PurgeConfirmation {
+    /// Has to be given to `CheckpointDb::purge`. It only stays valid as long as the same
+    /// checkpoints would be erased.
+    pub token: String,
+    /// The timestamps of the checkpoints that would be erased.
+    pub checkpoints: Vec<i64>,
+}
+
+impl CheckpointDb {
+    /// Returns everything stored about the checkpoints in the given `TimeRange` along with
+    /// their projects and clients, for answering requests for the data kept about someone.
+    pub fn export_everything(&self, range: &TimeRange) -> DataExport<'_> {
+        let mut projects = BTreeMap::new();
+        let mut clients = BTreeMap::new();
+        let checkpoints = self
+            .entries_in(range)
+            .map(|entry| {
+                if let ProjectId::Id(id) = entry.checkpoint.project_id {
+                    if let Some(project) = self.projects.get(&id) {
+                        projects.insert(id, project);
+                        if let Some(client_id) = project.client_id {
+                            if let Some(client) = self.clients.get(&client_id.0) {
+                                clients.insert(client_id.0, client);
+                            }
+                        }
+                    }
+                }
+
+                ExportedCheckpoint {
+                    timestamp: entry.timestamp,
+                    duration: entry.duration,
+                    checkpoint: entry.checkpoint,
+                }
+            })
+            .collect();
+
+        DataExport {
+            range: *range,
+            checkpoints,
+            projects,
+            clients,
+        }
+    }
+
+    /// Returns what purging the target would erase and the token needed to do it.
+    pub fn purge_confirmation(&self, target: &PurgeTarget) -> PurgeConfirmation {
+        let checkpoints = self.purged_checkpoints(target);
+
+        // FNV-1a over the target and the affected checkpoints, so that a token can't be reused
+        // for something else.
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let description = format!("{:?}{:?}", target, checkpoints);
+        for byte in description.bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+
+        PurgeConfirmation {
+            token: format!("{:016x}", hash),
+            checkpoints,
+        }
+    }
+
+    /// Erases the target, given the token from `purge_confirmation`, and returns the number of
+    /// erased checkpoints.
+    ///
+    /// Erased checkpoints are replaced by blank breaks rather than removed, so that the time
+    /// they tracked isn't counted towards the checkpoints after them. Purging a project also
+    /// removes the project.
+    pub fn purge(&mut self, target: &PurgeTarget, token: &str) -> Result<usize> {
+        let confirmation = self.purge_confirmation(target);
+        if confirmation.token != token {
+            return Err(CheckpointDbError::InvalidInput(
+                "the confirmation token does not match what would be purged".to_string(),
+            ));
+        }
+
+        for timestamp in &confirmation.checkpoints {
+            self.apply(Operation::ReplaceCheckpoint {
+                timestamp: Timestamp(*timestamp),
+                checkpoint: Checkpoint::builder().kind(CheckpointKind::Break).build(),
+            })?;
+        }
+        if let PurgeTarget::Project(project_id @ ProjectId::Id(id)) = target {
+            if self.projects.contains_key(id) {
+                self.apply(Operation::RemoveProject {
+                    project_id: *project_id,
+                })?;
+            }
+        }
+
+        Ok(confirmation.checkpoints.len())
+    }
+
+    fn purged_checkpoints(&self, target: &PurgeTarget) -> Vec<i64> {
+        match target {
+            PurgeTarget::Range(range) => self
+                .checkpoints
+                .range(range.start..range.end)
+                .map(|(timestamp, _)| *timestamp)
+                .collect(),
+            PurgeTarget::Project(project_id) => self
+                .checkpoints
+                .iter()
+                .filter(|(_, checkpoint)| checkpoint.project_id == *project_id)
+                .map(|(timestamp, _)| *timestamp)
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckpointId, TrackedDuration};
+
+    #[test]
+    /// Purging needs the right token, and keeps the durations of other checkpoints.
+    fn purge() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Jane's project", "jane").unwrap();
+        checkpoint_db
+            .add_checkpoint(1000, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(2000, "Call with Jane", project_id)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(3000, "Email", ProjectId::NoId)
+            .unwrap();
+
+        let export = checkpoint_db.export_everything(&TimeRange::new(1500, 2500));
+        assert_eq!(export.checkpoints.len(), 1);
+        assert_eq!(export.projects[&0].short_name, "jane");
+
+        let target = PurgeTarget::Project(project_id);
+        let confirmation = checkpoint_db.purge_confirmation(&target);
+        assert_eq!(confirmation.checkpoints, vec![2000]);
+        assert!(checkpoint_db.purge(&target, "wrong").is_err());
+        assert_eq!(
+            checkpoint_db.purge(&target, &confirmation.token).unwrap(),
+            1
+        );
+
+        assert!(checkpoint_db.projects.is_empty());
+        assert_eq!(&*checkpoint_db.checkpoints[&2000].message, "");
+        assert_eq!(
+            checkpoint_db.get_checkpoint_duration(&CheckpointId::Timestamp(3000)),
+            Some(TrackedDuration(1000))
+        );
+    }
+}
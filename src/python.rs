@@ -0,0 +1,141 @@
+use crate::{CheckpointDb, CheckpointDbError, ProjectId, TimeRange};
+use pyo3::{
+    exceptions::{PyIOError, PyValueError},
+    prelude::*,
+};
+use serde::Serialize;
+use std::path::PathBuf;
+
+impl From<CheckpointDbError> for PyErr {
+    fn from(error: CheckpointDbError) -> PyErr {
+        match error {
+            CheckpointDbError::Io(e) => PyIOError::new_err(e.to_string()),
+            e => PyValueError::new_err(e.to_string()),
+        }
+    }
+}
+
+/// A `CheckpointDb` for Python, where it's called `time_track.CheckpointDb`. Times are seconds
+/// since the Unix epoch, and results are dicts and lists shaped like the crate's JSON, with the
+/// durations already worked out.
+#[pyclass(name = "CheckpointDb")]
+#[derive(Clone, Debug, Default)]
+pub struct PyCheckpointDb {
+    checkpoint_db: CheckpointDb,
+}
+
+#[pymethods]
+impl PyCheckpointDb {
+    #[new]
+    fn new() -> PyCheckpointDb {
+        PyCheckpointDb::default()
+    }
+
+    /// Reads the database file at the path, creating an empty one if there is none.
+    #[staticmethod]
+    fn read(path: PathBuf) -> PyResult<PyCheckpointDb> {
+        Ok(CheckpointDb::read(&path)?.into())
+    }
+
+    fn write(&self, path: PathBuf) -> PyResult<()> {
+        Ok(self.checkpoint_db.write(&path)?)
+    }
+
+    /// Adds a checkpoint, failing if there already is one at the same time.
+    #[pyo3(signature = (timestamp, message, project_id=None))]
+    fn add_checkpoint(
+        &mut self,
+        timestamp: i64,
+        message: &str,
+        project_id: Option<u16>,
+    ) -> PyResult<()> {
+        let project_id = project_id.map_or(ProjectId::NoId, ProjectId::Id);
+        Ok(self
+            .checkpoint_db
+            .add_checkpoint(timestamp, message, project_id)?)
+    }
+
+    /// Adds a project and returns its id.
+    fn add_project(&mut self, long_name: &str, short_name: &str) -> PyResult<u16> {
+        match self.checkpoint_db.add_project(long_name, short_name)? {
+            ProjectId::Id(id) => Ok(id),
+            ProjectId::NoId => unreachable!("added projects always have an id"),
+        }
+    }
+
+    /// Returns the ids and projects.
+    fn projects(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let projects: Vec<_> = self.checkpoint_db.projects().collect();
+        to_python(py, &projects)
+    }
+
+    /// Returns the checkpoints between two times with their durations, from the latest to the
+    /// oldest.
+    fn log(&self, py: Python<'_>, start: i64, end: i64) -> PyResult<PyObject> {
+        to_python(py, &self.checkpoint_db.get_log_between_times(start, end))
+    }
+
+    /// Returns the time tracked per day and project between two times.
+    fn report(&self, py: Python<'_>, start: i64, end: i64) -> PyResult<PyObject> {
+        to_python(
+            py,
+            &self.checkpoint_db.report(&TimeRange::new(start, end), None),
+        )
+    }
+
+    fn __len__(&self) -> usize {
+        self.checkpoint_db.len()
+    }
+}
+
+impl From<CheckpointDb> for PyCheckpointDb {
+    fn from(checkpoint_db: CheckpointDb) -> PyCheckpointDb {
+        PyCheckpointDb { checkpoint_db }
+    }
+}
+
+/// Converts a value to Python objects through its JSON.
+fn to_python<T: Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value).map_err(CheckpointDbError::from)?;
+    Ok(py.import("json")?.call_method1("loads", (json,))?.unbind())
+}
+
+/// The `time_track` Python module.
+#[pymodule]
+fn time_track(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyCheckpointDb>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::{PyDict, PyList};
+
+    #[test]
+    /// The log returned to Python has the durations worked out by the crate.
+    fn log_to_python() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let mut py_db = PyCheckpointDb::new();
+            let project_id = py_db.add_project("Website", "web").unwrap();
+            py_db.add_checkpoint(1000, "Start", None).unwrap();
+            py_db
+                .add_checkpoint(1600, "Styling", Some(project_id))
+                .unwrap();
+            assert!(py_db.add_checkpoint(2000, "", Some(9)).is_err());
+
+            let log = py_db.log(py, 0, 5000).unwrap();
+            let log = log.bind(py).downcast::<PyList>().unwrap();
+            assert_eq!(log.len(), 2);
+            let latest = log.get_item(0).unwrap();
+            let latest = latest.downcast::<PyDict>().unwrap();
+            let duration: i64 = latest
+                .get_item("duration")
+                .unwrap()
+                .unwrap()
+                .extract()
+                .unwrap();
+            assert_eq!(duration, 600);
+        });
+    }
+}
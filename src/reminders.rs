@@ -0,0 +1,275 @@
+use crate::{CheckpointDb, ProjectId, Result, Timestamp};
+use chrono::prelude::*;
+
+/// The local days and times of day that count as working time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct WorkingHours {
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    /// The end of working time, which isn't part of it.
+    pub end: NaiveTime,
+}
+
+impl Default for WorkingHours {
+    /// Monday to Friday from 9:00 to 17:00.
+    fn default() -> WorkingHours {
+        WorkingHours {
+            days: vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ],
+            start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+        }
+    }
+}
+
+impl WorkingHours {
+    /// Whether the time is during working time, in the local time zone.
+    pub fn contains(&self, timestamp: i64) -> bool {
+        let Some(time) = Local.timestamp_opt(timestamp, 0).earliest() else {
+            return false;
+        };
+        self.days.contains(&time.weekday()) && (self.start..self.end).contains(&time.time())
+    }
+}
+
+/// Something a user should be told about their time tracking.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub enum Reminder {
+    /// No checkpoint has been added for a while during working time.
+    NoCheckpoint {
+        /// The time of the latest checkpoint, if there is one.
+        latest: Option<Timestamp>,
+        /// The seconds since the latest checkpoint.
+        elapsed: i64,
+    },
+    /// The daily goal of the database, or of a project, has been reached.
+    GoalReached {
+        date: NaiveDate,
+        /// The project whose goal was reached, or `ProjectId::NoId` for the database's goal.
+        project_id: ProjectId,
+        achieved: i64,
+    },
+}
+
+impl Reminder {
+    /// Returns a short text about the reminder, for showing to the user.
+    pub fn message(&self, checkpoint_db: &CheckpointDb) -> String {
+        match *self {
+            Reminder::NoCheckpoint { elapsed, .. } => format!(
+                "No checkpoint in {}h {:02}m. What are you working on?",
+                elapsed / 3600,
+                elapsed % 3600 / 60
+            ),
+            Reminder::GoalReached {
+                project_id: ProjectId::NoId,
+                ..
+            } => "You reached your daily goal.".to_string(),
+            Reminder::GoalReached { project_id, .. } => format!(
+                "You reached the daily goal of {}.",
+                checkpoint_db.report_project_name(project_id)
+            ),
+        }
+    }
+}
+
+/// Shows reminders to the user, like a desktop notification or a line in a terminal.
+pub trait Notifier {
+    fn notify(&self, checkpoint_db: &CheckpointDb, reminder: &Reminder) -> Result<()>;
+}
+
+/// Decides when to remind the user, so that every frontend reminds about the same things at the
+/// same times. It's meant to be checked regularly, like once a minute, and remembers what it has
+/// reminded about so that each reminder is only given once.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReminderSchedule {
+    pub working_hours: WorkingHours,
+    /// The seconds without a checkpoint during working time before reminding about it, or
+    /// `None` to never remind about it. Two hours by default.
+    pub no_checkpoint_after: Option<i64>,
+    /// Whether to tell when daily goals are reached. On by default.
+    pub goals: bool,
+    /// The latest checkpoint when `Reminder::NoCheckpoint` was last given.
+    reminded_since: Option<Option<Timestamp>>,
+    /// The goals that have been reached, and the day they were reached on.
+    reached_goals: Vec<(NaiveDate, ProjectId)>,
+}
+
+impl Default for ReminderSchedule {
+    fn default() -> ReminderSchedule {
+        ReminderSchedule {
+            working_hours: WorkingHours::default(),
+            no_checkpoint_after: Some(2 * 3600),
+            goals: true,
+            reminded_since: None,
+            reached_goals: Vec::new(),
+        }
+    }
+}
+
+impl ReminderSchedule {
+    pub fn new() -> ReminderSchedule {
+        ReminderSchedule::default()
+    }
+
+    /// Returns the reminders that are due at the time and haven't been given yet.
+    pub fn due(&mut self, checkpoint_db: &CheckpointDb, now: i64) -> Vec<Reminder> {
+        let mut reminders = Vec::new();
+
+        if let Some(after) = self.no_checkpoint_after {
+            let latest = checkpoint_db
+                .checkpoints
+                .keys()
+                .next_back()
+                .map(|t| Timestamp(*t));
+            let elapsed = latest.map_or(i64::MAX, |latest| now - latest.seconds());
+            if elapsed >= after
+                && self.working_hours.contains(now)
+                && self.reminded_since != Some(latest)
+            {
+                self.reminded_since = Some(latest);
+                reminders.push(Reminder::NoCheckpoint {
+                    latest,
+                    elapsed: latest.map_or(0, |_| elapsed),
+                });
+            }
+        }
+
+        if self.goals {
+            let date = checkpoint_db.date_of(now);
+            self.reached_goals.retain(|(reached, _)| *reached == date);
+            let progress = checkpoint_db.goal_progress(date);
+            let goals = progress
+                .total
+                .map(|total| (ProjectId::NoId, total))
+                .into_iter()
+                .chain(progress.projects.iter().map(|p| (p.project_id, p.progress)));
+            for (project_id, progress) in goals {
+                if progress.remaining == 0 && !self.reached_goals.contains(&(date, project_id)) {
+                    self.reached_goals.push((date, project_id));
+                    reminders.push(Reminder::GoalReached {
+                        date,
+                        project_id,
+                        achieved: progress.achieved,
+                    });
+                }
+            }
+        }
+
+        reminders
+    }
+
+    /// Gives the reminders that are due at the time to the notifier, and returns how many
+    /// there were.
+    pub fn notify<N: Notifier + ?Sized>(
+        &mut self,
+        checkpoint_db: &CheckpointDb,
+        now: i64,
+        notifier: &N,
+    ) -> Result<usize> {
+        let reminders = self.due(checkpoint_db, now);
+        for reminder in &reminders {
+            notifier.notify(checkpoint_db, reminder)?;
+        }
+        Ok(reminders.len())
+    }
+}
+
+/// Shows reminders as desktop notifications.
+#[cfg(feature = "notifications")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DesktopNotifier;
+
+#[cfg(feature = "notifications")]
+impl Notifier for DesktopNotifier {
+    fn notify(&self, checkpoint_db: &CheckpointDb, reminder: &Reminder) -> Result<()> {
+        notify_rust::Notification::new()
+            .appname("Time Track")
+            .summary("Time Track")
+            .body(&reminder.message(checkpoint_db))
+            .show()
+            .map_err(std::io::Error::other)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TimeRange;
+    use std::cell::RefCell;
+
+    #[derive(Default)]
+    struct Recorder(RefCell<Vec<Reminder>>);
+
+    impl Notifier for Recorder {
+        fn notify(&self, _checkpoint_db: &CheckpointDb, reminder: &Reminder) -> Result<()> {
+            self.0.borrow_mut().push(*reminder);
+            Ok(())
+        }
+    }
+
+    #[test]
+    /// Reminders are given once when due, and again after the next checkpoint or day.
+    fn reminders_are_given_once() {
+        // A Tuesday.
+        let date = NaiveDate::from_ymd_opt(2021, 6, 15).unwrap();
+        let morning = TimeRange::day(date).start + 9 * 3600;
+        let mut checkpoint_db = CheckpointDb::new();
+        checkpoint_db.set_daily_goal(Some(3 * 3600)).unwrap();
+        checkpoint_db
+            .add_checkpoint(morning, "Start", ProjectId::NoId)
+            .unwrap();
+
+        let mut schedule = ReminderSchedule::new();
+        let recorder = Recorder::default();
+        assert_eq!(
+            schedule
+                .notify(&checkpoint_db, morning + 3600, &recorder)
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            schedule
+                .notify(&checkpoint_db, morning + 7200, &recorder)
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            schedule
+                .notify(&checkpoint_db, morning + 7300, &recorder)
+                .unwrap(),
+            0
+        );
+        assert_eq!(
+            recorder.0.borrow()[0],
+            Reminder::NoCheckpoint {
+                latest: Some(Timestamp(morning)),
+                elapsed: 7200,
+            }
+        );
+
+        checkpoint_db
+            .add_checkpoint(morning + 3 * 3600, "Work", ProjectId::NoId)
+            .unwrap();
+        let due = schedule.due(&checkpoint_db, morning + 5 * 3600);
+        assert_eq!(due.len(), 2);
+        assert_eq!(
+            due[1].message(&checkpoint_db),
+            "You reached your daily goal."
+        );
+        assert!(schedule.due(&checkpoint_db, morning + 5 * 3600).is_empty());
+
+        // Nobody is reminded after working hours.
+        checkpoint_db
+            .add_checkpoint(morning + 8 * 3600, "Done", ProjectId::NoId)
+            .unwrap();
+        assert!(schedule.due(&checkpoint_db, morning + 11 * 3600).is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+use crate::{CheckpointDb, DayTotal, ProjectId, Report, Result, TrackedDuration};
+
+/// The name of the time without a project in rendered reports.
+pub(crate) const NO_PROJECT_NAME: &str = "No project";
+
+/// Turns a report structure, like a `Report`, into text.
+pub trait ReportRenderer<R: ?Sized> {
+    /// Renders the report, looking up the names of its projects in the database.
+    fn render(&self, checkpoint_db: &CheckpointDb, report: &R) -> Result<String>;
+}
+
+/// Renders `Report`s with `CheckpointDb::render_markdown`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MarkdownRenderer;
+
+impl ReportRenderer<Report> for MarkdownRenderer {
+    fn render(&self, checkpoint_db: &CheckpointDb, report: &Report) -> Result<String> {
+        Ok(checkpoint_db.render_markdown(report))
+    }
+}
+
+/// Renders `Report`s with `CheckpointDb::render_html`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HtmlRenderer;
+
+impl ReportRenderer<Report> for HtmlRenderer {
+    fn render(&self, checkpoint_db: &CheckpointDb, report: &Report) -> Result<String> {
+        Ok(checkpoint_db.render_html(report))
+    }
+}
+
+impl CheckpointDb {
+    /// Renders a `Report` as Markdown, with a table of the total time of each project and a
+    /// table of the time of each project per day.
+    pub fn render_markdown(&self, report: &Report) -> String {
+        let mut markdown = format!("# Report {}\n", self.report_title(report));
+
+        markdown.push_str("\n## Projects\n\n| Project | Time |\n| --- | ---: |\n");
+        for total in &report.projects {
+            markdown.push_str(&format!(
+                "| {} | {} |\n",
+                escape_markdown(self.report_project_name(total.project_id)),
+                total.duration
+            ));
+        }
+        markdown.push_str(&format!("| **Total** | **{}** |\n", report.duration));
+
+        markdown.push_str("\n## Days\n\n| Date |");
+        for total in &report.projects {
+            markdown.push_str(&format!(
+                " {} |",
+                escape_markdown(self.report_project_name(total.project_id))
+            ));
+        }
+        markdown.push_str(" Total |\n| --- |");
+        markdown.push_str(&" ---: |".repeat(report.projects.len() + 1));
+        markdown.push('\n');
+        for day in &report.days {
+            markdown.push_str(&format!("| {} |", self.format_day(day.date)));
+            for total in &report.projects {
+                let duration = project_duration(day, total.project_id);
+                markdown.push_str(&format!(" {} |", duration));
+            }
+            markdown.push_str(&format!(" {} |\n", day.duration));
+        }
+
+        markdown
+    }
+
+    /// Renders a `Report` as a self-contained HTML page with the same tables as
+    /// `render_markdown`, styled to be readable when sent by email.
+    pub fn render_html(&self, report: &Report) -> String {
+        let title = format!("Report {}", escape_html(&self.report_title(report)));
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+             <style>\n{}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+            title, HTML_STYLE, title
+        );
+        let project_names: Vec<String> = report
+            .projects
+            .iter()
+            .map(|total| escape_html(self.report_project_name(total.project_id)))
+            .collect();
+
+        html.push_str("<h2>Projects</h2>\n<table>\n<tr><th>Project</th><th>Time</th></tr>\n");
+        for (name, total) in project_names.iter().zip(&report.projects) {
+            html.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td></tr>\n",
+                name, total.duration
+            ));
+        }
+        html.push_str(&format!(
+            "<tr class=\"total\"><td>Total</td><td>{}</td></tr>\n</table>\n",
+            report.duration
+        ));
+
+        html.push_str("<h2>Days</h2>\n<table>\n<tr><th>Date</th>");
+        for name in &project_names {
+            html.push_str(&format!("<th>{}</th>", name));
+        }
+        html.push_str("<th>Total</th></tr>\n");
+        for day in &report.days {
+            html.push_str(&format!("<tr><td>{}</td>", self.format_day(day.date)));
+            for total in &report.projects {
+                let duration = project_duration(day, total.project_id);
+                html.push_str(&format!("<td>{}</td>", duration));
+            }
+            html.push_str(&format!("<td>{}</td></tr>\n", day.duration));
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+
+        html
+    }
+
+    /// Returns the first and last date of a report's range.
+    fn report_title(&self, report: &Report) -> String {
+        let first = self.date_of(report.range.start);
+        let last = self.date_of(report.range.end - 1);
+        if first == last {
+            self.format_date(first)
+        } else {
+            format!("{} – {}", self.format_date(first), self.format_date(last))
+        }
+    }
+
+    pub(crate) fn report_project_name(&self, project_id: ProjectId) -> &str {
+        self.project_from_project_id(project_id)
+            .map_or(NO_PROJECT_NAME, |project| &project.long_name)
+    }
+}
+
+/// Returns the time of a project on a day.
+fn project_duration(day: &DayTotal, project_id: ProjectId) -> TrackedDuration {
+    day.projects
+        .iter()
+        .find(|total| total.project_id == project_id)
+        .map_or(TrackedDuration::ZERO, |total| total.duration)
+}
+
+const HTML_STYLE: &str = "body { font-family: sans-serif; color: #222; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { padding: 0.3em 0.8em; border-bottom: 1px solid #ddd; }
+th { text-align: left; background: #f4f4f4; }
+td:not(:first-child), th:not(:first-child) { text-align: right; }
+tr.total td { font-weight: bold; border-top: 2px solid #222; }
+";
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Keeps text from breaking out of a table cell or being formatted.
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '\\' | '|' | '*' | '_' | '`' | '[' | ']' | '<' | '>' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' | '\r' => escaped.push(' '),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TimeRange;
+    use chrono::NaiveDate;
+
+    #[test]
+    /// Renders the project totals and a row per day.
+    fn render_markdown() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Web | site", "web").unwrap();
+        let monday = NaiveDate::from_ymd_opt(2021, 6, 14).unwrap();
+        let start = TimeRange::day(monday).start + 9 * 3600;
+        checkpoint_db
+            .add_checkpoint(start, "", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 5400, "Fix the header", project_id)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 24 * 3600, "", ProjectId::NoId)
+            .unwrap();
+
+        let range = TimeRange::new(
+            TimeRange::day(monday).start,
+            TimeRange::day(monday + chrono::Days::new(6)).end,
+        );
+        let report = checkpoint_db.report(&range, None);
+        assert_eq!(
+            checkpoint_db.render_markdown(&report),
+            "# Report 2021-06-14 – 2021-06-20\n\
+             \n\
+             ## Projects\n\
+             \n\
+             | Project | Time |\n\
+             | --- | ---: |\n\
+             | No project | 22:30 |\n\
+             | Web \\| site | 1:30 |\n\
+             | **Total** | **24:00** |\n\
+             \n\
+             ## Days\n\
+             \n\
+             | Date | No project | Web \\| site | Total |\n\
+             | --- | ---: | ---: | ---: |\n\
+             | Mon 2021-06-14 | 0:00 | 1:30 | 1:30 |\n\
+             | Tue 2021-06-15 | 22:30 | 0:00 | 22:30 |\n"
+        );
+    }
+
+    #[test]
+    /// Escapes project names and puts the total last.
+    fn render_html() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("<Website>", "web").unwrap();
+        let monday = NaiveDate::from_ymd_opt(2021, 6, 14).unwrap();
+        let start = TimeRange::day(monday).start + 9 * 3600;
+        checkpoint_db
+            .add_checkpoint(start, "", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 5400, "Fix the header", project_id)
+            .unwrap();
+
+        let report = checkpoint_db.report(&TimeRange::day(monday), None);
+        let html = checkpoint_db.render_html(&report);
+        assert!(html.contains("<title>Report 2021-06-14</title>"));
+        assert!(
+            html.contains("<tr><td>&lt;Website&gt;</td><td>1:30</td></tr>\n<tr class=\"total\">")
+        );
+        assert!(html
+            .contains("<tr><td>Mon 2021-06-14</td><td>0:00</td><td>1:30</td><td>1:30</td></tr>"));
+    }
+}
@@ -0,0 +1,458 @@
+use crate::{Checkpoint, CheckpointDb, ProjectId, TrackedDuration};
+use chrono::{prelude::*, Duration, Months};
+use std::{
+    cmp::{max, min},
+    collections::BTreeMap,
+};
+
+/// A span of time in seconds since the Unix epoch. `start` is inclusive and `end` is exclusive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct TimeRange {
+    pub start: i64,
+    pub end: i64,
+}
+
+impl TimeRange {
+    /// Creates a `TimeRange` between two timestamps, in whichever order they are given.
+    pub fn new(start: i64, end: i64) -> TimeRange {
+        TimeRange {
+            start: min(start, end),
+            end: max(start, end),
+        }
+    }
+
+    /// Creates a `TimeRange` between two `DateTime`s, in whichever order they are given.
+    pub fn between<Tz: TimeZone>(start: &DateTime<Tz>, end: &DateTime<Tz>) -> TimeRange {
+        TimeRange::new(start.timestamp(), end.timestamp())
+    }
+
+    /// Creates a `TimeRange` covering the given local calendar day.
+    pub fn day(date: NaiveDate) -> TimeRange {
+        let next_day = date.succ_opt().unwrap_or(date);
+        TimeRange::new(local_midnight(date), local_midnight(next_day))
+    }
+
+    /// Creates a `TimeRange` covering the local calendar month of the given date.
+    pub fn month(date: NaiveDate) -> TimeRange {
+        let first_day = date.with_day(1).unwrap_or(date);
+        let next_month = first_day
+            .checked_add_months(Months::new(1))
+            .unwrap_or(first_day);
+        TimeRange::new(local_midnight(first_day), local_midnight(next_month))
+    }
+
+    pub fn contains(&self, timestamp: i64) -> bool {
+        timestamp >= self.start && timestamp < self.end
+    }
+
+    /// The length of the range in seconds.
+    pub fn duration(&self) -> i64 {
+        max(self.end - self.start, 0)
+    }
+}
+
+/// The direction a `RoundingPolicy` rounds durations in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum RoundingMode {
+    Nearest,
+    Up,
+    Down,
+}
+
+/// What a `RoundingPolicy` is applied to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum RoundingScope {
+    /// Every checkpoint's duration is rounded on its own.
+    Entry,
+    /// The summed duration of each project on each day is rounded.
+    ProjectDay,
+}
+
+/// Describes how durations are rounded in reports, for example "up to the nearest 15 minutes
+/// for each project and day".
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct RoundingPolicy {
+    pub mode: RoundingMode,
+    /// The increment to round to, in seconds.
+    pub increment: i64,
+    pub scope: RoundingScope,
+}
+
+impl RoundingPolicy {
+    pub fn new(mode: RoundingMode, increment_minutes: i64, scope: RoundingScope) -> RoundingPolicy {
+        RoundingPolicy {
+            mode,
+            increment: increment_minutes * 60,
+            scope,
+        }
+    }
+
+    /// Rounds the given duration in seconds according to the policy.
+    pub fn round(&self, duration: i64) -> i64 {
+        if self.increment <= 0 {
+            return duration;
+        }
+
+        let remainder = duration.rem_euclid(self.increment);
+        if remainder == 0 {
+            return duration;
+        }
+
+        let rounded_down = duration - remainder;
+        match self.mode {
+            RoundingMode::Down => rounded_down,
+            RoundingMode::Up => rounded_down + self.increment,
+            RoundingMode::Nearest => {
+                if remainder * 2 >= self.increment {
+                    rounded_down + self.increment
+                } else {
+                    rounded_down
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct ProjectTotal {
+    pub project_id: ProjectId,
+    pub duration: TrackedDuration,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct DayTotal {
+    pub date: NaiveDate,
+    pub projects: Vec<ProjectTotal>,
+    pub duration: TrackedDuration,
+}
+
+/// Tracked time in a `TimeRange`, summed per day and per project.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct Report {
+    pub range: TimeRange,
+    pub days: Vec<DayTotal>,
+    pub projects: Vec<ProjectTotal>,
+    pub duration: TrackedDuration,
+}
+
+/// A checkpoint along with the time tracked by it.
+#[derive(Clone, Copy)]
+pub(crate) struct Entry<'a> {
+    pub timestamp: i64,
+    pub checkpoint: &'a Checkpoint,
+    pub duration: i64,
+}
+
+impl CheckpointDb {
+    /// Returns a `Report` of the time tracked in the given `TimeRange`, with durations rounded
+    /// according to the given `RoundingPolicy`.
+    pub fn report(&self, range: &TimeRange, rounding: Option<&RoundingPolicy>) -> Report {
+        let scope = rounding.map_or(RoundingScope::Entry, |rounding| rounding.scope);
+        let project_days = self.project_days(self.entries_in(range), scope, |_, duration| {
+            rounding.map_or(duration, |rounding| rounding.round(duration))
+        });
+
+        let mut days: Vec<DayTotal> = Vec::new();
+        let mut projects: BTreeMap<ProjectId, TrackedDuration> = BTreeMap::new();
+        for ((date, project_id), duration) in project_days {
+            let duration = TrackedDuration(duration);
+            *projects.entry(project_id).or_default() += duration;
+
+            let project_total = ProjectTotal {
+                project_id,
+                duration,
+            };
+            match days.last_mut() {
+                Some(day) if day.date == date => {
+                    day.projects.push(project_total);
+                    day.duration += duration;
+                }
+                _ => days.push(DayTotal {
+                    date,
+                    projects: vec![project_total],
+                    duration,
+                }),
+            }
+        }
+
+        Report {
+            range: *range,
+            duration: days.iter().map(|day| day.duration).sum(),
+            days,
+            projects: projects
+                .into_iter()
+                .map(|(project_id, duration)| ProjectTotal {
+                    project_id,
+                    duration,
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the checkpoints in the given `TimeRange` in chronological order.
+    pub(crate) fn entries_in(&self, range: &TimeRange) -> impl Iterator<Item = Entry<'_>> + Clone {
+        self.checkpoints
+            .range(range.start..max(range.start, range.end))
+            .map(move |(timestamp, checkpoint)| Entry {
+                timestamp: *timestamp,
+                checkpoint,
+                duration: self.duration_before(*timestamp),
+            })
+    }
+
+    /// Returns the number of seconds between the given timestamp and the checkpoint preceding it.
+    pub(crate) fn duration_before(&self, timestamp: i64) -> i64 {
+        self.checkpoints
+            .range(..timestamp)
+            .next_back()
+            .map_or(0, |(previous, _)| timestamp - previous)
+    }
+}
+
+impl CheckpointDb {
+    /// Sums the durations of the given entries per day, as given by `date_of`, and project.
+    /// `adjust` is applied to each entry or to each sum, depending on `scope`.
+    pub(crate) fn project_days<'a, I, F>(
+        &self,
+        entries: I,
+        scope: RoundingScope,
+        adjust: F,
+    ) -> BTreeMap<(NaiveDate, ProjectId), i64>
+    where
+        I: Iterator<Item = Entry<'a>>,
+        F: Fn(ProjectId, i64) -> i64,
+    {
+        let mut project_days = BTreeMap::new();
+        for entry in entries {
+            let project_id = entry.checkpoint.project_id;
+            let duration = match scope {
+                RoundingScope::Entry => adjust(project_id, entry.duration),
+                RoundingScope::ProjectDay => entry.duration,
+            };
+            *project_days
+                .entry((self.date_of(entry.timestamp), project_id))
+                .or_insert(0) += duration;
+        }
+
+        if scope == RoundingScope::ProjectDay {
+            for ((_, project_id), duration) in project_days.iter_mut() {
+                *duration = adjust(*project_id, *duration);
+            }
+        }
+
+        project_days
+    }
+}
+
+/// Returns the timestamp of the first second of the given local calendar day.
+pub(crate) fn local_midnight(date: NaiveDate) -> i64 {
+    day_start_in(&Local, date, 0)
+}
+
+/// Returns the date of the day that the timestamp is in, in the time zone, where days start at
+/// `boundary` seconds of wall clock time after midnight.
+pub(crate) fn date_in<Tz: TimeZone>(time_zone: &Tz, timestamp: i64, boundary: i64) -> NaiveDate {
+    time_zone
+        .timestamp_opt(timestamp, 0)
+        .earliest()
+        .map_or(NaiveDate::MIN, |time| {
+            (time.naive_local() - Duration::seconds(boundary)).date()
+        })
+}
+
+/// Returns the timestamp that the day of the date starts at in the time zone, `boundary`
+/// seconds of wall clock time after midnight. Days are as long as the wall clock says, so the
+/// days when daylight saving time starts or ends are 23 or 25 hours long.
+pub(crate) fn day_start_in<Tz: TimeZone>(time_zone: &Tz, date: NaiveDate, boundary: i64) -> i64 {
+    let start = date.and_time(NaiveTime::MIN) + Duration::seconds(boundary);
+    // A start that's skipped when the clocks are set forward is at the end of the gap.
+    (0..=4)
+        .map(|half_hours| start + Duration::minutes(30 * half_hours))
+        .find_map(|time| time_zone.from_local_datetime(&time).earliest())
+        .map_or_else(|| start.and_utc().timestamp(), |time| time.timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ReportTimeZone, Settings};
+    use chrono::LocalResult;
+
+    #[test]
+    /// Rounds durations up, down and to the nearest increment, both per entry and per project
+    /// and day.
+    fn rounding() {
+        let up = RoundingPolicy::new(RoundingMode::Up, 15, RoundingScope::Entry);
+        let down = RoundingPolicy::new(RoundingMode::Down, 15, RoundingScope::Entry);
+        let nearest = RoundingPolicy::new(RoundingMode::Nearest, 6, RoundingScope::Entry);
+        assert_eq!(up.round(60), 900);
+        assert_eq!(up.round(900), 900);
+        assert_eq!(down.round(899), 0);
+        assert_eq!(nearest.round(179), 0);
+        assert_eq!(nearest.round(180), 360);
+
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Project", "prj").unwrap();
+        let start = Utc
+            .with_ymd_and_hms(2021, 6, 15, 12, 0, 0)
+            .unwrap()
+            .timestamp();
+        for minutes in [0, 10, 20] {
+            checkpoint_db
+                .add_checkpoint(start + minutes * 60, "Work", project_id)
+                .unwrap();
+        }
+
+        let range = TimeRange::new(start, start + 3600);
+        let per_entry = checkpoint_db.report(&range, Some(&up));
+        assert_eq!(per_entry.duration, TrackedDuration(2 * 900));
+
+        let per_project_day =
+            RoundingPolicy::new(RoundingMode::Down, 15, RoundingScope::ProjectDay);
+        let per_project_day = checkpoint_db.report(&range, Some(&per_project_day));
+        assert_eq!(per_project_day.duration, TrackedDuration(900));
+        assert_eq!(
+            per_project_day.projects,
+            vec![ProjectTotal {
+                project_id,
+                duration: TrackedDuration(900)
+            }]
+        );
+
+        assert_eq!(
+            checkpoint_db.report(&range, None).duration,
+            TrackedDuration(1200)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    /// Reports can be written as JSON and read back.
+    fn report_json() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Project", "prj").unwrap();
+        checkpoint_db
+            .add_checkpoint(0, "", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(3600, "Work", project_id)
+            .unwrap();
+
+        let report = checkpoint_db.report(&TimeRange::new(0, 7200), None);
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains(r#""duration":3600"#));
+        assert_eq!(serde_json::from_str::<Report>(&json).unwrap(), report);
+    }
+
+    /// A time zone that is UTC+1, and UTC+2 during the daylight saving time of 2021, like
+    /// central Europe.
+    #[derive(Clone, Copy, Debug)]
+    struct Dst;
+
+    impl TimeZone for Dst {
+        type Offset = FixedOffset;
+
+        fn from_offset(_offset: &FixedOffset) -> Dst {
+            Dst
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let offsets: Vec<FixedOffset> = [7200, 3600]
+                .into_iter()
+                .map(|seconds| FixedOffset::east_opt(seconds).unwrap())
+                .filter(|offset| {
+                    let utc = *local - Duration::seconds(offset.local_minus_utc().into());
+                    self.offset_from_utc_datetime(&utc) == *offset
+                })
+                .collect();
+            match offsets[..] {
+                [offset] => LocalResult::Single(offset),
+                [earliest, latest] => LocalResult::Ambiguous(earliest, latest),
+                _ => LocalResult::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            let summer = Utc
+                .with_ymd_and_hms(2021, 3, 28, 1, 0, 0)
+                .unwrap()
+                .naive_utc()
+                ..Utc
+                    .with_ymd_and_hms(2021, 10, 31, 1, 0, 0)
+                    .unwrap()
+                    .naive_utc();
+            let hours = if summer.contains(utc) { 2 } else { 1 };
+            FixedOffset::east_opt(hours * 3600).unwrap()
+        }
+    }
+
+    #[test]
+    /// The days when daylight saving time starts and ends are 23 and 25 hours long, also with
+    /// a day boundary, and every day is 24 hours long when days are counted in UTC.
+    fn daylight_saving_days() {
+        let date = |month, day| NaiveDate::from_ymd_opt(2021, month, day).unwrap();
+        let day_length = |date: NaiveDate, boundary| {
+            day_start_in(&Dst, date.succ_opt().unwrap(), boundary)
+                - day_start_in(&Dst, date, boundary)
+        };
+        assert_eq!(day_length(date(3, 27), 0), 24 * 3600);
+        assert_eq!(day_length(date(3, 28), 0), 23 * 3600);
+        assert_eq!(day_length(date(10, 31), 0), 25 * 3600);
+        // With days starting at 4:00, the clocks change at 2:00 or 3:00 of the day before.
+        assert_eq!(day_length(date(3, 27), 4 * 3600), 23 * 3600);
+        assert_eq!(day_length(date(3, 28), 4 * 3600), 24 * 3600);
+        assert_eq!(day_length(date(10, 30), 4 * 3600), 25 * 3600);
+        // Days starting at 2:30 start at 3:00 on the day that 2:30 is skipped.
+        assert_eq!(
+            day_start_in(&Dst, date(3, 28), 2 * 3600 + 1800),
+            Utc.with_ymd_and_hms(2021, 3, 28, 1, 0, 0)
+                .unwrap()
+                .timestamp()
+        );
+
+        // The last second of the long day is 23:59:59 in winter time.
+        let end = Utc.with_ymd_and_hms(2021, 10, 31, 22, 59, 59).unwrap();
+        assert_eq!(date_in(&Dst, end.timestamp(), 0), date(10, 31));
+        assert_eq!(date_in(&Dst, end.timestamp() + 1, 0), date(11, 1));
+        assert_eq!(
+            date_in(&Dst, end.timestamp() + 4 * 3600, 4 * 3600),
+            date(10, 31)
+        );
+
+        let mut checkpoint_db = CheckpointDb::new();
+        checkpoint_db
+            .set_settings(Settings {
+                time_zone: ReportTimeZone::Utc,
+                ..Settings::default()
+            })
+            .unwrap();
+        for date in [date(3, 28), date(10, 31)] {
+            let range = checkpoint_db.day_range(date);
+            assert_eq!(range.duration(), 24 * 3600);
+            assert_eq!(
+                range.start,
+                Utc.from_utc_datetime(&date.and_time(NaiveTime::MIN))
+                    .timestamp()
+            );
+            assert_eq!(checkpoint_db.date_of(range.end - 1), date);
+        }
+    }
+}
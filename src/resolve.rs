@@ -0,0 +1,108 @@
+use crate::{CheckpointDb, CheckpointDbError, ProjectId, Result};
+use std::str::FromStr;
+
+/// The most edits a short name can be away from what was typed to be suggested.
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+impl FromStr for ProjectId {
+    type Err = CheckpointDbError;
+
+    /// Parses a numeric project id, or an empty string as `ProjectId::NoId`. Use
+    /// `CheckpointDb::resolve_project` to also accept short names.
+    fn from_str(s: &str) -> Result<ProjectId> {
+        if s.is_empty() {
+            return Ok(ProjectId::NoId);
+        }
+        s.parse()
+            .map(ProjectId::Id)
+            .map_err(|_| CheckpointDbError::InvalidInput(format!("{:?} is not a project id", s)))
+    }
+}
+
+impl CheckpointDb {
+    /// Finds the project a user refers to by its short name or by its numeric id, with the
+    /// short name taking precedence. An empty string is `ProjectId::NoId`. If there is no such
+    /// project, the error is a `CheckpointDbError::UnknownProject` with the short names of the
+    /// projects that are spelled similarly or whose names contain what was given.
+    pub fn resolve_project(&self, project: &str) -> Result<ProjectId> {
+        let project = project.trim();
+        if let Some(project_id) = self.project_id_from_short_name(project) {
+            return Ok(project_id);
+        }
+        if let Ok(project_id @ ProjectId::Id(_)) = project.parse() {
+            if self.project_from_project_id(project_id).is_some() {
+                return Ok(project_id);
+            }
+        }
+
+        let lowercase = project.to_lowercase();
+        let mut suggestions: Vec<(usize, &str)> = self
+            .projects()
+            .filter_map(|(_, candidate)| {
+                let distance = edit_distance(&lowercase, &candidate.short_name.to_lowercase());
+                let contains = candidate.short_name.to_lowercase().contains(&lowercase)
+                    || candidate.long_name.to_lowercase().contains(&lowercase);
+                if distance <= MAX_SUGGESTION_DISTANCE || contains {
+                    Some((distance, candidate.short_name.as_str()))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        suggestions.sort();
+
+        Err(CheckpointDbError::UnknownProject {
+            name: project.to_string(),
+            suggestions: suggestions
+                .into_iter()
+                .map(|(_, short_name)| short_name.to_string())
+                .collect(),
+        })
+    }
+}
+
+/// The number of characters that have to be inserted, removed or replaced to turn one string
+/// into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let replace = previous[j] + usize::from(a_char != *b_char);
+            current.push(replace.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Projects are found by short name or id, and misspelled names get suggestions.
+    fn resolve_project() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let website = checkpoint_db.add_project("Website", "web").unwrap();
+        let webshop = checkpoint_db.add_project("Web shop", "shop").unwrap();
+
+        assert_eq!(checkpoint_db.resolve_project("shop").unwrap(), webshop);
+        assert_eq!(checkpoint_db.resolve_project("0").unwrap(), website);
+        assert_eq!(checkpoint_db.resolve_project("").unwrap(), ProjectId::NoId);
+        assert!("web".parse::<ProjectId>().is_err());
+
+        match checkpoint_db.resolve_project("wbe") {
+            Err(CheckpointDbError::UnknownProject { name, suggestions }) => {
+                assert_eq!(name, "wbe");
+                assert_eq!(suggestions, vec!["web".to_string()]);
+            }
+            other => panic!("expected an unknown project, got {:?}", other),
+        }
+        assert!(matches!(
+            checkpoint_db.resolve_project("Web"),
+            Err(CheckpointDbError::UnknownProject { suggestions, .. }) if suggestions == ["web", "shop"]
+        ));
+    }
+}
@@ -0,0 +1,195 @@
+use crate::{
+    Checkpoint, CheckpointDbError, CheckpointKind, LogCheckpoint, Report, SharedCheckpointDb,
+    Timestamp,
+};
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::prelude::*;
+use std::{path::PathBuf, sync::Arc};
+
+#[derive(Clone)]
+struct RestState {
+    checkpoint_db: SharedCheckpointDb,
+    path: Option<Arc<PathBuf>>,
+}
+
+#[derive(Deserialize)]
+struct RangeQuery {
+    from: Option<i64>,
+    to: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct WeekQuery {
+    /// Any day in the week, which is the current week by default.
+    date: Option<NaiveDate>,
+}
+
+/// The body of `POST /checkpoints`.
+#[derive(Deserialize)]
+struct NewCheckpoint {
+    /// Now by default.
+    timestamp: Option<i64>,
+    #[serde(default)]
+    message: String,
+    /// The short name or id of the project, none by default.
+    project: Option<String>,
+    #[serde(default, rename = "break")]
+    is_break: bool,
+}
+
+#[derive(Serialize)]
+struct Created {
+    timestamp: Timestamp,
+}
+
+/// Returns an HTTP API over the database, which saves it to `path` after every change when
+/// given one:
+///
+/// - `GET /checkpoints?from=&to=` returns the checkpoints between two timestamps, from the
+///   latest to the oldest. Both ends are optional.
+/// - `POST /checkpoints` adds a checkpoint from a JSON object with the optional fields
+///   `timestamp`, `message`, `project` and `break`, and returns its timestamp.
+/// - `GET /reports/week?date=` returns the report of the local week that contains the date, or
+///   of the current week, following the database's `Settings`.
+///
+/// Errors are returned as a JSON object with an `error` message.
+pub fn rest_router(checkpoint_db: SharedCheckpointDb, path: Option<PathBuf>) -> Router {
+    Router::new()
+        .route("/checkpoints", get(get_checkpoints).post(post_checkpoint))
+        .route("/reports/week", get(get_week_report))
+        .with_state(RestState {
+            checkpoint_db,
+            path: path.map(Arc::new),
+        })
+}
+
+async fn get_checkpoints(
+    State(state): State<RestState>,
+    Query(query): Query<RangeQuery>,
+) -> Json<Vec<LogCheckpoint>> {
+    // The log leaves out checkpoints at the ends, so widen the range to include them.
+    let from = query.from.map_or(i64::MIN, |from| from.saturating_sub(1));
+    let to = query.to.map_or(i64::MAX, |to| to.saturating_add(1));
+    Json(state.checkpoint_db.read().get_log_between_times(from, to))
+}
+
+async fn post_checkpoint(
+    State(state): State<RestState>,
+    Json(new): Json<NewCheckpoint>,
+) -> Result<(StatusCode, Json<Created>), RestError> {
+    let mut checkpoint_db = state.checkpoint_db.write();
+
+    let mut checkpoint = Checkpoint::builder().message(&new.message);
+    if let Some(project) = &new.project {
+        checkpoint = checkpoint.project(checkpoint_db.resolve_project(project)?);
+    }
+    if new.is_break {
+        checkpoint = checkpoint.kind(CheckpointKind::Break);
+    }
+    let timestamp = new.timestamp.map_or_else(Timestamp::now, Timestamp);
+    checkpoint_db.add_checkpoint_full(timestamp, checkpoint)?;
+    if let Some(path) = &state.path {
+        checkpoint_db.write(path)?;
+    }
+
+    Ok((StatusCode::CREATED, Json(Created { timestamp })))
+}
+
+async fn get_week_report(
+    State(state): State<RestState>,
+    Query(query): Query<WeekQuery>,
+) -> Json<Report> {
+    let checkpoint_db = state.checkpoint_db.read();
+    let date = query
+        .date
+        .unwrap_or_else(|| checkpoint_db.date_of(Timestamp::now().seconds()));
+    let range = checkpoint_db.week_range(date);
+    Json(checkpoint_db.report(&range, checkpoint_db.rounding()))
+}
+
+struct RestError(CheckpointDbError);
+
+impl From<CheckpointDbError> for RestError {
+    fn from(error: CheckpointDbError) -> RestError {
+        RestError(error)
+    }
+}
+
+impl IntoResponse for RestError {
+    fn into_response(self) -> Response {
+        let status = match self.0 {
+            CheckpointDbError::CheckpointExists { .. }
+            | CheckpointDbError::InvoiceNumberTaken { .. } => StatusCode::CONFLICT,
+            CheckpointDbError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        let body = serde_json::json!({ "error": self.0.to_string() });
+        (status, Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CheckpointDb;
+    use axum::{body::Body, http::Request};
+    use tower::ServiceExt;
+
+    fn request(router: &Router, method: &str, uri: &str, body: &str) -> (StatusCode, String) {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let request = Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap();
+            let response = router.clone().oneshot(request).await.unwrap();
+            let status = response.status();
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            (status, String::from_utf8(bytes.to_vec()).unwrap())
+        })
+    }
+
+    #[test]
+    /// Checkpoints posted to the API can be listed, and bad ones are refused.
+    fn post_and_get_checkpoints() {
+        let mut checkpoint_db = CheckpointDb::new();
+        checkpoint_db.add_project("Website", "web").unwrap();
+        let shared = SharedCheckpointDb::new(checkpoint_db);
+        let router = rest_router(shared.clone(), None);
+
+        let (status, _) = request(
+            &router,
+            "POST",
+            "/checkpoints",
+            r#"{"timestamp": 1000, "message": "Styling", "project": "web"}"#,
+        );
+        assert_eq!(status, StatusCode::CREATED);
+        let (status, body) = request(
+            &router,
+            "POST",
+            "/checkpoints",
+            r#"{"timestamp": 2000, "project": "wbe"}"#,
+        );
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body.contains("did you mean web?"), "{}", body);
+
+        let (status, body) = request(&router, "GET", "/checkpoints?from=1000&to=1000", "");
+        assert_eq!(status, StatusCode::OK);
+        let log: Vec<LogCheckpoint> = serde_json::from_str(&body).unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(&*log[0].checkpoint.message, "Styling");
+        assert_eq!(shared.snapshot().len(), 1);
+    }
+}
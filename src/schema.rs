@@ -0,0 +1,81 @@
+use crate::{CheckpointDb, CheckpointDbError, ProjectId, Result};
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+impl CheckpointDb {
+    /// Returns the JSON Schema of the files written by `write`.
+    #[cfg(feature = "schema")]
+    pub fn json_schema() -> Value {
+        schemars::schema_for!(CheckpointDb).to_value()
+    }
+
+    /// Checks that a JSON value is a database that `read` accepts and that is consistent: every
+    /// checkpoint's project and every project's client exists, and project short names are
+    /// unique and not empty, and the settings are valid.
+    pub fn validate_json(value: &Value) -> Result<()> {
+        let checkpoint_db: CheckpointDb = serde_json::from_value(value.clone())?;
+        CheckpointDb::check_settings(&checkpoint_db.settings)?;
+        let error = |message: String| Err(CheckpointDbError::InvalidInput(message));
+
+        let mut short_names = BTreeSet::new();
+        for (id, project) in &checkpoint_db.projects {
+            if project.short_name.is_empty() {
+                return error(format!("project {} has no short name", id));
+            }
+            if !short_names.insert(project.short_name.as_str()) {
+                return error(format!(
+                    "the short name {:?} is used by several projects",
+                    project.short_name
+                ));
+            }
+            if let Some(client_id) = project.client_id {
+                if !checkpoint_db.clients.contains_key(&client_id.0) {
+                    return error(format!("project {} has a missing client {}", id, client_id));
+                }
+            }
+        }
+
+        for (timestamp, checkpoint) in &checkpoint_db.checkpoints {
+            if let ProjectId::Id(id) = checkpoint.project_id {
+                if !checkpoint_db.projects.contains_key(&id) {
+                    return error(format!(
+                        "the checkpoint at {} has a missing project {}",
+                        timestamp, id
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Accepts what `write` produces, and rejects checkpoints of missing projects.
+    fn validate_json() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Website", "web").unwrap();
+        checkpoint_db
+            .add_checkpoint(1000, "Fix the header", project_id)
+            .unwrap();
+        let mut value = serde_json::to_value(&checkpoint_db).unwrap();
+        assert!(CheckpointDb::validate_json(&value).is_ok());
+
+        value["projects"] = serde_json::json!({});
+        assert!(CheckpointDb::validate_json(&value).is_err());
+        assert!(CheckpointDb::validate_json(&serde_json::json!({ "projects": [] })).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "schema")]
+    /// The schema describes the top level maps of the database.
+    fn json_schema() {
+        let schema = CheckpointDb::json_schema();
+        assert!(schema["properties"]["checkpoints"].is_object());
+        assert!(schema["properties"]["projects"].is_object());
+    }
+}
@@ -0,0 +1,293 @@
+use crate::{
+    report::{date_in, day_start_in},
+    CheckpointDb, CheckpointDbError, DateFormat, Operation, Result, RoundingPolicy, TimeRange,
+    Timestamp,
+};
+use chrono::prelude::*;
+use chrono::{Duration, Months};
+
+/// The time zone that reports count days in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum ReportTimeZone {
+    /// The local time zone, where the days when daylight saving time starts or ends are 23 or
+    /// 25 hours long.
+    #[default]
+    Local,
+    /// UTC, where every day is 24 hours long, whatever the local time zone.
+    Utc,
+}
+
+/// The reporting rules saved in a database, so that every frontend using it reports the same
+/// way. The daily goal and the currency are also saved in the database, as
+/// `CheckpointDb::daily_goal` and `CheckpointDb::currency`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json", serde(default))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Settings {
+    /// The first day of weeks. Monday by default.
+    #[cfg_attr(feature = "schema", schemars(with = "String"))]
+    pub week_start: Weekday,
+    /// The seconds after local midnight that days start at, so that work after midnight can
+    /// count towards the day before. Midnight by default.
+    pub day_boundary: i64,
+    /// How reports round durations. Not at all by default.
+    #[cfg_attr(feature = "json", serde(skip_serializing_if = "Option::is_none"))]
+    pub rounding: Option<RoundingPolicy>,
+    /// Whether adding checkpoints more than a minute in the future fails. Off by default.
+    #[cfg_attr(feature = "json", serde(skip_serializing_if = "crate::is_false"))]
+    pub reject_future: bool,
+    /// The earliest time checkpoints can be added at, to catch mistyped years. Any time by
+    /// default.
+    #[cfg_attr(feature = "json", serde(skip_serializing_if = "Option::is_none"))]
+    pub earliest: Option<i64>,
+    /// How reports write dates. ISO 8601 by default.
+    #[cfg_attr(feature = "json", serde(skip_serializing_if = "DateFormat::is_iso"))]
+    pub date_format: DateFormat,
+    /// The time zone days, and so weeks and other periods, are counted in. Local by default.
+    #[cfg_attr(
+        feature = "json",
+        serde(skip_serializing_if = "ReportTimeZone::is_local")
+    )]
+    pub time_zone: ReportTimeZone,
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        Settings {
+            week_start: Weekday::Mon,
+            day_boundary: 0,
+            rounding: None,
+            reject_future: false,
+            earliest: None,
+            date_format: DateFormat::Iso,
+            time_zone: ReportTimeZone::Local,
+        }
+    }
+}
+
+impl ReportTimeZone {
+    #[cfg(feature = "json")]
+    fn is_local(&self) -> bool {
+        *self == ReportTimeZone::Local
+    }
+}
+
+impl Settings {
+    #[cfg(feature = "json")]
+    pub(crate) fn is_default(&self) -> bool {
+        *self == Settings::default()
+    }
+}
+
+impl CheckpointDb {
+    pub fn settings(&self) -> &Settings {
+        &self.settings
+    }
+
+    /// Replaces the settings, failing with `CheckpointDbError::InvalidInput` if the day
+    /// boundary isn't within a day or the rounding increment is negative.
+    pub fn set_settings(&mut self, settings: Settings) -> Result<()> {
+        self.apply(Operation::SetSettings { settings })
+    }
+
+    pub(crate) fn check_settings(settings: &Settings) -> Result<()> {
+        if !(0..24 * 3600).contains(&settings.day_boundary) {
+            return Err(CheckpointDbError::InvalidInput(format!(
+                "the day boundary {} isn't within a day",
+                settings.day_boundary
+            )));
+        }
+        if settings
+            .rounding
+            .is_some_and(|rounding| rounding.increment < 0)
+        {
+            return Err(CheckpointDbError::InvalidInput(
+                "the rounding increment is negative".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fails with `CheckpointDbError::ImplausibleTimestamp` if the settings don't allow adding a
+    /// checkpoint at the timestamp.
+    pub(crate) fn check_plausible(&self, timestamp: Timestamp) -> Result<()> {
+        let too_late = self.settings.reject_future && timestamp.seconds() > Timestamp::now().0 + 60;
+        let too_early = self
+            .settings
+            .earliest
+            .is_some_and(|earliest| timestamp.seconds() < earliest);
+        if too_late || too_early {
+            return Err(CheckpointDbError::ImplausibleTimestamp { timestamp });
+        }
+        Ok(())
+    }
+
+    pub fn week_start(&self) -> Weekday {
+        self.settings.week_start
+    }
+
+    pub fn set_week_start(&mut self, week_start: Weekday) -> Result<()> {
+        self.set_settings(Settings {
+            week_start,
+            ..self.settings
+        })
+    }
+
+    pub fn day_boundary(&self) -> i64 {
+        self.settings.day_boundary
+    }
+
+    pub fn set_day_boundary(&mut self, day_boundary: i64) -> Result<()> {
+        self.set_settings(Settings {
+            day_boundary,
+            ..self.settings
+        })
+    }
+
+    pub fn rounding(&self) -> Option<&RoundingPolicy> {
+        self.settings.rounding.as_ref()
+    }
+
+    pub fn set_rounding(&mut self, rounding: Option<RoundingPolicy>) -> Result<()> {
+        self.set_settings(Settings {
+            rounding,
+            ..self.settings
+        })
+    }
+
+    /// Returns the day of the date in the time zone of the settings, starting at the day
+    /// boundary.
+    pub fn day_range(&self, date: NaiveDate) -> TimeRange {
+        let next_day = date.succ_opt().unwrap_or(date);
+        TimeRange::new(self.day_start(date), self.day_start(next_day))
+    }
+
+    /// Returns the date of the day that the timestamp counts towards in reports, in the time
+    /// zone of the settings and with the day boundary.
+    pub fn date_of(&self, timestamp: i64) -> NaiveDate {
+        let boundary = self.settings.day_boundary;
+        match self.settings.time_zone {
+            ReportTimeZone::Local => date_in(&Local, timestamp, boundary),
+            ReportTimeZone::Utc => date_in(&Utc, timestamp, boundary),
+        }
+    }
+
+    fn day_start(&self, date: NaiveDate) -> i64 {
+        self.time_on(date, self.settings.day_boundary)
+    }
+
+    /// Returns the timestamp `seconds` of wall clock time after midnight on the date, in the
+    /// time zone of the settings.
+    pub(crate) fn time_on(&self, date: NaiveDate, seconds: i64) -> i64 {
+        match self.settings.time_zone {
+            ReportTimeZone::Local => day_start_in(&Local, date, seconds),
+            ReportTimeZone::Utc => day_start_in(&Utc, date, seconds),
+        }
+    }
+
+    /// Returns the local week that contains the date, starting on the week start at the day
+    /// boundary.
+    pub fn week_range(&self, date: NaiveDate) -> TimeRange {
+        let days_since_start = date.weekday().days_since(self.settings.week_start);
+        let first_day = date - Duration::days(i64::from(days_since_start));
+        TimeRange::new(
+            self.day_range(first_day).start,
+            self.day_range(first_day + Duration::days(7)).start,
+        )
+    }
+
+    /// Returns the local month that contains the date, starting at the day boundary.
+    pub fn month_range(&self, date: NaiveDate) -> TimeRange {
+        let first_day = date.with_day(1).unwrap_or(date);
+        let next_month = first_day
+            .checked_add_months(Months::new(1))
+            .unwrap_or(first_day);
+        TimeRange::new(
+            self.day_range(first_day).start,
+            self.day_range(next_month).start,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Checkpoint, ProjectId};
+
+    #[test]
+    /// Weeks and days follow the settings, and invalid settings are refused.
+    fn settings_ranges() {
+        let mut checkpoint_db = CheckpointDb::new();
+        // A Wednesday.
+        let date = NaiveDate::from_ymd_opt(2021, 6, 16).unwrap();
+        let monday = NaiveDate::from_ymd_opt(2021, 6, 14).unwrap();
+        assert_eq!(
+            checkpoint_db.week_range(date).start,
+            TimeRange::day(monday).start
+        );
+
+        checkpoint_db.set_week_start(Weekday::Sun).unwrap();
+        checkpoint_db.set_day_boundary(4 * 3600).unwrap();
+        let sunday = NaiveDate::from_ymd_opt(2021, 6, 13).unwrap();
+        assert_eq!(
+            checkpoint_db.week_range(date).start,
+            TimeRange::day(sunday).start + 4 * 3600
+        );
+        assert_eq!(
+            checkpoint_db.day_range(date).end,
+            TimeRange::day(date).end + 4 * 3600
+        );
+
+        assert_eq!(
+            checkpoint_db.month_range(date),
+            TimeRange::new(
+                TimeRange::day(NaiveDate::from_ymd_opt(2021, 6, 1).unwrap()).start + 4 * 3600,
+                TimeRange::day(NaiveDate::from_ymd_opt(2021, 7, 1).unwrap()).start + 4 * 3600
+            )
+        );
+
+        assert!(checkpoint_db.set_day_boundary(-1).is_err());
+        assert_eq!(checkpoint_db.day_boundary(), 4 * 3600);
+        assert_eq!(checkpoint_db.week_start(), Weekday::Sun);
+    }
+
+    #[test]
+    /// Checkpoints in the future or before the earliest time are refused once the settings ask
+    /// for it, unless they're forced.
+    fn plausible_timestamps() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let future = Timestamp::now().0 + 3600;
+        let early = Utc.with_ymd_and_hms(1985, 1, 1, 0, 0, 0).unwrap();
+        checkpoint_db
+            .add_checkpoint(future, "", ProjectId::NoId)
+            .unwrap();
+
+        checkpoint_db
+            .set_settings(Settings {
+                reject_future: true,
+                earliest: Some(
+                    Utc.with_ymd_and_hms(1990, 1, 1, 0, 0, 0)
+                        .unwrap()
+                        .timestamp(),
+                ),
+                ..Settings::default()
+            })
+            .unwrap();
+        for time in [future + 1, early.timestamp()] {
+            assert!(matches!(
+                checkpoint_db.add_checkpoint(time, "", ProjectId::NoId),
+                Err(CheckpointDbError::ImplausibleTimestamp { .. })
+            ));
+        }
+        checkpoint_db
+            .add_checkpoint(Timestamp::now(), "", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .force_add_checkpoint(early, Checkpoint::default())
+            .unwrap();
+        assert_eq!(checkpoint_db.len(), 3);
+    }
+}
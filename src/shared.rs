@@ -0,0 +1,94 @@
+use crate::CheckpointDb;
+use std::sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A `CheckpointDb` that can be shared between threads, where any number of threads can read it
+/// at the same time and writes wait for the reads to finish. Cloning it makes another handle to
+/// the same database.
+///
+/// A thread that panics while holding the lock doesn't make the database unusable for the
+/// others. The changes it made before panicking are kept.
+#[derive(Clone, Debug, Default)]
+pub struct SharedCheckpointDb {
+    checkpoint_db: Arc<RwLock<CheckpointDb>>,
+}
+
+impl SharedCheckpointDb {
+    pub fn new(checkpoint_db: CheckpointDb) -> SharedCheckpointDb {
+        SharedCheckpointDb {
+            checkpoint_db: Arc::new(RwLock::new(checkpoint_db)),
+        }
+    }
+
+    /// Locks the database for reading, blocking while it's locked for writing.
+    pub fn read(&self) -> RwLockReadGuard<'_, CheckpointDb> {
+        self.checkpoint_db
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Locks the database for writing, blocking while it's locked by anyone else.
+    pub fn write(&self) -> RwLockWriteGuard<'_, CheckpointDb> {
+        self.checkpoint_db
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Runs a function with the database locked for reading and returns its result.
+    pub fn with<T, F: FnOnce(&CheckpointDb) -> T>(&self, f: F) -> T {
+        f(&self.read())
+    }
+
+    /// Runs a function with the database locked for writing and returns its result, so that
+    /// several changes are made without anyone reading in between.
+    pub fn update<T, F: FnOnce(&mut CheckpointDb) -> T>(&self, f: F) -> T {
+        f(&mut self.write())
+    }
+
+    /// Returns a copy of the database as it is now, which can be read without holding the lock.
+    pub fn snapshot(&self) -> CheckpointDb {
+        self.read().clone()
+    }
+}
+
+impl From<CheckpointDb> for SharedCheckpointDb {
+    fn from(checkpoint_db: CheckpointDb) -> SharedCheckpointDb {
+        SharedCheckpointDb::new(checkpoint_db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProjectId;
+    use std::thread;
+
+    #[test]
+    /// Writes from several threads all end up in the database, and panics don't lock others out.
+    fn shared_checkpoint_db() {
+        let shared = SharedCheckpointDb::new(CheckpointDb::new());
+        let threads: Vec<_> = (0..4)
+            .map(|n| {
+                let shared = shared.clone();
+                thread::spawn(move || {
+                    shared
+                        .update(|checkpoint_db| {
+                            checkpoint_db.add_checkpoint(n, "", ProjectId::NoId)
+                        })
+                        .unwrap();
+                    shared.with(|checkpoint_db| checkpoint_db.len())
+                })
+            })
+            .collect();
+        for thread in threads {
+            assert!(thread.join().unwrap() >= 1);
+        }
+
+        let panicking = shared.clone();
+        let _ = thread::spawn(move || {
+            let _guard = panicking.write();
+            panic!("poisoning the lock");
+        })
+        .join();
+        assert_eq!(shared.snapshot().len(), 4);
+    }
+}
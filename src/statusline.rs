@@ -0,0 +1,111 @@
+use crate::{CheckpointDb, TrackedDuration};
+
+impl CheckpointDb {
+    /// Fills in a template for status bars like i3status, polybar or tmux. The placeholders
+    /// are:
+    ///
+    /// - `{project}`: the short name of the latest checkpoint's project
+    /// - `{project_long}`: the long name of the latest checkpoint's project
+    /// - `{message}`: the message of the latest checkpoint
+    /// - `{elapsed}`: the hours and minutes since the latest checkpoint
+    /// - `{today_total}`: the hours and minutes tracked today, up to the latest checkpoint
+    ///
+    /// A placeholder can be given a maximum width in characters, like `{message:20}`, and is
+    /// then truncated with an ellipsis if it's longer. `{{` and `}}` write literal braces and
+    /// unknown placeholders are written as they are.
+    pub fn format_status(&self, template: &str, now: i64) -> String {
+        let status = self.current_status(now);
+        let project = status
+            .as_ref()
+            .and_then(|status| self.project_from_project_id(status.project_id));
+        let today_total = self
+            .report(&self.day_range(self.date_of(now)), None)
+            .duration;
+
+        let mut output = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find(['{', '}']) {
+            output.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            if rest.starts_with("{{") || rest.starts_with("}}") {
+                output.push_str(&rest[..1]);
+                rest = &rest[2..];
+                continue;
+            }
+            let Some(end) = rest.find('}').filter(|_| rest.starts_with('{')) else {
+                output.push_str(&rest[..1]);
+                rest = &rest[1..];
+                continue;
+            };
+
+            let placeholder = &rest[1..end];
+            let (name, width) = match placeholder.split_once(':') {
+                Some((name, width)) => (name, width.parse::<usize>().ok()),
+                None => (placeholder, None),
+            };
+            let value = match name {
+                "project" => project.map_or(String::new(), |p| p.short_name.clone()),
+                "project_long" => project.map_or(String::new(), |p| p.long_name.clone()),
+                "message" => status.as_ref().map_or(String::new(), |status| {
+                    status.latest.checkpoint.message.to_string()
+                }),
+                "elapsed" => status.as_ref().map_or(String::new(), |status| {
+                    TrackedDuration(status.elapsed).to_string()
+                }),
+                "today_total" => today_total.to_string(),
+                _ => rest[..=end].to_string(),
+            };
+            output.push_str(&truncate(&value, width));
+            rest = &rest[end + 1..];
+        }
+        output.push_str(rest);
+
+        output
+    }
+}
+
+/// Shortens text to at most `width` characters, ending it with an ellipsis if anything was
+/// cut off. Line breaks are replaced by spaces, since a status line is a single line.
+fn truncate(text: &str, width: Option<usize>) -> String {
+    let text = text.replace(['\n', '\r'], " ");
+    match width {
+        Some(width) if text.chars().count() > width => {
+            let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+            truncated.truncate(truncated.trim_end().len());
+            if width > 0 {
+                truncated.push('…');
+            }
+            truncated
+        }
+        _ => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ProjectId, TimeRange};
+
+    #[test]
+    /// Fills in placeholders, truncates the message and keeps unknown placeholders.
+    fn format_status() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Website", "web").unwrap();
+        let midnight = TimeRange::day(chrono::NaiveDate::from_ymd_opt(2021, 6, 15).unwrap()).start;
+        checkpoint_db
+            .add_checkpoint(midnight + 3600, "", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(midnight + 9000, "Fix the header of the page", project_id)
+            .unwrap();
+
+        assert_eq!(
+            checkpoint_db.format_status(
+                "[{project}] {message:12} +{elapsed} ({today_total}) {{{unknown}}}",
+                midnight + 9000 + 600
+            ),
+            "[web] Fix the hea… +0:10 (1:30) {{unknown}}"
+        );
+    }
+}
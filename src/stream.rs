@@ -0,0 +1,181 @@
+use crate::{Checkpoint, CheckpointDb, CheckpointDbError, Result};
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, Visitor};
+use serde_json::{Map, Value};
+use std::{
+    fmt,
+    io::{Read, Write},
+};
+
+/// Writes a database one checkpoint at a time, so that huge databases can be written, like
+/// when converting from another tracker, without having all of their checkpoints in memory.
+/// The file is written as compact JSON and is read like any other database file.
+#[derive(Debug)]
+pub struct DbWriter<W: Write> {
+    writer: W,
+    latest: Option<i64>,
+}
+
+impl<W: Write> DbWriter<W> {
+    /// Starts writing a database with the projects, clients and settings of `base`. The
+    /// checkpoints of `base` aren't written.
+    pub fn new(mut writer: W, base: &CheckpointDb) -> Result<DbWriter<W>> {
+        let Value::Object(mut fields) = serde_json::to_value(base)? else {
+            unreachable!("databases are serialized as objects");
+        };
+        fields.remove("checkpoints");
+        fields.remove("indexes");
+
+        writer.write_all(b"{")?;
+        for (name, value) in &fields {
+            serde_json::to_writer(&mut writer, name)?;
+            writer.write_all(b":")?;
+            serde_json::to_writer(&mut writer, value)?;
+            writer.write_all(b",")?;
+        }
+        writer.write_all(b"\"checkpoints\":{")?;
+        Ok(DbWriter {
+            writer,
+            latest: None,
+        })
+    }
+
+    /// Writes a checkpoint, failing with `CheckpointDbError::InvalidInput` unless it's later
+    /// than the previous one.
+    pub fn write_checkpoint(&mut self, timestamp: i64, checkpoint: &Checkpoint) -> Result<()> {
+        if let Some(latest) = self.latest {
+            if timestamp <= latest {
+                return Err(CheckpointDbError::InvalidInput(format!(
+                    "the checkpoint at {} isn't after the one at {}",
+                    timestamp, latest
+                )));
+            }
+            self.writer.write_all(b",")?;
+        }
+        write!(self.writer, "\"{}\":", timestamp)?;
+        serde_json::to_writer(&mut self.writer, checkpoint)?;
+        self.latest = Some(timestamp);
+        Ok(())
+    }
+
+    /// Ends the database and returns the writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.write_all(b"}}")?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads a database, passing each checkpoint to `f` as it's read instead of keeping it, so that
+/// huge databases can be gone through with little memory. Returns the rest of the database,
+/// without any checkpoints. Files should be wrapped in a `BufReader`.
+pub fn read_checkpoints<R, F>(reader: R, mut f: F) -> Result<CheckpointDb>
+where
+    R: Read,
+    F: FnMut(i64, Checkpoint),
+{
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let fields = deserializer.deserialize_map(DbVisitor { f: &mut f })?;
+    deserializer.end()?;
+    Ok(serde_json::from_value(Value::Object(fields))?)
+}
+
+/// Visits the top level of a database, keeping every field but the checkpoints.
+struct DbVisitor<'f, F> {
+    f: &'f mut F,
+}
+
+impl<'de, F: FnMut(i64, Checkpoint)> Visitor<'de> for DbVisitor<'_, F> {
+    type Value = Map<String, Value>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a database")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut fields = Map::new();
+        fields.insert("checkpoints".to_string(), Value::Object(Map::new()));
+        while let Some(name) = map.next_key::<String>()? {
+            match name.as_str() {
+                "checkpoints" => map.next_value_seed(CheckpointsSeed { f: &mut *self.f })?,
+                // The indexes refer to the checkpoints that aren't kept.
+                "indexes" => {
+                    map.next_value::<de::IgnoredAny>()?;
+                }
+                _ => {
+                    fields.insert(name, map.next_value()?);
+                }
+            }
+        }
+        Ok(fields)
+    }
+}
+
+/// Passes each checkpoint of a database's checkpoint map to a function.
+struct CheckpointsSeed<'f, F> {
+    f: &'f mut F,
+}
+
+impl<'de, F: FnMut(i64, Checkpoint)> DeserializeSeed<'de> for CheckpointsSeed<'_, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, F: FnMut(i64, Checkpoint)> Visitor<'de> for CheckpointsSeed<'_, F> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("checkpoints by timestamp")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(timestamp) = map.next_key::<String>()? {
+            let timestamp = timestamp.parse().map_err(de::Error::custom)?;
+            (self.f)(timestamp, map.next_value()?);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// A database written one checkpoint at a time is read back the same, whole or streamed.
+    fn stream_checkpoints() {
+        let mut base = CheckpointDb::new();
+        let project_id = base.add_project("Website", "web").unwrap();
+        base.set_currency(Some("EUR")).unwrap();
+
+        let mut writer = DbWriter::new(Vec::new(), &base).unwrap();
+        let mut expected = base.clone();
+        for i in 0..100 {
+            let checkpoint = Checkpoint::builder()
+                .message(&format!("Task {}", i % 7))
+                .project(project_id)
+                .build();
+            writer.write_checkpoint(i * 60, &checkpoint).unwrap();
+            expected.add_checkpoint_full(i * 60, checkpoint).unwrap();
+        }
+        assert!(writer.write_checkpoint(0, &Checkpoint::default()).is_err());
+        let json = writer.finish().unwrap();
+
+        let read: CheckpointDb = serde_json::from_slice(&json).unwrap();
+        assert_eq!(read, expected);
+
+        let mut count = 0;
+        let rest = read_checkpoints(json.as_slice(), |timestamp, checkpoint| {
+            assert_eq!(checkpoint.project_id, project_id);
+            assert_eq!(timestamp, count * 60);
+            count += 1;
+        })
+        .unwrap();
+        assert_eq!(count, 100);
+        assert!(rest.is_empty());
+        assert_eq!(rest.currency(), Some("EUR"));
+        assert_eq!(rest.project_id_from_short_name("web"), Some(project_id));
+    }
+}
@@ -0,0 +1,616 @@
+use crate::{CheckpointDb, CheckpointDbError, ClientId, ExpenseId, Operation, ProjectId, Result};
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    routing::post,
+    Json, Router,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+};
+
+/// What a device sends to a sync server: the operations it made that the server may not have
+/// yet, and how far into the server's log it has already applied.
+///
+/// The operations a device makes are numbered from 0, and `first_sequence` is the number of the
+/// first one sent. A server that already has some of them, because the answer to an earlier
+/// request was lost, skips those, so sending operations again never adds them twice.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SyncRequest {
+    pub cursor: usize,
+    #[serde(default)]
+    pub first_sequence: u64,
+    pub operations: Vec<Operation>,
+}
+
+/// What a sync server answers: every operation in its log after the cursor of the request,
+/// including the ones the device just sent, and the cursor to send next time.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SyncResponse {
+    pub cursor: usize,
+    pub operations: Vec<Operation>,
+}
+
+/// An operation in the log of a sync server.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SyncedOperation {
+    /// The name of the device that made it.
+    pub device: String,
+    pub operation: Operation,
+}
+
+/// Everything a sync server keeps.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncState {
+    /// The name of the device each authentication token belongs to.
+    pub devices: BTreeMap<String, String>,
+    /// Every operation pushed by any device, in the order they were pushed. Every device
+    /// applies them in this order, so they all end up with the same database.
+    pub log: Vec<SyncedOperation>,
+    /// How far into the log each device has synced, by name.
+    pub cursors: BTreeMap<String, usize>,
+    /// How many operations each device has pushed, by name.
+    #[serde(default)]
+    pub sequences: BTreeMap<String, u64>,
+    /// The ids of projects, clients and expenses added by each device that were changed
+    /// because another device added one with the same id first, until the device has synced
+    /// with the new ids.
+    #[serde(default)]
+    id_changes: BTreeMap<String, IdChanges>,
+}
+
+/// The new ids of the projects, clients and expenses added by a device, by the ids it gave
+/// them.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct IdChanges {
+    projects: BTreeMap<u16, u16>,
+    clients: BTreeMap<u16, u16>,
+    expenses: BTreeMap<u32, u32>,
+}
+
+/// The ids of the projects, clients and expenses that have been added by any device.
+#[derive(Default)]
+struct UsedIds {
+    projects: BTreeSet<u16>,
+    clients: BTreeSet<u16>,
+    expenses: BTreeSet<u32>,
+}
+
+/// Keeps a log of the operations of several devices, so that they can share a database without
+/// syncing its file. Each device pushes the operations it made and pulls the log.
+#[derive(Clone, Debug, Default)]
+pub struct SyncServer {
+    state: SyncState,
+    path: Option<PathBuf>,
+}
+
+impl SyncServer {
+    /// Returns a server that only keeps its state in memory.
+    pub fn new() -> SyncServer {
+        SyncServer::default()
+    }
+
+    /// Reads the state at the path, which is saved to after every change, starting empty if
+    /// there is no file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<SyncServer> {
+        let path = path.as_ref().to_path_buf();
+        let state = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => SyncState::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(SyncServer {
+            state,
+            path: Some(path),
+        })
+    }
+
+    pub fn state(&self) -> &SyncState {
+        &self.state
+    }
+
+    /// Lets a device sync with the token, failing if the name or the token is already used.
+    pub fn add_device(&mut self, name: &str, token: &str) -> Result<()> {
+        if self.state.devices.values().any(|device| device == name) {
+            return Err(CheckpointDbError::InvalidInput(format!(
+                "there already is a device named {}",
+                name
+            )));
+        }
+        if self.state.devices.contains_key(token) {
+            return Err(CheckpointDbError::InvalidInput(
+                "the token is already used by another device".to_string(),
+            ));
+        }
+        self.state
+            .devices
+            .insert(token.to_string(), name.to_string());
+        self.save()
+    }
+
+    /// Returns the name of the device with the token, if it's known.
+    pub fn device(&self, token: &str) -> Option<&str> {
+        self.state.devices.get(token).map(String::as_str)
+    }
+
+    /// Adds the operations of the device with the token that aren't in the log yet, and returns
+    /// the log after the cursor of the request. Fails with `CheckpointDbError::InvalidInput` if
+    /// the token is unknown, the cursor is past the end of the log, or operations of the device
+    /// are missing before the first one sent.
+    ///
+    /// Projects, clients and expenses that are added with an id another device has used are
+    /// given a new one, which the device's later operations are changed to use as well.
+    pub fn sync(&mut self, token: &str, request: SyncRequest) -> Result<SyncResponse> {
+        let device = self
+            .device(token)
+            .ok_or_else(|| CheckpointDbError::InvalidInput("unknown token".to_string()))?
+            .to_string();
+        if request.cursor > self.state.log.len() {
+            return Err(CheckpointDbError::InvalidInput(format!(
+                "the cursor {} is too far",
+                request.cursor
+            )));
+        }
+        let sequence = self.state.sequences.get(&device).copied().unwrap_or(0);
+        if request.first_sequence > sequence {
+            return Err(CheckpointDbError::InvalidInput(format!(
+                "operations {} to {} of {} are missing",
+                sequence,
+                request.first_sequence - 1,
+                device
+            )));
+        }
+        if request.first_sequence == sequence {
+            // The device has synced with the ids that were changed, and uses them now.
+            self.state.id_changes.remove(&device);
+        }
+
+        let mut used = self.used_ids();
+        let id_changes = self.state.id_changes.entry(device.clone()).or_default();
+        let already_pushed = (sequence - request.first_sequence) as usize;
+        let new_operations: Vec<Operation> = request
+            .operations
+            .into_iter()
+            .skip(already_pushed)
+            .map(|mut operation| {
+                change_ids(&mut operation, id_changes, &mut used);
+                operation
+            })
+            .collect();
+        self.state
+            .sequences
+            .insert(device.clone(), sequence + new_operations.len() as u64);
+        self.state
+            .log
+            .extend(new_operations.into_iter().map(|operation| SyncedOperation {
+                device: device.clone(),
+                operation,
+            }));
+
+        let operations = self.state.log[request.cursor..]
+            .iter()
+            .map(|synced| synced.operation.clone())
+            .collect();
+        let cursor = self.state.log.len();
+        self.state.cursors.insert(device, cursor);
+        self.save()?;
+
+        Ok(SyncResponse { cursor, operations })
+    }
+
+    fn used_ids(&self) -> UsedIds {
+        let mut used = UsedIds::default();
+        for synced in &self.state.log {
+            match &synced.operation {
+                Operation::AddProject { id, .. } => {
+                    used.projects.insert(*id);
+                }
+                Operation::AddClient { client_id, .. } => {
+                    used.clients.insert(client_id.0);
+                }
+                Operation::AddExpense { expense_id, .. } => {
+                    used.expenses.insert(expense_id.0);
+                }
+                _ => (),
+            }
+        }
+        used
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent().filter(|dir| !dir.exists()) {
+                fs::create_dir_all(dir)?;
+            }
+            fs::write(path, serde_json::to_string(&self.state)?)?;
+        }
+        Ok(())
+    }
+}
+
+/// Gives a project, client or expense added by the operation a new id if another one has used
+/// its id, and changes the ids the operation refers to that have been changed.
+fn change_ids(operation: &mut Operation, id_changes: &mut IdChanges, used: &mut UsedIds) {
+    let project = |project_id: &mut ProjectId, id_changes: &IdChanges| {
+        if let ProjectId::Id(id) = project_id {
+            *id = id_changes.projects.get(id).copied().unwrap_or(*id);
+        }
+    };
+    let client = |client_id: &mut ClientId, id_changes: &IdChanges| {
+        client_id.0 = id_changes
+            .clients
+            .get(&client_id.0)
+            .copied()
+            .unwrap_or(client_id.0);
+    };
+    let expense = |expense_id: &mut ExpenseId, id_changes: &IdChanges| {
+        expense_id.0 = id_changes
+            .expenses
+            .get(&expense_id.0)
+            .copied()
+            .unwrap_or(expense_id.0);
+    };
+
+    match operation {
+        Operation::AddProject { id, .. } => {
+            let new_id = unused(*id, &used.projects, 0..=u16::MAX);
+            id_changes.projects.insert(*id, new_id);
+            used.projects.insert(new_id);
+            *id = new_id;
+        }
+        Operation::AddClient { client_id, .. } => {
+            let new_id = unused(client_id.0, &used.clients, 0..=u16::MAX);
+            id_changes.clients.insert(client_id.0, new_id);
+            used.clients.insert(new_id);
+            client_id.0 = new_id;
+        }
+        Operation::AddExpense {
+            expense_id,
+            expense: added,
+        } => {
+            project(&mut added.project_id, id_changes);
+            let new_id = unused(expense_id.0, &used.expenses, 0..=u32::MAX);
+            id_changes.expenses.insert(expense_id.0, new_id);
+            used.expenses.insert(new_id);
+            expense_id.0 = new_id;
+        }
+        Operation::AddCheckpoint { checkpoint, .. }
+        | Operation::ReplaceCheckpoint { checkpoint, .. } => {
+            project(&mut checkpoint.project_id, id_changes);
+        }
+        Operation::SetProjectClient {
+            project_id,
+            client_id,
+        } => {
+            project(project_id, id_changes);
+            if let Some(client_id) = client_id {
+                client(client_id, id_changes);
+            }
+        }
+        Operation::SetCheckpointProject { project_id, .. }
+        | Operation::RemoveProject { project_id }
+        | Operation::RenameProject { project_id, .. }
+        | Operation::SetProjectRate { project_id, .. }
+        | Operation::SetProjectMinimumBillable { project_id, .. }
+        | Operation::SetProjectBillable { project_id, .. }
+        | Operation::SetProjectCurrency { project_id, .. }
+        | Operation::SetProjectBudget { project_id, .. }
+        | Operation::SetProjectDailyGoal { project_id, .. }
+        | Operation::SetProjectTaxRate { project_id, .. } => project(project_id, id_changes),
+        Operation::RemoveClient { client_id }
+        | Operation::RenameClient { client_id, .. }
+        | Operation::SetClientTaxRate { client_id, .. } => client(client_id, id_changes),
+        Operation::RemoveExpense { expense_id }
+        | Operation::MarkExpenseInvoiced { expense_id, .. } => expense(expense_id, id_changes),
+        Operation::RemoveCheckpoint { .. }
+        | Operation::SetCheckpointMessage { .. }
+        | Operation::SetCheckpointKind { .. }
+        | Operation::SetCheckpointCategory { .. }
+        | Operation::SetCheckpointPomodoro { .. }
+        | Operation::SetCheckpointTags { .. }
+        | Operation::SetCheckpointRate { .. }
+        | Operation::MarkInvoiced { .. }
+        | Operation::AllocateInvoiceNumber { .. }
+        | Operation::MarkInvoicePaid { .. }
+        | Operation::SetSettings { .. }
+        | Operation::SetCurrency { .. }
+        | Operation::SetDailyGoal { .. }
+        | Operation::SetPayRules { .. }
+        | Operation::SetInvoiceNumbering { .. }
+        | Operation::SetTimesheetState { .. } => (),
+    }
+}
+
+/// Returns the id if it isn't used, and otherwise the lowest one in the range that isn't. If
+/// they're all used, the id is kept and adding it fails when it's applied.
+fn unused<T, R>(id: T, used: &BTreeSet<T>, mut range: R) -> T
+where
+    T: Ord + Copy,
+    R: Iterator<Item = T>,
+{
+    if !used.contains(&id) {
+        return id;
+    }
+    range.find(|id| !used.contains(id)).unwrap_or(id)
+}
+
+/// Returns an HTTP API for the server with `POST /sync`, which takes a `SyncRequest` as JSON
+/// along with the device's token as `Authorization: Bearer <token>`, and returns a
+/// `SyncResponse`.
+pub fn sync_router(server: Arc<Mutex<SyncServer>>) -> Router {
+    Router::new()
+        .route("/sync", post(post_sync))
+        .with_state(server)
+}
+
+async fn post_sync(
+    State(server): State<Arc<Mutex<SyncServer>>>,
+    headers: HeaderMap,
+    Json(request): Json<SyncRequest>,
+) -> Result<Json<SyncResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let error = |status: StatusCode, message: String| {
+        (status, Json(serde_json::json!({ "error": message })))
+    };
+    let mut server = server.lock().unwrap_or_else(PoisonError::into_inner);
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .filter(|token| server.device(token).is_some())
+        .ok_or_else(|| error(StatusCode::UNAUTHORIZED, "unknown token".to_string()))?
+        .to_string();
+
+    match server.sync(&token, request) {
+        Ok(response) => Ok(Json(response)),
+        Err(e @ CheckpointDbError::Io(_)) => {
+            Err(error(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+        }
+        Err(e) => Err(error(StatusCode::BAD_REQUEST, e.to_string())),
+    }
+}
+
+/// What a sync did.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SyncSummary {
+    pub pushed: usize,
+    pub pulled: usize,
+    /// The operations that couldn't be applied in the order of the server's log, like removing
+    /// a checkpoint that another device has already removed.
+    pub conflicts: usize,
+}
+
+/// A device's connection to a sync server. It's meant to be saved along with the database, so
+/// that operations made offline are pushed on the next sync.
+///
+/// The client keeps the database as the server's log makes it, and applies the log to it again
+/// on every sync, with the operations made here in the order the server put them in. Changes to
+/// the database have to be made through `apply`, so that they aren't lost.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SyncClient {
+    /// The URL of the server's API, without `/sync`.
+    pub url: String,
+    pub token: String,
+    cursor: usize,
+    /// How many of the operations made here the server has.
+    #[serde(default)]
+    pushed: u64,
+    /// The operations made here that the server may not have yet.
+    pending: Vec<Operation>,
+    /// The database as the server's log up to the cursor makes it.
+    #[serde(default)]
+    synced: CheckpointDb,
+}
+
+impl SyncClient {
+    pub fn new(url: &str, token: &str) -> SyncClient {
+        SyncClient {
+            url: url.to_string(),
+            token: token.to_string(),
+            cursor: 0,
+            pushed: 0,
+            pending: Vec::new(),
+            synced: CheckpointDb::new(),
+        }
+    }
+
+    /// Applies an operation to the database, and keeps it to be pushed on the next sync.
+    pub fn apply(&mut self, checkpoint_db: &mut CheckpointDb, operation: Operation) -> Result<()> {
+        checkpoint_db.apply(operation.clone())?;
+        self.pending.push(operation);
+        Ok(())
+    }
+
+    /// Returns the number of operations waiting to be pushed.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Pushes the pending operations to the server and applies its log to the database.
+    pub fn sync(&mut self, checkpoint_db: &mut CheckpointDb) -> Result<SyncSummary> {
+        let url = format!("{}/sync", self.url.trim_end_matches('/'));
+        let token = format!("Bearer {}", self.token);
+        self.sync_with(checkpoint_db, |request| {
+            let response = ureq::post(&url)
+                .set("Authorization", &token)
+                .set("Content-Type", "application/json")
+                .send_string(&serde_json::to_string(request)?)?;
+            Ok(serde_json::from_str(&response.into_string()?)?)
+        })
+    }
+
+    /// Like `sync`, but sends the request with the function instead of over HTTP.
+    ///
+    /// Fails with `CheckpointDbError::InvalidInput`, without sending anything, if the database
+    /// has been changed other than through `apply`. If sending fails, nothing is changed, and
+    /// the pending operations are sent again on the next sync.
+    pub fn sync_with<F>(&mut self, checkpoint_db: &mut CheckpointDb, send: F) -> Result<SyncSummary>
+    where
+        F: FnOnce(&SyncRequest) -> Result<SyncResponse>,
+    {
+        let mut expected = self.synced.clone();
+        let applies = self
+            .pending
+            .iter()
+            .try_for_each(|operation| expected.apply(operation.clone()));
+        if applies.is_err() || expected != *checkpoint_db {
+            return Err(CheckpointDbError::InvalidInput(
+                "the database has changes that weren't made through the sync client".to_string(),
+            ));
+        }
+
+        let request = SyncRequest {
+            cursor: self.cursor,
+            first_sequence: self.pushed,
+            operations: self.pending.clone(),
+        };
+        let response = send(&request)?;
+
+        let mut summary = SyncSummary {
+            pushed: self.pending.len(),
+            pulled: response.operations.len().saturating_sub(self.pending.len()),
+            conflicts: 0,
+        };
+        let mut synced = self.synced.clone();
+        for operation in response.operations {
+            if synced.apply(operation).is_err() {
+                summary.conflicts += 1;
+            }
+        }
+        checkpoint_db.replace_data(synced.clone());
+        self.synced = synced;
+        self.pushed += self.pending.len() as u64;
+        self.pending.clear();
+        self.cursor = response.cursor;
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Checkpoint, CheckpointId, Timestamp};
+
+    #[test]
+    /// Operations made on one device end up on the other, and unknown tokens are refused.
+    fn sync_two_devices() {
+        let mut server = SyncServer::new();
+        server.add_device("laptop", "secret-1").unwrap();
+        server.add_device("phone", "secret-2").unwrap();
+        assert!(server.add_device("tablet", "secret-2").is_err());
+        let add = |timestamp: i64, message: &str| Operation::AddCheckpoint {
+            timestamp: Timestamp(timestamp),
+            checkpoint: Checkpoint::builder().message(message).build(),
+        };
+
+        let (mut laptop_db, mut phone_db) = (CheckpointDb::new(), CheckpointDb::new());
+        let mut laptop = SyncClient::new("http://localhost", "secret-1");
+        let mut phone = SyncClient::new("http://localhost", "secret-2");
+        laptop.apply(&mut laptop_db, add(1000, "Styling")).unwrap();
+        phone.apply(&mut phone_db, add(2000, "Email")).unwrap();
+
+        let summary = laptop
+            .sync_with(&mut laptop_db, |r| server.sync("secret-1", r.clone()))
+            .unwrap();
+        assert_eq!((summary.pushed, summary.pulled), (1, 0));
+        let summary = phone
+            .sync_with(&mut phone_db, |r| server.sync("secret-2", r.clone()))
+            .unwrap();
+        assert_eq!((summary.pushed, summary.pulled), (1, 1));
+        let summary = laptop
+            .sync_with(&mut laptop_db, |r| server.sync("secret-1", r.clone()))
+            .unwrap();
+        assert_eq!((summary.pushed, summary.pulled), (0, 1));
+
+        assert_eq!(laptop_db, phone_db);
+        assert_eq!(laptop_db.len(), 2);
+        assert_eq!(server.state().cursors["phone"], 2);
+        assert!(server
+            .sync(
+                "guess",
+                SyncRequest {
+                    cursor: 0,
+                    first_sequence: 0,
+                    operations: Vec::new()
+                }
+            )
+            .is_err());
+    }
+
+    #[test]
+    /// Devices end up with the same database when they add projects with the same id and
+    /// checkpoints at the same time, and operations sent again after a lost answer aren't added
+    /// twice.
+    fn sync_converges() {
+        let mut server = SyncServer::new();
+        server.add_device("laptop", "secret-1").unwrap();
+        server.add_device("phone", "secret-2").unwrap();
+        let add_project = |id: u16, name: &str| Operation::AddProject {
+            id,
+            long_name: name.to_string(),
+            short_name: name.to_lowercase(),
+        };
+        let add = |timestamp: i64, message: &str, project_id: u16| Operation::AddCheckpoint {
+            timestamp: Timestamp(timestamp),
+            checkpoint: Checkpoint::builder()
+                .message(message)
+                .project(ProjectId::Id(project_id))
+                .build(),
+        };
+
+        let (mut laptop_db, mut phone_db) = (CheckpointDb::new(), CheckpointDb::new());
+        let mut laptop = SyncClient::new("http://localhost", "secret-1");
+        let mut phone = SyncClient::new("http://localhost", "secret-2");
+        laptop.apply(&mut laptop_db, add_project(0, "Web")).unwrap();
+        laptop
+            .apply(&mut laptop_db, add(1000, "Styling", 0))
+            .unwrap();
+        phone.apply(&mut phone_db, add_project(0, "App")).unwrap();
+        phone.apply(&mut phone_db, add(1000, "Email", 0)).unwrap();
+
+        laptop
+            .sync_with(&mut laptop_db, |r| server.sync("secret-1", r.clone()))
+            .unwrap();
+        // The answer to the phone's first sync is lost, so it sends its operations again along
+        // with a new one.
+        assert!(phone
+            .sync_with(&mut phone_db, |r| {
+                server.sync("secret-2", r.clone())?;
+                Err(CheckpointDbError::InvalidInput("lost".to_string()))
+            })
+            .is_err());
+        phone.apply(&mut phone_db, add(2000, "Tests", 0)).unwrap();
+        let summary = phone
+            .sync_with(&mut phone_db, |r| server.sync("secret-2", r.clone()))
+            .unwrap();
+        assert_eq!(summary.conflicts, 1);
+        laptop
+            .sync_with(&mut laptop_db, |r| server.sync("secret-1", r.clone()))
+            .unwrap();
+        assert_eq!(server.state().log.len(), 5);
+        assert_eq!(laptop_db, phone_db);
+
+        // The phone's project was given the id 1, and so were its checkpoints, including ones
+        // it makes with the id 0 before it has synced.
+        let phone_project = laptop_db.project_id_from_short_name("app").unwrap();
+        assert_eq!(phone_project, ProjectId::Id(1));
+        let tests = laptop_db
+            .get_checkpoint(&CheckpointId::Timestamp(2000))
+            .unwrap();
+        assert_eq!(tests.project_id, phone_project);
+        let styling = laptop_db
+            .get_checkpoint(&CheckpointId::Timestamp(1000))
+            .unwrap();
+        assert_eq!(&*styling.message, "Styling");
+
+        // Changes made without the client would be lost, so they're refused.
+        laptop_db
+            .add_checkpoint(3000, "Offline", ProjectId::NoId)
+            .unwrap();
+        assert!(laptop
+            .sync_with(&mut laptop_db, |r| server.sync("secret-1", r.clone()))
+            .is_err());
+    }
+}
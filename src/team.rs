@@ -0,0 +1,171 @@
+#[cfg(feature = "json")]
+use crate::Result;
+use crate::{CheckpointDb, TimeRange, TrackedDuration};
+use std::collections::BTreeMap;
+#[cfg(feature = "json")]
+use std::path::Path;
+
+/// The databases of the members of a team, who each track their time on their own. Projects
+/// and clients are matched between the databases by short name and by name.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Team {
+    members: BTreeMap<String, CheckpointDb>,
+}
+
+/// The time of a project over all members of a team.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct TeamProjectTotal {
+    /// The short name of the project, or `None` for time without a project.
+    pub short_name: Option<String>,
+    /// The long name of the project in the first database that has it.
+    pub long_name: Option<String>,
+    /// The name of the project's client in the first database that has the project.
+    pub client: Option<String>,
+    pub duration: TrackedDuration,
+    /// The time of each member on the project.
+    pub members: BTreeMap<String, TrackedDuration>,
+}
+
+/// The time of a client's projects over all members of a team.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct TeamClientTotal {
+    /// The name of the client, or `None` for time on projects without a client.
+    pub client: Option<String>,
+    pub duration: TrackedDuration,
+    pub members: BTreeMap<String, TrackedDuration>,
+}
+
+/// The time tracked by a team in a `TimeRange`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct TeamReport {
+    pub range: TimeRange,
+    /// Sorted by short name, with the time without a project first.
+    pub projects: Vec<TeamProjectTotal>,
+    /// Sorted by name, with the time without a client first.
+    pub clients: Vec<TeamClientTotal>,
+    /// The total time of each member.
+    pub members: BTreeMap<String, TrackedDuration>,
+    pub duration: TrackedDuration,
+}
+
+impl Team {
+    pub fn new() -> Team {
+        Team::default()
+    }
+
+    /// Adds a member with their database, replacing any member with the same name.
+    pub fn add_member(&mut self, name: &str, checkpoint_db: CheckpointDb) {
+        self.members.insert(name.to_string(), checkpoint_db);
+    }
+
+    /// Reads the database of a member from a file.
+    #[cfg(feature = "json")]
+    pub fn read_member(&mut self, name: &str, path: &Path) -> Result<()> {
+        self.add_member(name, CheckpointDb::read(path)?);
+        Ok(())
+    }
+
+    /// Returns the names of the members in alphabetical order.
+    pub fn members(&self) -> impl Iterator<Item = &str> {
+        self.members.keys().map(String::as_str)
+    }
+
+    /// Returns the time of the team in the range, with each member's durations rounded
+    /// according to the rounding settings of their database.
+    pub fn report(&self, range: &TimeRange) -> TeamReport {
+        let mut projects: BTreeMap<Option<String>, TeamProjectTotal> = BTreeMap::new();
+        let mut clients: BTreeMap<Option<String>, TeamClientTotal> = BTreeMap::new();
+        let mut members = BTreeMap::new();
+
+        for (member, checkpoint_db) in &self.members {
+            let report = checkpoint_db.report(range, checkpoint_db.rounding());
+            members.insert(member.clone(), report.duration);
+
+            for total in &report.projects {
+                let project = checkpoint_db.project_from_project_id(total.project_id);
+                let client = project
+                    .and_then(|project| project.client_id)
+                    .and_then(|client_id| checkpoint_db.client_from_client_id(client_id))
+                    .map(|client| client.name.clone());
+                let short_name = project.map(|project| project.short_name.clone());
+
+                let project_total =
+                    projects
+                        .entry(short_name.clone())
+                        .or_insert_with(|| TeamProjectTotal {
+                            short_name,
+                            long_name: project.map(|project| project.long_name.clone()),
+                            client: client.clone(),
+                            duration: TrackedDuration::default(),
+                            members: BTreeMap::new(),
+                        });
+                project_total.duration += total.duration;
+                *project_total.members.entry(member.clone()).or_default() += total.duration;
+
+                let client_total =
+                    clients
+                        .entry(client.clone())
+                        .or_insert_with(|| TeamClientTotal {
+                            client,
+                            duration: TrackedDuration::default(),
+                            members: BTreeMap::new(),
+                        });
+                client_total.duration += total.duration;
+                *client_total.members.entry(member.clone()).or_default() += total.duration;
+            }
+        }
+
+        TeamReport {
+            range: *range,
+            projects: projects.into_values().collect(),
+            clients: clients.into_values().collect(),
+            duration: members.values().copied().sum(),
+            members,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProjectId;
+
+    #[test]
+    /// Projects with the same short name are added up over members, along with their clients.
+    fn team_report() {
+        let member = |project_first: bool, seconds: i64| {
+            let mut checkpoint_db = CheckpointDb::new();
+            if !project_first {
+                checkpoint_db.add_project("Intranet", "intra").unwrap();
+            }
+            let project_id = checkpoint_db.add_project("Website", "web").unwrap();
+            let client_id = checkpoint_db.add_client("Acme").unwrap();
+            checkpoint_db
+                .set_project_client(project_id, Some(client_id))
+                .unwrap();
+            checkpoint_db
+                .add_checkpoint(1000, "Start", ProjectId::NoId)
+                .unwrap();
+            checkpoint_db
+                .add_checkpoint(1000 + seconds, "Styling", project_id)
+                .unwrap();
+            checkpoint_db
+        };
+        let mut team = Team::new();
+        team.add_member("alex", member(true, 3600));
+        team.add_member("sam", member(false, 1800));
+
+        let report = team.report(&TimeRange::new(0, 10000));
+        assert_eq!(report.duration.seconds(), 5400);
+        assert_eq!(report.projects.len(), 2);
+        let web = &report.projects[1];
+        assert_eq!(web.short_name.as_deref(), Some("web"));
+        assert_eq!(web.members["sam"].seconds(), 1800);
+        assert_eq!(report.clients[1].client.as_deref(), Some("Acme"));
+        assert_eq!(report.clients[1].duration.seconds(), 5400);
+        assert_eq!(report.members["alex"].seconds(), 3600);
+    }
+}
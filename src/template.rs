@@ -0,0 +1,137 @@
+use crate::{
+    render::NO_PROJECT_NAME, CheckpointDb, CheckpointDbError, ProjectId, ReportRenderer, Result,
+    TrackedDuration,
+};
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, HelperResult, Output, RenderContext, RenderErrorReason,
+};
+use serde::Serialize;
+
+/// Renders any serializable report structure with a user supplied Handlebars template.
+///
+/// The fields of the report are available at the root of the template. Two helpers are
+/// registered as well: `project_name` takes a `ProjectId` and writes the long name of its
+/// project, and `duration` takes seconds and writes them as hours and minutes. For example:
+///
+/// ```text
+/// {{#each projects}}{{project_name project_id}}: {{duration duration}}
+/// {{/each}}
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HandlebarsRenderer {
+    pub template: String,
+    /// Whether values are escaped for HTML, which should be turned off for other formats.
+    pub escape_html: bool,
+}
+
+impl HandlebarsRenderer {
+    pub fn new(template: &str) -> HandlebarsRenderer {
+        HandlebarsRenderer {
+            template: template.to_string(),
+            escape_html: true,
+        }
+    }
+}
+
+impl<R: Serialize> ReportRenderer<R> for HandlebarsRenderer {
+    fn render(&self, checkpoint_db: &CheckpointDb, report: &R) -> Result<String> {
+        let mut handlebars = Handlebars::new();
+        handlebars.set_strict_mode(true);
+        if !self.escape_html {
+            handlebars.register_escape_fn(handlebars::no_escape);
+        }
+        handlebars.register_helper("project_name", Box::new(ProjectNameHelper(checkpoint_db)));
+        handlebars.register_helper("duration", Box::new(duration_helper));
+
+        handlebars
+            .render_template(&self.template, report)
+            .map_err(CheckpointDbError::Template)
+    }
+}
+
+struct ProjectNameHelper<'a>(&'a CheckpointDb);
+
+impl HelperDef for ProjectNameHelper<'_> {
+    fn call<'reg: 'rc, 'rc>(
+        &self,
+        helper: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        _: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+        out: &mut dyn Output,
+    ) -> HelperResult {
+        let value = helper
+            .param(0)
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex("project_name", 0))?
+            .value();
+        let project_id: ProjectId = serde_json::from_value(value.clone()).map_err(|_| {
+            RenderErrorReason::ParamTypeMismatchForName(
+                "project_name",
+                "0".to_string(),
+                "ProjectId".to_string(),
+            )
+        })?;
+
+        let name = self
+            .0
+            .project_from_project_id(project_id)
+            .map_or(NO_PROJECT_NAME, |project| &project.long_name);
+        out.write(name)?;
+        Ok(())
+    }
+}
+
+fn duration_helper(
+    helper: &Helper,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let seconds = helper
+        .param(0)
+        .ok_or(RenderErrorReason::ParamNotFoundForIndex("duration", 0))?
+        .value()
+        .as_i64()
+        .ok_or_else(|| {
+            RenderErrorReason::ParamTypeMismatchForName(
+                "duration",
+                "0".to_string(),
+                "i64".to_string(),
+            )
+        })?;
+    out.write(&TrackedDuration(seconds).to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TimeRange;
+
+    #[test]
+    /// Renders a report with the helpers, without escaping.
+    fn render_template() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Q&A", "qa").unwrap();
+        checkpoint_db
+            .add_checkpoint(1000, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(6400, "Answer", project_id)
+            .unwrap();
+        let report = checkpoint_db.report(&TimeRange::new(0, 10000), None);
+
+        let mut renderer = HandlebarsRenderer::new(
+            "{{#each projects}}{{project_name project_id}}: {{duration duration}}\n{{/each}}",
+        );
+        renderer.escape_html = false;
+        assert_eq!(
+            renderer.render(&checkpoint_db, &report).unwrap(),
+            "No project: 0:00\nQ&A: 1:30\n"
+        );
+
+        renderer.template = "{{missing}}".to_string();
+        assert!(renderer.render(&checkpoint_db, &report).is_err());
+    }
+}
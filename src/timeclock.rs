@@ -0,0 +1,87 @@
+use crate::{CheckpointDb, CheckpointKind, Result, TimeRange};
+use chrono::prelude::*;
+use std::io::{self, Write};
+
+/// The account of time without a project.
+const NO_PROJECT_ACCOUNT: &str = "no project";
+
+impl CheckpointDb {
+    /// Writes the work in the given `TimeRange` in the timeclock format read by hledger and
+    /// ledger. Each checkpoint becomes a clock-in at the checkpoint before it and a clock-out at
+    /// the checkpoint itself, on an account named after the project's long name, with the
+    /// message as description.
+    pub fn export_timeclock<W: Write>(&self, mut writer: W, range: &TimeRange) -> Result<()> {
+        for entry in self.entries_in(range) {
+            if entry.duration == 0 || entry.checkpoint.kind == CheckpointKind::Break {
+                continue;
+            }
+
+            let account = self
+                .project_from_project_id(entry.checkpoint.project_id)
+                .map_or(NO_PROJECT_ACCOUNT, |project| &project.long_name);
+            // Two spaces end the account name, and a line break would end the entry.
+            let account = collapse_whitespace(account);
+            let description = collapse_whitespace(&entry.checkpoint.message);
+
+            let clock_in = timeclock_time(entry.timestamp - entry.duration)?;
+            if description.is_empty() {
+                writeln!(writer, "i {} {}", clock_in, account)?;
+            } else {
+                writeln!(writer, "i {} {}  {}", clock_in, account, description)?;
+            }
+            writeln!(writer, "o {}", timeclock_time(entry.timestamp)?)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn timeclock_time(timestamp: i64) -> io::Result<String> {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "timestamp out of range"))
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProjectId;
+
+    #[test]
+    /// Clocks in and out for each checkpoint, with the project as account.
+    fn export_timeclock() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Client  website", "web").unwrap();
+        let start = Local
+            .with_ymd_and_hms(2021, 6, 15, 9, 0, 0)
+            .unwrap()
+            .timestamp();
+        checkpoint_db
+            .add_checkpoint(start, "Start", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 5400, "Fix the\nheader", project_id)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(start + 7200, "", ProjectId::NoId)
+            .unwrap();
+
+        let mut output = Vec::new();
+        checkpoint_db
+            .export_timeclock(&mut output, &TimeRange::new(0, i64::MAX))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "i 2021-06-15 09:00:00 Client website  Fix the header\n\
+             o 2021-06-15 10:30:00\n\
+             i 2021-06-15 10:30:00 no project\n\
+             o 2021-06-15 11:00:00\n"
+        );
+    }
+}
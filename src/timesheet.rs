@@ -0,0 +1,244 @@
+use crate::{CheckpointDb, CheckpointDbError, Operation, Result, TimeRange};
+use chrono::NaiveDate;
+
+/// Where the timesheet of a week is in its review.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum TimesheetState {
+    /// Still being worked on. Weeks that have never been submitted are drafts.
+    #[default]
+    Draft,
+    /// Waiting for review. Its checkpoints can't be changed.
+    Submitted,
+    /// Accepted by the reviewer. Its checkpoints can't be changed.
+    Approved,
+    /// Sent back by the reviewer, to be corrected and submitted again.
+    Rejected,
+}
+
+impl TimesheetState {
+    /// Returns whether the checkpoints of the timesheet can't be changed.
+    pub fn is_locked(self) -> bool {
+        matches!(self, TimesheetState::Submitted | TimesheetState::Approved)
+    }
+}
+
+/// The review of a week's time, kept in the database by the timestamp the week starts at.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Timesheet {
+    /// The timestamp the week ends at, exclusive.
+    pub end: i64,
+    pub state: TimesheetState,
+    /// Who approved or rejected the timesheet.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub reviewer: Option<String>,
+    /// A note left with the last change, like the reason for a rejection.
+    #[cfg_attr(
+        feature = "json",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub comment: Option<String>,
+}
+
+impl CheckpointDb {
+    /// Returns the timesheet of the week that contains the date, if it has ever been submitted.
+    pub fn timesheet(&self, date: NaiveDate) -> Option<&Timesheet> {
+        self.timesheets.get(&self.week_range(date).start)
+    }
+
+    /// Returns the state of the timesheet of the week that contains the date.
+    pub fn timesheet_state(&self, date: NaiveDate) -> TimesheetState {
+        self.timesheet(date)
+            .map_or(TimesheetState::Draft, |timesheet| timesheet.state)
+    }
+
+    /// Returns every timesheet that has been submitted, with the range of its week, in order.
+    pub fn timesheets(&self) -> impl Iterator<Item = (TimeRange, &Timesheet)> {
+        self.timesheets
+            .iter()
+            .map(|(start, timesheet)| (TimeRange::new(*start, timesheet.end), timesheet))
+    }
+
+    /// Submits the week that contains the date for review, which locks its checkpoints.
+    pub fn submit_timesheet(&mut self, date: NaiveDate, comment: Option<&str>) -> Result<()> {
+        self.set_timesheet_state(date, TimesheetState::Submitted, None, comment)
+    }
+
+    /// Takes back the submission of the week that contains the date, before it's reviewed.
+    pub fn withdraw_timesheet(&mut self, date: NaiveDate) -> Result<()> {
+        self.set_timesheet_state(date, TimesheetState::Draft, None, None)
+    }
+
+    /// Approves the submitted week that contains the date, which keeps it locked.
+    pub fn approve_timesheet(
+        &mut self,
+        date: NaiveDate,
+        reviewer: &str,
+        comment: Option<&str>,
+    ) -> Result<()> {
+        self.set_timesheet_state(date, TimesheetState::Approved, Some(reviewer), comment)
+    }
+
+    /// Rejects the submitted week that contains the date, which unlocks it.
+    pub fn reject_timesheet(
+        &mut self,
+        date: NaiveDate,
+        reviewer: &str,
+        comment: Option<&str>,
+    ) -> Result<()> {
+        self.set_timesheet_state(date, TimesheetState::Rejected, Some(reviewer), comment)
+    }
+
+    fn set_timesheet_state(
+        &mut self,
+        date: NaiveDate,
+        state: TimesheetState,
+        reviewer: Option<&str>,
+        comment: Option<&str>,
+    ) -> Result<()> {
+        self.apply(Operation::SetTimesheetState {
+            range: self.week_range(date),
+            state,
+            reviewer: reviewer.map(str::to_string),
+            comment: comment.map(str::to_string),
+        })
+    }
+
+    /// Fails with `CheckpointDbError::InvalidInput` unless the timesheet of the range can go to
+    /// the state. Drafts and rejected timesheets can be submitted, submitted ones can be
+    /// withdrawn, or approved or rejected by a reviewer, and approved ones are final.
+    pub(crate) fn check_timesheet_state(
+        &self,
+        range: &TimeRange,
+        state: TimesheetState,
+        reviewer: Option<&str>,
+    ) -> Result<()> {
+        let error = |message: String| Err(CheckpointDbError::InvalidInput(message));
+        if let Some((start, _)) = self.timesheets.iter().find(|(start, timesheet)| {
+            **start != range.start && **start < range.end && range.start < timesheet.end
+        }) {
+            return error(format!(
+                "the timesheet overlaps the one starting at {}",
+                start
+            ));
+        }
+
+        let current = self
+            .timesheets
+            .get(&range.start)
+            .map_or(TimesheetState::Draft, |timesheet| timesheet.state);
+        let allowed = match state {
+            TimesheetState::Draft => current == TimesheetState::Submitted,
+            TimesheetState::Submitted => {
+                matches!(current, TimesheetState::Draft | TimesheetState::Rejected)
+            }
+            TimesheetState::Approved | TimesheetState::Rejected => {
+                current == TimesheetState::Submitted
+            }
+        };
+        if !allowed {
+            return error(format!(
+                "a timesheet can't go from {:?} to {:?}",
+                current, state
+            ));
+        }
+        if matches!(state, TimesheetState::Approved | TimesheetState::Rejected)
+            && reviewer.is_none_or(str::is_empty)
+        {
+            return error("a reviewed timesheet needs a reviewer".to_string());
+        }
+        Ok(())
+    }
+
+    /// Fails with `CheckpointDbError::TimesheetLocked` if the timestamp is in a submitted or
+    /// approved timesheet.
+    pub(crate) fn check_unlocked(&self, timestamp: i64) -> Result<()> {
+        match self.timesheets.range(..=timestamp).next_back() {
+            Some((start, timesheet))
+                if timestamp < timesheet.end && timesheet.state.is_locked() =>
+            {
+                Err(CheckpointDbError::TimesheetLocked {
+                    range: TimeRange::new(*start, timesheet.end),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{report::local_midnight, CheckpointId, ProjectId};
+
+    #[test]
+    /// Submitted weeks are locked until they are rejected, and approved ones stay locked.
+    fn timesheet_review() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let date = NaiveDate::from_ymd_opt(2021, 6, 16).unwrap();
+        let noon = local_midnight(date) + 12 * 3600;
+        let project_id = checkpoint_db.add_project("Website", "web").unwrap();
+        let client_id = checkpoint_db.add_client("Acme").unwrap();
+        checkpoint_db
+            .add_checkpoint(noon, "Styling", project_id)
+            .unwrap();
+        let before = noon - 7 * 86400;
+        checkpoint_db
+            .add_checkpoint(before, "Planning", ProjectId::NoId)
+            .unwrap();
+
+        assert!(checkpoint_db.approve_timesheet(date, "kim", None).is_err());
+        checkpoint_db.submit_timesheet(date, None).unwrap();
+        assert!(matches!(
+            checkpoint_db.add_checkpoint(noon + 60, "Email", ProjectId::NoId),
+            Err(CheckpointDbError::TimesheetLocked { .. })
+        ));
+        // Removing the project or billing it to a client would change the locked checkpoint.
+        assert!(matches!(
+            checkpoint_db.remove_project(project_id),
+            Err(CheckpointDbError::TimesheetLocked { .. })
+        ));
+        assert!(matches!(
+            checkpoint_db.set_project_client(project_id, Some(client_id)),
+            Err(CheckpointDbError::TimesheetLocked { .. })
+        ));
+        assert!(checkpoint_db.reject_timesheet(date, "", None).is_err());
+        checkpoint_db
+            .reject_timesheet(date, "kim", Some("Missing Friday"))
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(noon + 60, "Email", ProjectId::NoId)
+            .unwrap();
+
+        checkpoint_db.submit_timesheet(date, None).unwrap();
+        checkpoint_db.approve_timesheet(date, "kim", None).unwrap();
+        assert!(checkpoint_db.withdraw_timesheet(date).is_err());
+        assert!(checkpoint_db
+            .remove_checkpoint(&CheckpointId::Position(1))
+            .is_none());
+        let timesheet = checkpoint_db.timesheet(date).unwrap();
+        assert_eq!(timesheet.state, TimesheetState::Approved);
+        assert_eq!(timesheet.reviewer.as_deref(), Some("kim"));
+        assert_eq!(checkpoint_db.timesheets().count(), 1);
+        assert_eq!(checkpoint_db.len(), 3);
+
+        // Adding or removing a checkpoint before the week would change the duration of its
+        // first checkpoint.
+        assert!(matches!(
+            checkpoint_db.add_checkpoint(before + 60, "Email", ProjectId::NoId),
+            Err(CheckpointDbError::TimesheetLocked { .. })
+        ));
+        assert!(checkpoint_db
+            .remove_checkpoint(&CheckpointId::Timestamp(before))
+            .is_none());
+        checkpoint_db
+            .add_checkpoint(noon + 7 * 86400, "Email", ProjectId::NoId)
+            .unwrap();
+    }
+}
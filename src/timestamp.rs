@@ -0,0 +1,125 @@
+use crate::CheckpointId;
+use chrono::prelude::*;
+use std::{fmt, ops::Sub};
+
+/// A point in time in seconds since the Unix epoch, which is how checkpoints are keyed. It can be
+/// made from raw seconds or from any chrono `DateTime`, so that functions taking an
+/// `impl Into<Timestamp>` accept either without mixing up units or time zones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "json", serde(transparent))]
+pub struct Timestamp(pub i64);
+
+impl Timestamp {
+    pub fn now() -> Timestamp {
+        Timestamp(Utc::now().timestamp())
+    }
+
+    pub fn seconds(self) -> i64 {
+        self.0
+    }
+
+    /// Returns the time in UTC, or `None` if it's outside of the range chrono supports.
+    pub fn to_utc(self) -> Option<DateTime<Utc>> {
+        Utc.timestamp_opt(self.0, 0).single()
+    }
+
+    /// Returns the time in the local time zone, or `None` if it's outside of the range chrono
+    /// supports.
+    pub fn to_local(self) -> Option<DateTime<Local>> {
+        Local.timestamp_opt(self.0, 0).single()
+    }
+}
+
+impl From<i64> for Timestamp {
+    fn from(seconds: i64) -> Timestamp {
+        Timestamp(seconds)
+    }
+}
+
+impl From<Timestamp> for i64 {
+    fn from(timestamp: Timestamp) -> i64 {
+        timestamp.0
+    }
+}
+
+impl<Tz: TimeZone> From<DateTime<Tz>> for Timestamp {
+    fn from(time: DateTime<Tz>) -> Timestamp {
+        Timestamp(time.timestamp())
+    }
+}
+
+impl<Tz: TimeZone> From<&DateTime<Tz>> for Timestamp {
+    fn from(time: &DateTime<Tz>) -> Timestamp {
+        Timestamp(time.timestamp())
+    }
+}
+
+/// Lets functions taking an `impl Into<Timestamp>` accept times from the `time` crate.
+#[cfg(feature = "time")]
+impl From<time::OffsetDateTime> for Timestamp {
+    fn from(time: time::OffsetDateTime) -> Timestamp {
+        Timestamp(time.unix_timestamp())
+    }
+}
+
+#[cfg(feature = "time")]
+impl Timestamp {
+    /// Returns the time in UTC as a `time` crate `OffsetDateTime`, or `None` if it's outside of
+    /// the range it supports.
+    pub fn to_offset_date_time(self) -> Option<time::OffsetDateTime> {
+        time::OffsetDateTime::from_unix_timestamp(self.0).ok()
+    }
+}
+
+impl From<Timestamp> for CheckpointId {
+    fn from(timestamp: Timestamp) -> CheckpointId {
+        CheckpointId::Timestamp(timestamp.0)
+    }
+}
+
+/// The seconds between two timestamps.
+impl Sub for Timestamp {
+    type Output = i64;
+
+    fn sub(self, other: Timestamp) -> i64 {
+        self.0 - other.0
+    }
+}
+
+/// Writes the time in RFC 3339 in UTC, or the raw seconds if it's out of range.
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_utc() {
+            Some(time) => write!(f, "{}", time.to_rfc3339_opts(SecondsFormat::Secs, true)),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// Times in any time zone become the same timestamp.
+    fn from_date_time() {
+        let utc = Utc.with_ymd_and_hms(2021, 6, 15, 9, 0, 0).unwrap();
+        let offset = utc.with_timezone(&FixedOffset::east_opt(7200).unwrap());
+        assert_eq!(Timestamp::from(utc), Timestamp::from(&offset));
+        assert_eq!(Timestamp::from(utc).to_utc(), Some(utc));
+        assert_eq!(Timestamp::from(utc).to_string(), "2021-06-15T09:00:00Z");
+    }
+
+    #[test]
+    #[cfg(feature = "time")]
+    /// Times from the `time` crate become the same timestamp as chrono times.
+    fn from_offset_date_time() {
+        let utc = Utc.with_ymd_and_hms(2021, 6, 15, 9, 0, 0).unwrap();
+        let time = time::OffsetDateTime::from_unix_timestamp(utc.timestamp())
+            .unwrap()
+            .to_offset(time::UtcOffset::from_hms(2, 0, 0).unwrap());
+        assert_eq!(Timestamp::from(time), Timestamp::from(utc));
+        assert_eq!(Timestamp::from(utc).to_offset_date_time(), Some(time));
+    }
+}
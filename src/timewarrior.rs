@@ -0,0 +1,297 @@
+use crate::{
+    import::map_project, CheckpointDb, CheckpointKind, ImportedCheckpoint, Importer, Result,
+    TimeRange,
+};
+use chrono::prelude::*;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// Reads Timewarrior intervals, either from its data files or from the JSON of `timew export`.
+/// Open intervals are still being tracked and are skipped.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TimewarriorImporter {
+    /// Maps tags to the short names of projects in the database. The first tag of an interval
+    /// with a mapping decides its project and the other tags are kept as tags. If there are no
+    /// mappings at all, the first tag of each interval is taken as the project's short name,
+    /// the way `CheckpointDb::export_timewarrior` writes them.
+    pub projects: BTreeMap<String, String>,
+}
+
+impl TimewarriorImporter {
+    /// Splits the tags of an interval into its project and its other tags.
+    fn project_and_tags(&self, mut tags: Vec<String>) -> (Option<String>, Vec<String>) {
+        let position = if self.projects.is_empty() {
+            (!tags.is_empty()).then_some(0)
+        } else {
+            tags.iter().position(|tag| self.projects.contains_key(tag))
+        };
+
+        match position {
+            Some(position) => {
+                let tag = tags.remove(position);
+                (map_project(&self.projects, &tag), tags)
+            }
+            None => (None, tags),
+        }
+    }
+
+    fn read_data(&self, input: &str) -> Result<Vec<ImportedCheckpoint>, String> {
+        let mut checkpoints = Vec::new();
+        for (i, line) in input.lines().enumerate() {
+            let error = |message: String| format!("line {}: {}", i + 1, message);
+            let tokens = split_timewarrior_line(line);
+            let mut tokens = tokens.iter().map(String::as_str);
+            match tokens.next() {
+                Some("inc") => (),
+                None => continue,
+                Some(other) => return Err(error(format!("unknown line type {:?}", other))),
+            }
+
+            let start = tokens
+                .next()
+                .ok_or_else(|| error("missing start".to_string()))?;
+            let start = parse_timewarrior_time(start).map_err(error)?;
+            let end = match tokens.next() {
+                Some("-") => {
+                    let end = tokens
+                        .next()
+                        .ok_or_else(|| error("missing end".to_string()))?;
+                    let end = parse_timewarrior_time(end).map_err(error)?;
+                    tokens.next();
+                    end
+                }
+                _ => continue,
+            };
+
+            let tags: Vec<String> = tokens
+                .by_ref()
+                .take_while(|token| *token != "#")
+                .map(str::to_string)
+                .collect();
+            let annotation = tokens.collect::<Vec<&str>>().join(" ");
+            let (project, tags) = self.project_and_tags(tags);
+
+            checkpoints.push(ImportedCheckpoint {
+                timestamp: end,
+                message: annotation,
+                project,
+                tags,
+                start: Some(start),
+                external_id: None,
+                kind: None,
+            });
+        }
+        Ok(checkpoints)
+    }
+
+    fn read_json(&self, input: &str) -> Result<Vec<ImportedCheckpoint>, String> {
+        let value: Value = serde_json::from_str(input).map_err(|e| e.to_string())?;
+        let intervals = value.as_array().ok_or("expected an array of intervals")?;
+
+        let mut checkpoints = Vec::new();
+        for (i, interval) in intervals.iter().enumerate() {
+            let time = |name: &str| {
+                interval
+                    .get(name)
+                    .and_then(Value::as_str)
+                    .map(|time| {
+                        parse_timewarrior_time(time)
+                            .map_err(|message| format!("interval {}: {}", i, message))
+                    })
+                    .transpose()
+            };
+            let (start, end) = match (time("start")?, time("end")?) {
+                (Some(start), Some(end)) => (start, end),
+                (None, _) => return Err(format!("interval {}: there is no start", i)),
+                (Some(_), None) => continue,
+            };
+            let tags =
+                interval
+                    .get("tags")
+                    .and_then(Value::as_array)
+                    .map_or_else(Vec::new, |tags| {
+                        tags.iter()
+                            .filter_map(Value::as_str)
+                            .map(str::to_string)
+                            .collect()
+                    });
+            let (project, tags) = self.project_and_tags(tags);
+
+            checkpoints.push(ImportedCheckpoint {
+                timestamp: end,
+                message: interval
+                    .get("annotation")
+                    .and_then(Value::as_str)
+                    .unwrap_or("")
+                    .to_string(),
+                project,
+                tags,
+                start: Some(start),
+                external_id: None,
+                kind: None,
+            });
+        }
+        Ok(checkpoints)
+    }
+}
+
+impl Importer for TimewarriorImporter {
+    fn read(&self, input: &str) -> Result<Vec<ImportedCheckpoint>, String> {
+        if input.trim_start().starts_with('[') {
+            self.read_json(input)
+        } else {
+            self.read_data(input)
+        }
+    }
+}
+
+impl CheckpointDb {
+    /// Writes the work in the given `TimeRange` as lines of a Timewarrior data file. Each
+    /// checkpoint becomes an interval from the checkpoint before it, tagged with the project's
+    /// short name followed by the checkpoint's tags, and annotated with the message.
+    pub fn export_timewarrior<W: Write>(&self, mut writer: W, range: &TimeRange) -> Result<()> {
+        for entry in self.entries_in(range) {
+            if entry.duration == 0 || entry.checkpoint.kind == CheckpointKind::Break {
+                continue;
+            }
+
+            let start = timewarrior_time(entry.timestamp - entry.duration)?;
+            let end = timewarrior_time(entry.timestamp)?;
+            write!(writer, "inc {} - {}", start, end)?;
+
+            let project = self
+                .project_from_project_id(entry.checkpoint.project_id)
+                .map(|project| project.short_name.as_str());
+            let tags: Vec<&str> = project
+                .into_iter()
+                .chain(entry.checkpoint.tags.iter().map(String::as_str))
+                .collect();
+            let message = &entry.checkpoint.message;
+            if !tags.is_empty() || !message.is_empty() {
+                write!(writer, " #")?;
+                for tag in tags {
+                    write!(writer, " {}", quote_timewarrior(tag))?;
+                }
+            }
+            if !message.is_empty() {
+                write!(writer, " # \"{}\"", escape_timewarrior(message))?;
+            }
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn timewarrior_time(timestamp: i64) -> io::Result<String> {
+    Utc.timestamp_opt(timestamp, 0)
+        .single()
+        .map(|time| time.format("%Y%m%dT%H%M%SZ").to_string())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "timestamp out of range"))
+}
+
+fn parse_timewarrior_time(value: &str) -> Result<i64, String> {
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ")
+        .map(|time| time.and_utc().timestamp())
+        .map_err(|e| format!("could not parse the time {:?}: {}", value, e))
+}
+
+/// Quotes a tag if it would otherwise be split or mistaken for something else.
+fn quote_timewarrior(tag: &str) -> String {
+    if tag.is_empty() || tag.contains([' ', '"', '#']) {
+        format!("\"{}\"", escape_timewarrior(tag))
+    } else {
+        tag.to_string()
+    }
+}
+
+fn escape_timewarrior(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Splits a line of a Timewarrior data file on whitespace, keeping quoted strings together and
+/// unquoting them.
+fn split_timewarrior_line(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+
+        let mut token = String::new();
+        if c == '"' {
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => token.extend(chars.next()),
+                    '"' => break,
+                    _ => token.push(c),
+                }
+            }
+        } else {
+            token.push(c);
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                token.push(c);
+            }
+        }
+        tokens.push(token);
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CheckpointId, ProjectId};
+
+    #[test]
+    /// Exports intervals and imports them into another database.
+    fn timewarrior_round_trip() {
+        let mut checkpoint_db = CheckpointDb::new();
+        let project_id = checkpoint_db.add_project("Website", "web").unwrap();
+        checkpoint_db
+            .add_checkpoint(1_623_747_600, "", ProjectId::NoId)
+            .unwrap();
+        checkpoint_db
+            .add_checkpoint(1_623_753_000, "Fix \"the\" header", project_id)
+            .unwrap();
+        checkpoint_db
+            .set_checkpoint_tags(
+                CheckpointId::Timestamp(1_623_753_000),
+                vec!["bug fix".to_string()],
+            )
+            .unwrap();
+
+        let mut output = Vec::new();
+        checkpoint_db
+            .export_timewarrior(&mut output, &TimeRange::new(0, i64::MAX))
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(
+            output,
+            "inc 20210615T090000Z - 20210615T103000Z # web \"bug fix\" # \"Fix \\\"the\\\" header\"\n"
+        );
+
+        let checkpoints = TimewarriorImporter::default().read(&output).unwrap();
+        assert_eq!(
+            checkpoints,
+            vec![ImportedCheckpoint {
+                timestamp: 1_623_753_000,
+                message: "Fix \"the\" header".to_string(),
+                project: Some("web".to_string()),
+                tags: vec!["bug fix".to_string()],
+                start: Some(1_623_747_600),
+                external_id: None,
+                kind: None,
+            }]
+        );
+
+        let json = r#"[{"id": 1, "start": "20210615T090000Z", "end": "20210615T103000Z",
+                        "tags": ["web", "bug fix"], "annotation": "Fix \"the\" header"}]"#;
+        assert_eq!(
+            TimewarriorImporter::default().read(json).unwrap(),
+            checkpoints
+        );
+    }
+}
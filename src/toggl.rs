@@ -0,0 +1,197 @@
+use crate::{
+    csv::{header_column, parse_csv, parse_local_time},
+    import::map_project,
+    ImportedCheckpoint, Importer,
+};
+#[cfg(feature = "json")]
+use chrono::prelude::*;
+#[cfg(feature = "json")]
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// The formats Toggl Track exports time entries in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TogglFormat {
+    /// The CSV of the detailed report, with local start and end dates and times.
+    Csv,
+    /// A JSON array of time entries, or an object with one in its `data` field, with start and
+    /// end times in RFC 3339.
+    #[cfg(feature = "json")]
+    Json,
+}
+
+/// Reads time entries exported from Toggl Track.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TogglImporter {
+    pub format: TogglFormat,
+    /// Maps Toggl project names to the short names of projects in the database. Projects
+    /// without a mapping keep their Toggl name as short name.
+    pub projects: BTreeMap<String, String>,
+}
+
+impl TogglImporter {
+    pub fn new(format: TogglFormat) -> TogglImporter {
+        TogglImporter {
+            format,
+            projects: BTreeMap::new(),
+        }
+    }
+
+    fn read_csv(&self, input: &str) -> Result<Vec<ImportedCheckpoint>, String> {
+        let rows = parse_csv(input, ',');
+        let header = rows.first().ok_or("the file is empty")?;
+        let column = |name: &str| header_column(header, name);
+        let project = column("Project")?;
+        let description = column("Description")?;
+        let start_date = column("Start date")?;
+        let start_time = column("Start time")?;
+        let end_date = column("End date")?;
+        let end_time = column("End time")?;
+        let tags = column("Tags").ok();
+
+        rows.iter()
+            .enumerate()
+            .skip(1)
+            .filter(|(_, row)| row.iter().any(|field| !field.is_empty()))
+            .map(|(i, row)| {
+                let field = |column: usize| row.get(column).map_or("", |field| field.trim());
+                let local_time = |date: usize, time: usize| {
+                    let value = format!("{} {}", field(date), field(time));
+                    parse_local_time(&value, "%Y-%m-%d %H:%M:%S")
+                        .map_err(|message| format!("row {}: {}", i + 1, message))
+                };
+
+                Ok(ImportedCheckpoint {
+                    timestamp: local_time(end_date, end_time)?,
+                    message: field(description).to_string(),
+                    project: map_project(&self.projects, field(project)),
+                    tags: tags.map_or_else(Vec::new, |tags| split_tags(field(tags))),
+                    start: Some(local_time(start_date, start_time)?),
+                    external_id: None,
+                    kind: None,
+                })
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "json")]
+    fn read_json(&self, input: &str) -> Result<Vec<ImportedCheckpoint>, String> {
+        let value: Value = serde_json::from_str(input).map_err(|e| e.to_string())?;
+        let entries = value
+            .get("data")
+            .unwrap_or(&value)
+            .as_array()
+            .ok_or("expected an array of time entries")?;
+
+        entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let string = |name: &str| entry.get(name).and_then(Value::as_str);
+                let time = |names: &[&str]| {
+                    let value = names
+                        .iter()
+                        .find_map(|name| string(name))
+                        .ok_or_else(|| format!("entry {}: there is no {}", i, names[0]))?;
+                    DateTime::parse_from_rfc3339(value)
+                        .map(|time| time.timestamp())
+                        .map_err(|e| format!("entry {}: could not parse {:?}: {}", i, value, e))
+                };
+
+                Ok(ImportedCheckpoint {
+                    timestamp: time(&["end", "stop"])?,
+                    message: string("description").unwrap_or("").to_string(),
+                    project: string("project")
+                        .or_else(|| string("project_name"))
+                        .and_then(|name| map_project(&self.projects, name)),
+                    tags: entry.get("tags").and_then(Value::as_array).map_or_else(
+                        Vec::new,
+                        |tags| {
+                            tags.iter()
+                                .filter_map(Value::as_str)
+                                .map(str::to_string)
+                                .collect()
+                        },
+                    ),
+                    start: Some(time(&["start"])?),
+                    external_id: entry.get("id").map(|id| match id {
+                        Value::String(id) => format!("toggl:{}", id),
+                        id => format!("toggl:{}", id),
+                    }),
+                    kind: None,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Importer for TogglImporter {
+    fn read(&self, input: &str) -> Result<Vec<ImportedCheckpoint>, String> {
+        match self.format {
+            TogglFormat::Csv => self.read_csv(input),
+            #[cfg(feature = "json")]
+            TogglFormat::Json => self.read_json(input),
+        }
+    }
+}
+
+/// Splits a comma separated list of tags, as exported by Toggl and Clockify.
+pub(crate) fn split_tags(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+    use crate::CheckpointDb;
+
+    #[test]
+    /// Imports the same entry from CSV and JSON, mapping the project and keeping the tags.
+    fn import_toggl() {
+        let csv = "User,Email,Client,Project,Task,Description,Billable,Start date,Start time,\
+                   End date,End time,Duration,Tags\n\
+                   Jo,jo@example.com,,Website,,Fix the header,Yes,2021-06-15,09:00:00,\
+                   2021-06-15,10:30:00,01:30:00,\"design, bug\"\n";
+        let start = Local
+            .with_ymd_and_hms(2021, 6, 15, 9, 0, 0)
+            .unwrap()
+            .timestamp();
+        let json = format!(
+            r#"{{"data": [{{"description": "Fix the header", "project": "Website",
+                "start": "{}", "end": "{}", "tags": ["design", "bug"]}}]}}"#,
+            Local.timestamp_opt(start, 0).unwrap().to_rfc3339(),
+            Local.timestamp_opt(start + 5400, 0).unwrap().to_rfc3339(),
+        );
+
+        let mut importer = TogglImporter::new(TogglFormat::Csv);
+        importer
+            .projects
+            .insert("Website".to_string(), "web".to_string());
+        let from_csv = importer.read(csv).unwrap();
+        importer.format = TogglFormat::Json;
+        assert_eq!(importer.read(&json).unwrap(), from_csv);
+
+        assert_eq!(
+            from_csv,
+            vec![ImportedCheckpoint {
+                timestamp: start + 5400,
+                message: "Fix the header".to_string(),
+                project: Some("web".to_string()),
+                tags: vec!["design".to_string(), "bug".to_string()],
+                start: Some(start),
+                external_id: None,
+                kind: None,
+            }]
+        );
+
+        let mut checkpoint_db = CheckpointDb::new();
+        checkpoint_db
+            .import_from(csv.as_bytes(), &TogglImporter::new(TogglFormat::Csv), false)
+            .unwrap();
+        assert_eq!(checkpoint_db.checkpoints[&(start + 5400)].tags.len(), 2);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::{CheckpointDb, ProjectId, Result, TimeRange};
+use serde::Serialize;
+use std::io;
+use wasm_bindgen::prelude::*;
+
+/// Keeps a database in the browser's `localStorage` under a key, as JSON like a database file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BrowserStorage {
+    key: String,
+}
+
+impl BrowserStorage {
+    pub fn new(key: &str) -> BrowserStorage {
+        BrowserStorage {
+            key: key.to_string(),
+        }
+    }
+
+    /// Reads the database stored under the key, or an empty one if nothing is stored there.
+    pub fn load(&self) -> Result<CheckpointDb> {
+        match local_storage()?
+            .get_item(&self.key)
+            .map_err(storage_error)?
+        {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Ok(CheckpointDb::new()),
+        }
+    }
+
+    /// Stores the database under the key, replacing what was there.
+    pub fn save(&self, checkpoint_db: &CheckpointDb) -> Result<()> {
+        let json = serde_json::to_string(checkpoint_db)?;
+        local_storage()?
+            .set_item(&self.key, &json)
+            .map_err(storage_error)
+    }
+}
+
+fn local_storage() -> Result<web_sys::Storage> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or_else(|| io::Error::other("localStorage is not available").into())
+}
+
+/// Storage fails when it's full or the user has blocked it.
+fn storage_error(_: JsValue) -> crate::CheckpointDbError {
+    io::Error::other("localStorage could not be used").into()
+}
+
+/// A `CheckpointDb` for JavaScript, where it's called `CheckpointDb`. Times are seconds since
+/// the Unix epoch, and results are plain objects shaped like the crate's JSON.
+#[wasm_bindgen(js_name = CheckpointDb)]
+#[derive(Clone, Debug, Default)]
+pub struct WasmCheckpointDb {
+    checkpoint_db: CheckpointDb,
+}
+
+#[wasm_bindgen(js_class = CheckpointDb)]
+impl WasmCheckpointDb {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmCheckpointDb {
+        WasmCheckpointDb::default()
+    }
+
+    /// Reads the database stored in `localStorage` under the key.
+    pub fn load(key: &str) -> std::result::Result<WasmCheckpointDb, JsError> {
+        Ok(BrowserStorage::new(key).load()?.into())
+    }
+
+    /// Stores the database in `localStorage` under the key.
+    pub fn save(&self, key: &str) -> std::result::Result<(), JsError> {
+        Ok(BrowserStorage::new(key).save(&self.checkpoint_db)?)
+    }
+
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> std::result::Result<WasmCheckpointDb, JsError> {
+        Ok(serde_json::from_str::<CheckpointDb>(json)?.into())
+    }
+
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> std::result::Result<String, JsError> {
+        Ok(serde_json::to_string(&self.checkpoint_db)?)
+    }
+
+    /// Adds a checkpoint, failing if there already is one at the same time. Leaving out the
+    /// project adds it without one.
+    #[wasm_bindgen(js_name = addCheckpoint)]
+    pub fn add_checkpoint(
+        &mut self,
+        timestamp: f64,
+        message: &str,
+        project_id: Option<u16>,
+    ) -> std::result::Result<(), JsError> {
+        let project_id = project_id.map_or(ProjectId::NoId, ProjectId::Id);
+        Ok(self
+            .checkpoint_db
+            .add_checkpoint(timestamp as i64, message, project_id)?)
+    }
+
+    /// Adds a project and returns its id.
+    #[wasm_bindgen(js_name = addProject)]
+    pub fn add_project(
+        &mut self,
+        long_name: &str,
+        short_name: &str,
+    ) -> std::result::Result<u16, JsError> {
+        match self.checkpoint_db.add_project(long_name, short_name)? {
+            ProjectId::Id(id) => Ok(id),
+            ProjectId::NoId => unreachable!("added projects always have an id"),
+        }
+    }
+
+    /// Returns the checkpoints between two times, from the latest to the oldest.
+    pub fn log(&self, start: f64, end: f64) -> std::result::Result<JsValue, JsError> {
+        to_js(
+            &self
+                .checkpoint_db
+                .get_log_between_times(start as i64, end as i64),
+        )
+    }
+
+    /// Returns the time tracked per day and project between two times.
+    pub fn report(&self, start: f64, end: f64) -> std::result::Result<JsValue, JsError> {
+        to_js(
+            &self
+                .checkpoint_db
+                .report(&TimeRange::new(start as i64, end as i64), None),
+        )
+    }
+}
+
+impl From<CheckpointDb> for WasmCheckpointDb {
+    fn from(checkpoint_db: CheckpointDb) -> WasmCheckpointDb {
+        WasmCheckpointDb { checkpoint_db }
+    }
+}
+
+fn to_js<T: Serialize>(value: &T) -> std::result::Result<JsValue, JsError> {
+    let json = serde_json::to_string(value)?;
+    js_sys::JSON::parse(&json).map_err(|_| JsError::new("the result could not be converted"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    /// The database survives a round trip through JSON, the way it's stored in the browser.
+    fn json_round_trip() {
+        let mut wasm_db = WasmCheckpointDb::new();
+        let project_id = wasm_db.add_project("Website", "web").unwrap();
+        wasm_db
+            .add_checkpoint(1000.0, "Styling", Some(project_id))
+            .unwrap();
+        wasm_db.add_checkpoint(2000.0, "Break", None).unwrap();
+
+        let json = wasm_db.to_json().unwrap();
+        let loaded = WasmCheckpointDb::from_json(&json).unwrap();
+        assert_eq!(loaded.checkpoint_db, wasm_db.checkpoint_db);
+        assert_eq!(loaded.checkpoint_db.len(), 2);
+    }
+}
@@ -0,0 +1,244 @@
+use crate::{ChangeEvent, Checkpoint, CheckpointDb, Result};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+    time::Duration,
+};
+
+/// The header holding the signature of a payload, as `sha256=` and the HMAC-SHA256 of the body
+/// in hexadecimal, keyed with the secret of the webhook.
+pub const WEBHOOK_SIGNATURE_HEADER: &str = "X-Time-Track-Signature";
+
+/// A URL that is sent a `WebhookPayload` as JSON for every checkpoint that is added, changed or
+/// removed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    /// Signs the payloads in `WEBHOOK_SIGNATURE_HEADER` when set, so the receiver can tell
+    /// they're real.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// How many more times to send a payload that failed before giving up until the next
+    /// delivery.
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+fn default_retries() -> u32 {
+    3
+}
+
+impl Webhook {
+    pub fn new(url: &str) -> Webhook {
+        Webhook {
+            url: url.to_string(),
+            secret: None,
+            retries: default_retries(),
+        }
+    }
+}
+
+/// What a webhook is sent about a change to a checkpoint.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WebhookPayload {
+    pub event: ChangeEvent,
+    /// The checkpoint as it is when the payload is sent, which is missing if it has been
+    /// removed.
+    pub checkpoint: Option<Checkpoint>,
+}
+
+/// Sends the changes to the checkpoints of a database to webhooks.
+///
+/// Changes are queued as they are made, and sent when `deliver` is called, so that changing the
+/// database never waits for the network. Changes that couldn't be sent stay queued for the next
+/// delivery.
+#[derive(Clone, Debug)]
+pub struct Webhooks {
+    webhooks: Vec<Webhook>,
+    /// The changes not yet sent to each webhook, in the order of `webhooks`.
+    pending: Arc<Mutex<Vec<VecDeque<ChangeEvent>>>>,
+    retry_delay: Duration,
+}
+
+impl Webhooks {
+    pub fn new(webhooks: Vec<Webhook>) -> Webhooks {
+        let pending = vec![VecDeque::new(); webhooks.len()];
+        Webhooks {
+            webhooks,
+            pending: Arc::new(Mutex::new(pending)),
+            retry_delay: Duration::from_secs(1),
+        }
+    }
+
+    /// Sets how long to wait before the first retry. The wait doubles for every retry after
+    /// it.
+    pub fn retry_delay(mut self, retry_delay: Duration) -> Webhooks {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Queues the changes to the checkpoints of the database from now on.
+    pub fn watch(&self, checkpoint_db: &mut CheckpointDb) {
+        let pending = Arc::clone(&self.pending);
+        checkpoint_db.on_change(move |event| {
+            if let ChangeEvent::CheckpointAdded { .. }
+            | ChangeEvent::CheckpointChanged { .. }
+            | ChangeEvent::CheckpointRemoved { .. } = event
+            {
+                let mut pending = pending.lock().unwrap_or_else(PoisonError::into_inner);
+                for queue in pending.iter_mut() {
+                    queue.push_back(*event);
+                }
+            }
+        });
+    }
+
+    /// Returns the number of queued payloads for all webhooks together.
+    pub fn pending(&self) -> usize {
+        self.lock().iter().map(VecDeque::len).sum()
+    }
+
+    /// Sends the queued changes, with the checkpoints as they are in the database now, and
+    /// returns the number of payloads sent. Each webhook gets its changes in order, and stops
+    /// at the first one that fails after retrying. The first failure is returned after trying
+    /// all webhooks.
+    pub fn deliver(&self, checkpoint_db: &CheckpointDb) -> Result<usize> {
+        let mut sent = 0;
+        let mut first_error = None;
+        for (index, webhook) in self.webhooks.iter().enumerate() {
+            let mut queue = std::mem::take(&mut self.lock()[index]);
+            while let Some(event) = queue.front() {
+                let payload = WebhookPayload {
+                    event: *event,
+                    checkpoint: match event {
+                        ChangeEvent::CheckpointAdded { timestamp }
+                        | ChangeEvent::CheckpointChanged { timestamp } => {
+                            checkpoint_db.get_checkpoint(&(*timestamp).into()).cloned()
+                        }
+                        _ => None,
+                    },
+                };
+                if let Err(e) = self.send(webhook, &serde_json::to_string(&payload)?) {
+                    first_error.get_or_insert(e);
+                    break;
+                }
+                queue.pop_front();
+                sent += 1;
+            }
+
+            // Put what wasn't sent before what was queued while sending.
+            let mut pending = self.lock();
+            queue.append(&mut pending[index]);
+            pending[index] = queue;
+        }
+
+        match first_error {
+            Some(e) => Err(e),
+            None => Ok(sent),
+        }
+    }
+
+    fn send(&self, webhook: &Webhook, body: &str) -> Result<()> {
+        let mut delay = self.retry_delay;
+        let mut attempt = 0;
+        loop {
+            let mut request = ureq::post(&webhook.url).set("Content-Type", "application/json");
+            if let Some(secret) = &webhook.secret {
+                request = request.set(WEBHOOK_SIGNATURE_HEADER, &sign(secret, body));
+            }
+            match request.send_string(body) {
+                Ok(_) => return Ok(()),
+                Err(_) if attempt < webhook.retries => {
+                    thread::sleep(delay);
+                    delay *= 2;
+                    attempt += 1;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<VecDeque<ChangeEvent>>> {
+        self.pending.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Returns the value of `WEBHOOK_SIGNATURE_HEADER` for the body.
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any size");
+    mac.update(body.as_bytes());
+    let signature: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("sha256={}", signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProjectId;
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+    };
+
+    #[test]
+    /// Payloads are signed and sent again when the receiver fails.
+    fn deliver_with_retry() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let receiver = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for status in ["500 Internal Server Error", "200 OK"] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let (mut headers, mut line) = (String::new(), String::new());
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    headers.push_str(&line);
+                    line.clear();
+                }
+                let length: usize = headers
+                    .to_lowercase()
+                    .lines()
+                    .find_map(|header| header.strip_prefix("content-length:")?.trim().parse().ok())
+                    .unwrap();
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                )
+                .unwrap();
+                requests.push((headers, String::from_utf8(body).unwrap()));
+            }
+            requests
+        });
+
+        let mut checkpoint_db = CheckpointDb::new();
+        let mut webhook = Webhook::new(&url);
+        webhook.secret = Some("hush".to_string());
+        webhook.retries = 1;
+        let webhooks = Webhooks::new(vec![webhook]).retry_delay(Duration::ZERO);
+        webhooks.watch(&mut checkpoint_db);
+        checkpoint_db
+            .add_checkpoint(1000, "Mirrored", ProjectId::NoId)
+            .unwrap();
+        assert_eq!(webhooks.pending(), 1);
+
+        assert_eq!(webhooks.deliver(&checkpoint_db).unwrap(), 1);
+        assert_eq!(webhooks.pending(), 0);
+        let requests = receiver.join().unwrap();
+        let (headers, body) = &requests[1];
+        assert!(body.contains("Mirrored"));
+        let signature = format!("{}: {}", WEBHOOK_SIGNATURE_HEADER, sign("hush", body));
+        assert!(headers.contains(&signature), "{}", headers);
+    }
+}
@@ -0,0 +1,179 @@
+use crate::{CheckpointDb, CheckpointDbError, Report, Result, TimeRange, TrackedDuration};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The contents of a workspace manifest file.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceManifest {
+    /// The path of each database by name. Relative paths are relative to the manifest.
+    pub databases: BTreeMap<String, PathBuf>,
+    /// The name of the database in use.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current: Option<String>,
+}
+
+/// Several named databases, like "work" and "personal", listed in a manifest file. Each
+/// database stays in its own file, and reports over all of them are put together when asked
+/// for.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Workspace {
+    path: PathBuf,
+    manifest: WorkspaceManifest,
+}
+
+/// The reports of every database in a workspace over the same range.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorkspaceReport {
+    pub reports: BTreeMap<String, Report>,
+    /// The time of all databases together.
+    pub duration: TrackedDuration,
+}
+
+impl Workspace {
+    /// Reads the manifest at the path, or starts an empty workspace if there is no file.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Workspace> {
+        let path = path.as_ref().to_path_buf();
+        let manifest = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => WorkspaceManifest::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Workspace { path, manifest })
+    }
+
+    /// Writes the manifest.
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.exists()) {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string_pretty(&self.manifest)?)?;
+        Ok(())
+    }
+
+    pub fn manifest(&self) -> &WorkspaceManifest {
+        &self.manifest
+    }
+
+    /// Returns the names of the databases in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.manifest.databases.keys().map(String::as_str)
+    }
+
+    /// Adds a database to the workspace. The first database that is added is made current.
+    pub fn add<P: AsRef<Path>>(&mut self, name: &str, path: P) -> Result<()> {
+        if self.manifest.databases.contains_key(name) {
+            return Err(CheckpointDbError::InvalidInput(format!(
+                "there already is a database named {}",
+                name
+            )));
+        }
+        self.manifest
+            .databases
+            .insert(name.to_string(), path.as_ref().to_path_buf());
+        self.manifest
+            .current
+            .get_or_insert_with(|| name.to_string());
+        Ok(())
+    }
+
+    /// Removes a database from the workspace, leaving its file alone, and returns its path.
+    pub fn remove(&mut self, name: &str) -> Result<PathBuf> {
+        let path = self.database_path(name)?;
+        self.manifest.databases.remove(name);
+        if self.manifest.current.as_deref() == Some(name) {
+            self.manifest.current = None;
+        }
+        Ok(path)
+    }
+
+    /// Makes the named database the current one.
+    pub fn switch(&mut self, name: &str) -> Result<()> {
+        self.database_path(name)?;
+        self.manifest.current = Some(name.to_string());
+        Ok(())
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.manifest.current.as_deref()
+    }
+
+    /// Returns the path of the named database, resolved against the manifest's directory.
+    pub fn database_path(&self, name: &str) -> Result<PathBuf> {
+        let path = self.manifest.databases.get(name).ok_or_else(|| {
+            CheckpointDbError::InvalidInput(format!("there is no database named {}", name))
+        })?;
+        Ok(match self.path.parent() {
+            Some(dir) if path.is_relative() => dir.join(path),
+            _ => path.clone(),
+        })
+    }
+
+    /// Reads the named database, creating an empty one if there is no file.
+    pub fn read(&self, name: &str) -> Result<CheckpointDb> {
+        CheckpointDb::read(&self.database_path(name)?)
+    }
+
+    /// Reads the current database.
+    pub fn read_current(&self) -> Result<CheckpointDb> {
+        let name = self
+            .current()
+            .ok_or_else(|| CheckpointDbError::InvalidInput("no database is current".to_string()))?;
+        self.read(name)
+    }
+
+    /// Returns a report of every database over the range.
+    pub fn report(&self, range: &TimeRange) -> Result<WorkspaceReport> {
+        let mut reports = BTreeMap::new();
+        for name in self.names() {
+            reports.insert(name.to_string(), self.read(name)?.report(range, None));
+        }
+        Ok(WorkspaceReport {
+            duration: reports.values().map(|report| report.duration).sum(),
+            reports,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ProjectId;
+
+    #[test]
+    /// Databases are kept apart, and reports over them are added up.
+    fn workspace_report() {
+        let _ = fs::remove_dir_all("test_files/workspace");
+        let manifest = Path::new("test_files/workspace/workspace.json");
+        let mut workspace = Workspace::open(manifest).unwrap();
+        workspace.add("work", "work.json").unwrap();
+        workspace.add("personal", "personal.json").unwrap();
+        assert!(workspace.add("work", "other.json").is_err());
+        assert_eq!(workspace.current(), Some("work"));
+        workspace.switch("personal").unwrap();
+        assert!(workspace.switch("hobby").is_err());
+        workspace.save().unwrap();
+
+        for (name, seconds) in [("work", 3600), ("personal", 600)] {
+            let mut checkpoint_db = workspace.read(name).unwrap();
+            checkpoint_db
+                .add_checkpoint(1000, "Start", ProjectId::NoId)
+                .unwrap();
+            checkpoint_db
+                .add_checkpoint(1000 + seconds, "Done", ProjectId::NoId)
+                .unwrap();
+            checkpoint_db
+                .write(&workspace.database_path(name).unwrap())
+                .unwrap();
+        }
+
+        let workspace = Workspace::open(manifest).unwrap();
+        assert_eq!(workspace.current(), Some("personal"));
+        assert!(Path::new("test_files/workspace/work.json").exists());
+        let report = workspace.report(&TimeRange::new(0, 10000)).unwrap();
+        assert_eq!(report.reports["work"].duration.seconds(), 3600);
+        assert_eq!(report.duration.seconds(), 4200);
+    }
+}